@@ -1,7 +1,7 @@
 //! Build script for compiling Protocol Buffers definitions.
 //!
 //! This script runs at compile time to generate Rust code from
-//! the telemetry.proto file using prost-build.
+//! telemetry.proto and vector_tile.proto using prost-build.
 
 fn main() {
     setup_proto_compilation();
@@ -9,8 +9,8 @@ fn main() {
 
 /// Sets up and executes Protocol Buffers compilation.
 ///
-/// Configures prost-build and compiles the telemetry.proto file,
-/// generating Rust type definitions that will be available at compile time.
+/// Configures prost-build and compiles the telemetry and vector tile
+/// definitions, generating Rust type definitions that will be available at compile time.
 ///
 /// # Panics
 ///
@@ -19,12 +19,16 @@ fn main() {
 /// - The proto file contains syntax errors
 /// - Include paths are misconfigured
 fn setup_proto_compilation() {
+    let protos = ["../../proto/telemetry.proto", "../../proto/vector_tile.proto"];
+
+    // The protos live outside this crate, so cargo would not notice edits
+    for proto in protos {
+        println!("cargo:rerun-if-changed={}", proto);
+    }
+
     let mut config = prost_build::Config::new();
 
     config
-        .compile_protos(
-            &["../../proto/telemetry.proto"],
-            &["../../proto/"],
-        )
+        .compile_protos(&protos, &["../../proto/"])
         .expect("Failed to compile protos");
 }
\ No newline at end of file
@@ -0,0 +1,110 @@
+//! Kafka and Redis clients.
+//!
+//! Locally both brokers run in plaintext without credentials, but managed
+//! services (Confluent Cloud, Amazon MSK, ElastiCache) require TLS and
+//! usually authentication: SASL/PLAIN, SASL/SCRAM or a client certificate
+//! (mTLS) for Kafka, a password in a `rediss://` URL and optionally a
+//! client certificate for Redis. Every service creates its clients through
+//! [`Config::kafka_client`] and [`Config::redis_client`], so the security
+//! options of the [`Config`] apply to all connections alike.
+
+use std::fs;
+use rdkafka::config::ClientConfig;
+use redis::{ClientTlsConfig, TlsCertificates};
+use crate::config::Config;
+use crate::error::{Result, TrafficError};
+
+impl Config {
+    /// Returns the Kafka security protocol to connect with.
+    ///
+    /// `KAFKA_SECURITY_PROTOCOL` if set; otherwise `sasl_ssl` if SASL
+    /// credentials are configured, `ssl` if TLS certificates are, and
+    /// `plaintext` without either.
+    pub fn kafka_security_protocol(&self) -> &str {
+        if let Some(protocol) = &self.kafka_security_protocol {
+            protocol
+        } else if self.kafka_sasl_username.is_some() {
+            "sasl_ssl"
+        } else if self.kafka_ssl_ca_location.is_some() || self.kafka_ssl_certificate_location.is_some() {
+            "ssl"
+        } else {
+            "plaintext"
+        }
+    }
+
+    /// Returns the librdkafka settings for connecting to the brokers: the
+    /// bootstrap servers and the security options.
+    ///
+    /// Callers add their producer or consumer settings on top.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use rdkafka::producer::FutureProducer;
+    /// use traffic_common::Config;
+    ///
+    /// let config = Config::from_env().expect("Failed to load config");
+    /// let producer: FutureProducer = config
+    ///     .kafka_client()
+    ///     .set("message.timeout.ms", "5000")
+    ///     .create()
+    ///     .expect("Failed to create producer");
+    /// ```
+    pub fn kafka_client(&self) -> ClientConfig {
+        let mut client = ClientConfig::new();
+        client
+            .set("bootstrap.servers", &self.kafka_brokers)
+            .set("security.protocol", self.kafka_security_protocol());
+
+        let options = [
+            ("sasl.mechanism", &self.kafka_sasl_mechanism),
+            ("sasl.username", &self.kafka_sasl_username),
+            ("sasl.password", &self.kafka_sasl_password),
+            ("ssl.ca.location", &self.kafka_ssl_ca_location),
+            ("ssl.certificate.location", &self.kafka_ssl_certificate_location),
+            ("ssl.key.location", &self.kafka_ssl_key_location),
+            ("ssl.key.password", &self.kafka_ssl_key_password),
+        ];
+        for (key, value) in options {
+            if let Some(value) = value {
+                client.set(key, value);
+            }
+        }
+        client
+    }
+
+    /// Creates a Redis client for `REDIS_URL`.
+    ///
+    /// `rediss://` URLs connect over TLS, verifying the server against the
+    /// system trust store or against `REDIS_TLS_CA_PATH` if set, and
+    /// presenting the client certificate in `REDIS_TLS_CERT_PATH` and
+    /// `REDIS_TLS_KEY_PATH` if set. No connection is opened yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL is invalid, a certificate file cannot be
+    /// read, only one of the client certificate and key is set, or
+    /// certificates are set for a URL that is not `rediss://`.
+    pub fn redis_client(&self) -> Result<redis::Client> {
+        let client_tls = match (&self.redis_tls_cert_path, &self.redis_tls_key_path) {
+            (Some(cert), Some(key)) => Some(ClientTlsConfig { client_cert: read_pem(cert)?, client_key: read_pem(key)? }),
+            (None, None) => None,
+            _ => {
+                return Err(TrafficError::Config(
+                    "REDIS_TLS_CERT_PATH and REDIS_TLS_KEY_PATH must be set together".to_string(),
+                ))
+            }
+        };
+        let root_cert = self.redis_tls_ca_path.as_deref().map(read_pem).transpose()?;
+
+        if client_tls.is_none() && root_cert.is_none() {
+            return Ok(redis::Client::open(self.redis_url.as_str())?);
+        }
+        Ok(redis::Client::build_with_tls(self.redis_url.as_str(), TlsCertificates { client_tls, root_cert })?)
+    }
+}
+
+/// Reads a PEM certificate or key file.
+fn read_pem(path: &str) -> Result<Vec<u8>> {
+    fs::read(path).map_err(|e| TrafficError::Config(format!("Could not read {}: {}", path, e)))
+}
@@ -0,0 +1,41 @@
+//! Virtual checkpoints (toll gantries, ANPR cameras) on selected roads.
+//!
+//! A checkpoint sits where an OSM way passes through one of its nodes.
+//! Every vehicle driving along the way through that node produces a
+//! [`CheckpointEvent`], published in JSON to the [`CHECKPOINT_EVENTS_TOPIC`]
+//! Kafka topic and stored by ingest, as a synthetic source of individual
+//! vehicle sightings to match against aggregate counts.
+
+use serde::{Deserialize, Serialize};
+use crate::map::Direction;
+
+/// Kafka topic carrying checkpoint crossing events.
+pub const CHECKPOINT_EVENTS_TOPIC: &str = "checkpoint-events";
+
+/// A virtual checkpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Unique checkpoint identifier, e.g. "gantry-a100-north"
+    pub id: String,
+    /// OSM way ID of the monitored road
+    pub way_id: i64,
+    /// OSM node ID of the way where the checkpoint stands; an inner node
+    /// of the way sees traffic in both directions
+    pub node_id: i64,
+}
+
+/// A vehicle passing a checkpoint, as published on the checkpoint events
+/// topic.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckpointEvent {
+    /// Checkpoint that was passed
+    pub checkpoint_id: String,
+    /// Vehicle that passed it
+    pub vehicle_id: String,
+    /// OSM way ID of the monitored road
+    pub way_id: i64,
+    /// Direction of travel relative to the node order of the way
+    pub direction: Direction,
+    /// Unix timestamp (milliseconds) of the crossing
+    pub timestamp_ms: i64,
+}
@@ -0,0 +1,81 @@
+//! Command-line options shared by all service binaries.
+//!
+//! Every binary flattens [`CommonArgs`] into its own clap parser, so
+//! `--config`, `--profile` and `--log-format` work the same everywhere:
+//!
+//! ```text
+//! traffic-ingest --profile staging --log-format json run
+//! ```
+//!
+//! Configuration still comes from environment variables (see [`Config`]);
+//! the config file and profile only decide which env files are loaded
+//! before reading them. Variables set in the real environment always win.
+
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use clap::Args;
+use crate::config::Config;
+use crate::telemetry::{init_tracing_with_format, LogFormat};
+
+/// Env file loaded when `--config` is not given.
+const DEFAULT_CONFIG_FILE: &str = ".env";
+
+/// Options accepted by every service binary.
+#[derive(Debug, Clone, Args)]
+pub struct CommonArgs {
+    /// Env file to load configuration from (default: `.env`, if present)
+    #[arg(long, global = true, env = "TRAFFIC_CONFIG", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Profile whose env file (`<config>.<profile>`, e.g. `.env.staging`)
+    /// overrides the config file
+    #[arg(long, global = true, env = "TRAFFIC_PROFILE", value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Format of log lines
+    #[arg(long, global = true, env = "LOG_FORMAT", value_enum, default_value_t = LogFormat::Pretty)]
+    pub log_format: LogFormat,
+}
+
+impl CommonArgs {
+    /// Initializes logging and loads the configuration.
+    ///
+    /// Loads the profile's env file first and the config file second, so
+    /// profile values take precedence (env files never override variables
+    /// that are already set).
+    ///
+    /// # Arguments
+    ///
+    /// * `service_name` - Name of the service, for the startup log line
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an explicitly requested config or profile file
+    /// cannot be read, or if the environment variables are malformed.
+    ///
+    /// # Panics
+    ///
+    /// May panic if a global tracing subscriber has already been set.
+    pub fn init(&self, service_name: &str) -> Result<Config> {
+        init_tracing_with_format(service_name, self.log_format);
+
+        let config_file = self.config.as_deref().unwrap_or(Path::new(DEFAULT_CONFIG_FILE));
+        if let Some(profile) = &self.profile {
+            let mut profile_file = config_file.as_os_str().to_owned();
+            profile_file.push(format!(".{}", profile));
+            dotenvy::from_path(&profile_file)
+                .with_context(|| format!("Could not load profile '{}' from {:?}", profile, profile_file))?;
+            tracing::info!("📄 Profile '{}' loaded from {:?}", profile, profile_file);
+        }
+        match &self.config {
+            Some(path) => {
+                dotenvy::from_path(path).with_context(|| format!("Could not load config file {}", path.display()))?;
+            }
+            None => {
+                dotenvy::from_path(config_file).ok();
+            }
+        }
+
+        Config::from_env_vars()
+    }
+}
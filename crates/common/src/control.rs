@@ -0,0 +1,110 @@
+//! Simulation control commands.
+//!
+//! Control commands are produced as JSON to the [`CONTROL_TOPIC`] Kafka topic
+//! (typically by traffic-api) and consumed by traffic-sim, which applies them
+//! between simulation ticks.
+
+use serde::{Deserialize, Serialize};
+use crate::fleet::FleetTask;
+use crate::signals::SignalPlan;
+use crate::VehiclePriority;
+
+/// Kafka topic carrying simulation control commands.
+pub const CONTROL_TOPIC: &str = "sim-control";
+
+/// A command changing the runtime behaviour of the simulator.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum SimCommand {
+    /// Freeze vehicle movement while keeping keepalive telemetry flowing
+    Pause,
+    /// Resume vehicle movement after a pause
+    Resume,
+    /// Install or replace the signal plan of an intersection
+    SetSignalPlan(SignalPlan),
+    /// Remove the signal plan of an intersection, leaving it unsignalized
+    ClearSignalPlan {
+        /// OSM node ID of the intersection
+        node_id: i64,
+    },
+    /// Queue a fleet task for dispatch to the nearest suitable vehicle
+    CreateTask(FleetTask),
+    /// Change how often vehicles of each priority tier are broadcast
+    SetEmissionRates(EmissionRates),
+    /// Close a road in both directions, e.g. for road works or an incident
+    CloseRoad {
+        /// OSM way ID of the road
+        way_id: i64,
+    },
+    /// Reopen a closed road
+    ReopenRoad {
+        /// OSM way ID of the road
+        way_id: i64,
+    },
+}
+
+/// Ticks between telemetry frames per vehicle priority tier.
+///
+/// Emergency vehicles are typically emitted every tick while ordinary cars
+/// are subsampled, so the important vehicles stay fresh when the pipeline
+/// is overloaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmissionRates {
+    /// Ticks between frames of emergency vehicles
+    pub emergency: u32,
+    /// Ticks between frames of transit vehicles
+    pub transit: u32,
+    /// Ticks between frames of all other vehicles
+    pub car: u32,
+}
+
+impl Default for EmissionRates {
+    fn default() -> Self {
+        Self {
+            emergency: 1,
+            transit: 3,
+            car: 10,
+        }
+    }
+}
+
+impl EmissionRates {
+    /// Returns the ticks between frames for a priority tier.
+    pub fn interval(&self, priority: VehiclePriority) -> u32 {
+        match priority {
+            VehiclePriority::PriorityEmergency => self.emergency,
+            VehiclePriority::PriorityTransit => self.transit,
+            VehiclePriority::PriorityCar => self.car,
+        }
+    }
+
+    /// Checks that every interval is at least one tick.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first zero interval.
+    pub fn validate(&self) -> Result<(), String> {
+        for (tier, interval) in [("emergency", self.emergency), ("transit", self.transit), ("car", self.car)] {
+            if interval == 0 {
+                return Err(format!("{} emission interval must be at least 1 tick", tier));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SimCommand {
+    /// Serializes the command into its JSON wire format.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("SimCommand is always serializable")
+    }
+
+    /// Parses a command from its JSON wire format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the payload is not a known command.
+    pub fn from_json(payload: &[u8]) -> serde_json::Result<Self> {
+        serde_json::from_slice(payload)
+    }
+}
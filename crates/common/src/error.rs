@@ -20,6 +20,9 @@ pub enum TrafficError {
     #[error("Configuration error: {0}")]
     Config(String),
 
+    #[error("Dependency unavailable: {0}")]
+    Unavailable(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
\ No newline at end of file
@@ -0,0 +1,77 @@
+//! Fleet task model shared by the simulator and API.
+//!
+//! Fleet vehicles (taxis and delivery vans) serve pickup/drop-off tasks.
+//! Every lifecycle transition of a task is published as a [`TaskEvent`] in
+//! JSON to the [`FLEET_EVENTS_TOPIC`] Kafka topic.
+
+use serde::{Deserialize, Serialize};
+
+/// Kafka topic carrying fleet task lifecycle events.
+pub const FLEET_EVENTS_TOPIC: &str = "fleet-events";
+
+/// Kind of fleet vehicle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FleetKind {
+    /// Serves one passenger ride at a time
+    Taxi,
+    /// Queues several parcel deliveries
+    DeliveryVan,
+}
+
+impl FleetKind {
+    /// Maximum number of tasks a vehicle of this kind holds at once
+    /// (including the one being served).
+    pub fn capacity(&self) -> usize {
+        match self {
+            FleetKind::Taxi => 1,
+            FleetKind::DeliveryVan => 5,
+        }
+    }
+}
+
+/// Lifecycle status of a fleet task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    /// Waiting for a vehicle to be assigned
+    Pending,
+    /// Queued on a vehicle
+    Assigned,
+    /// Vehicle is driving to the pickup location
+    EnRouteToPickup,
+    /// Load or passenger picked up; driving to the drop-off location
+    PickedUp,
+    /// Dropped off at the destination
+    Completed,
+    /// Could not be served (e.g. unreachable location)
+    Failed,
+}
+
+/// A pickup/drop-off task served by the fleet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FleetTask {
+    /// Unique task identifier
+    pub id: String,
+    /// Kind of vehicle that must serve the task
+    pub kind: FleetKind,
+    /// OSM node ID of the pickup location
+    pub pickup_node: i64,
+    /// OSM node ID of the drop-off location
+    pub dropoff_node: i64,
+}
+
+/// A task lifecycle transition, as published on the fleet events topic.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskEvent {
+    /// Task the event refers to
+    pub task_id: String,
+    /// Kind of vehicle serving the task
+    pub kind: FleetKind,
+    /// New status of the task
+    pub status: TaskStatus,
+    /// Vehicle serving the task, once assigned
+    pub vehicle_id: Option<String>,
+    /// Unix timestamp (seconds) of the transition
+    pub timestamp: i64,
+}
@@ -0,0 +1,85 @@
+//! Geographic bounding boxes, e.g. for restricting loaded map data or
+//! subscriptions to an area.
+
+use std::fmt;
+use std::str::FromStr;
+use anyhow::{ensure, Context};
+use serde::{Deserialize, Serialize};
+
+/// Axis-aligned box in longitude/latitude degrees.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    /// Western edge (minimum longitude)
+    pub min_lon: f64,
+    /// Southern edge (minimum latitude)
+    pub min_lat: f64,
+    /// Eastern edge (maximum longitude)
+    pub max_lon: f64,
+    /// Northern edge (maximum latitude)
+    pub max_lat: f64,
+}
+
+impl BoundingBox {
+    /// Returns `true` if the point lies inside the box (edges included).
+    pub fn contains(&self, lon: f64, lat: f64) -> bool {
+        (self.min_lon..=self.max_lon).contains(&lon) && (self.min_lat..=self.max_lat).contains(&lat)
+    }
+
+    /// Returns the smallest box containing all `(lon, lat)` points, or
+    /// `None` if there are none.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use traffic_common::geo::BoundingBox;
+    ///
+    /// let bbox = BoundingBox::enclosing([(13.3, 52.5), (13.4, 52.4)]).unwrap();
+    /// assert_eq!(bbox, BoundingBox { min_lon: 13.3, min_lat: 52.4, max_lon: 13.4, max_lat: 52.5 });
+    /// assert!(bbox.contains(13.35, 52.45));
+    /// ```
+    pub fn enclosing(points: impl IntoIterator<Item = (f64, f64)>) -> Option<Self> {
+        points.into_iter().fold(None, |bbox, (lon, lat)| {
+            Some(match bbox {
+                None => Self { min_lon: lon, min_lat: lat, max_lon: lon, max_lat: lat },
+                Some(b) => Self {
+                    min_lon: b.min_lon.min(lon),
+                    min_lat: b.min_lat.min(lat),
+                    max_lon: b.max_lon.max(lon),
+                    max_lat: b.max_lat.max(lat),
+                },
+            })
+        })
+    }
+}
+
+/// Parses `"min_lon,min_lat,max_lon,max_lat"`, e.g. from `MAP_BBOX`.
+impl FromStr for BoundingBox {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(',')
+            .map(|part| part.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .context("bounding box values must be numbers")?;
+        ensure!(values.len() == 4, "bounding box needs 4 values: min_lon,min_lat,max_lon,max_lat");
+
+        let bbox = Self {
+            min_lon: values[0],
+            min_lat: values[1],
+            max_lon: values[2],
+            max_lat: values[3],
+        };
+        ensure!(
+            bbox.min_lon < bbox.max_lon && bbox.min_lat < bbox.max_lat,
+            "bounding box minimums must be below maximums"
+        );
+        Ok(bbox)
+    }
+}
+
+impl fmt::Display for BoundingBox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{},{}", self.min_lon, self.min_lat, self.max_lon, self.max_lat)
+    }
+}
@@ -0,0 +1,146 @@
+//! Loading of administrative boundaries as zones.
+//!
+//! City and state open-data portals publish district boundaries as GeoJSON
+//! FeatureCollections of `Polygon`/`MultiPolygon` features carrying the
+//! district's name and official code. Loading them into a [`ZoneSet`] lets
+//! zone statistics and API filters refer to those codes instead of
+//! hand-drawn polygons.
+
+use std::path::Path;
+use anyhow::{bail, Context, Result};
+use ::geo::{LineString, MultiPolygon, Polygon};
+use serde_json::Value;
+use super::{Zone, ZoneSet};
+
+/// Feature properties holding a boundary's official identifier, in order of
+/// preference (after the feature's own `id`).
+const ID_PROPERTIES: [&str; 3] = ["ref", "official_id", "id"];
+
+impl ZoneSet {
+    /// Loads zones from a file.
+    ///
+    /// Files ending in `.geojson` are read as boundary FeatureCollections
+    /// (see [`ZoneSet::from_geojson`]); anything else as a JSON array of
+    /// zones.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Zone list or GeoJSON boundary file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn load(path: &str) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read zones file {}", path))?;
+
+        let is_geojson = Path::new(path)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("geojson"));
+        let zones = if is_geojson {
+            Self::from_geojson(&json)
+        } else {
+            serde_json::from_str(&json).map_err(Into::into)
+        };
+        zones.with_context(|| format!("Invalid zones file {}", path))
+    }
+
+    /// Builds zones from a GeoJSON FeatureCollection of boundaries.
+    ///
+    /// Every `Polygon` or `MultiPolygon` feature becomes a zone; other
+    /// geometries are skipped. The official identifier is taken from the
+    /// feature `id`, or else the `ref`, `official_id` or `id` property
+    /// (strings or numbers). The name comes from the `name` property and
+    /// falls back to the identifier.
+    ///
+    /// # Arguments
+    ///
+    /// * `geojson` - GeoJSON text
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text is not a GeoJSON FeatureCollection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use traffic_common::geo::ZoneSet;
+    ///
+    /// let districts = ZoneSet::from_geojson(r#"{
+    ///     "type": "FeatureCollection",
+    ///     "features": [{
+    ///         "type": "Feature",
+    ///         "properties": { "name": "Mitte", "ref": "11001001" },
+    ///         "geometry": {
+    ///             "type": "Polygon",
+    ///             "coordinates": [[[13.36, 52.50], [13.43, 52.50], [13.43, 52.54], [13.36, 52.54], [13.36, 52.50]]]
+    ///         }
+    ///     }]
+    /// }"#).unwrap();
+    ///
+    /// let zone = districts.first_containing(13.40, 52.52).unwrap();
+    /// assert_eq!(zone.id.as_deref(), Some("11001001"));
+    /// assert_eq!(districts.get_by_id("11001001").unwrap().name, "Mitte");
+    /// ```
+    pub fn from_geojson(geojson: &str) -> Result<Self> {
+        let root: Value = serde_json::from_str(geojson).context("Not valid JSON")?;
+        let Some(features) = root.get("features").and_then(Value::as_array) else {
+            bail!("Not a GeoJSON FeatureCollection");
+        };
+
+        let zones: Vec<Zone> = features.iter().enumerate().filter_map(|(index, feature)| {
+            let properties = feature.get("properties").unwrap_or(&Value::Null);
+            let area = boundary_area(feature.get("geometry")?)?;
+
+            let id = std::iter::once(feature.get("id"))
+                .chain(ID_PROPERTIES.iter().map(|key| properties.get(*key)))
+                .find_map(|value| value.and_then(identifier));
+            let name = properties
+                .get("name")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .or_else(|| id.clone())
+                .unwrap_or_else(|| format!("zone {}", index));
+
+            Some(Zone { name, id, area })
+        }).collect();
+
+        Ok(Self::new(zones))
+    }
+}
+
+/// Reads an identifier given as a string or a number.
+fn identifier(value: &Value) -> Option<String> {
+    match value {
+        Value::String(id) => Some(id.clone()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// Converts a `Polygon` or `MultiPolygon` geometry into an area.
+fn boundary_area(geometry: &Value) -> Option<MultiPolygon<f64>> {
+    let coordinates = geometry.get("coordinates")?.as_array()?;
+    let polygons = match geometry.get("type")?.as_str()? {
+        "Polygon" => vec![polygon(coordinates)?],
+        "MultiPolygon" => coordinates
+            .iter()
+            .filter_map(|rings| polygon(rings.as_array()?))
+            .collect(),
+        _ => return None,
+    };
+    Some(MultiPolygon::new(polygons))
+}
+
+/// Converts GeoJSON rings (exterior first, holes after) into a polygon.
+fn polygon(rings: &[Value]) -> Option<Polygon<f64>> {
+    let ring = |ring: &Value| -> Option<LineString<f64>> {
+        let points = ring.as_array()?.iter().filter_map(|point| {
+            Some((point.get(0)?.as_f64()?, point.get(1)?.as_f64()?))
+        });
+        Some(LineString::from(points.collect::<Vec<_>>()))
+    };
+    let mut rings = rings.iter().filter_map(ring);
+    let exterior = rings.next()?;
+    Some(Polygon::new(exterior, rings.collect()))
+}
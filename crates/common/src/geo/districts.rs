@@ -0,0 +1,237 @@
+//! Administrative districts from OSM boundary relations.
+//!
+//! OSM maps districts as `boundary=administrative` relations whose member
+//! ways (roles `outer` and `inner`) form the boundary rings, with the
+//! relation's `admin_level` telling countries (2) from states (4), cities
+//! (6–8) and city districts (9–10). [`Districts`] assembles those rings
+//! into polygons and answers "which district is this point in", preferring
+//! the most specific level where boundaries of several levels overlap.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use anyhow::{Context, Result};
+use ::geo::{Contains, LineString, MultiPolygon, Point, Polygon};
+use osmpbfreader::{OsmId, OsmObj, OsmPbfReader};
+use super::{Zone, ZoneSet};
+
+/// Relation tags holding a district's official code, in order of preference.
+const CODE_TAGS: [&str; 3] = ["ref", "de:amtlicher_gemeindeschluessel", "ISO3166-2"];
+
+/// Districts indexed for point lookups.
+///
+/// # Examples
+///
+/// ```
+/// use geo::polygon;
+/// use traffic_common::geo::{Districts, Zone};
+///
+/// let berlin = Zone::new("Berlin", polygon![
+///     (x: 13.0, y: 52.3), (x: 13.8, y: 52.3), (x: 13.8, y: 52.7), (x: 13.0, y: 52.7),
+/// ].into());
+/// let mitte = Zone::new("Mitte", polygon![
+///     (x: 13.36, y: 52.50), (x: 13.43, y: 52.50), (x: 13.43, y: 52.54), (x: 13.36, y: 52.54),
+/// ].into());
+/// let districts = Districts::new(vec![(berlin, Some(4)), (mitte, Some(9))]);
+///
+/// assert_eq!(districts.district_of(13.40, 52.52).map(|d| d.name.as_str()), Some("Mitte"));
+/// assert_eq!(districts.district_of(13.20, 52.40).map(|d| d.name.as_str()), Some("Berlin"));
+/// assert!(districts.district_of(14.00, 52.40).is_none());
+/// ```
+#[derive(Debug, Default)]
+pub struct Districts {
+    zones: ZoneSet,
+    /// `admin_level` of each zone, in the order of `zones`
+    admin_levels: Vec<Option<u8>>,
+}
+
+impl Districts {
+    /// Indexes districts given with their `admin_level`.
+    pub fn new(districts: Vec<(Zone, Option<u8>)>) -> Self {
+        let (zones, admin_levels) = districts.into_iter().unzip();
+        Self { zones: ZoneSet::new(zones), admin_levels }
+    }
+
+    /// Loads districts from a file.
+    ///
+    /// `.osm.pbf` extracts are read with [`Districts::load_from_pbf`];
+    /// anything else is read as zones (see [`ZoneSet::load`]), all without
+    /// an admin level.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - OSM extract, GeoJSON boundaries or JSON zone list
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn load(path: &str) -> Result<Self> {
+        let is_pbf = Path::new(path)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("pbf"));
+        if is_pbf {
+            return Self::load_from_pbf(path);
+        }
+        let zones = ZoneSet::load(path)?;
+        let admin_levels = vec![None; zones.len()];
+        Ok(Self { zones, admin_levels })
+    }
+
+    /// Loads all `boundary=administrative` relations of an OSM PBF extract.
+    ///
+    /// Every relation becomes a district named after its `name` tag, with
+    /// the official code from its `ref` (or `de:amtlicher_gemeindeschluessel`,
+    /// `ISO3166-2`) tag. Rings that cannot be closed, e.g. because the
+    /// boundary leaves the extract, are dropped; relations left without an
+    /// outer ring are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read.
+    pub fn load_from_pbf(path: &str) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("Could not open districts file {}", path))?;
+        let mut pbf = OsmPbfReader::new(file);
+        let objs = pbf.get_objs_and_deps(|obj| {
+            obj.is_relation() && obj.tags().contains("boundary", "administrative")
+        })?;
+
+        let mut positions: HashMap<i64, (f64, f64)> = HashMap::new();
+        let mut ways: HashMap<i64, Vec<i64>> = HashMap::new();
+        for obj in objs.values() {
+            match obj {
+                OsmObj::Node(node) => {
+                    positions.insert(node.id.0, (node.lon(), node.lat()));
+                }
+                OsmObj::Way(way) => {
+                    ways.insert(way.id.0, way.nodes.iter().map(|node| node.0).collect());
+                }
+                OsmObj::Relation(_) => {}
+            }
+        }
+
+        let mut districts = Vec::new();
+        let mut open_rings = 0;
+        for obj in objs.values() {
+            let OsmObj::Relation(relation) = obj else { continue };
+            if !relation.tags.contains("boundary", "administrative") {
+                continue;
+            }
+
+            let mut outer = Vec::new();
+            let mut inner = Vec::new();
+            for member in &relation.refs {
+                let OsmId::Way(way) = member.member else { continue };
+                let Some(nodes) = ways.get(&way.0) else { continue };
+                match member.role.as_str() {
+                    "inner" => inner.push(nodes.clone()),
+                    _ => outer.push(nodes.clone()),
+                }
+            }
+            let (outer, outer_open) = close_rings(outer, &positions);
+            let (inner, inner_open) = close_rings(inner, &positions);
+            open_rings += outer_open + inner_open;
+            if outer.is_empty() {
+                continue;
+            }
+
+            let tag = |key: &str| relation.tags.get(key).map(|value| value.to_string());
+            let code = CODE_TAGS.iter().find_map(|key| tag(key));
+            let name = tag("name").or_else(|| code.clone()).unwrap_or_else(|| format!("relation {}", relation.id.0));
+            let zone = Zone { name, id: code, area: polygons(outer, inner) };
+            let admin_level = relation.tags.get("admin_level").and_then(|level| level.parse().ok());
+            districts.push((zone, admin_level));
+        }
+        if open_rings > 0 {
+            tracing::warn!("⚠️ Dropped {} district boundary rings that could not be closed", open_rings);
+        }
+
+        // Relations come out of a hash map; keep the order stable
+        districts.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name));
+        Ok(Self::new(districts))
+    }
+
+    /// Returns the most specific district containing a point.
+    ///
+    /// Among overlapping districts the one with the highest `admin_level`
+    /// wins; districts without a level rank below all others.
+    pub fn district_of(&self, lon: f64, lat: f64) -> Option<&Zone> {
+        self.zones
+            .indices_containing(lon, lat)
+            .max_by_key(|&index| self.admin_levels[index])
+            .map(|index| &self.zones.zones()[index])
+    }
+
+    /// Returns the `admin_level` of a district by name, if known.
+    pub fn admin_level(&self, name: &str) -> Option<u8> {
+        let index = self.zones.zones().iter().position(|zone| zone.name == name)?;
+        self.admin_levels[index]
+    }
+
+    /// Returns all districts.
+    pub fn zones(&self) -> &[Zone] {
+        self.zones.zones()
+    }
+
+    /// Returns the number of districts.
+    pub fn len(&self) -> usize {
+        self.zones.len()
+    }
+
+    /// Returns `true` if there are no districts.
+    pub fn is_empty(&self) -> bool {
+        self.zones.is_empty()
+    }
+}
+
+/// Joins boundary ways sharing end nodes into closed rings.
+///
+/// # Returns
+///
+/// The closed rings and the number of rings that could not be closed.
+fn close_rings(mut ways: Vec<Vec<i64>>, positions: &HashMap<i64, (f64, f64)>) -> (Vec<LineString<f64>>, usize) {
+    let mut rings = Vec::new();
+    let mut open = 0;
+    while let Some(mut ring) = ways.pop() {
+        while let (Some(&first), Some(&last)) = (ring.first(), ring.last()) {
+            if first == last && ring.len() >= 4 {
+                let points: Vec<(f64, f64)> = ring.iter().filter_map(|node| positions.get(node).copied()).collect();
+                if points.len() == ring.len() {
+                    rings.push(LineString::from(points));
+                } else {
+                    open += 1;
+                }
+                break;
+            }
+            let Some(next) = ways.iter().position(|way| way.first() == Some(&last) || way.last() == Some(&last)) else {
+                open += 1;
+                break;
+            };
+            let mut next = ways.swap_remove(next);
+            if next.first() != Some(&last) {
+                next.reverse();
+            }
+            ring.extend(next.into_iter().skip(1));
+        }
+    }
+    (rings, open)
+}
+
+/// Builds polygons from outer rings, putting every inner ring into the
+/// outer ring that contains it.
+fn polygons(outer: Vec<LineString<f64>>, inner: Vec<LineString<f64>>) -> MultiPolygon<f64> {
+    let exteriors: Vec<Polygon<f64>> = outer.into_iter().map(|ring| Polygon::new(ring, Vec::new())).collect();
+    let mut holes: Vec<Vec<LineString<f64>>> = vec![Vec::new(); exteriors.len()];
+    for ring in inner {
+        let Some(&start) = ring.0.first() else { continue };
+        if let Some(index) = exteriors.iter().position(|polygon| polygon.contains(&Point::from(start))) {
+            holes[index].push(ring);
+        }
+    }
+    MultiPolygon::new(
+        exteriors
+            .into_iter()
+            .zip(holes)
+            .map(|(polygon, holes)| Polygon::new(polygon.into_inner().0, holes))
+            .collect(),
+    )
+}
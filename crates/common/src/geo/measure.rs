@@ -0,0 +1,72 @@
+//! Distances, bearings and projected points on the earth's surface.
+//!
+//! Points are `(longitude, latitude)` pairs in degrees, as everywhere else
+//! in the services. Distances use the haversine formula on a sphere of the
+//! earth's mean radius, which is within 0.5% of the ellipsoid and far below
+//! GPS noise at street scale.
+
+use ::geo::{HaversineBearing, HaversineDestination, HaversineDistance, Point};
+use glam::DVec2;
+
+/// Great-circle distance between two points, in meters.
+///
+/// # Examples
+///
+/// ```
+/// use traffic_common::geo::haversine_distance;
+///
+/// // One degree of latitude is about 111 km
+/// let distance = haversine_distance((13.4, 52.0), (13.4, 53.0));
+/// assert!((distance - 111_195.0).abs() < 1.0);
+/// ```
+pub fn haversine_distance(from: (f64, f64), to: (f64, f64)) -> f64 {
+    Point::from(from).haversine_distance(&Point::from(to))
+}
+
+/// Length of a polyline of `(lon, lat)` points, in meters.
+pub fn path_length(points: &[DVec2]) -> f64 {
+    points
+        .windows(2)
+        .map(|pair| haversine_distance((pair[0].x, pair[0].y), (pair[1].x, pair[1].y)))
+        .sum()
+}
+
+/// Initial bearing of the great circle from one point to another, in
+/// degrees clockwise from north (`0.0..360.0`).
+///
+/// Identical points have no bearing; the result is then `0.0`.
+///
+/// # Examples
+///
+/// ```
+/// use traffic_common::geo::bearing;
+///
+/// assert!((bearing((13.4, 52.5), (13.4, 52.6)) - 0.0).abs() < 1e-9);
+/// assert!((bearing((13.4, 52.5), (13.3, 52.5)) - 270.0).abs() < 0.1);
+/// ```
+pub fn bearing(from: (f64, f64), to: (f64, f64)) -> f64 {
+    Point::from(from).haversine_bearing(Point::from(to)).rem_euclid(360.0)
+}
+
+/// Point reached by travelling `distance_m` meters from `from` along the
+/// great circle with the given initial bearing.
+///
+/// # Arguments
+///
+/// * `from` - Starting point
+/// * `bearing` - Initial bearing in degrees clockwise from north
+/// * `distance_m` - Distance to travel in meters
+///
+/// # Examples
+///
+/// ```
+/// use traffic_common::geo::{destination, haversine_distance};
+///
+/// let start = (13.4, 52.5);
+/// let end = destination(start, 90.0, 1000.0);
+/// assert!(end.0 > start.0);
+/// assert!((haversine_distance(start, end) - 1000.0).abs() < 1e-6);
+/// ```
+pub fn destination(from: (f64, f64), bearing: f64, distance_m: f64) -> (f64, f64) {
+    Point::from(from).haversine_destination(bearing, distance_m).x_y()
+}
@@ -0,0 +1,130 @@
+//! Geographic coordinate utilities shared by all services.
+//!
+//! Invalid coordinates (NaN, out of range, or the `0,0` "null island" that
+//! broken GPS units and default-initialized messages report) must never
+//! reach Redis `GEOADD` or TimescaleDB, so both the map loader and the ingest
+//! service pass every coordinate through [`normalize_coordinate`].
+//!
+//! Distances, bearings and projected points on the sphere live in
+//! [`haversine_distance`], [`bearing`] and [`destination`]; rectangular
+//! areas are [`BoundingBox`]es.
+//!
+//! Named polygon zones with fast point-in-polygon lookups are grouped in a
+//! [`ZoneSet`], which can also be loaded from GeoJSON district boundaries.
+//! [`Districts`] are loaded from OSM administrative boundary relations.
+
+mod bbox;
+mod boundaries;
+mod districts;
+mod measure;
+mod zones;
+
+pub use bbox::BoundingBox;
+pub use districts::Districts;
+pub use measure::{bearing, destination, haversine_distance, path_length};
+pub use zones::{Zone, ZoneSet};
+
+use serde::Serialize;
+use thiserror::Error;
+
+/// Largest latitude, north or south, that Redis `GEOADD` accepts: the
+/// limit of the Web Mercator projection its geohashes are based on.
+pub const MAX_GEO_LATITUDE: f64 = 85.05112878;
+
+/// Reason a coordinate was rejected.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoordinateError {
+    #[error("coordinate is NaN or infinite")]
+    NotFinite,
+
+    #[error("latitude is outside [-85.05112878, 85.05112878]")]
+    LatitudeOutOfRange,
+
+    #[error("coordinate is 0,0 (null island)")]
+    NullIsland,
+}
+
+/// Validates a coordinate and normalizes its longitude into `[-180, 180]`.
+///
+/// Longitudes outside the range are wrapped around the antimeridian, since
+/// they still describe a unique place. Latitudes beyond
+/// [`MAX_GEO_LATITUDE`] are rejected: those outside `[-90, 90]` cannot be
+/// repaired, and those near the poles cannot be stored in the Redis geo
+/// index, so letting them through would fail halfway through ingestion.
+///
+/// # Arguments
+///
+/// * `lon` - Longitude in degrees
+/// * `lat` - Latitude in degrees
+///
+/// # Returns
+///
+/// The normalized `(lon, lat)` pair.
+///
+/// # Errors
+///
+/// Returns the reason the coordinate is unusable.
+///
+/// # Examples
+///
+/// ```
+/// use traffic_common::geo::{normalize_coordinate, CoordinateError};
+///
+/// assert_eq!(normalize_coordinate(13.4, 52.5), Ok((13.4, 52.5)));
+/// assert_eq!(normalize_coordinate(190.0, 10.0), Ok((-170.0, 10.0)));
+/// assert_eq!(normalize_coordinate(0.0, 0.0), Err(CoordinateError::NullIsland));
+/// assert_eq!(normalize_coordinate(13.4, 87.0), Err(CoordinateError::LatitudeOutOfRange));
+/// assert_eq!(normalize_coordinate(f64::NAN, 52.5), Err(CoordinateError::NotFinite));
+/// ```
+pub fn normalize_coordinate(lon: f64, lat: f64) -> Result<(f64, f64), CoordinateError> {
+    if !lon.is_finite() || !lat.is_finite() {
+        return Err(CoordinateError::NotFinite);
+    }
+    if !(-MAX_GEO_LATITUDE..=MAX_GEO_LATITUDE).contains(&lat) {
+        return Err(CoordinateError::LatitudeOutOfRange);
+    }
+    if lon == 0.0 && lat == 0.0 {
+        return Err(CoordinateError::NullIsland);
+    }
+
+    let lon = if (-180.0..=180.0).contains(&lon) {
+        lon
+    } else {
+        (lon + 180.0).rem_euclid(360.0) - 180.0
+    };
+    Ok((lon, lat))
+}
+
+/// Running counts of coordinate validation outcomes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CoordinateCounters {
+    /// Coordinates accepted after wrapping their longitude
+    pub normalized: u64,
+    /// Coordinates rejected for being NaN or infinite
+    pub not_finite: u64,
+    /// Coordinates rejected for a latitude beyond [`MAX_GEO_LATITUDE`]
+    pub out_of_range: u64,
+    /// Coordinates rejected for being exactly `0,0`
+    pub null_island: u64,
+}
+
+impl CoordinateCounters {
+    /// Validates a coordinate like [`normalize_coordinate`] and counts the
+    /// outcome.
+    pub fn check(&mut self, lon: f64, lat: f64) -> Result<(f64, f64), CoordinateError> {
+        let result = normalize_coordinate(lon, lat);
+        match result {
+            Ok((normalized_lon, _)) if normalized_lon != lon => self.normalized += 1,
+            Ok(_) => {}
+            Err(CoordinateError::NotFinite) => self.not_finite += 1,
+            Err(CoordinateError::LatitudeOutOfRange) => self.out_of_range += 1,
+            Err(CoordinateError::NullIsland) => self.null_island += 1,
+        }
+        result
+    }
+
+    /// Total number of rejected coordinates.
+    pub fn rejected(&self) -> u64 {
+        self.not_finite + self.out_of_range + self.null_island
+    }
+}
@@ -0,0 +1,191 @@
+//! Named polygon zones and point-in-polygon lookups.
+//!
+//! Geofences, toll zones and districts are all "which named areas contain
+//! this point" questions. A [`ZoneSet`] answers them with an R-tree over the
+//! zones' bounding boxes, so only the few zones whose box contains the point
+//! get the exact (and much more expensive) polygon test.
+
+use ::geo::{BoundingRect, Contains, LineString, MultiPolygon, Point, Polygon};
+use rstar::primitives::{GeomWithData, Rectangle};
+use rstar::RTree;
+use serde::{Deserialize, Serialize};
+
+/// Bounding box of a zone, tagged with the zone's index in the set.
+type ZoneBox = GeomWithData<Rectangle<[f64; 2]>, usize>;
+
+/// A named area made of one or more polygons (with optional holes).
+///
+/// Serialized as `{"name": ..., "id": ..., "coordinates": [...]}`, where
+/// `id` is optional and `coordinates` uses the GeoJSON `MultiPolygon`
+/// layout: polygons, then rings (exterior first, holes after), then
+/// `[lon, lat]` points.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "ZoneDefinition", into = "ZoneDefinition")]
+pub struct Zone {
+    /// Zone name, e.g. a district or toll zone identifier
+    pub name: String,
+    /// Official identifier, e.g. an administrative district code
+    pub id: Option<String>,
+    /// Area covered by the zone, in longitude/latitude degrees
+    pub area: MultiPolygon<f64>,
+}
+
+/// Serialized form of a [`Zone`].
+#[derive(Serialize, Deserialize)]
+struct ZoneDefinition {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    coordinates: Vec<Vec<Vec<[f64; 2]>>>,
+}
+
+impl From<ZoneDefinition> for Zone {
+    fn from(definition: ZoneDefinition) -> Self {
+        let polygons = definition.coordinates.into_iter().filter_map(|rings| {
+            let mut rings = rings.into_iter().map(LineString::from);
+            let exterior = rings.next()?;
+            Some(Polygon::new(exterior, rings.collect()))
+        });
+        Self {
+            name: definition.name,
+            id: definition.id,
+            area: MultiPolygon::new(polygons.collect()),
+        }
+    }
+}
+
+impl From<Zone> for ZoneDefinition {
+    fn from(zone: Zone) -> Self {
+        let ring = |line: &LineString<f64>| line.coords().map(|c| [c.x, c.y]).collect::<Vec<_>>();
+        Self {
+            name: zone.name,
+            id: zone.id,
+            coordinates: zone.area
+                .iter()
+                .map(|polygon| {
+                    std::iter::once(ring(polygon.exterior()))
+                        .chain(polygon.interiors().iter().map(ring))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Zone {
+    /// Creates a zone from a name and its area.
+    pub fn new(name: impl Into<String>, area: MultiPolygon<f64>) -> Self {
+        Self { name: name.into(), id: None, area }
+    }
+
+    /// Sets the zone's official identifier.
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Returns `true` if the point lies strictly inside the zone.
+    pub fn contains(&self, lon: f64, lat: f64) -> bool {
+        self.area.contains(&Point::new(lon, lat))
+    }
+}
+
+/// A collection of zones indexed for point lookups.
+///
+/// Deserializes from a JSON array of zones; district boundaries published
+/// as GeoJSON are read with [`ZoneSet::from_geojson`].
+///
+/// # Examples
+///
+/// ```
+/// use geo::polygon;
+/// use traffic_common::geo::{Zone, ZoneSet};
+///
+/// let mitte = Zone::new("Mitte", polygon![
+///     (x: 13.36, y: 52.50), (x: 13.43, y: 52.50), (x: 13.43, y: 52.54), (x: 13.36, y: 52.54),
+/// ].into());
+/// let zones = ZoneSet::new(vec![mitte]);
+///
+/// assert_eq!(zones.first_containing(13.40, 52.52).map(|z| z.name.as_str()), Some("Mitte"));
+/// assert!(zones.first_containing(13.50, 52.52).is_none());
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(from = "Vec<Zone>")]
+pub struct ZoneSet {
+    zones: Vec<Zone>,
+    index: RTree<ZoneBox>,
+}
+
+impl ZoneSet {
+    /// Builds the set and its bounding-box index.
+    ///
+    /// Zones without any polygon are kept but never match.
+    pub fn new(zones: Vec<Zone>) -> Self {
+        let boxes = zones
+            .iter()
+            .enumerate()
+            .filter_map(|(index, zone)| {
+                let rect = zone.area.bounding_rect()?;
+                let (min, max) = (rect.min(), rect.max());
+                Some(GeomWithData::new(Rectangle::from_corners([min.x, min.y], [max.x, max.y]), index))
+            })
+            .collect();
+
+        Self {
+            zones,
+            index: RTree::bulk_load(boxes),
+        }
+    }
+
+    /// Returns all zones containing a point (zones may overlap).
+    pub fn containing(&self, lon: f64, lat: f64) -> impl Iterator<Item = &Zone> + '_ {
+        self.indices_containing(lon, lat).map(|index| &self.zones[index])
+    }
+
+    /// Returns the positions in [`ZoneSet::zones`] of all zones containing
+    /// a point.
+    pub fn indices_containing(&self, lon: f64, lat: f64) -> impl Iterator<Item = usize> + '_ {
+        self.index
+            .locate_all_at_point(&[lon, lat])
+            .map(|zone_box| zone_box.data)
+            .filter(move |&index| self.zones[index].contains(lon, lat))
+    }
+
+    /// Returns one zone containing a point, if any.
+    ///
+    /// Use this when zones are known not to overlap, e.g. districts.
+    pub fn first_containing(&self, lon: f64, lat: f64) -> Option<&Zone> {
+        self.containing(lon, lat).next()
+    }
+
+    /// Looks a zone up by name.
+    pub fn get(&self, name: &str) -> Option<&Zone> {
+        self.zones.iter().find(|zone| zone.name == name)
+    }
+
+    /// Looks a zone up by its official identifier.
+    pub fn get_by_id(&self, id: &str) -> Option<&Zone> {
+        self.zones.iter().find(|zone| zone.id.as_deref() == Some(id))
+    }
+
+    /// Returns all zones in the order they were added.
+    pub fn zones(&self) -> &[Zone] {
+        &self.zones
+    }
+
+    /// Returns the number of zones.
+    pub fn len(&self) -> usize {
+        self.zones.len()
+    }
+
+    /// Returns `true` if the set has no zones.
+    pub fn is_empty(&self) -> bool {
+        self.zones.is_empty()
+    }
+}
+
+impl From<Vec<Zone>> for ZoneSet {
+    fn from(zones: Vec<Zone>) -> Self {
+        Self::new(zones)
+    }
+}
@@ -14,6 +14,9 @@ pub use proto::*;
 pub mod config;
 pub use config::Config;
 
+// Command-line options shared by the service binaries
+pub mod cli;
+
 // Error handling types
 pub mod error;
 pub use error::{Result, TrafficError};
@@ -21,7 +24,31 @@ pub use error::{Result, TrafficError};
 // Telemetry and observability
 pub mod telemetry;
 
+// Kafka and Redis clients with TLS and authentication
+pub mod brokers;
+
+// Waiting for Kafka, Redis and Postgres at startup
+pub mod startup;
+
 // Map and geographic data operations
 pub mod map;
 
-pub use telemetry::init_tracing;
\ No newline at end of file
+// Coordinate validation shared by the map loader and ingest
+pub mod geo;
+
+// Units of telemetry fields and speed validation
+pub mod units;
+
+// Simulation control commands shared by the API and simulator
+pub mod control;
+
+// Traffic signal phase plans and intersection metrics
+pub mod signals;
+
+// Fleet tasks and lifecycle events
+pub mod fleet;
+
+// Virtual checkpoints and crossing events
+pub mod checkpoints;
+
+pub use telemetry::{init_tracing, init_tracing_with_format, resident_memory_bytes};
\ No newline at end of file
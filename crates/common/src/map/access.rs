@@ -0,0 +1,116 @@
+//! Legal access for cars from OSM `access`, `vehicle`, `motor_vehicle`,
+//! `motorcar` and `service` tags.
+//!
+//! A `highway` class alone says a way is built for cars, not that the
+//! public may drive it. Ways closed to motor traffic are left out of the
+//! graph; driveways, parking aisles and private roads are kept (vehicles
+//! do start and end trips there) but marked, so spawning, random driving
+//! and route planning stop sending through traffic along them.
+
+use serde::{Deserialize, Serialize};
+
+/// Factor by which route costs of [`Access::Destination`] segments are
+/// raised, so routes only use them to reach a place on them.
+const DESTINATION_COST_FACTOR: f64 = 10.0;
+
+/// Factor by which route costs of [`Access::Private`] segments are raised.
+const PRIVATE_COST_FACTOR: f64 = 100.0;
+
+/// Who may drive a road segment by car.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Access {
+    /// Open to all traffic
+    #[default]
+    Public,
+    /// Only to reach a destination on the road (`destination`, driveways,
+    /// parking aisles)
+    Destination,
+    /// Only with the owner's permission (`private`, `customers`,
+    /// `delivery`, ...)
+    Private,
+}
+
+impl Access {
+    /// Returns `true` if traffic may pass along the segment on the way
+    /// elsewhere.
+    pub fn allows_through_traffic(self) -> bool {
+        self == Self::Public
+    }
+
+    /// Returns the factor route costs of the segment are multiplied by.
+    ///
+    /// Restricted segments stay routable, but only win when the route
+    /// starts or ends on them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use traffic_common::map::Access;
+    ///
+    /// assert_eq!(Access::Public.cost_factor(), 1.0);
+    /// assert!(Access::Private.cost_factor() > Access::Destination.cost_factor());
+    /// ```
+    pub fn cost_factor(self) -> f64 {
+        match self {
+            Self::Public => 1.0,
+            Self::Destination => DESTINATION_COST_FACTOR,
+            Self::Private => PRIVATE_COST_FACTOR,
+        }
+    }
+}
+
+/// Access tags of a way, most general first.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct AccessTags<'a> {
+    pub(crate) access: Option<&'a str>,
+    pub(crate) vehicle: Option<&'a str>,
+    pub(crate) motor_vehicle: Option<&'a str>,
+    pub(crate) motorcar: Option<&'a str>,
+    pub(crate) service: Option<&'a str>,
+}
+
+impl AccessTags<'_> {
+    /// Works out who may drive the way by car.
+    ///
+    /// The most specific tag with a known value wins (`motorcar` over
+    /// `motor_vehicle` over `vehicle` over `access`), as in OSM's access
+    /// hierarchy. Driveways and parking aisles default to destination
+    /// traffic.
+    ///
+    /// # Returns
+    ///
+    /// The access of the way, or `None` if cars may not use it at all.
+    pub(crate) fn car_access(&self) -> Option<Access> {
+        let tagged = [self.motorcar, self.motor_vehicle, self.vehicle, self.access]
+            .into_iter()
+            .flatten()
+            .find_map(parse_access_value);
+        let access = match tagged {
+            Some(access) => access?,
+            None if matches!(self.service, Some("emergency_access")) => return None,
+            None if matches!(self.service, Some("driveway" | "parking_aisle")) => Access::Destination,
+            None => Access::Public,
+        };
+        Some(access)
+    }
+}
+
+/// Parses an access tag value.
+///
+/// # Returns
+///
+/// `Some(None)` for values that forbid access, `Some(Some(access))` for
+/// values that grant it and `None` for unknown values, which defer to a
+/// more general tag.
+fn parse_access_value(value: &str) -> Option<Option<Access>> {
+    // Conditional and multi-valued tags: the first value is the default
+    let value = value.split(';').next().unwrap_or_default().trim();
+    match value {
+        "yes" | "permissive" | "designated" | "official" => Some(Some(Access::Public)),
+        "destination" | "discouraged" => Some(Some(Access::Destination)),
+        "private" | "customers" | "delivery" | "permit" | "agricultural" | "forestry" => Some(Some(Access::Private)),
+        "no" | "use_sidepath" => Some(None),
+        _ => None,
+    }
+}
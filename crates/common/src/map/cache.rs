@@ -0,0 +1,212 @@
+//! Binary cache of parsed road graphs.
+//!
+//! Parsing a city-sized PBF file takes a long time, and both the API and the
+//! simulator do it on startup. The parsed graph is therefore cached next to
+//! the PBF file in bincode format, keyed on the source file's size and
+//! modification time so an updated map invalidates the cache. A graph
+//! merged from several files is cached next to the first of them, keyed on
+//! all of them.
+
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use anyhow::{bail, Context, Result};
+use super::progress::{LoadStage, ProgressFn, Reporter};
+use super::{BoundingBox, MapSource, RoadGraph};
+
+/// Version of the cache layout; bump whenever `RoadGraph`'s serialized
+/// fields change so stale caches are rebuilt instead of misread.
+const CACHE_VERSION: u32 = 18;
+
+/// File extension appended to the PBF path to form the cache path.
+const CACHE_EXTENSION: &str = "cache";
+
+/// File extension appended to the first map path to form the cache path of
+/// a merged graph.
+const MERGED_CACHE_EXTENSION: &str = "merged.cache";
+
+impl RoadGraph {
+    /// Writes the graph to a binary cache file.
+    ///
+    /// Derived indexes (adjacency list, spatial index) are not stored; they
+    /// are rebuilt by [`RoadGraph::load_cache`].
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Destination of the cache file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save_cache(&self, path: &str) -> Result<()> {
+        self.write_cache(path, "")
+    }
+
+    /// Reads a graph from a binary cache file written by
+    /// [`RoadGraph::save_cache`] and rebuilds its derived indexes.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the cache file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, was written by an
+    /// incompatible version, or is corrupted.
+    pub fn load_cache(path: &str) -> Result<Self> {
+        let (_, graph) = read_cache(path, &Reporter::silent())?;
+        Ok(graph)
+    }
+
+    /// Loads a graph from its cache if it is up to date, otherwise parses
+    /// the map file (any [`MapSource`] format) and refreshes the cache.
+    ///
+    /// The cache lives at `<pbf_path>.cache` and is considered up to date if
+    /// it was built from a PBF file with the same size and modification
+    /// time and with the same bounding box. Failing to write the cache is
+    /// logged but not fatal.
+    ///
+    /// # Arguments
+    ///
+    /// * `pbf_path` - Path to the map file; the format is picked by extension
+    /// * `bbox` - Optional bounding box restricting the loaded area
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache is unusable and the PBF file cannot be
+    /// loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use traffic_common::map::RoadGraph;
+    ///
+    /// // First start parses the PBF; later starts read the cache
+    /// let graph = RoadGraph::load_or_build("map.osm.pbf", None)
+    ///     .expect("Failed to load map");
+    /// ```
+    pub fn load_or_build(pbf_path: &str, bbox: Option<BoundingBox>) -> Result<Self> {
+        Self::load_or_build_reporting(pbf_path, bbox, &Reporter::silent())
+    }
+
+    /// [`RoadGraph::load_or_build`] reporting its progress.
+    pub(crate) fn load_or_build_reporting(pbf_path: &str, bbox: Option<BoundingBox>, reporter: &Reporter) -> Result<Self> {
+        let cache_path = format!("{}.{}", pbf_path, CACHE_EXTENSION);
+        let mut key = source_key(pbf_path)?;
+        if let Some(bbox) = &bbox {
+            key = format!("{}@{}", key, bbox);
+        }
+
+        Self::load_or_build_cached(&cache_path, &key, reporter, || {
+            MapSource::from_path(pbf_path).load_reporting(bbox, reporter)
+        })
+    }
+
+    /// Loads a graph merged from several map files from its cache if none
+    /// of the files changed, otherwise merges them (see
+    /// [`RoadGraph::load_or_build_many`]) and refreshes the cache.
+    pub(crate) fn load_or_build_merged(paths: &[&str], bbox: Option<BoundingBox>, progress: &ProgressFn) -> Result<Self> {
+        let cache_path = format!("{}.{}", paths[0], MERGED_CACHE_EXTENSION);
+        let mut key = paths
+            .iter()
+            .map(|path| Ok(format!("{}={}", path, source_key(path)?)))
+            .collect::<Result<Vec<_>>>()?
+            .join(";");
+        if let Some(bbox) = &bbox {
+            key = format!("{}@{}", key, bbox);
+        }
+
+        let reporter = Reporter::new(progress, 1, paths.len());
+        Self::load_or_build_cached(&cache_path, &key, &reporter, || Self::build_merged(paths, bbox, progress))
+    }
+
+    /// Reads the cache at `cache_path` if it was built for `key`, otherwise
+    /// builds the graph and writes the cache.
+    fn load_or_build_cached(
+        cache_path: &str,
+        key: &str,
+        reporter: &Reporter,
+        build: impl FnOnce() -> Result<Self>,
+    ) -> Result<Self> {
+        match read_cache(cache_path, reporter) {
+            Ok((cached_key, mut graph)) if cached_key == key => {
+                if let Some(meta) = &mut graph.meta {
+                    meta.touch();
+                }
+                tracing::info!(
+                    "✅ Map loaded from cache {}: {} nodes, {} road segments.",
+                    cache_path,
+                    graph.nodes.len(),
+                    graph.edges.len()
+                );
+                return Ok(graph);
+            }
+            Ok(_) => tracing::info!("🔄 Map cache {} is stale, rebuilding", cache_path),
+            Err(e) => tracing::info!("🔄 No usable map cache at {} ({}), building", cache_path, e),
+        }
+
+        let graph = build()?;
+        if let Err(e) = graph.write_cache(cache_path, key) {
+            tracing::warn!("⚠️ Failed to write map cache {}: {}", cache_path, e);
+        }
+        Ok(graph)
+    }
+
+    /// Writes the graph with a header identifying the cache version and the
+    /// source it was built from.
+    ///
+    /// The cache is written to a temporary file next to `path` and renamed
+    /// into place once complete, so a crash or a concurrent writer never
+    /// leaves a truncated cache behind for the next start to load.
+    fn write_cache(&self, path: &str, key: &str) -> Result<()> {
+        let temp_path = format!("{}.{}.tmp", path, std::process::id());
+        let written = self.write_cache_file(&temp_path, key).and_then(|()| {
+            fs::rename(&temp_path, path).with_context(|| format!("Could not move cache file into place at {}", path))
+        });
+        if written.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        written
+    }
+
+    /// Writes the cache file at `path` and flushes it to disk.
+    fn write_cache_file(&self, path: &str, key: &str) -> Result<()> {
+        let file = File::create(path).with_context(|| format!("Could not create cache file {}", path))?;
+        let mut writer = BufWriter::new(file);
+        bincode::serialize_into(&mut writer, &(CACHE_VERSION, key))?;
+        bincode::serialize_into(&mut writer, self)?;
+        writer.into_inner()?.sync_all()?;
+        Ok(())
+    }
+}
+
+/// Reads a cache file, returning its source key and the graph with
+/// rebuilt indexes.
+fn read_cache(path: &str, reporter: &Reporter) -> Result<(String, RoadGraph)> {
+    let file = reporter
+        .open(path, LoadStage::ReadingCache)
+        .with_context(|| format!("Could not open cache file {}", path))?;
+    let mut reader = BufReader::new(file);
+
+    let (version, key): (u32, String) = bincode::deserialize_from(&mut reader)?;
+    if version != CACHE_VERSION {
+        bail!("cache version {} does not match {}", version, CACHE_VERSION);
+    }
+
+    let mut graph: RoadGraph = bincode::deserialize_from(&mut reader)?;
+    graph.rebuild_indexes();
+    Ok((key, graph))
+}
+
+/// Identifies a source file by its size and modification time.
+fn source_key(path: &str) -> Result<String> {
+    let metadata = fs::metadata(Path::new(path)).context("Could not open map file")?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    Ok(format!("{}:{}", metadata.len(), modified))
+}
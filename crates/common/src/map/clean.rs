@@ -0,0 +1,91 @@
+//! Cleaning of degenerate OSM data.
+//!
+//! OSM ways occasionally contain distinct nodes at identical coordinates
+//! (e.g. where two ways were joined carelessly) or repeat a node. Both
+//! produce zero-length edges, on which vehicle progress becomes NaN and
+//! vehicles get stuck. The cleaning pass collapses such nodes and drops the
+//! resulting degenerate edges. Nodes used on different levels (a bridge
+//! above a street) stay apart even at identical coordinates.
+
+use std::collections::HashMap;
+use serde::Serialize;
+use super::layers::node_levels;
+use super::RoadGraph;
+
+/// Counts of what [`RoadGraph::clean`] removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CleanReport {
+    /// Nodes merged into another node at the same coordinates
+    pub duplicate_nodes: usize,
+    /// Edges dropped because they had zero length
+    pub zero_length_edges: usize,
+}
+
+impl RoadGraph {
+    /// Merges duplicate nodes and drops zero-length edges.
+    ///
+    /// Nodes at exactly the same coordinates and on the same levels are
+    /// collapsed into the one with the smallest ID (which inherits any
+    /// traffic signal), edges are
+    /// re-pointed to it, and edges that end up with zero length are removed.
+    /// The derived indexes are rebuilt afterwards.
+    ///
+    /// # Returns
+    ///
+    /// How many nodes and edges were removed.
+    pub fn clean(&mut self) -> CleanReport {
+        let mut report = CleanReport::default();
+
+        let levels = node_levels(self.edges.iter().map(|road| (road.level(), [road.start, road.end])));
+        let replacement = self.duplicate_nodes(&levels);
+        for (duplicate, id) in &replacement {
+            self.nodes.remove(duplicate);
+            if self.signals.remove(duplicate) {
+                self.signals.insert(*id);
+            }
+        }
+        report.duplicate_nodes = replacement.len();
+
+        let edge_count = self.edges.len();
+        for road in &mut self.edges {
+            road.start = replacement.get(&road.start).copied().unwrap_or(road.start);
+            road.end = replacement.get(&road.end).copied().unwrap_or(road.end);
+            road.geometry.dedup();
+        }
+        self.edges.retain(|road| road.start != road.end && road.length > 0.0);
+        report.zero_length_edges = edge_count - self.edges.len();
+
+        self.rebuild_indexes();
+        report
+    }
+
+    /// Maps every node that shares its coordinates and levels with a node of
+    /// smaller ID to the smallest such ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `levels` - Levels each node is used on (see
+    ///   [`node_levels`](super::layers::node_levels)); unused nodes only
+    ///   merge with each other
+    pub(crate) fn duplicate_nodes(&self, levels: &HashMap<i64, Vec<i8>>) -> HashMap<i64, i64> {
+        let key = |id: i64, x: f64, y: f64| (x.to_bits(), y.to_bits(), levels.get(&id).cloned().unwrap_or_default());
+
+        // Smallest node ID at every coordinate and set of levels
+        let mut canonical: HashMap<(u64, u64, Vec<i8>), i64> = HashMap::new();
+        for node in self.nodes.values() {
+            canonical
+                .entry(key(node.id, node.pos.x, node.pos.y))
+                .and_modify(|id| *id = (*id).min(node.id))
+                .or_insert(node.id);
+        }
+
+        let mut replacement: HashMap<i64, i64> = HashMap::new();
+        for node in self.nodes.values() {
+            let id = canonical[&key(node.id, node.pos.x, node.pos.y)];
+            if id != node.id {
+                replacement.insert(node.id, id);
+            }
+        }
+        replacement
+    }
+}
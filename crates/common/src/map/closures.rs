@@ -0,0 +1,149 @@
+//! Runtime road closures.
+//!
+//! Road works and incidents close roads for a while. Rebuilding the graph
+//! for that would invalidate every edge index held elsewhere, so closures
+//! are kept in a separate [`ClosureSet`] overlay that routing
+//! ([`RoadGraph::shortest_path_open`]) and next-edge selection
+//! ([`RoadGraph::random_open_out_edge`]) consult, leaving the graph itself
+//! immutable.
+
+use std::collections::HashSet;
+use bevy_ecs::prelude::Resource;
+use rand::Rng;
+use super::RoadGraph;
+
+/// Closed road segments, by edge index.
+///
+/// # Examples
+///
+/// ```no_run
+/// use traffic_common::map::{ClosureSet, RoadGraph};
+///
+/// let graph = RoadGraph::load_from_pbf("map.osm.pbf").unwrap();
+/// let mut closures = ClosureSet::default();
+/// closures.close_way(&graph, 4_045_215);
+/// let detour = graph.shortest_path_open(1, 2, &closures);
+/// ```
+#[derive(Debug, Clone, Default, Resource)]
+pub struct ClosureSet {
+    closed: HashSet<usize>,
+}
+
+impl ClosureSet {
+    /// Closes a road segment.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the segment was open before.
+    pub fn close(&mut self, edge: usize) -> bool {
+        self.closed.insert(edge)
+    }
+
+    /// Reopens a road segment.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the segment was closed before.
+    pub fn reopen(&mut self, edge: usize) -> bool {
+        self.closed.remove(&edge)
+    }
+
+    /// Closes every segment of an OSM way, in both directions.
+    ///
+    /// # Returns
+    ///
+    /// The number of segments of the way, or 0 if it is not in the graph.
+    pub fn close_way(&mut self, graph: &RoadGraph, way_id: i64) -> usize {
+        let edges = graph.edges_for_way(way_id);
+        self.closed.extend(edges);
+        edges.len()
+    }
+
+    /// Reopens every segment of an OSM way.
+    ///
+    /// # Returns
+    ///
+    /// The number of segments of the way, or 0 if it is not in the graph.
+    pub fn reopen_way(&mut self, graph: &RoadGraph, way_id: i64) -> usize {
+        let edges = graph.edges_for_way(way_id);
+        for edge in edges {
+            self.closed.remove(edge);
+        }
+        edges.len()
+    }
+
+    /// Returns `true` if a road segment is closed.
+    pub fn is_closed(&self, edge: usize) -> bool {
+        self.closed.contains(&edge)
+    }
+
+    /// Returns the indices of all closed segments, in no particular order.
+    pub fn edges(&self) -> impl Iterator<Item = usize> + '_ {
+        self.closed.iter().copied()
+    }
+
+    /// Returns the number of closed segments.
+    pub fn len(&self) -> usize {
+        self.closed.len()
+    }
+
+    /// Returns `true` if no segment is closed.
+    pub fn is_empty(&self) -> bool {
+        self.closed.is_empty()
+    }
+}
+
+impl RoadGraph {
+    /// Finds the shortest path between two nodes over open roads only.
+    ///
+    /// # Returns
+    ///
+    /// The indices of the edges to traverse in order, or `None` if `to`
+    /// cannot be reached without using a closed segment.
+    pub fn shortest_path_open(&self, from: i64, to: i64, closures: &ClosureSet) -> Option<Vec<usize>> {
+        self.shortest_path_by(from, to, |edge_idx| {
+            if closures.is_closed(edge_idx) {
+                f64::INFINITY
+            } else {
+                self.edges[edge_idx].length
+            }
+        })
+    }
+
+    /// Picks a random open outgoing edge from the given node.
+    ///
+    /// Without closures at the node this consumes the random number
+    /// generator exactly like [`RoadGraph::random_out_edge`].
+    ///
+    /// # Returns
+    ///
+    /// The index of a uniformly selected open edge, or `None` if every
+    /// way on is closed or the node is a dead end.
+    pub fn random_open_out_edge<R: Rng + ?Sized>(&self, node: i64, closures: &ClosureSet, rng: &mut R) -> Option<usize> {
+        let next_edges = self.out_edges.get(&node)?;
+        let through = |edge: usize| self.edges[edge].access.allows_through_traffic();
+        pick_open(next_edges.iter().copied(), closures, through, rng)
+    }
+}
+
+/// Picks a uniformly random edge among the open ones.
+///
+/// Edges open to through traffic are preferred; driveways, parking aisles
+/// and private roads are only taken when there is no other way on.
+pub(crate) fn pick_open<R: Rng + ?Sized>(
+    edges: impl Iterator<Item = usize> + Clone,
+    closures: &ClosureSet,
+    through: impl Fn(usize) -> bool,
+    rng: &mut R,
+) -> Option<usize> {
+    let open = edges.filter(|edge| !closures.is_closed(*edge));
+    let public = open.clone().filter(|&edge| through(edge)).count();
+    if public > 0 {
+        return open.filter(|&edge| through(edge)).nth(rng.gen_range(0..public));
+    }
+    let count = open.clone().count();
+    if count == 0 {
+        return None;
+    }
+    open.clone().nth(rng.gen_range(0..count))
+}
@@ -0,0 +1,265 @@
+//! Compact, read-only form of the road graph.
+//!
+//! [`RoadGraph`] keeps adjacency in hash maps of small vectors and one
+//! geometry vector per edge, which costs an allocation (plus hashing and
+//! pointer chasing) per node and edge. For the hot simulation path the
+//! topology never changes, so [`CompactRoadGraph`] stores it in flat
+//! arrays instead:
+//!
+//! - nodes are numbered `0..n` in ascending OSM ID order
+//! - adjacency is in compressed sparse row (CSR) form: the outgoing edges
+//!   of node `i` are `out_edges[out_offsets[i]..out_offsets[i + 1]]`
+//! - all polylines share one coordinate buffer, indexed the same way
+//! - highway classes are interned and referenced by a small index
+//!
+//! Edge indices are the same as in the source graph, so positions and
+//! routes computed on either graph are interchangeable.
+
+use bevy_ecs::prelude::Resource;
+use glam::DVec2;
+use rand::Rng;
+use std::collections::HashMap;
+use std::mem::size_of;
+use super::closures::pick_open;
+use super::{Access, ClosureSet, RoadGraph};
+
+/// A road segment of a [`CompactRoadGraph`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompactEdge {
+    /// OpenStreetMap way ID
+    pub way_id: i64,
+    /// Index of the starting node
+    pub start: u32,
+    /// Index of the ending node
+    pub end: u32,
+    /// Physical length in meters
+    pub length: f64,
+    /// Legal speed limit in m/s, if tagged
+    pub speed_limit_mps: Option<f64>,
+    /// Index of the highway class in the interned class table
+    pub highway_type: u16,
+    /// Who may drive the segment
+    pub access: Access,
+    /// Number of lanes in the direction of travel (at least one)
+    pub lanes: u8,
+}
+
+/// Road graph in flat arrays, built from a [`RoadGraph`].
+#[derive(Debug, Default, Resource)]
+pub struct CompactRoadGraph {
+    /// OSM ID of each node, ascending
+    node_ids: Vec<i64>,
+    /// Position (longitude, latitude) of each node
+    node_positions: Vec<DVec2>,
+    /// Start of each node's outgoing edges in `out_edges`; one extra entry at the end
+    out_offsets: Vec<u32>,
+    /// Outgoing edge indices, grouped by node
+    out_edges: Vec<u32>,
+    /// Start of each node's incoming edges in `in_edges`; one extra entry at the end
+    in_offsets: Vec<u32>,
+    /// Incoming edge indices, grouped by node
+    in_edges: Vec<u32>,
+    /// Road segments, in the order of the source graph
+    edges: Vec<CompactEdge>,
+    /// Start of each edge's polyline in `geometry`; one extra entry at the end
+    geometry_offsets: Vec<u32>,
+    /// Polylines of all edges, back to back
+    geometry: Vec<DVec2>,
+    /// Interned highway classes
+    highway_types: Vec<String>,
+}
+
+impl From<&RoadGraph> for CompactRoadGraph {
+    fn from(graph: &RoadGraph) -> Self {
+        // Nodes referenced by edges but missing from the node table keep
+        // the position of the edge's end point
+        let mut positions: HashMap<i64, DVec2> = graph.nodes.values().map(|node| (node.id, node.pos)).collect();
+        for road in &graph.edges {
+            if let (Some(&first), Some(&last)) = (road.geometry.first(), road.geometry.last()) {
+                positions.entry(road.start).or_insert(first);
+                positions.entry(road.end).or_insert(last);
+            }
+        }
+        let mut node_ids: Vec<i64> = positions.keys().copied().collect();
+        node_ids.sort_unstable();
+        let node_positions = node_ids.iter().map(|id| positions[id]).collect();
+        let index_of = |id: i64| node_ids.binary_search(&id).map(|i| i as u32).ok();
+
+        let mut highway_types: Vec<String> = Vec::new();
+        let mut edges = Vec::with_capacity(graph.edges.len());
+        let mut geometry_offsets = Vec::with_capacity(graph.edges.len() + 1);
+        let mut geometry = Vec::with_capacity(graph.edges.iter().map(|road| road.geometry.len()).sum());
+        geometry_offsets.push(0);
+        for road in &graph.edges {
+            let highway_type = match highway_types.iter().position(|class| *class == road.highway_type) {
+                Some(index) => index,
+                None => {
+                    highway_types.push(road.highway_type.clone());
+                    highway_types.len() - 1
+                }
+            };
+            edges.push(CompactEdge {
+                way_id: road.id,
+                start: index_of(road.start).unwrap_or_default(),
+                end: index_of(road.end).unwrap_or_default(),
+                length: road.length,
+                speed_limit_mps: road.speed_limit_mps,
+                highway_type: highway_type as u16,
+                access: road.access,
+                lanes: road.lane_count(),
+            });
+            geometry.extend_from_slice(&road.geometry);
+            geometry_offsets.push(geometry.len() as u32);
+        }
+
+        let (out_offsets, out_edges) = csr(node_ids.len(), edges.iter().map(|edge| edge.start));
+        let (in_offsets, in_edges) = csr(node_ids.len(), edges.iter().map(|edge| edge.end));
+
+        Self {
+            node_ids,
+            node_positions,
+            out_offsets,
+            out_edges,
+            in_offsets,
+            in_edges,
+            edges,
+            geometry_offsets,
+            geometry,
+            highway_types,
+        }
+    }
+}
+
+impl CompactRoadGraph {
+    /// Returns the number of nodes.
+    pub fn node_count(&self) -> usize {
+        self.node_ids.len()
+    }
+
+    /// Returns the number of road segments.
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Returns the index of a node by OSM ID.
+    pub fn node_index(&self, id: i64) -> Option<u32> {
+        self.node_ids.binary_search(&id).ok().map(|index| index as u32)
+    }
+
+    /// Returns the OSM ID of a node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `node` is out of range.
+    pub fn node_id(&self, node: u32) -> i64 {
+        self.node_ids[node as usize]
+    }
+
+    /// Returns the position (longitude, latitude) of a node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `node` is out of range.
+    pub fn node_position(&self, node: u32) -> DVec2 {
+        self.node_positions[node as usize]
+    }
+
+    /// Returns a road segment, or `None` if `edge` is out of range.
+    pub fn edge(&self, edge: usize) -> Option<&CompactEdge> {
+        self.edges.get(edge)
+    }
+
+    /// Returns the polyline of a road segment (empty if out of range).
+    pub fn edge_geometry(&self, edge: usize) -> &[DVec2] {
+        match (self.geometry_offsets.get(edge), self.geometry_offsets.get(edge + 1)) {
+            (Some(&start), Some(&end)) => &self.geometry[start as usize..end as usize],
+            _ => &[],
+        }
+    }
+
+    /// Returns the highway class of a road segment, e.g. "residential".
+    pub fn highway_type(&self, edge: &CompactEdge) -> &str {
+        &self.highway_types[edge.highway_type as usize]
+    }
+
+    /// Returns the indices of the segments leaving a node.
+    pub fn out_edges(&self, node: u32) -> &[u32] {
+        slice(&self.out_offsets, &self.out_edges, node)
+    }
+
+    /// Returns the indices of the segments entering a node.
+    pub fn in_edges(&self, node: u32) -> &[u32] {
+        slice(&self.in_offsets, &self.in_edges, node)
+    }
+
+    /// Picks a random outgoing segment of a node.
+    ///
+    /// Chooses among the same segments in the same order as
+    /// [`RoadGraph::random_out_edge`], so both consume the random number
+    /// generator identically.
+    ///
+    /// # Returns
+    ///
+    /// The edge index, or `None` at a dead end.
+    pub fn random_out_edge<R: Rng + ?Sized>(&self, node: u32, rng: &mut R) -> Option<usize> {
+        let through = |edge: usize| self.edges[edge].access.allows_through_traffic();
+        pick_open(self.out_edges(node).iter().map(|&edge| edge as usize), &ClosureSet::default(), through, rng)
+    }
+
+    /// Picks a random open outgoing segment of a node.
+    ///
+    /// Chooses like [`RoadGraph::random_open_out_edge`], and like
+    /// [`CompactRoadGraph::random_out_edge`] while no segment at the node
+    /// is closed.
+    ///
+    /// # Returns
+    ///
+    /// The edge index, or `None` if every way on is closed or the node is
+    /// a dead end.
+    pub fn random_open_out_edge<R: Rng + ?Sized>(&self, node: u32, closures: &ClosureSet, rng: &mut R) -> Option<usize> {
+        let through = |edge: usize| self.edges[edge].access.allows_through_traffic();
+        pick_open(self.out_edges(node).iter().map(|&edge| edge as usize), closures, through, rng)
+    }
+
+    /// Estimates the heap memory used by the arrays, in bytes.
+    pub fn memory_bytes(&self) -> usize {
+        self.node_ids.capacity() * size_of::<i64>()
+            + self.node_positions.capacity() * size_of::<DVec2>()
+            + (self.out_offsets.capacity() + self.out_edges.capacity()) * size_of::<u32>()
+            + (self.in_offsets.capacity() + self.in_edges.capacity()) * size_of::<u32>()
+            + self.edges.capacity() * size_of::<CompactEdge>()
+            + self.geometry_offsets.capacity() * size_of::<u32>()
+            + self.geometry.capacity() * size_of::<DVec2>()
+            + self.highway_types.iter().map(|class| size_of::<String>() + class.capacity()).sum::<usize>()
+    }
+}
+
+/// Builds CSR offsets and entries grouping edge indices by node.
+///
+/// Edges of a node keep ascending index order, matching the adjacency
+/// lists of [`RoadGraph`].
+fn csr(node_count: usize, nodes: impl Iterator<Item = u32> + Clone) -> (Vec<u32>, Vec<u32>) {
+    let mut offsets = vec![0u32; node_count + 1];
+    for node in nodes.clone() {
+        offsets[node as usize + 1] += 1;
+    }
+    for i in 0..node_count {
+        offsets[i + 1] += offsets[i];
+    }
+
+    let mut next = offsets.clone();
+    let mut entries = vec![0u32; offsets[node_count] as usize];
+    for (edge, node) in nodes.enumerate() {
+        entries[next[node as usize] as usize] = edge as u32;
+        next[node as usize] += 1;
+    }
+    (offsets, entries)
+}
+
+/// Returns the CSR entries of one node (empty if out of range).
+fn slice<'a>(offsets: &[u32], entries: &'a [u32], node: u32) -> &'a [u32] {
+    match (offsets.get(node as usize), offsets.get(node as usize + 1)) {
+        (Some(&start), Some(&end)) => &entries[start as usize..end as usize],
+        _ => &[],
+    }
+}
@@ -0,0 +1,253 @@
+//! Comparison of two versions of a road network.
+//!
+//! Before a newer extract replaces the one the simulator and API run on,
+//! [`RoadGraph::diff`] lists what changed between them. Road segments are
+//! matched by their way, end nodes and direction, so a way that was split
+//! or rerouted shows up as removed and added segments, while retagging
+//! (a new speed limit, a renamed street) shows up as a changed segment.
+//! [`GraphDiff::to_geojson`] draws the changed segments for review on a map.
+
+use std::collections::HashMap;
+use serde::Serialize;
+use serde_json::{json, Value};
+use super::{Direction, Road, RoadGraph};
+
+/// Largest position change of a node, in degrees, still considered the
+/// same position (about a centimeter).
+const POSITION_TOLERANCE_DEG: f64 = 1e-7;
+
+/// Largest length change of a segment, in meters, still considered the
+/// same length.
+const LENGTH_TOLERANCE_M: f64 = 0.01;
+
+/// Identity of a road segment across map versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct EdgeKey {
+    /// OSM way ID
+    pub way_id: i64,
+    /// Starting node ID
+    pub start: i64,
+    /// Ending node ID
+    pub end: i64,
+    /// Direction along the way
+    pub direction: Direction,
+}
+
+impl EdgeKey {
+    fn of(road: &Road) -> Self {
+        Self { way_id: road.id, start: road.start, end: road.end, direction: road.direction }
+    }
+}
+
+/// A road segment present in both versions with different attributes.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EdgeChange {
+    pub edge: EdgeKey,
+    /// Names of the attributes that differ, e.g. `["speed_limit", "name"]`
+    pub fields: Vec<&'static str>,
+}
+
+/// Differences between two versions of a road network, as returned by
+/// [`RoadGraph::diff`].
+///
+/// All lists are sorted.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GraphDiff {
+    /// Nodes only in the newer version
+    pub nodes_added: Vec<i64>,
+    /// Nodes only in the older version
+    pub nodes_removed: Vec<i64>,
+    /// Nodes in both versions at different positions
+    pub nodes_moved: Vec<i64>,
+    /// Nodes that became traffic signals
+    pub signals_added: Vec<i64>,
+    /// Nodes that are no longer traffic signals
+    pub signals_removed: Vec<i64>,
+    /// Road segments only in the newer version
+    pub edges_added: Vec<EdgeKey>,
+    /// Road segments only in the older version
+    pub edges_removed: Vec<EdgeKey>,
+    /// Road segments in both versions with different attributes
+    pub edges_changed: Vec<EdgeChange>,
+}
+
+impl GraphDiff {
+    /// Returns `true` if both versions are the same.
+    pub fn is_empty(&self) -> bool {
+        self.nodes_added.is_empty()
+            && self.nodes_removed.is_empty()
+            && self.nodes_moved.is_empty()
+            && self.signals_added.is_empty()
+            && self.signals_removed.is_empty()
+            && self.edges_added.is_empty()
+            && self.edges_removed.is_empty()
+            && self.edges_changed.is_empty()
+    }
+
+    /// Draws the segment changes as a GeoJSON changeset.
+    ///
+    /// Every added, removed or changed segment becomes a `LineString`
+    /// feature with the properties `change` (`"added"`, `"removed"` or
+    /// `"changed"`), `way_id`, `start`, `end`, `direction`, `highway` and
+    /// `name`; changed segments also list their changed `fields`. Removed
+    /// segments are drawn as they were, the others as they are now. Node
+    /// changes are left out, they show in the segments touching them.
+    ///
+    /// # Arguments
+    ///
+    /// * `older` - The graph the diff was computed on
+    /// * `newer` - The graph it was compared with
+    ///
+    /// # Returns
+    ///
+    /// A GeoJSON `FeatureCollection`, ordered like the diff's lists.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use traffic_common::map::RoadGraph;
+    ///
+    /// let current = RoadGraph::load_from_pbf("berlin-2024.osm.pbf").unwrap();
+    /// let next = RoadGraph::load_from_pbf("berlin-2025.osm.pbf").unwrap();
+    /// let changeset = current.diff(&next).to_geojson(&current, &next);
+    /// std::fs::write("changes.geojson", changeset.to_string()).unwrap();
+    /// ```
+    pub fn to_geojson(&self, older: &RoadGraph, newer: &RoadGraph) -> Value {
+        let older_edges = edges_by_key(older);
+        let newer_edges = edges_by_key(newer);
+
+        let added = self.edges_added.iter().map(|key| (key, "added", None, newer_edges.get(key)));
+        let removed = self.edges_removed.iter().map(|key| (key, "removed", None, older_edges.get(key)));
+        let changed = self
+            .edges_changed
+            .iter()
+            .map(|change| (&change.edge, "changed", Some(&change.fields), newer_edges.get(&change.edge)));
+        let features: Vec<Value> = added
+            .chain(removed)
+            .chain(changed)
+            .filter_map(|(key, change, fields, road)| {
+                let road = road?;
+                let mut properties = json!({
+                    "change": change,
+                    "way_id": key.way_id,
+                    "start": key.start,
+                    "end": key.end,
+                    "direction": key.direction,
+                    "highway": road.highway_type,
+                    "name": road.name,
+                });
+                if let Some(fields) = fields {
+                    properties["fields"] = json!(fields);
+                }
+                Some(json!({
+                    "type": "Feature",
+                    "geometry": {
+                        "type": "LineString",
+                        "coordinates": road.geometry.iter().map(|p| [p.x, p.y]).collect::<Vec<_>>(),
+                    },
+                    "properties": properties,
+                }))
+            })
+            .collect();
+
+        json!({ "type": "FeatureCollection", "features": features })
+    }
+}
+
+/// Indexes the segments of a graph by their identity across versions.
+fn edges_by_key(graph: &RoadGraph) -> HashMap<EdgeKey, &Road> {
+    graph.edges.iter().map(|road| (EdgeKey::of(road), road)).collect()
+}
+
+impl RoadGraph {
+    /// Lists what changed from this graph to a newer version of it.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The newer version
+    ///
+    /// # Returns
+    ///
+    /// Nodes and road segments added, removed or changed in `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use traffic_common::map::RoadGraph;
+    ///
+    /// let current = RoadGraph::load_from_pbf("berlin-2024.osm.pbf").unwrap();
+    /// let next = RoadGraph::load_from_pbf("berlin-2025.osm.pbf").unwrap();
+    /// let diff = current.diff(&next);
+    /// println!("{} segments added, {} removed", diff.edges_added.len(), diff.edges_removed.len());
+    /// ```
+    pub fn diff(&self, other: &RoadGraph) -> GraphDiff {
+        let mut diff = GraphDiff::default();
+
+        for (id, node) in &self.nodes {
+            match other.nodes.get(id) {
+                None => diff.nodes_removed.push(*id),
+                Some(newer) if node.pos.distance(newer.pos) > POSITION_TOLERANCE_DEG => diff.nodes_moved.push(*id),
+                Some(_) => {}
+            }
+        }
+        diff.nodes_added = other.nodes.keys().filter(|id| !self.nodes.contains_key(id)).copied().collect();
+        diff.signals_added = other.signals.difference(&self.signals).copied().collect();
+        diff.signals_removed = self.signals.difference(&other.signals).copied().collect();
+
+        let older = edges_by_key(self);
+        let newer = edges_by_key(other);
+        for (key, road) in &older {
+            match newer.get(key) {
+                None => diff.edges_removed.push(*key),
+                Some(newer) => {
+                    let fields = changed_fields(road, newer);
+                    if !fields.is_empty() {
+                        diff.edges_changed.push(EdgeChange { edge: *key, fields });
+                    }
+                }
+            }
+        }
+        diff.edges_added = newer.keys().filter(|key| !older.contains_key(key)).copied().collect();
+
+        diff.nodes_added.sort_unstable();
+        diff.nodes_removed.sort_unstable();
+        diff.nodes_moved.sort_unstable();
+        diff.signals_added.sort_unstable();
+        diff.signals_removed.sort_unstable();
+        diff.edges_added.sort_unstable();
+        diff.edges_removed.sort_unstable();
+        diff.edges_changed.sort_unstable_by_key(|change| change.edge);
+        diff
+    }
+}
+
+/// Names the attributes that differ between two versions of a segment.
+fn changed_fields(old: &Road, new: &Road) -> Vec<&'static str> {
+    let same_geometry = old.geometry.len() == new.geometry.len()
+        && old.geometry.iter().zip(&new.geometry).all(|(a, b)| a.distance(*b) <= POSITION_TOLERANCE_DEG);
+    let same_grade = match (old.grade, new.grade) {
+        (Some(a), Some(b)) => (a - b).abs() < 1e-6,
+        (a, b) => a.is_none() && b.is_none(),
+    };
+
+    [
+        ("length", (old.length - new.length).abs() <= LENGTH_TOLERANCE_M),
+        ("geometry", same_geometry),
+        ("highway_type", old.highway_type == new.highway_type),
+        ("speed_limit", old.speed_limit_mps == new.speed_limit_mps),
+        ("name", old.name == new.name),
+        ("ref", old.ref_ == new.ref_),
+        ("grade", same_grade),
+        ("turn_lanes", old.turn_lanes == new.turn_lanes),
+        ("lanes", old.lanes == new.lanes),
+        ("bridge", old.bridge == new.bridge),
+        ("tunnel", old.tunnel == new.tunnel),
+        ("layer", old.layer == new.layer),
+        ("roundabout", old.roundabout == new.roundabout),
+        ("access", old.access == new.access),
+    ]
+    .into_iter()
+    .filter(|(_, same)| !same)
+    .map(|(field, _)| field)
+    .collect()
+}
@@ -0,0 +1,192 @@
+//! Pruning of disconnected parts of the road graph.
+//!
+//! Extracts cut at a bounding box, private driveways and badly tagged ways
+//! leave small islands that cannot be reached from (or left towards) the
+//! main network. Vehicles spawned there dead-end immediately, so the graph
+//! can be reduced to its strongly connected components: sets of nodes where
+//! every node can reach every other along directed edges.
+
+use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use super::RoadGraph;
+
+/// Which strongly connected components [`RoadGraph::prune_components`] keeps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComponentFilter {
+    /// Keep the whole graph
+    #[default]
+    KeepAll,
+    /// Keep only the largest component
+    Largest,
+    /// Keep every component with at least this many nodes
+    MinNodes(usize),
+}
+
+/// Counts of what [`RoadGraph::prune_components`] found and removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ComponentReport {
+    /// Strongly connected components in the graph before pruning
+    pub components: usize,
+    /// Components kept
+    pub kept_components: usize,
+    /// Nodes of the largest component
+    pub largest_nodes: usize,
+    /// Nodes removed
+    pub removed_nodes: usize,
+    /// Edges removed
+    pub removed_edges: usize,
+}
+
+impl RoadGraph {
+    /// Computes the strongly connected components of the graph.
+    ///
+    /// Uses an iterative form of Tarjan's algorithm, so deep road chains
+    /// cannot overflow the stack. Nodes are visited in ID order, which makes
+    /// the result deterministic.
+    ///
+    /// # Returns
+    ///
+    /// The node IDs of every component, largest component first.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<i64>> {
+        let mut ids: Vec<i64> = self.nodes.keys().copied().collect();
+        ids.sort_unstable();
+        let position: HashMap<i64, usize> = ids.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+
+        let successors: Vec<Vec<usize>> = ids
+            .iter()
+            .map(|id| {
+                self.out_edges
+                    .get(id)
+                    .map(|edges| edges.iter().filter_map(|&e| position.get(&self.edges[e].end).copied()).collect())
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        let mut index: Vec<Option<usize>> = vec![None; ids.len()];
+        let mut lowlink = vec![0; ids.len()];
+        let mut on_stack = vec![false; ids.len()];
+        let mut stack: Vec<usize> = Vec::new();
+        let mut next_index = 0;
+        let mut components: Vec<Vec<i64>> = Vec::new();
+
+        for root in 0..ids.len() {
+            if index[root].is_some() {
+                continue;
+            }
+
+            // Explicit call stack of (node, next successor to look at)
+            let mut calls: Vec<(usize, usize)> = vec![(root, 0)];
+            index[root] = Some(next_index);
+            lowlink[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+
+            while let Some((node, child)) = calls.last_mut() {
+                let node = *node;
+                if let Some(&next) = successors[node].get(*child) {
+                    *child += 1;
+                    match index[next] {
+                        None => {
+                            index[next] = Some(next_index);
+                            lowlink[next] = next_index;
+                            next_index += 1;
+                            stack.push(next);
+                            on_stack[next] = true;
+                            calls.push((next, 0));
+                        }
+                        Some(visited) if on_stack[next] => {
+                            lowlink[node] = lowlink[node].min(visited);
+                        }
+                        Some(_) => {}
+                    }
+                    continue;
+                }
+
+                calls.pop();
+                if let Some(&(parent, _)) = calls.last() {
+                    lowlink[parent] = lowlink[parent].min(lowlink[node]);
+                }
+                if Some(lowlink[node]) == index[node] {
+                    let mut component = Vec::new();
+                    while let Some(member) = stack.pop() {
+                        on_stack[member] = false;
+                        component.push(ids[member]);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+            }
+        }
+
+        components.sort_by_key(|component| std::cmp::Reverse(component.len()));
+        components
+    }
+
+    /// Removes the strongly connected components rejected by `filter`.
+    ///
+    /// Nodes outside the kept components are removed together with every
+    /// edge touching them; derived indexes are rebuilt afterwards. Nothing
+    /// is computed for [`ComponentFilter::KeepAll`].
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - Which components to keep
+    ///
+    /// # Returns
+    ///
+    /// How many components were found and what was removed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use traffic_common::map::{ComponentFilter, RoadGraph};
+    ///
+    /// let mut graph = RoadGraph::load_from_pbf("map.osm.pbf").unwrap();
+    /// let report = graph.prune_components(ComponentFilter::MinNodes(50));
+    /// println!("removed {} nodes", report.removed_nodes);
+    /// ```
+    pub fn prune_components(&mut self, filter: ComponentFilter) -> ComponentReport {
+        if filter == ComponentFilter::KeepAll {
+            return ComponentReport::default();
+        }
+
+        let components = self.strongly_connected_components();
+        let mut report = ComponentReport {
+            components: components.len(),
+            largest_nodes: components.first().map_or(0, Vec::len),
+            ..ComponentReport::default()
+        };
+
+        let kept: Vec<&Vec<i64>> = match filter {
+            ComponentFilter::KeepAll => components.iter().collect(),
+            ComponentFilter::Largest => components.iter().take(1).collect(),
+            ComponentFilter::MinNodes(min) => components.iter().filter(|c| c.len() >= min).collect(),
+        };
+        report.kept_components = kept.len();
+        let kept: HashSet<i64> = kept.into_iter().flatten().copied().collect();
+
+        let node_count = self.nodes.len();
+        self.nodes.retain(|id, _| kept.contains(id));
+        self.signals.retain(|id| kept.contains(id));
+        report.removed_nodes = node_count - self.nodes.len();
+
+        let edge_count = self.edges.len();
+        self.edges.retain(|road| kept.contains(&road.start) && kept.contains(&road.end));
+        report.removed_edges = edge_count - self.edges.len();
+
+        tracing::info!(
+            "🏝️ Pruned map components: kept {} of {} (largest {} nodes), removed {} nodes and {} road segments",
+            report.kept_components,
+            report.components,
+            report.largest_nodes,
+            report.removed_nodes,
+            report.removed_edges
+        );
+        self.rebuild_indexes();
+        report
+    }
+}
@@ -0,0 +1,451 @@
+//! Incremental map updates from OsmChange (`.osc`) files.
+//!
+//! OSM publishes minutely/hourly/daily diffs listing created, modified and
+//! deleted nodes and ways. Applying them to a loaded graph lets long-running
+//! services pick up map edits without reloading the whole extract.
+//!
+//! Changed ways are split into road segments at decision points like the
+//! loader does: way ends, nodes shared with other ways and signals. The
+//! loaded graph no longer knows the shape-only nodes inside its edges'
+//! geometry, so a changed way referencing them is connected through the
+//! nodes that are still known. Segments whose endpoints did not change
+//! keep their previous geometry and grade.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::BufReader;
+use anyhow::{Context, Result};
+use glam::DVec2;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::Serialize;
+use crate::geo::{normalize_coordinate, path_length};
+use super::source::attribute;
+use super::access::{Access, AccessTags};
+use super::direction::is_roundabout;
+use super::lanes::WayLanes;
+use super::layers::WayLevel;
+use super::turn_lanes::WayTurnLanes;
+use super::{is_drivable, parse_maxspeed, travel_directions, Direction, Node, Road, RoadGraph};
+
+/// Section of an OsmChange file an element appears in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Create,
+    Modify,
+    Delete,
+}
+
+/// A changed OSM element.
+enum Change {
+    Node { id: i64, pos: Option<DVec2>, tags: HashMap<String, String> },
+    Way { id: i64, nodes: Vec<i64>, tags: HashMap<String, String> },
+}
+
+/// Counts of what [`RoadGraph::apply_diff`] changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct DiffReport {
+    /// Nodes created or moved
+    pub nodes_upserted: usize,
+    /// Nodes removed
+    pub nodes_deleted: usize,
+    /// Ways created or modified
+    pub ways_upserted: usize,
+    /// Ways removed (including ways that stopped being drivable)
+    pub ways_deleted: usize,
+    /// Road segments added
+    pub edges_added: usize,
+    /// Road segments removed
+    pub edges_removed: usize,
+}
+
+impl RoadGraph {
+    /// Applies an OsmChange file to the graph.
+    ///
+    /// Created and modified ways replace all road segments of the way, one
+    /// per span between decision points; ways that are deleted (or no longer drivable) lose theirs. Moved
+    /// nodes drag the ends of their segments along, deleted nodes take
+    /// their segments with them, and `highway=traffic_signals` tags on
+    /// changed nodes update the signal set. Derived indexes are rebuilt
+    /// afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the `.osc` file
+    ///
+    /// # Returns
+    ///
+    /// How many nodes, ways and road segments changed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not valid OsmChange
+    /// XML. The graph is left untouched in that case.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use traffic_common::map::RoadGraph;
+    ///
+    /// let mut graph = RoadGraph::load_from_pbf("berlin.osm.pbf").unwrap();
+    /// let report = graph.apply_diff("berlin-daily.osc").unwrap();
+    /// println!("{} road segments added", report.edges_added);
+    /// ```
+    pub fn apply_diff(&mut self, path: &str) -> Result<DiffReport> {
+        let changes = read_osm_change(path)?;
+        let mut report = DiffReport::default();
+
+        // Positions of created or moved nodes, for ways referencing them
+        let mut positions: HashMap<i64, DVec2> = HashMap::new();
+        let mut moved: HashSet<i64> = HashSet::new();
+        let mut deleted_nodes: HashSet<i64> = HashSet::new();
+        let mut signals: Vec<i64> = Vec::new();
+        for (action, change) in &changes {
+            let Change::Node { id, pos, tags } = change else { continue };
+            if *action == Action::Delete {
+                deleted_nodes.insert(*id);
+                continue;
+            }
+            let Some(pos) = pos.and_then(|p| normalize_coordinate(p.x, p.y).ok()) else { continue };
+            let pos = DVec2::new(pos.0, pos.1);
+
+            positions.insert(*id, pos);
+            if let Some(node) = self.nodes.get_mut(id) {
+                if node.pos != pos {
+                    node.pos = pos;
+                    moved.insert(*id);
+                }
+            }
+            if tags.get("highway").is_some_and(|v| v == "traffic_signals") {
+                signals.push(*id);
+            } else {
+                self.signals.remove(id);
+            }
+            report.nodes_upserted += 1;
+        }
+
+        // How often each node is referenced by a way, counting changed ways
+        // by their new nodes and every other way by its segments' ends
+        let changed_ways: HashSet<i64> = changes
+            .iter()
+            .filter_map(|(_, change)| match change {
+                Change::Way { id, .. } => Some(*id),
+                Change::Node { .. } => None,
+            })
+            .collect();
+        let mut references: HashMap<i64, u32> = HashMap::new();
+        let kept_ends: HashSet<(i64, i64)> = self
+            .edges
+            .iter()
+            .filter(|road| !changed_ways.contains(&road.id))
+            .flat_map(|road| [(road.start, road.id), (road.end, road.id)])
+            .collect();
+        for (node, _) in kept_ends {
+            *references.entry(node).or_default() += 1;
+        }
+        for (action, change) in &changes {
+            let Change::Way { nodes, tags, .. } = change else { continue };
+            if way_access(*action, tags).is_some() {
+                let mut nodes = nodes.clone();
+                nodes.dedup();
+                for node in nodes {
+                    *references.entry(node).or_default() += 1;
+                }
+            }
+        }
+
+        let mut endpoints: HashSet<i64> = HashSet::new();
+        for (action, change) in &changes {
+            let Change::Way { id, nodes, tags } = change else { continue };
+
+            // Every change replaces the way's segments; keep their geometry
+            // (in the way's node order) and grade for segments that come
+            // back unchanged
+            let mut previous: HashMap<(i64, i64), (Vec<DVec2>, Option<f64>)> = HashMap::new();
+            self.edges.retain_mut(|road| {
+                if road.id != *id {
+                    return true;
+                }
+                endpoints.extend([road.start, road.end]);
+                let mut geometry = std::mem::take(&mut road.geometry);
+                let (key, grade) = match road.direction {
+                    Direction::Forward => ((road.start, road.end), road.grade),
+                    Direction::Backward => {
+                        geometry.reverse();
+                        ((road.end, road.start), road.grade.map(|grade| -grade))
+                    }
+                };
+                previous.insert(key, (geometry, grade));
+                report.edges_removed += 1;
+                false
+            });
+
+            let tag = |key: &str| tags.get(key).cloned();
+            let highway = tag("highway").unwrap_or_default();
+            let Some(access) = way_access(*action, tags) else {
+                report.ways_deleted += usize::from(!previous.is_empty() || *action == Action::Delete);
+                continue;
+            };
+
+            // Connect the way through the nodes that are known
+            let mut known: Vec<(i64, DVec2)> = nodes
+                .iter()
+                .filter_map(|node| {
+                    let pos = positions.get(node).or_else(|| self.nodes.get(node).map(|n| &n.pos))?;
+                    Some((*node, *pos))
+                })
+                .collect();
+            known.dedup_by_key(|(node, _)| *node);
+            if known.is_empty() {
+                continue;
+            }
+            let directions = travel_directions(&highway, tags.get("oneway").map(String::as_str), tags.get("junction").map(String::as_str));
+            let turn_lanes = WayTurnLanes::from_tags(
+                tags.get("turn:lanes").map(String::as_str),
+                tags.get("turn:lanes:forward").map(String::as_str),
+                tags.get("turn:lanes:backward").map(String::as_str),
+                directions,
+            );
+            let lanes = WayLanes::from_tags(
+                tags.get("lanes").map(String::as_str),
+                tags.get("lanes:forward").map(String::as_str),
+                tags.get("lanes:backward").map(String::as_str),
+                directions,
+            );
+            let level = WayLevel::from_tags(
+                tags.get("bridge").map(String::as_str),
+                tags.get("tunnel").map(String::as_str),
+                tags.get("layer").map(String::as_str),
+            );
+            // Cut the way into spans at decision points, as the loader does
+            let mut spans: Vec<&[(i64, DVec2)]> = Vec::new();
+            let mut span_start = 0;
+            for (index, (node, _)) in known.iter().enumerate().skip(1) {
+                let decision = index + 1 == known.len()
+                    || references.get(node).is_some_and(|&count| count > 1)
+                    || self.signals.contains(node)
+                    || signals.contains(node);
+                if decision {
+                    push_span(&mut spans, &known[span_start..=index]);
+                    span_start = index;
+                }
+            }
+
+            let mut segments: Vec<Road> = Vec::new();
+            for span in spans {
+                let (from, to) = (span[0], span[span.len() - 1]);
+                let (geometry, grade) = previous
+                    .remove(&(from.0, to.0))
+                    .filter(|(geometry, _)| geometry.first() == Some(&from.1) && geometry.last() == Some(&to.1))
+                    .unwrap_or_else(|| (span.iter().map(|(_, pos)| *pos).collect(), None));
+                let road = Road {
+                    id: *id,
+                    direction: Direction::Forward,
+                    start: from.0,
+                    end: to.0,
+                    length: path_length(&geometry),
+                    geometry,
+                    highway_type: highway.clone(),
+                    speed_limit_mps: tag("maxspeed").as_deref().and_then(parse_maxspeed),
+                    name: tag("name"),
+                    ref_: tag("ref"),
+                    grade,
+                    turn_lanes: None,
+                    lanes: None,
+                    bridge: level.bridge,
+                    tunnel: level.tunnel,
+                    layer: level.layer,
+                    roundabout: is_roundabout(tags.get("junction").map(String::as_str)),
+                    access,
+                };
+                for &direction in directions {
+                    let mut segment = road.with_direction(direction);
+                    segment.turn_lanes = turn_lanes.get(direction);
+                    segment.lanes = lanes.get(direction);
+                    segments.push(segment);
+                }
+            }
+
+            // Shape points live on in the geometry only
+            for road in &segments {
+                for (node, pos) in [(road.start, road.geometry[0]), (road.end, road.geometry[road.geometry.len() - 1])] {
+                    self.nodes.entry(node).or_insert(Node { id: node, pos });
+                }
+            }
+            report.edges_added += segments.len();
+            self.edges.extend(segments);
+            report.ways_upserted += 1;
+        }
+
+        // Signals only count on nodes that are part of the road network
+        self.signals.extend(signals.into_iter().filter(|id| self.nodes.contains_key(id)));
+
+        // Deleted nodes take their segments with them
+        for id in &deleted_nodes {
+            if self.nodes.remove(id).is_some() {
+                report.nodes_deleted += 1;
+            }
+            self.signals.remove(id);
+        }
+        self.edges.retain(|road| {
+            let keep = !deleted_nodes.contains(&road.start) && !deleted_nodes.contains(&road.end);
+            if !keep {
+                endpoints.extend([road.start, road.end]);
+                report.edges_removed += 1;
+            }
+            keep
+        });
+
+        // Moved nodes drag the ends of their segments along
+        for road in &mut self.edges {
+            let mut changed = false;
+            if moved.contains(&road.start) {
+                road.geometry[0] = self.nodes[&road.start].pos;
+                changed = true;
+            }
+            if moved.contains(&road.end) {
+                let last = road.geometry.len() - 1;
+                road.geometry[last] = self.nodes[&road.end].pos;
+                changed = true;
+            }
+            if changed {
+                road.length = path_length(&road.geometry);
+            }
+        }
+
+        // Drop nodes left without any segment
+        let referenced: HashSet<i64> = self.edges.iter().flat_map(|road| [road.start, road.end]).collect();
+        for id in endpoints.difference(&referenced) {
+            if self.nodes.remove(id).is_some() {
+                self.signals.remove(id);
+                report.nodes_deleted += 1;
+            }
+        }
+
+        tracing::info!(
+            "📝 Applied map diff {}: {} ways and {} nodes changed, +{} / -{} road segments",
+            path,
+            report.ways_upserted + report.ways_deleted,
+            report.nodes_upserted + report.nodes_deleted,
+            report.edges_added,
+            report.edges_removed
+        );
+        self.rebuild_indexes();
+        Ok(report)
+    }
+}
+
+/// Returns the car access of a changed way, or `None` if the way is deleted
+/// or not part of the road network for cars.
+fn way_access(action: Action, tags: &HashMap<String, String>) -> Option<Access> {
+    if action == Action::Delete || !tags.get("highway").is_some_and(|highway| is_drivable(highway)) {
+        return None;
+    }
+    AccessTags {
+        access: tags.get("access").map(String::as_str),
+        vehicle: tags.get("vehicle").map(String::as_str),
+        motor_vehicle: tags.get("motor_vehicle").map(String::as_str),
+        motorcar: tags.get("motorcar").map(String::as_str),
+        service: tags.get("service").map(String::as_str),
+    }
+    .car_access()
+}
+
+/// Adds a span of a changed way; spans of fewer than two nodes are skipped.
+///
+/// A span that closes on itself is cut in two at its middle node, so every
+/// segment connects two distinct nodes.
+fn push_span<'a>(spans: &mut Vec<&'a [(i64, DVec2)]>, span: &'a [(i64, DVec2)]) {
+    if span.len() < 2 {
+        return;
+    }
+    if span[0].0 == span[span.len() - 1].0 {
+        let middle = span.len() / 2;
+        push_span(spans, &span[..=middle]);
+        push_span(spans, &span[middle..]);
+        return;
+    }
+    spans.push(span);
+}
+
+/// Reads all changes from an OsmChange file, in file order.
+fn read_osm_change(path: &str) -> Result<Vec<(Action, Change)>> {
+    let file = File::open(path).with_context(|| format!("Could not open diff file {}", path))?;
+    let mut reader = Reader::from_reader(BufReader::new(file));
+    let mut buf = Vec::new();
+    let mut action: Option<Action> = None;
+    let mut open: Option<Change> = None;
+    let mut changes = Vec::new();
+
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .with_context(|| format!("Malformed OsmChange XML at byte {}", reader.buffer_position()))?;
+        let self_closing = matches!(event, Event::Empty(_));
+        match event {
+            Event::Start(element) | Event::Empty(element) => {
+                let change = match element.name().as_ref() {
+                    b"create" => {
+                        action = Some(Action::Create);
+                        None
+                    }
+                    b"modify" => {
+                        action = Some(Action::Modify);
+                        None
+                    }
+                    b"delete" => {
+                        action = Some(Action::Delete);
+                        None
+                    }
+                    // Deleted nodes may come without coordinates
+                    b"node" => Some(Change::Node {
+                        id: attribute(&element, "id")?,
+                        pos: attribute(&element, "lon")
+                            .and_then(|lon| Ok(DVec2::new(lon, attribute(&element, "lat")?)))
+                            .ok(),
+                        tags: HashMap::new(),
+                    }),
+                    b"way" => Some(Change::Way {
+                        id: attribute(&element, "id")?,
+                        nodes: Vec::new(),
+                        tags: HashMap::new(),
+                    }),
+                    b"nd" => {
+                        if let Some(Change::Way { nodes, .. }) = &mut open {
+                            nodes.push(attribute(&element, "ref")?);
+                        }
+                        None
+                    }
+                    b"tag" => {
+                        if let Some(Change::Node { tags, .. } | Change::Way { tags, .. }) = &mut open {
+                            tags.insert(attribute(&element, "k")?, attribute(&element, "v")?);
+                        }
+                        None
+                    }
+                    _ => None,
+                };
+
+                if let (Some(change), Some(action)) = (change, action) {
+                    if self_closing {
+                        changes.push((action, change));
+                    } else {
+                        open = Some(change);
+                    }
+                }
+            }
+            Event::End(element) => match element.name().as_ref() {
+                b"node" | b"way" => {
+                    if let (Some(change), Some(action)) = (open.take(), action) {
+                        changes.push((action, change));
+                    }
+                }
+                b"create" | b"modify" | b"delete" => action = None,
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(changes)
+}
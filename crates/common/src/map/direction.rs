@@ -0,0 +1,143 @@
+//! Direction of travel along OSM ways.
+//!
+//! OSM ways are undirected lines unless tagged `oneway`, so the loader
+//! turns every way into explicit directed segments: one along the order of
+//! the way's nodes and, where traffic may flow both ways, a reversed copy.
+//! Each segment records which of the two it is, so the reverse of an edge
+//! can be found again (e.g. to detect vehicles driving the wrong way).
+
+use serde::{Deserialize, Serialize};
+use super::{Road, RoadGraph};
+
+/// Direction of a road segment relative to the node order of its OSM way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Direction {
+    /// Along the order of the way's nodes
+    #[default]
+    Forward,
+    /// Against the order of the way's nodes
+    Backward,
+}
+
+impl Direction {
+    /// Returns the opposite direction.
+    pub fn reverse(self) -> Self {
+        match self {
+            Self::Forward => Self::Backward,
+            Self::Backward => Self::Forward,
+        }
+    }
+}
+
+/// Both directions, for ways open to traffic both ways.
+const BOTH: &[Direction] = &[Direction::Forward, Direction::Backward];
+
+/// Determines in which directions a way may be driven.
+///
+/// Follows the OSM conventions: `oneway=yes` (or `true`, `1`) allows only
+/// the node order, `oneway=-1` (or `reverse`) only the opposite. Without
+/// an explicit tag, motorways and roundabouts are one-way and everything
+/// else two-way. Time-dependent values (`reversible`, `alternating`) are
+/// treated as two-way.
+///
+/// # Arguments
+///
+/// * `highway` - OSM highway tag value
+/// * `oneway` - Raw `oneway` tag value, if tagged
+/// * `junction` - Raw `junction` tag value, if tagged
+///
+/// # Returns
+///
+/// The directions segments of the way are generated for.
+///
+/// # Examples
+///
+/// ```
+/// use traffic_common::map::{travel_directions, Direction};
+///
+/// assert_eq!(travel_directions("residential", None, None), [Direction::Forward, Direction::Backward]);
+/// assert_eq!(travel_directions("residential", Some("yes"), None), [Direction::Forward]);
+/// assert_eq!(travel_directions("primary", Some("-1"), None), [Direction::Backward]);
+/// assert_eq!(travel_directions("motorway", None, None), [Direction::Forward]);
+/// assert_eq!(travel_directions("tertiary", None, Some("roundabout")), [Direction::Forward]);
+/// ```
+pub fn travel_directions(highway: &str, oneway: Option<&str>, junction: Option<&str>) -> &'static [Direction] {
+    match oneway.map(str::trim) {
+        Some("yes" | "true" | "1") => &[Direction::Forward],
+        Some("-1" | "reverse") => &[Direction::Backward],
+        Some("no" | "false" | "0" | "reversible" | "alternating") => BOTH,
+        _ if highway == "motorway" || is_roundabout(junction) => &[Direction::Forward],
+        _ => BOTH,
+    }
+}
+
+/// Returns `true` if a `junction` tag marks a roundabout (or a circular
+/// road without roundabout right of way).
+pub(crate) fn is_roundabout(junction: Option<&str>) -> bool {
+    matches!(junction.map(str::trim), Some("roundabout" | "circular"))
+}
+
+impl Road {
+    /// Returns this segment oriented in `direction`, reversing it if needed.
+    pub(crate) fn with_direction(&self, direction: Direction) -> Road {
+        if self.direction == direction {
+            self.clone()
+        } else {
+            self.reversed()
+        }
+    }
+
+    /// Returns the same segment driven the other way.
+    ///
+    /// Start and end are swapped, the geometry is reversed and the
+    /// direction and grade flipped. Turn lanes and the lane count are
+    /// dropped, as they only describe one direction of travel; everything
+    /// else is kept.
+    pub fn reversed(&self) -> Road {
+        let mut geometry = self.geometry.clone();
+        geometry.reverse();
+        Road {
+            start: self.end,
+            end: self.start,
+            geometry,
+            direction: self.direction.reverse(),
+            grade: self.grade.map(|grade| -grade),
+            turn_lanes: None,
+            lanes: None,
+            ..self.clone()
+        }
+    }
+}
+
+impl RoadGraph {
+    /// Finds the edge covering the same stretch of road in the other
+    /// direction.
+    ///
+    /// # Arguments
+    ///
+    /// * `edge` - Index of the edge in `edges`
+    ///
+    /// # Returns
+    ///
+    /// The index of the opposite edge, or `None` if the road is one-way
+    /// (or `edge` is out of range).
+    pub fn reverse_edge(&self, edge: usize) -> Option<usize> {
+        let road = self.edges.get(edge)?;
+        self.edges_for_way(road.id).iter().copied().find(|&other| {
+            let candidate = &self.edges[other];
+            candidate.start == road.end && candidate.end == road.start && candidate.direction != road.direction
+        })
+    }
+
+    /// Returns `true` if the edge stands for its stretch of road when
+    /// drawing the network.
+    ///
+    /// Two-way roads have two coincident edges; only the forward one is
+    /// drawn. Roads that are one-way against the node order only have a
+    /// backward edge, which is drawn.
+    pub fn is_drawn_edge(&self, edge: usize) -> bool {
+        self.edges.get(edge).is_some_and(|road| {
+            road.direction == Direction::Forward || self.reverse_edge(edge).is_none()
+        })
+    }
+}
@@ -0,0 +1,304 @@
+//! Terrain elevation and road grades.
+//!
+//! Elevation comes from digital elevation models in geographic
+//! coordinates (WGS84 longitude/latitude):
+//!
+//! - SRTM `.hgt` tiles (1 or 3 arc-second), named after their south-west
+//!   corner, e.g. `N52E013.hgt`
+//! - Single-band GeoTIFF rasters (`.tif`, `.tiff`), with the `geotiff`
+//!   feature enabled
+//!
+//! Elevations are interpolated bilinearly between samples; voids in the
+//! data are skipped. Each road then gets its grade from the elevations of
+//! its end nodes, for emission and EV-range modeling in the simulator.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use anyhow::{bail, ensure, Context, Result};
+use super::RoadGraph;
+
+/// Sample value marking a void in SRTM data.
+const HGT_VOID: i16 = -32768;
+
+/// One raster of elevation samples in meters.
+#[derive(Debug, Clone)]
+struct ElevationTile {
+    /// Longitude of the centre of the first column
+    west: f64,
+    /// Latitude of the centre of the first row
+    north: f64,
+    /// Degrees of longitude between two columns
+    dx: f64,
+    /// Degrees of latitude between two rows
+    dy: f64,
+    width: usize,
+    height: usize,
+    /// Samples row by row from the north; `NaN` marks voids
+    data: Vec<f32>,
+}
+
+impl ElevationTile {
+    /// Interpolates the elevation at a position, or `None` if the position
+    /// is outside the tile or only surrounded by voids.
+    fn elevation(&self, lon: f64, lat: f64) -> Option<f64> {
+        let x = (lon - self.west) / self.dx;
+        let y = (self.north - lat) / self.dy;
+        if !(0.0..=(self.width - 1) as f64).contains(&x) || !(0.0..=(self.height - 1) as f64).contains(&y) {
+            return None;
+        }
+
+        let col = (x.floor() as usize).min(self.width.saturating_sub(2));
+        let row = (y.floor() as usize).min(self.height.saturating_sub(2));
+        let (fx, fy) = (x - col as f64, y - row as f64);
+
+        // Weighted average of the surrounding samples that are not voids
+        let mut sum = 0.0;
+        let mut weights = 0.0;
+        for (r, c, weight) in [
+            (row, col, (1.0 - fx) * (1.0 - fy)),
+            (row, col + 1, fx * (1.0 - fy)),
+            (row + 1, col, (1.0 - fx) * fy),
+            (row + 1, col + 1, fx * fy),
+        ] {
+            let Some(&sample) = self.data.get(r * self.width + c) else { continue };
+            if !sample.is_nan() && weight > 0.0 {
+                sum += sample as f64 * weight;
+                weights += weight;
+            }
+        }
+        (weights > 0.0).then(|| sum / weights)
+    }
+}
+
+/// Elevation data from one or more raster tiles.
+#[derive(Debug, Clone, Default)]
+pub struct ElevationModel {
+    tiles: Vec<ElevationTile>,
+}
+
+impl ElevationModel {
+    /// Loads elevation tiles from files or directories.
+    ///
+    /// Directories are searched (not recursively) for `.hgt`, `.tif` and
+    /// `.tiff` files.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - Tile files and directories holding tiles
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no tile is found, a tile cannot be read or is
+    /// malformed, or a GeoTIFF is given without the `geotiff` feature.
+    pub fn load(paths: &[&str]) -> Result<Self> {
+        let mut files = Vec::new();
+        for path in paths {
+            let path = Path::new(path);
+            if path.is_dir() {
+                let mut found: Vec<PathBuf> = std::fs::read_dir(path)
+                    .with_context(|| format!("Could not read elevation directory {}", path.display()))?
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|file| matches!(extension(file).as_deref(), Some("hgt" | "tif" | "tiff")))
+                    .collect();
+                found.sort();
+                files.extend(found);
+            } else {
+                files.push(path.to_path_buf());
+            }
+        }
+        ensure!(!files.is_empty(), "no elevation tiles found in {}", paths.join(","));
+
+        let tiles = files
+            .iter()
+            .map(|file| {
+                let tile = match extension(file).as_deref() {
+                    Some("hgt") => read_hgt(file),
+                    Some("tif" | "tiff") => read_geotiff(file),
+                    _ => bail!("unsupported elevation format (expected .hgt, .tif or .tiff)"),
+                };
+                tile.with_context(|| format!("Could not load elevation tile {}", file.display()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { tiles })
+    }
+
+    /// Returns the number of loaded tiles.
+    pub fn tile_count(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Returns the terrain elevation at a position.
+    ///
+    /// # Arguments
+    ///
+    /// * `lon` - Longitude in degrees
+    /// * `lat` - Latitude in degrees
+    ///
+    /// # Returns
+    ///
+    /// The elevation in meters above sea level, or `None` if no tile
+    /// covers the position with valid data.
+    pub fn elevation(&self, lon: f64, lat: f64) -> Option<f64> {
+        self.tiles.iter().find_map(|tile| tile.elevation(lon, lat))
+    }
+}
+
+impl RoadGraph {
+    /// Sets the grade of every road from terrain elevation.
+    ///
+    /// The grade is the rise between the start and end node divided by
+    /// the road's length, so it is positive uphill and the reverse
+    /// segment of a two-way road has the opposite sign. Roads with an end
+    /// node outside the elevation data get no grade.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - Elevation data covering the map
+    ///
+    /// # Returns
+    ///
+    /// The number of roads that got a grade.
+    pub fn apply_elevation(&mut self, model: &ElevationModel) -> usize {
+        let elevations: HashMap<i64, f64> = self
+            .nodes
+            .values()
+            .filter_map(|node| Some((node.id, model.elevation(node.pos.x, node.pos.y)?)))
+            .collect();
+
+        let mut graded = 0;
+        for road in &mut self.edges {
+            road.grade = match (elevations.get(&road.start), elevations.get(&road.end)) {
+                (Some(start), Some(end)) if road.length > 0.0 => Some((end - start) / road.length),
+                _ => None,
+            };
+            graded += usize::from(road.grade.is_some());
+        }
+        graded
+    }
+}
+
+/// Returns the lowercase extension of a file.
+fn extension(path: &Path) -> Option<String> {
+    path.extension().map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+}
+
+/// Parses the south-west corner from an SRTM tile name like `N52E013`.
+fn hgt_corner(path: &Path) -> Option<(f64, f64)> {
+    let name = path.file_stem()?.to_str()?.to_ascii_uppercase();
+    let (lat_sign, lat_deg) = name.get(..3)?.split_at(1);
+    let (lon_sign, lon_deg) = name.get(3..7)?.split_at(1);
+    let lat: f64 = match lat_sign {
+        "N" => lat_deg.parse().ok()?,
+        "S" => -lat_deg.parse::<f64>().ok()?,
+        _ => return None,
+    };
+    let lon: f64 = match lon_sign {
+        "E" => lon_deg.parse().ok()?,
+        "W" => -lon_deg.parse::<f64>().ok()?,
+        _ => return None,
+    };
+    Some((lon, lat))
+}
+
+/// Reads an SRTM `.hgt` tile: a square grid of big-endian 16-bit samples
+/// covering one degree, with the outermost rows and columns shared with
+/// the neighbouring tiles.
+fn read_hgt(path: &Path) -> Result<ElevationTile> {
+    let Some((west, south)) = hgt_corner(path) else {
+        bail!("tile name does not match the SRTM scheme (e.g. N52E013.hgt)");
+    };
+    let bytes = std::fs::read(path)?;
+    let samples = bytes.len() / 2;
+    let size = (samples as f64).sqrt().round() as usize;
+    ensure!(
+        size >= 2 && size * size * 2 == bytes.len(),
+        "{} bytes is not a square grid of 16-bit samples",
+        bytes.len()
+    );
+
+    let data = bytes
+        .chunks_exact(2)
+        .map(|pair| match i16::from_be_bytes([pair[0], pair[1]]) {
+            HGT_VOID => f32::NAN,
+            sample => sample as f32,
+        })
+        .collect();
+    let step = 1.0 / (size - 1) as f64;
+    Ok(ElevationTile {
+        west,
+        north: south + 1.0,
+        dx: step,
+        dy: step,
+        width: size,
+        height: size,
+        data,
+    })
+}
+
+/// Reads a single-band GeoTIFF in geographic coordinates.
+#[cfg(feature = "geotiff")]
+fn read_geotiff(path: &Path) -> Result<ElevationTile> {
+    use tiff::decoder::{Decoder, DecodingResult};
+    use tiff::tags::Tag;
+
+    const MODEL_TYPE_KEY: u16 = 1024;
+    const RASTER_TYPE_KEY: u16 = 1025;
+    const MODEL_TYPE_PROJECTED: u16 = 1;
+    const RASTER_PIXEL_IS_POINT: u16 = 2;
+
+    let mut decoder = Decoder::new(std::io::BufReader::new(std::fs::File::open(path)?))?;
+    let (width, height) = decoder.dimensions()?;
+    let scale = decoder.get_tag_f64_vec(Tag::ModelPixelScaleTag).context("missing ModelPixelScale tag")?;
+    let tiepoint = decoder.get_tag_f64_vec(Tag::ModelTiepointTag).context("missing ModelTiepoint tag")?;
+    ensure!(scale.len() >= 2 && tiepoint.len() >= 6, "malformed georeferencing tags");
+
+    // GeoKey directory: a header of four values, then (key, location, count, value) entries
+    let keys = decoder
+        .find_tag_unsigned_vec::<u16>(Tag::GeoKeyDirectoryTag)?
+        .unwrap_or_default();
+    let key = |id: u16| keys.get(4..)?.chunks_exact(4).find(|entry| entry[0] == id && entry[1] == 0).map(|entry| entry[3]);
+    ensure!(
+        key(MODEL_TYPE_KEY) != Some(MODEL_TYPE_PROJECTED),
+        "projected rasters are not supported; reproject to WGS84 longitude/latitude"
+    );
+
+    let nodata = decoder
+        .find_tag(Tag::GdalNodata)?
+        .and_then(|value| value.into_string().ok())
+        .and_then(|value| value.trim_matches(char::from(0)).trim().parse::<f64>().ok());
+    let data: Vec<f64> = match decoder.read_image()? {
+        DecodingResult::U8(v) => v.into_iter().map(f64::from).collect(),
+        DecodingResult::U16(v) => v.into_iter().map(f64::from).collect(),
+        DecodingResult::U32(v) => v.into_iter().map(f64::from).collect(),
+        DecodingResult::I8(v) => v.into_iter().map(f64::from).collect(),
+        DecodingResult::I16(v) => v.into_iter().map(f64::from).collect(),
+        DecodingResult::I32(v) => v.into_iter().map(f64::from).collect(),
+        DecodingResult::F32(v) => v.into_iter().map(f64::from).collect(),
+        DecodingResult::F64(v) => v,
+        _ => bail!("unsupported sample format"),
+    };
+    let (width, height) = (width as usize, height as usize);
+    ensure!(width >= 2 && height >= 2 && data.len() == width * height, "expected a single band of at least 2x2 samples");
+
+    // Tiepoints refer to the corner of a pixel unless the raster is PixelIsPoint
+    let centre = if key(RASTER_TYPE_KEY) == Some(RASTER_PIXEL_IS_POINT) { 0.0 } else { 0.5 };
+    let (dx, dy) = (scale[0], scale[1]);
+    Ok(ElevationTile {
+        west: tiepoint[3] + (centre - tiepoint[0]) * dx,
+        north: tiepoint[4] - (centre - tiepoint[1]) * dy,
+        dx,
+        dy,
+        width,
+        height,
+        data: data
+            .into_iter()
+            .map(|sample| if nodata == Some(sample) { f32::NAN } else { sample as f32 })
+            .collect(),
+    })
+}
+
+/// GeoTIFF support is compiled out without the `geotiff` feature.
+#[cfg(not(feature = "geotiff"))]
+fn read_geotiff(_path: &Path) -> Result<ElevationTile> {
+    bail!("GeoTIFF elevation needs the `geotiff` feature; convert to SRTM .hgt or rebuild with it")
+}
@@ -0,0 +1,229 @@
+//! Geohash bucketing of road segments.
+//!
+//! A geohash names a cell of a fixed global grid by a base-32 string;
+//! every extra character subdivides the cell into 32, so a prefix covers
+//! all cells below it. Expressing broadcast partitions, viewports and
+//! regional shards as geohash prefixes lets every service agree on which
+//! roads belong where without sharing anything but the string.
+//!
+//! Each road piece is bucketed into the cells of [`GEOHASH_INDEX_PRECISION`]
+//! characters it crosses when the graph is loaded; longer prefixes are
+//! answered from their indexed ancestor and filtered by geometry.
+
+use std::collections::{BTreeMap, BTreeSet};
+use glam::DVec2;
+use super::{BoundingBox, RoadGraph};
+
+/// Length of the geohash cells road pieces are indexed by (about
+/// 1.2 × 0.6 km).
+pub const GEOHASH_INDEX_PRECISION: usize = 6;
+
+/// Geohash alphabet (base 32 without a, i, l, o).
+const ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// Encodes a position as a geohash.
+///
+/// # Arguments
+///
+/// * `lon`, `lat` - Position in degrees
+/// * `precision` - Number of characters
+///
+/// # Examples
+///
+/// ```
+/// use traffic_common::map::geohash_encode;
+///
+/// assert_eq!(geohash_encode(13.405, 52.52, 6), "u33dc0");
+/// ```
+pub fn geohash_encode(lon: f64, lat: f64, precision: usize) -> String {
+    let (mut lon_range, mut lat_range) = ((-180.0, 180.0), (-90.0, 90.0));
+    let mut hash = String::with_capacity(precision);
+    let mut even = true;
+    for _ in 0..precision {
+        let mut index = 0;
+        for _ in 0..5 {
+            // Bits alternate between longitude and latitude, longitude first
+            let (range, value) = if even { (&mut lon_range, lon) } else { (&mut lat_range, lat) };
+            let mid = (range.0 + range.1) / 2.0;
+            index <<= 1;
+            if value >= mid {
+                index |= 1;
+                range.0 = mid;
+            } else {
+                range.1 = mid;
+            }
+            even = !even;
+        }
+        hash.push(ALPHABET[index] as char);
+    }
+    hash
+}
+
+/// Returns the cell a geohash names.
+///
+/// # Returns
+///
+/// The cell's bounds (the whole world for an empty hash), or `None` if the
+/// hash contains a character outside the geohash alphabet.
+pub fn geohash_bbox(hash: &str) -> Option<BoundingBox> {
+    let (mut lon_range, mut lat_range) = ((-180.0, 180.0), (-90.0, 90.0));
+    let mut even = true;
+    for c in hash.bytes() {
+        let index = ALPHABET.iter().position(|&a| a == c.to_ascii_lowercase())?;
+        for bit in (0..5).rev() {
+            let range: &mut (f64, f64) = if even { &mut lon_range } else { &mut lat_range };
+            let mid = (range.0 + range.1) / 2.0;
+            if index >> bit & 1 == 1 {
+                range.0 = mid;
+            } else {
+                range.1 = mid;
+            }
+            even = !even;
+        }
+    }
+    Some(BoundingBox {
+        min_lon: lon_range.0,
+        min_lat: lat_range.0,
+        max_lon: lon_range.1,
+        max_lat: lat_range.1,
+    })
+}
+
+/// Road segments by the geohash cells their geometry crosses.
+#[derive(Debug, Default)]
+pub(crate) struct GeohashIndex {
+    cells: BTreeMap<String, Vec<usize>>,
+}
+
+impl GeohashIndex {
+    /// Buckets every edge of a graph.
+    pub(crate) fn build(graph: &RoadGraph) -> Self {
+        let mut cells: BTreeMap<String, BTreeSet<usize>> = BTreeMap::new();
+        for (edge, road) in graph.edges.iter().enumerate() {
+            for pair in road.geometry.windows(2) {
+                for cell in cells_crossed(pair[0], pair[1]) {
+                    cells.entry(cell).or_default().insert(edge);
+                }
+            }
+        }
+        Self {
+            cells: cells.into_iter().map(|(cell, edges)| (cell, edges.into_iter().collect())).collect(),
+        }
+    }
+
+    /// Estimates the heap memory of the index in bytes.
+    pub(crate) fn memory_bytes(&self) -> usize {
+        self.cells
+            .values()
+            .map(|edges| GEOHASH_INDEX_PRECISION + edges.capacity() * std::mem::size_of::<usize>())
+            .sum()
+    }
+
+    /// Returns the edges in indexed cells starting with `prefix`, which must
+    /// not be longer than [`GEOHASH_INDEX_PRECISION`].
+    fn edges_with_prefix(&self, prefix: &str) -> BTreeSet<usize> {
+        self.cells
+            .range(prefix.to_string()..)
+            .take_while(|(cell, _)| cell.starts_with(prefix))
+            .flat_map(|(_, edges)| edges.iter().copied())
+            .collect()
+    }
+}
+
+impl RoadGraph {
+    /// Returns the road segments whose geometry lies in or crosses a
+    /// geohash cell.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - Geohash of any length (case-insensitive); an empty
+    ///   prefix covers the whole network
+    ///
+    /// # Returns
+    ///
+    /// The edge indices in ascending order; empty if the prefix is not a
+    /// valid geohash.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use traffic_common::map::RoadGraph;
+    ///
+    /// let graph = RoadGraph::load_from_pbf("map.osm.pbf").unwrap();
+    /// let mitte = graph.edges_in_geohash("u33db");
+    /// println!("{} segments in u33db", mitte.len());
+    /// ```
+    pub fn edges_in_geohash(&self, prefix: &str) -> Vec<usize> {
+        let prefix = prefix.to_ascii_lowercase();
+        let Some(bbox) = geohash_bbox(&prefix) else {
+            return Vec::new();
+        };
+        if prefix.len() <= GEOHASH_INDEX_PRECISION {
+            return self.geohash_index.edges_with_prefix(&prefix).into_iter().collect();
+        }
+
+        // Finer than the index: filter the indexed ancestor cell
+        self.geohash_index
+            .edges_with_prefix(&prefix[..GEOHASH_INDEX_PRECISION])
+            .into_iter()
+            .filter(|&edge| {
+                self.edges[edge]
+                    .geometry
+                    .windows(2)
+                    .any(|pair| segment_crosses(pair[0], pair[1], &bbox))
+            })
+            .collect()
+    }
+}
+
+/// Returns the geohashes of [`GEOHASH_INDEX_PRECISION`] characters of all
+/// cells a straight piece from `a` to `b` lies in or crosses.
+fn cells_crossed(a: DVec2, b: DVec2) -> Vec<String> {
+    // Longitude gets the extra bit of an odd number of bits
+    let bits = 5 * GEOHASH_INDEX_PRECISION as i32;
+    let width = 360.0 / 2f64.powi((bits + 1) / 2);
+    let height = 180.0 / 2f64.powi(bits / 2);
+
+    let column = |lon: f64| ((lon + 180.0) / width).floor() as i64;
+    let row = |lat: f64| ((lat + 90.0) / height).floor() as i64;
+    let mut cells = Vec::new();
+    for x in column(a.x.min(b.x))..=column(a.x.max(b.x)) {
+        for y in row(a.y.min(b.y))..=row(a.y.max(b.y)) {
+            let min_lon = x as f64 * width - 180.0;
+            let min_lat = y as f64 * height - 90.0;
+            let cell = BoundingBox { min_lon, min_lat, max_lon: min_lon + width, max_lat: min_lat + height };
+            if segment_crosses(a, b, &cell) {
+                cells.push(geohash_encode(min_lon + width / 2.0, min_lat + height / 2.0, GEOHASH_INDEX_PRECISION));
+            }
+        }
+    }
+    cells
+}
+
+/// Returns `true` if the straight piece from `a` to `b` lies in or crosses
+/// a box (Liang-Barsky clipping).
+fn segment_crosses(a: DVec2, b: DVec2, bbox: &BoundingBox) -> bool {
+    let delta = b - a;
+    let (mut enter, mut leave) = (0.0_f64, 1.0_f64);
+    for (p, q) in [
+        (-delta.x, a.x - bbox.min_lon),
+        (delta.x, bbox.max_lon - a.x),
+        (-delta.y, a.y - bbox.min_lat),
+        (delta.y, bbox.max_lat - a.y),
+    ] {
+        if p == 0.0 {
+            // Parallel to this edge of the box: inside or never
+            if q < 0.0 {
+                return false;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                enter = enter.max(t);
+            } else {
+                leave = leave.min(t);
+            }
+        }
+    }
+    enter <= leave
+}
@@ -0,0 +1,161 @@
+//! Classification of intersections by how right of way is decided.
+//!
+//! Nodes where three or more roads meet are intersections. Traffic signals
+//! decide at signalized ones; at roundabouts circulating traffic has right
+//! of way over entering traffic. Elsewhere the roads of the highest class
+//! form the priority road if they clearly outrank the others (a residential
+//! street joining a primary road yields to it); between roads of equal
+//! class nobody has priority and every approach yields (right before left).
+
+use std::collections::BTreeSet;
+use glam::DVec2;
+use serde::Serialize;
+use super::RoadGraph;
+
+/// How right of way is decided at an intersection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntersectionControl {
+    /// Traffic signals
+    Signals,
+    /// Circulating traffic has priority over entering traffic
+    Roundabout,
+    /// A priority road through the intersection, the others yield
+    PriorityRoad,
+    /// Roads of equal rank; every approach yields
+    Uncontrolled,
+}
+
+/// An intersection and its right-of-way rules, from
+/// [`RoadGraph::classify_intersections`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Intersection {
+    /// OSM node ID
+    pub node: i64,
+    /// Position (longitude, latitude)
+    pub pos: DVec2,
+    /// Road segments entering the intersection
+    pub approaches: usize,
+    /// Whether the node is signalized
+    pub signalized: bool,
+    /// Whether the node lies on a roundabout
+    pub roundabout: bool,
+    /// OSM ways with right of way: the priority road, or the roundabout
+    /// itself; empty without one
+    pub priority_ways: Vec<i64>,
+    pub control: IntersectionControl,
+}
+
+impl Intersection {
+    /// Returns `true` if traffic arriving on `way_id` has to yield.
+    ///
+    /// Signalized intersections are left to the signals.
+    pub fn must_yield(&self, way_id: i64) -> bool {
+        match self.control {
+            IntersectionControl::Signals => false,
+            IntersectionControl::Roundabout | IntersectionControl::PriorityRoad => {
+                !self.priority_ways.contains(&way_id)
+            }
+            IntersectionControl::Uncontrolled => true,
+        }
+    }
+}
+
+impl RoadGraph {
+    /// Classifies every node where three or more roads meet.
+    ///
+    /// # Returns
+    ///
+    /// The intersections, ordered by node ID.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use traffic_common::map::{IntersectionControl, RoadGraph};
+    ///
+    /// let graph = RoadGraph::load_from_pbf("map.osm.pbf").unwrap();
+    /// let roundabouts = graph
+    ///     .classify_intersections()
+    ///     .into_iter()
+    ///     .filter(|intersection| intersection.control == IntersectionControl::Roundabout)
+    ///     .count();
+    /// ```
+    pub fn classify_intersections(&self) -> Vec<Intersection> {
+        let mut node_ids: Vec<i64> = self.nodes.keys().copied().collect();
+        node_ids.sort_unstable();
+
+        node_ids
+            .into_iter()
+            .filter_map(|node| self.classify_intersection(node))
+            .collect()
+    }
+
+    /// Classifies one node, or returns `None` if fewer than three roads
+    /// meet there.
+    fn classify_intersection(&self, node: i64) -> Option<Intersection> {
+        let incoming = self.in_edges.get(&node).map_or(&[][..], Vec::as_slice);
+        let outgoing = self.out_edges.get(&node).map_or(&[][..], Vec::as_slice);
+
+        // One arm per way and direction of departure, whichever directions
+        // it is open in; the first shape point tells apart two arms of a
+        // ring that lead to the same node
+        let mut arms: BTreeSet<(i64, [u64; 2], u8)> = BTreeSet::new();
+        let mut roundabout_ways: BTreeSet<i64> = BTreeSet::new();
+        for &edge in incoming.iter().chain(outgoing) {
+            let road = &self.edges[edge];
+            let geometry = &road.geometry;
+            let Some(next) = (if road.end == node { geometry.iter().rev().nth(1) } else { geometry.get(1) }) else {
+                continue;
+            };
+            arms.insert((road.id, [next.x.to_bits(), next.y.to_bits()], class_rank(&road.highway_type)));
+            if road.roundabout {
+                roundabout_ways.insert(road.id);
+            }
+        }
+        if arms.len() < 3 {
+            return None;
+        }
+
+        let signalized = self.signals.contains(&node);
+        let roundabout = !roundabout_ways.is_empty();
+        let (control, priority_ways) = if signalized {
+            (IntersectionControl::Signals, Vec::new())
+        } else if roundabout {
+            (IntersectionControl::Roundabout, roundabout_ways.into_iter().collect())
+        } else {
+            // The top class is a priority road if it forms at most one road
+            // through the intersection and outranks another arm
+            let top = arms.iter().map(|&(_, _, rank)| rank).max().unwrap_or_default();
+            let top_arms: Vec<i64> = arms.iter().filter(|&&(_, _, rank)| rank == top).map(|&(way, _, _)| way).collect();
+            if top_arms.len() <= 2 && top_arms.len() < arms.len() {
+                let ways: BTreeSet<i64> = top_arms.into_iter().collect();
+                (IntersectionControl::PriorityRoad, ways.into_iter().collect())
+            } else {
+                (IntersectionControl::Uncontrolled, Vec::new())
+            }
+        };
+
+        Some(Intersection {
+            node,
+            pos: self.nodes.get(&node)?.pos,
+            approaches: incoming.len(),
+            signalized,
+            roundabout,
+            priority_ways,
+            control,
+        })
+    }
+}
+
+/// Ranks highway classes by importance for right of way.
+fn class_rank(highway: &str) -> u8 {
+    match highway {
+        "motorway" => 6,
+        "trunk" => 5,
+        "primary" => 4,
+        "secondary" => 3,
+        "tertiary" => 2,
+        "residential" => 1,
+        _ => 0,
+    }
+}
@@ -0,0 +1,63 @@
+//! Lane counts from OSM `lanes` tags.
+//!
+//! `lanes` gives the number of marked lanes of the whole carriageway; on
+//! two-way roads `lanes:forward` and `lanes:backward` split them between
+//! the directions of travel. When only the total is tagged on a two-way
+//! road, the lanes are split evenly, with an odd lane going forward.
+
+use super::Direction;
+
+/// Most lanes a direction of travel can have; larger values are tagging
+/// errors.
+const MAX_LANES: u8 = 8;
+
+/// Lane counts of a way, per direction of travel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct WayLanes {
+    forward: Option<u8>,
+    backward: Option<u8>,
+}
+
+impl WayLanes {
+    /// Reads the lanes of a way from its `lanes`, `lanes:forward` and
+    /// `lanes:backward` tags.
+    pub(crate) fn from_tags(
+        lanes: Option<&str>,
+        forward: Option<&str>,
+        backward: Option<&str>,
+        directions: &[Direction],
+    ) -> Self {
+        let total = lanes.and_then(parse_lanes);
+        let (forward, backward) = (forward.and_then(parse_lanes), backward.and_then(parse_lanes));
+        match directions {
+            [Direction::Forward] => Self { forward: forward.or(total), backward: None },
+            [Direction::Backward] => Self { forward: None, backward: backward.or(total) },
+            _ => {
+                // The directional tags win; the total fills in the rest
+                let rest = |other: u8| total.map(|total| total.saturating_sub(other).max(1));
+                let (forward, backward) = match (forward, backward, total) {
+                    (Some(forward), None, _) => (Some(forward), rest(forward)),
+                    (None, Some(backward), _) => (rest(backward), Some(backward)),
+                    (None, None, Some(total)) => (Some(total.div_ceil(2)), Some((total / 2).max(1))),
+                    tagged => (tagged.0, tagged.1),
+                };
+                Self { forward, backward }
+            }
+        }
+    }
+
+    /// Returns the lanes in one direction of travel, if tagged.
+    pub(crate) fn get(&self, direction: Direction) -> Option<u8> {
+        match direction {
+            Direction::Forward => self.forward,
+            Direction::Backward => self.backward,
+        }
+    }
+}
+
+/// Parses a lane count; conditional and multi-valued tags give their first
+/// value.
+fn parse_lanes(value: &str) -> Option<u8> {
+    let lanes: u32 = value.split(';').next()?.trim().parse().ok()?;
+    (lanes > 0).then(|| lanes.min(MAX_LANES as u32) as u8)
+}
@@ -0,0 +1,82 @@
+//! Grade separation from OSM `bridge`, `tunnel` and `layer` tags.
+//!
+//! Two roads drawn across each other only meet if they share a node *and*
+//! run on the same level. `layer` gives the relative level explicitly
+//! (`-5` to `5`, `0` at ground); bridges and tunnels without a `layer` tag
+//! are taken to be one level above or below ground.
+//!
+//! Nodes are only ever joined on a level they share: distinct OSM nodes at
+//! the same coordinates are not merged across levels, and GeoJSON lines,
+//! whose nodes are identified by coordinate, only meet where one of them
+//! ends or both run on the same level.
+
+use std::collections::HashMap;
+
+/// Bridge, tunnel and layer tags of a way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct WayLevel {
+    pub(crate) bridge: bool,
+    pub(crate) tunnel: bool,
+    pub(crate) layer: Option<i8>,
+}
+
+impl WayLevel {
+    /// Reads the level of a way from its `bridge`, `tunnel` and `layer`
+    /// tags.
+    ///
+    /// Any `bridge`/`tunnel` value other than `no` counts (`yes`,
+    /// `viaduct`, `building_passage`, ...); `layer` values that are not
+    /// whole numbers are ignored.
+    pub(crate) fn from_tags(bridge: Option<&str>, tunnel: Option<&str>, layer: Option<&str>) -> Self {
+        let is_set = |value: Option<&str>| value.is_some_and(|value| !matches!(value.trim(), "" | "no"));
+        Self {
+            bridge: is_set(bridge),
+            tunnel: is_set(tunnel),
+            layer: layer.and_then(parse_layer),
+        }
+    }
+
+    /// Returns the vertical level: the `layer` if tagged, otherwise 1 for
+    /// bridges, -1 for tunnels and 0 at ground level.
+    pub(crate) fn level(&self) -> i8 {
+        self.layer.unwrap_or(if self.bridge {
+            1
+        } else if self.tunnel {
+            -1
+        } else {
+            0
+        })
+    }
+}
+
+/// Collects the levels each node is used on.
+///
+/// # Arguments
+///
+/// * `ways` - The level and nodes of every way (or road segment)
+///
+/// # Returns
+///
+/// The sorted, distinct levels of the ways through each node.
+pub(crate) fn node_levels<I>(ways: impl IntoIterator<Item = (i8, I)>) -> HashMap<i64, Vec<i8>>
+where
+    I: IntoIterator<Item = i64>,
+{
+    let mut levels: HashMap<i64, Vec<i8>> = HashMap::new();
+    for (level, nodes) in ways {
+        for node in nodes {
+            levels.entry(node).or_default().push(level);
+        }
+    }
+    for node_levels in levels.values_mut() {
+        node_levels.sort_unstable();
+        node_levels.dedup();
+    }
+    levels
+}
+
+/// Parses a `layer` value, clamped to the valid range of -5 to 5.
+fn parse_layer(value: &str) -> Option<i8> {
+    let layer: i64 = value.split(';').next()?.trim().parse().ok()?;
+    Some(layer.clamp(-5, 5) as i8)
+}
@@ -0,0 +1,242 @@
+//! Map matching of GPS traces onto the road graph.
+//!
+//! Each point is snapped to one of the road pieces near it. Plain
+//! nearest-edge snapping flips between parallel roads and the two
+//! directions of a street, so candidates are scored by distance plus a
+//! penalty for disagreeing with the direction of travel, with a bonus for
+//! staying on (or continuing from) the previously matched edge.
+//!
+//! Every match carries a confidence: the share of the candidates' weight
+//! that falls on the matched road, scaled down the further the point lies
+//! from it. A point between two parallel roads, or far from any road, gets
+//! a low confidence and should not count towards per-road statistics.
+
+use std::collections::HashMap;
+
+use geo::Point;
+use glam::DVec2;
+use serde::Serialize;
+use crate::geo::{bearing, haversine_distance, path_length};
+use super::spatial::SegmentCandidate;
+use super::RoadGraph;
+
+/// Candidate search radius around each GPS point in meters.
+const SEARCH_RADIUS_M: f64 = 50.0;
+
+/// Penalty in meters for a candidate pointing opposite to the travel direction.
+const HEADING_PENALTY_M: f64 = 30.0;
+
+/// Bonus in meters for staying on the previously matched edge.
+const SAME_EDGE_BONUS_M: f64 = 10.0;
+
+/// Bonus in meters for an edge that continues from the previously matched one.
+const CONNECTED_EDGE_BONUS_M: f64 = 5.0;
+
+/// Minimum movement in meters for the travel direction to be meaningful.
+const MIN_HEADING_DISTANCE_M: f64 = 2.0;
+
+/// Difference in score, in meters, that makes a candidate e (≈2.7) times
+/// less likely than another.
+const CONFIDENCE_SCALE_M: f64 = 10.0;
+
+/// Standard deviation of GPS positions around the road in meters.
+const GPS_SIGMA_M: f64 = 20.0;
+
+/// Confidence below which a match should not count towards per-road
+/// statistics such as average speeds.
+pub const MIN_MATCH_CONFIDENCE: f64 = 0.5;
+
+/// A GPS point snapped onto the road graph.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct MatchedPoint {
+    /// Index of the matched edge in `edges`
+    pub edge: usize,
+    /// OSM way ID of the matched edge
+    pub road_id: i64,
+    /// Snapped position on the road as (longitude, latitude)
+    pub snapped: [f64; 2],
+    /// Distance from the GPS point to the road in meters
+    pub distance_m: f64,
+    /// Distance from the start of the edge to the snapped position in meters
+    pub offset_m: f64,
+    /// How likely the point was on this road (either direction), from 0 to 1
+    pub confidence: f64,
+}
+
+impl RoadGraph {
+    /// Matches a GPS trace onto the road graph.
+    ///
+    /// Points are matched in order, each one taking the travel direction
+    /// from its predecessor and preferring the previously matched edge and
+    /// its successors. Points with no road within 50 m are snapped to the
+    /// nearest road anyway; check `confidence` (or `distance_m`) to discard
+    /// poor matches.
+    ///
+    /// # Arguments
+    ///
+    /// * `trace` - GPS points as (longitude, latitude), in driving order
+    ///
+    /// # Returns
+    ///
+    /// One matched point per input point, or an empty vector if the graph
+    /// has no edges.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use geo::Point;
+    /// use traffic_common::map::RoadGraph;
+    ///
+    /// let graph = RoadGraph::load_from_pbf("map.osm.pbf").unwrap();
+    /// let trace = [Point::new(13.4050, 52.5200), Point::new(13.4055, 52.5201)];
+    /// for matched in graph.match_trace(&trace) {
+    ///     println!("way {} at {:.0} m", matched.road_id, matched.offset_m);
+    /// }
+    /// ```
+    pub fn match_trace(&self, trace: &[Point]) -> Vec<MatchedPoint> {
+        let mut matched: Vec<MatchedPoint> = Vec::with_capacity(trace.len());
+        for (i, point) in trace.iter().enumerate() {
+            let previous = i.checked_sub(1).map(|j| trace[j]);
+            let Some(point) = self.match_point(*point, previous, matched.last().map(|m| m.edge)) else {
+                return Vec::new();
+            };
+            matched.push(point);
+        }
+        matched
+    }
+
+    /// Matches a single GPS point given what is known about its predecessor.
+    ///
+    /// This is the incremental form of [`RoadGraph::match_trace`] for
+    /// streams, where the caller keeps the previous point and edge per
+    /// vehicle.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - GPS point as (longitude, latitude)
+    /// * `previous_point` - Preceding GPS point of the same vehicle, if any
+    /// * `previous_edge` - Edge the preceding point was matched to, if any
+    ///
+    /// # Returns
+    ///
+    /// The best match, or `None` if the graph has no edges.
+    pub fn match_point(
+        &self,
+        point: Point,
+        previous_point: Option<Point>,
+        previous_edge: Option<usize>,
+    ) -> Option<MatchedPoint> {
+        self.match_candidates(point, previous_point, previous_edge, 1).into_iter().next()
+    }
+
+    /// Matches a single GPS point like [`RoadGraph::match_point`], returning
+    /// the runners-up as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - GPS point as (longitude, latitude)
+    /// * `previous_point` - Preceding GPS point of the same vehicle, if any
+    /// * `previous_edge` - Edge the preceding point was matched to, if any
+    /// * `limit` - Maximum number of matches to return
+    ///
+    /// # Returns
+    ///
+    /// Up to `limit` matches on distinct edges, best first; empty if the
+    /// graph has no edges.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use geo::Point;
+    /// use traffic_common::map::{RoadGraph, MIN_MATCH_CONFIDENCE};
+    ///
+    /// let graph = RoadGraph::load_from_pbf("map.osm.pbf").unwrap();
+    /// let candidates = graph.match_candidates(Point::new(13.4050, 52.5200), None, None, 3);
+    /// if candidates.first().is_some_and(|best| best.confidence < MIN_MATCH_CONFIDENCE) {
+    ///     println!("ambiguous: {:?}", candidates.iter().map(|m| m.road_id).collect::<Vec<_>>());
+    /// }
+    /// ```
+    pub fn match_candidates(
+        &self,
+        point: Point,
+        previous_point: Option<Point>,
+        previous_edge: Option<usize>,
+        limit: usize,
+    ) -> Vec<MatchedPoint> {
+        let heading = previous_point.and_then(|previous| travel_bearing(previous, point));
+
+        // Best piece of every edge, cheapest first
+        let mut best: HashMap<usize, (f64, SegmentCandidate)> = HashMap::new();
+        for candidate in self.edge_index.candidates(point.x(), point.y(), SEARCH_RADIUS_M) {
+            let cost = self.score(&candidate, heading, previous_edge);
+            if best.get(&candidate.edge).is_none_or(|(best_cost, _)| cost < *best_cost) {
+                best.insert(candidate.edge, (cost, candidate));
+            }
+        }
+        let mut ranked: Vec<(f64, SegmentCandidate)> = best.into_values().collect();
+        ranked.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.edge.cmp(&b.1.edge)));
+        let Some(&(lowest, _)) = ranked.first() else { return Vec::new() };
+
+        // Relative likelihood of each candidate; both directions of a road
+        // count towards it
+        let weights: Vec<f64> = ranked.iter().map(|(cost, _)| (-(cost - lowest) / CONFIDENCE_SCALE_M).exp()).collect();
+        let total: f64 = weights.iter().sum();
+        let mut road_weights: HashMap<i64, f64> = HashMap::new();
+        for ((_, candidate), weight) in ranked.iter().zip(&weights) {
+            *road_weights.entry(self.edges[candidate.edge].id).or_default() += weight;
+        }
+
+        ranked
+            .iter()
+            .take(limit)
+            .map(|(_, candidate)| {
+                let road = &self.edges[candidate.edge];
+                let proximity = (-0.5 * (candidate.distance_m / GPS_SIGMA_M).powi(2)).exp();
+                MatchedPoint {
+                    edge: candidate.edge,
+                    road_id: road.id,
+                    snapped: candidate.closest,
+                    distance_m: candidate.distance_m,
+                    offset_m: offset_along(&road.geometry, candidate.piece, candidate.closest).min(road.length),
+                    confidence: road_weights[&road.id] / total * proximity,
+                }
+            })
+            .collect()
+    }
+
+    /// Cost of matching to a candidate; lower is better.
+    fn score(&self, candidate: &SegmentCandidate, heading: Option<f64>, previous_edge: Option<usize>) -> f64 {
+        let mut cost = candidate.distance_m;
+
+        if let Some(heading) = heading {
+            let diff = (candidate.bearing - heading).rem_euclid(360.0);
+            let diff = diff.min(360.0 - diff);
+            cost += HEADING_PENALTY_M * diff / 180.0;
+        }
+
+        if let Some(previous) = previous_edge {
+            if previous == candidate.edge {
+                cost -= SAME_EDGE_BONUS_M;
+            } else if self.edges[previous].end == self.edges[candidate.edge].start
+                && self.edges[previous].start != self.edges[candidate.edge].end
+            {
+                // Continuing, but not turning back onto the opposite direction
+                cost -= CONNECTED_EDGE_BONUS_M;
+            }
+        }
+        cost
+    }
+}
+
+/// Bearing of travel between two points in degrees clockwise from north, or
+/// `None` if they are too close together to tell.
+fn travel_bearing(from: Point, to: Point) -> Option<f64> {
+    (haversine_distance(from.x_y(), to.x_y()) >= MIN_HEADING_DISTANCE_M).then(|| bearing(from.x_y(), to.x_y()))
+}
+
+/// Distance along a polyline up to a point on its `piece`-th segment, in meters.
+fn offset_along(geometry: &[DVec2], piece: usize, point: [f64; 2]) -> f64 {
+    let point = DVec2::new(point[0], point[1]);
+    let before = path_length(&geometry[..(piece + 1).min(geometry.len())]);
+    before + geometry.get(piece).map_or(0.0, |&start| haversine_distance((start.x, start.y), (point.x, point.y)))
+}
@@ -0,0 +1,123 @@
+//! Memory accounting for loaded road graphs.
+//!
+//! Sizing machines for bigger cities needs to know where memory goes. The
+//! figures are computed from container capacities and element sizes, so
+//! they count what the graph has allocated (not what the allocator reserved
+//! around it) and are cheap enough to log at startup.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::mem::size_of;
+use glam::DVec2;
+use serde::Serialize;
+use crate::telemetry::format_bytes;
+use super::{Node, Road, RoadGraph};
+
+/// Estimated heap memory of a [`RoadGraph`], in bytes per part.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct GraphMemory {
+    /// Node table
+    pub nodes: usize,
+    /// Road segment table, without geometry and strings
+    pub edges: usize,
+    /// Polylines of all road segments
+    pub geometry: usize,
+    /// Highway classes, names and refs of all road segments
+    pub strings: usize,
+    /// Traffic signal set
+    pub signals: usize,
+    /// Adjacency lists and way index
+    pub adjacency: usize,
+    /// Spatial indexes over edge geometry and nodes, and the geohash index
+    pub spatial_index: usize,
+    /// Projected (metric) copy of the geometry, if enabled
+    pub projected: usize,
+}
+
+impl GraphMemory {
+    /// Returns the sum of all parts.
+    pub fn total(&self) -> usize {
+        self.nodes
+            + self.edges
+            + self.geometry
+            + self.strings
+            + self.signals
+            + self.adjacency
+            + self.spatial_index
+            + self.projected
+    }
+}
+
+impl fmt::Display for GraphMemory {
+    /// Formats the breakdown with binary units, e.g. for startup logs.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let size = |bytes: usize| format_bytes(bytes as u64);
+        write!(
+            f,
+            "{} (nodes {}, edges {}, geometry {}, strings {}, signals {}, adjacency {}, spatial index {}, projected {})",
+            size(self.total()),
+            size(self.nodes),
+            size(self.edges),
+            size(self.geometry),
+            size(self.strings),
+            size(self.signals),
+            size(self.adjacency),
+            size(self.spatial_index),
+            size(self.projected)
+        )
+    }
+}
+
+impl RoadGraph {
+    /// Estimates the heap memory used by the graph and its indexes.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use traffic_common::map::RoadGraph;
+    ///
+    /// let graph = RoadGraph::load_from_pbf("map.osm.pbf").unwrap();
+    /// println!("graph uses ~{} MiB", graph.memory_usage().total() >> 20);
+    /// ```
+    pub fn memory_usage(&self) -> GraphMemory {
+        let string_bytes = |road: &Road| {
+            road.highway_type.capacity()
+                + road.name.as_ref().map_or(0, String::capacity)
+                + road.ref_.as_ref().map_or(0, String::capacity)
+        };
+
+        GraphMemory {
+            nodes: map_bytes::<i64, Node>(&self.nodes),
+            edges: self.edges.capacity() * size_of::<Road>(),
+            geometry: self.edges.iter().map(|road| road.geometry.capacity() * size_of::<DVec2>()).sum(),
+            strings: self.edges.iter().map(string_bytes).sum(),
+            signals: set_bytes(&self.signals),
+            adjacency: edge_lists_bytes(&self.out_edges)
+                + edge_lists_bytes(&self.in_edges)
+                + edge_lists_bytes(&self.way_edges),
+            spatial_index: self.edge_index.memory_bytes()
+                + self.node_index.memory_bytes()
+                + self.geohash_index.memory_bytes(),
+            projected: self.projected.as_ref().map_or(0, |projected| {
+                map_bytes::<i64, DVec2>(&projected.nodes)
+                    + projected.edges.capacity() * size_of::<Vec<DVec2>>()
+                    + projected.edges.iter().map(|edge| edge.capacity() * size_of::<DVec2>()).sum::<usize>()
+            }),
+        }
+    }
+}
+
+/// Table size of a hash map: one slot plus one control byte per bucket.
+fn map_bytes<K, V>(map: &HashMap<K, V>) -> usize {
+    map.capacity() * (size_of::<(K, V)>() + 1)
+}
+
+/// Table size of a hash set: one slot plus one control byte per bucket.
+fn set_bytes<T>(set: &HashSet<T>) -> usize {
+    set.capacity() * (size_of::<T>() + 1)
+}
+
+/// Table size of a map from IDs to edge lists, including the lists.
+fn edge_lists_bytes(map: &HashMap<i64, Vec<usize>>) -> usize {
+    map_bytes(map) + map.values().map(|edges| edges.capacity() * size_of::<usize>()).sum::<usize>()
+}
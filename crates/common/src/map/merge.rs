@@ -0,0 +1,133 @@
+//! Combining several map extracts into one graph.
+//!
+//! Extracts cut from the same OSM planet share node and way IDs, so
+//! neighbouring or overlapping regions are stitched together by treating
+//! equal node IDs at equal positions as the same node. Equal IDs at
+//! different positions (e.g. synthetic IDs of two GeoJSON files) are
+//! renumbered instead.
+//!
+//! Extracts are merged as read, before their ways are split into road
+//! segments and cleaned: a node in the middle of a way in one extract may
+//! be where the way is cut off in its neighbour, and only the merged ways
+//! show which nodes are decision points. Ways that overlapping extracts
+//! both contain are kept once, so they yield one set of segments.
+
+use std::collections::{HashMap, HashSet};
+use anyhow::{ensure, Result};
+use super::progress::{LoadStage, ProgressFn, Reporter};
+use super::source::GraphBuilder;
+use super::{BoundingBox, MapSource, RoadGraph};
+
+impl GraphBuilder {
+    /// Merges the nodes and ways read from another source into this one.
+    ///
+    /// Nodes with the same ID and position are deduplicated, so extracts
+    /// that overlap or touch connect at their shared nodes; nodes whose ID
+    /// is taken by a node elsewhere get a fresh negative ID. Ways with the
+    /// same ID and nodes in both sources are kept once.
+    pub(super) fn merge(&mut self, mut other: GraphBuilder) {
+        let nodes = &mut self.graph.nodes;
+
+        // Renumber colliding nodes below every ID in use
+        let mut next_free = nodes.keys().chain(other.graph.nodes.keys()).copied().min().unwrap_or(0).min(0) - 1;
+        let mut renumbered: HashMap<i64, i64> = HashMap::new();
+        let mut shared_nodes = 0;
+        for (id, mut node) in std::mem::take(&mut other.graph.nodes) {
+            match nodes.get(&id) {
+                Some(existing) if existing.pos == node.pos => shared_nodes += 1,
+                Some(_) => {
+                    renumbered.insert(id, next_free);
+                    node.id = next_free;
+                    nodes.insert(next_free, node);
+                    next_free -= 1;
+                }
+                None => {
+                    nodes.insert(id, node);
+                }
+            }
+        }
+
+        self.graph.meta = match (self.graph.meta.take(), other.graph.meta.take()) {
+            (Some(mine), Some(theirs)) => Some(mine.merged(&theirs)),
+            (mine, theirs) => mine.or(theirs),
+        };
+
+        let renumber = |id: i64| renumbered.get(&id).copied().unwrap_or(id);
+        self.graph.signals.extend(other.graph.signals.iter().map(|&id| renumber(id)));
+        for way in &mut other.ways {
+            for node in &mut way.nodes {
+                *node = renumber(*node);
+            }
+        }
+
+        let mut known: HashSet<(i64, Vec<i64>)> = self.ways.iter().map(|way| (way.id, way.nodes.clone())).collect();
+        let way_count = self.ways.len();
+        self.ways.extend(other.ways.into_iter().filter(|way| known.insert((way.id, way.nodes.clone()))));
+
+        let (mine, theirs) = (&mut self.coordinates, other.coordinates);
+        mine.normalized += theirs.normalized;
+        mine.not_finite += theirs.not_finite;
+        mine.out_of_range += theirs.out_of_range;
+        mine.null_island += theirs.null_island;
+
+        tracing::info!(
+            "🧩 Merged map: +{} ways, {} shared nodes, {} renumbered nodes",
+            self.ways.len() - way_count,
+            shared_nodes,
+            renumbered.len()
+        );
+    }
+}
+
+impl RoadGraph {
+    /// Loads and merges several map files into one graph.
+    ///
+    /// The files are read in order and merged before the graph is built,
+    /// so roads crossing from one extract into the next stay connected. A
+    /// single file is loaded through its own cache (see
+    /// [`RoadGraph::load_or_build`]); several files share one cache next to
+    /// the first, valid as long as none of them changes.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - Map files to load, in any supported format
+    /// * `bbox` - Optional bounding box applied to every file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `paths` is empty or any file fails to load.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use traffic_common::map::RoadGraph;
+    ///
+    /// let graph = RoadGraph::load_or_build_many(&["berlin.osm.pbf", "potsdam.osm.pbf"], None).unwrap();
+    /// ```
+    pub fn load_or_build_many(paths: &[&str], bbox: Option<BoundingBox>) -> Result<Self> {
+        Self::load_or_build_many_with_progress(paths, bbox, &|_| {})
+    }
+
+    /// Reads several map files and builds one graph from their merged
+    /// nodes and ways, without any cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `paths` is empty or any file fails to load.
+    pub(super) fn build_merged(paths: &[&str], bbox: Option<BoundingBox>, progress: &ProgressFn) -> Result<Self> {
+        ensure!(!paths.is_empty(), "No map files given");
+
+        let mut merged: Option<GraphBuilder> = None;
+        for (index, path) in paths.iter().enumerate() {
+            let builder = MapSource::from_path(path).parse(bbox, &Reporter::new(progress, index + 1, paths.len()))?;
+            match &mut merged {
+                Some(merged) => merged.merge(builder),
+                None => merged = Some(builder),
+            }
+        }
+
+        let last = paths[paths.len() - 1];
+        Reporter::new(progress, paths.len(), paths.len()).report(last, LoadStage::Building, 0, 0);
+        Ok(merged.map(GraphBuilder::build).unwrap_or_default())
+    }
+}
@@ -0,0 +1,156 @@
+//! Provenance of a loaded road network.
+//!
+//! Every service loads the map on its own, from its own copy of the file
+//! or its own cache. [`MapMeta`] records which build it ended up with: the
+//! source file and its SHA-256 checksum, the bounding box it was cut to and
+//! the version of this crate that parsed it. Comparing the checksums across
+//! services (the API reports it on `/health`) shows whether they all run
+//! on the same map. For OpenStreetMap data it also carries the licence and
+//! the attribution anyone showing the data has to display, and when the
+//! extract was taken if the file says so.
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::time::{SystemTime, UNIX_EPOCH};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use super::BoundingBox;
+
+/// Name of the OpenStreetMap project as a data source.
+pub const OSM_DATA_SOURCE: &str = "OpenStreetMap";
+
+/// SPDX identifier of the licence of OpenStreetMap data.
+pub const OSM_LICENCE: &str = "ODbL-1.0";
+
+/// Attribution the OpenStreetMap licence requires wherever its data is
+/// shown.
+pub const OSM_ATTRIBUTION: &str = "© OpenStreetMap contributors";
+
+/// Where a road graph was loaded from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapMeta {
+    /// Map file, or comma-separated files for a merged graph
+    pub source_path: String,
+    /// Hex SHA-256 of the map file; for a merged graph, of the checksums
+    /// of its files in order
+    pub file_hash: String,
+    /// When the graph was loaded, in seconds since the Unix epoch
+    pub loaded_at: u64,
+    /// Bounding box the map was cut to, if any
+    pub bbox: Option<BoundingBox>,
+    /// Version of `traffic-common` that parsed the file
+    pub crate_version: String,
+    /// Project the data comes from (e.g. "OpenStreetMap"), if known
+    #[serde(default)]
+    pub data_source: Option<String>,
+    /// When the extract was taken from its source, in seconds since the
+    /// Unix epoch, if the file records it (the replication timestamp of PBF
+    /// headers)
+    #[serde(default)]
+    pub extracted_at: Option<u64>,
+    /// Licence of the data as an SPDX identifier, if known
+    #[serde(default)]
+    pub licence: Option<String>,
+    /// Attribution to display wherever the data is shown, if required
+    #[serde(default)]
+    pub attribution: Option<String>,
+}
+
+impl MapMeta {
+    /// Describes a map file about to be loaded, reading it once to compute
+    /// its checksum.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read.
+    pub fn for_file(path: &str, bbox: Option<BoundingBox>) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("Could not open map file {}", path))?;
+        let mut reader = BufReader::new(file);
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0; 1 << 16];
+        loop {
+            let read = reader.read(&mut buffer).with_context(|| format!("Could not read map file {}", path))?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+
+        Ok(Self {
+            source_path: path.to_string(),
+            file_hash: format!("{:x}", hasher.finalize()),
+            loaded_at: now(),
+            bbox,
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            data_source: None,
+            extracted_at: None,
+            licence: None,
+            attribution: None,
+        })
+    }
+
+    /// Marks the map as OpenStreetMap data, with its licence and required
+    /// attribution.
+    ///
+    /// # Arguments
+    ///
+    /// * `extracted_at` - When the extract was taken, in seconds since the
+    ///   Unix epoch, if known
+    pub fn openstreetmap(self, extracted_at: Option<u64>) -> Self {
+        Self {
+            data_source: Some(OSM_DATA_SOURCE.to_string()),
+            extracted_at,
+            licence: Some(OSM_LICENCE.to_string()),
+            attribution: Some(OSM_ATTRIBUTION.to_string()),
+            ..self
+        }
+    }
+
+    /// Describes a graph merged from this map and another one.
+    ///
+    /// Sources, licences and attributions of both maps are listed when they
+    /// differ; the extract time is that of the older extract.
+    pub fn merged(&self, other: &MapMeta) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(self.file_hash.as_bytes());
+        hasher.update(other.file_hash.as_bytes());
+        Self {
+            source_path: format!("{},{}", self.source_path, other.source_path),
+            file_hash: format!("{:x}", hasher.finalize()),
+            loaded_at: self.loaded_at.max(other.loaded_at),
+            bbox: self.bbox,
+            crate_version: self.crate_version.clone(),
+            data_source: merge_text(&self.data_source, &other.data_source),
+            extracted_at: self.extracted_at.into_iter().chain(other.extracted_at).min(),
+            licence: merge_text(&self.licence, &other.licence),
+            attribution: merge_text(&self.attribution, &other.attribution),
+        }
+    }
+
+    /// Marks the graph as loaded now, e.g. after reading it from a cache.
+    pub(crate) fn touch(&mut self) {
+        self.loaded_at = now();
+    }
+
+    /// Returns the first 12 hex digits of the checksum, enough to tell
+    /// builds apart in logs.
+    pub fn short_hash(&self) -> &str {
+        &self.file_hash[..self.file_hash.len().min(12)]
+    }
+}
+
+/// Combines a text field of two merged maps: the common value if they
+/// agree, both values if they differ.
+fn merge_text(a: &Option<String>, b: &Option<String>) -> Option<String> {
+    match (a, b) {
+        (Some(a), Some(b)) if a != b => Some(format!("{}; {}", a, b)),
+        (Some(a), _) => Some(a.clone()),
+        (None, b) => b.clone(),
+    }
+}
+
+/// Returns the current time in seconds since the Unix epoch.
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
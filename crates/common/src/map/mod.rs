@@ -0,0 +1,484 @@
+//! Road network graph loading and management.
+//!
+//! This module handles loading OpenStreetMap data from PBF files and building
+//! a routing graph for traffic simulation. It uses OSM highway data to create
+//! a directed graph of drivable roads.
+
+mod access;
+mod cache;
+mod clean;
+mod closures;
+mod compact;
+mod compare;
+mod components;
+mod diff;
+mod direction;
+mod elevation;
+mod geohash;
+mod intersections;
+mod lanes;
+mod layers;
+mod matching;
+mod memory;
+mod merge;
+mod meta;
+mod poi;
+mod polyline;
+mod progress;
+mod projection;
+mod routing;
+mod simplify;
+mod source;
+mod spatial;
+mod speed_limits;
+mod stats;
+mod tiles;
+mod travel_time;
+mod turn_lanes;
+mod weights;
+
+pub use access::Access;
+pub use crate::geo::BoundingBox;
+pub use clean::CleanReport;
+pub use closures::ClosureSet;
+pub use compact::{CompactEdge, CompactRoadGraph};
+pub use compare::{EdgeChange, EdgeKey, GraphDiff};
+pub use components::{ComponentFilter, ComponentReport};
+pub use diff::DiffReport;
+pub use direction::{travel_directions, Direction};
+pub use elevation::ElevationModel;
+pub use geohash::{geohash_bbox, geohash_encode, GEOHASH_INDEX_PRECISION};
+pub use intersections::{Intersection, IntersectionControl};
+pub use matching::{MatchedPoint, MIN_MATCH_CONFIDENCE};
+pub use memory::GraphMemory;
+pub use meta::{MapMeta, OSM_ATTRIBUTION, OSM_DATA_SOURCE, OSM_LICENCE};
+pub use poi::{Poi, PoiKind, PoiSet};
+pub use polyline::{simplify_polyline, simplify_polyline_indices};
+pub use progress::{LoadProgress, LoadStage, ProgressFn};
+pub use projection::{LocalProjection, ProjectedGeometry};
+pub use source::MapSource;
+pub use spatial::{EdgeIndex, SegmentCandidate};
+pub use speed_limits::{default_speed_limit_mps, parse_maxspeed};
+pub use stats::{ClassStats, Connectivity, GraphStats};
+pub use tiles::{is_valid_tile, min_zoom, MAX_TILE_ZOOM, ROADS_LAYER};
+pub use travel_time::{HeuristicTravelTimeModel, TravelTimeModel};
+pub use turn_lanes::{parse_turn_lanes, Turn, TurnLanes};
+pub use weights::{DynamicWeights, TravelTimeProvider, ROAD_SPEEDS_KEY};
+#[cfg(feature = "onnx")]
+pub use travel_time::OnnxTravelTimeModel;
+
+use std::collections::{HashMap, HashSet};
+use anyhow::Result;
+use glam::DVec2;
+use bevy_ecs::prelude::Resource;
+use serde::{Serialize, Deserialize};
+use rand::Rng;
+use rand::distributions::{Distribution, WeightedIndex};
+use geohash::GeohashIndex;
+use spatial::NodeIndex;
+
+/// Represents a node in the road network graph.
+///
+/// Each node corresponds to an intersection or point along a road
+/// in the OpenStreetMap data.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Node {
+    /// OpenStreetMap node ID
+    pub id: i64,
+    /// Geographic position (longitude, latitude)
+    pub pos: DVec2,
+}
+
+/// Represents a road segment (edge) in the road network graph.
+///
+/// Each road connects two nodes and stores information about the
+/// segment's length, geometry, and road classification.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Road {
+    /// OpenStreetMap way ID
+    pub id: i64,
+    /// Whether the segment runs along or against the way's node order
+    #[serde(default)]
+    pub direction: Direction,
+    /// Starting node ID
+    pub start: i64,
+    /// Ending node ID
+    pub end: i64,
+    /// Physical length in meters (calculated using Haversine distance)
+    pub length: f64,
+    /// Geometric points along the road segment
+    pub geometry: Vec<DVec2>,
+    /// OSM highway classification (e.g., "motorway", "residential")
+    pub highway_type: String,
+    /// Legal speed limit in m/s from the `maxspeed` tag, if tagged
+    #[serde(default)]
+    pub speed_limit_mps: Option<f64>,
+    /// Street name from the `name` tag, e.g. "Unter den Linden"
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Route number from the `ref` tag, e.g. "A100"
+    #[serde(default)]
+    pub ref_: Option<String>,
+    /// Rise over run from start to end node (e.g. 0.05 for 5 % uphill),
+    /// once elevation data is applied with [`RoadGraph::apply_elevation`]
+    #[serde(default)]
+    pub grade: Option<f64>,
+    /// Turns allowed per lane at the end of the way, from the `turn:lanes`
+    /// tags for this direction of travel
+    #[serde(default)]
+    pub turn_lanes: Option<TurnLanes>,
+    /// Number of lanes in this direction of travel, from the `lanes` tags
+    #[serde(default)]
+    pub lanes: Option<u8>,
+    /// Whether the segment is a bridge (`bridge` tag)
+    #[serde(default)]
+    pub bridge: bool,
+    /// Whether the segment runs through a tunnel (`tunnel` tag)
+    #[serde(default)]
+    pub tunnel: bool,
+    /// Relative vertical level from the `layer` tag, if tagged
+    #[serde(default)]
+    pub layer: Option<i8>,
+    /// Whether the segment is part of a roundabout (`junction=roundabout`)
+    #[serde(default)]
+    pub roundabout: bool,
+    /// Who may drive the segment, from the way's access tags
+    #[serde(default)]
+    pub access: Access,
+}
+
+impl Road {
+    /// Returns the speed limit in m/s, falling back to the typical limit of
+    /// the road's class when no `maxspeed` is tagged.
+    pub fn effective_speed_limit_mps(&self) -> f64 {
+        self.speed_limit_mps
+            .unwrap_or_else(|| default_speed_limit_mps(&self.highway_type))
+    }
+
+    /// Returns the number of lanes in the direction of travel: the tagged
+    /// count, else the number of turn lanes, else one.
+    pub fn lane_count(&self) -> u8 {
+        self.lanes
+            .or_else(|| self.turn_lanes.as_ref().map(|lanes| lanes.lane_count().min(u8::MAX as usize) as u8))
+            .unwrap_or(1)
+            .max(1)
+    }
+
+    /// Returns the vertical level of the segment: its `layer` if tagged,
+    /// otherwise 1 for bridges, -1 for tunnels and 0 at ground level.
+    ///
+    /// Roads sharing a node only meet there if they are on the same level;
+    /// otherwise one crosses over the other.
+    pub fn level(&self) -> i8 {
+        layers::WayLevel { bridge: self.bridge, tunnel: self.tunnel, layer: self.layer }.level()
+    }
+}
+
+/// The complete road network graph structure.
+///
+/// Contains all nodes, road segments, and topological information
+/// for efficient routing and simulation.
+#[derive(Debug, Default, Resource, Serialize, Deserialize)]
+pub struct RoadGraph {
+    /// All nodes in the network, indexed by OSM node ID
+    pub nodes: HashMap<i64, Node>,
+    /// All road segments in the network
+    pub edges: Vec<Road>,
+    /// IDs of nodes tagged as traffic signals (`highway=traffic_signals`)
+    #[serde(default)]
+    pub signals: HashSet<i64>,
+    /// Source file, checksum and loader version, for graphs loaded from a
+    /// map file
+    #[serde(default)]
+    pub meta: Option<MapMeta>,
+    /// Adjacency list: maps each node ID to indices of outgoing road segments
+    #[serde(skip)]
+    pub out_edges: HashMap<i64, Vec<usize>>,
+    /// Reverse adjacency list: maps each node ID to indices of incoming road segments
+    #[serde(skip)]
+    pub in_edges: HashMap<i64, Vec<usize>>,
+    /// Indices of the road segments of each OSM way
+    #[serde(skip)]
+    way_edges: HashMap<i64, Vec<usize>>,
+    /// Spatial index over edge geometry for nearest-edge queries
+    #[serde(skip)]
+    edge_index: EdgeIndex,
+    /// Spatial index over nodes for nearest-node queries
+    #[serde(skip)]
+    node_index: NodeIndex,
+    /// Edges by the geohash cells they cross
+    #[serde(skip)]
+    geohash_index: GeohashIndex,
+    /// Geometry in meters, once enabled with [`RoadGraph::enable_projection`]
+    #[serde(skip)]
+    projected: Option<ProjectedGeometry>,
+}
+
+impl RoadGraph {
+    /// Loads a road network graph from an OpenStreetMap PBF file.
+    ///
+    /// Parses the OSM data, extracts drivable roads, and builds a routing
+    /// graph with nodes and directed edges. Only roads marked as drivable
+    /// (motorways, residential streets, etc.) are included. Ways are split
+    /// only at decision points (nodes shared with other ways, signals, way
+    /// ends), so each edge carries the full polyline of the nodes between.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the .osm.pbf file
+    ///
+    /// # Returns
+    ///
+    /// A populated `RoadGraph` ready for routing and simulation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The file cannot be opened
+    /// - The PBF data is malformed
+    /// - Required geographic data is missing
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use traffic_common::map::RoadGraph;
+    ///
+    /// let graph = RoadGraph::load_from_pbf("map.osm.pbf")
+    ///     .expect("Failed to load map");
+    /// println!("Loaded {} nodes", graph.nodes.len());
+    /// ```
+    pub fn load_from_pbf(path: &str) -> Result<Self> {
+        MapSource::Pbf(path.to_string()).load()
+    }
+
+    /// Loads only the part of a road network inside a bounding box.
+    ///
+    /// Nodes outside the box are discarded while parsing, and so are road
+    /// segments with an endpoint outside it. Use this to simulate a single
+    /// district without paying for the whole extract.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the .osm.pbf file
+    /// * `min_lon`, `min_lat`, `max_lon`, `max_lat` - Box edges in degrees
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened or the PBF data is
+    /// malformed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use traffic_common::map::RoadGraph;
+    ///
+    /// // Berlin-Mitte only
+    /// let graph = RoadGraph::load_from_pbf_bbox("berlin.osm.pbf", 13.36, 52.50, 13.43, 52.54)
+    ///     .expect("Failed to load map");
+    /// ```
+    pub fn load_from_pbf_bbox(path: &str, min_lon: f64, min_lat: f64, max_lon: f64, max_lat: f64) -> Result<Self> {
+        MapSource::Pbf(path.to_string()).load_bbox(Some(BoundingBox { min_lon, min_lat, max_lon, max_lat }))
+    }
+
+    /// Loads a road network from an OpenStreetMap XML file.
+    ///
+    /// Handy for tests and small hand-edited networks (e.g. exported from
+    /// JOSM) that don't warrant producing a PBF extract.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the .osm file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened or is not valid OSM XML.
+    pub fn load_from_osm_xml(path: &str) -> Result<Self> {
+        MapSource::OsmXml(path.to_string()).load()
+    }
+
+    /// Loads a road network from a GeoJSON `FeatureCollection`.
+    ///
+    /// Each `LineString` feature is a road; `highway` and `maxspeed`
+    /// properties are read like OSM tags, and lines sharing a coordinate are
+    /// connected there.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the .geojson file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened or is not a GeoJSON
+    /// `FeatureCollection`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use traffic_common::map::RoadGraph;
+    ///
+    /// let graph = RoadGraph::load_from_geojson("test_grid.geojson")
+    ///     .expect("Failed to load map");
+    /// println!("Loaded {} roads", graph.edges.len());
+    /// ```
+    pub fn load_from_geojson(path: &str) -> Result<Self> {
+        MapSource::GeoJson(path.to_string()).load()
+    }
+
+    /// Rebuilds all derived lookup structures from `nodes` and `edges`.
+    ///
+    /// The adjacency lists, way index, spatial and geohash indexes and
+    /// projected geometry are not serialized, so this must be called
+    /// whenever the graph is constructed or its edges are modified.
+    pub fn rebuild_indexes(&mut self) {
+        // Build adjacency list for efficient routing
+        let mut out_edges: HashMap<i64, Vec<usize>> = HashMap::new();
+        let mut in_edges: HashMap<i64, Vec<usize>> = HashMap::new();
+        let mut way_edges: HashMap<i64, Vec<usize>> = HashMap::new();
+        for (index, road) in self.edges.iter().enumerate() {
+            out_edges.entry(road.start).or_default().push(index);
+            in_edges.entry(road.end).or_default().push(index);
+            way_edges.entry(road.id).or_default().push(index);
+        }
+        self.out_edges = out_edges;
+        self.in_edges = in_edges;
+        self.way_edges = way_edges;
+
+        self.edge_index = EdgeIndex::build(self);
+        self.node_index = NodeIndex::build(self, self.edge_index.lon_scale());
+        self.geohash_index = GeohashIndex::build(self);
+        self.rebuild_projection();
+    }
+
+    /// Returns the indices of all road segments of an OSM way.
+    ///
+    /// A way is split into one segment per pair of consecutive junctions
+    /// and, for two-way roads, one per direction, so incidents and closures
+    /// reported per way usually cover several edges.
+    ///
+    /// # Arguments
+    ///
+    /// * `way_id` - OSM way ID, as stored in [`Road::id`]
+    ///
+    /// # Returns
+    ///
+    /// The edge indices in ascending order, or an empty slice if the way is
+    /// not part of the graph.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use traffic_common::map::RoadGraph;
+    ///
+    /// let graph = RoadGraph::load_from_pbf("map.osm.pbf").expect("Failed to load map");
+    /// for &edge in graph.edges_for_way(4_045_215) {
+    ///     println!("{} m", graph.edges[edge].length);
+    /// }
+    /// ```
+    pub fn edges_for_way(&self, way_id: i64) -> &[usize] {
+        self.way_edges.get(&way_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Samples edge indices with probability proportional to edge length.
+    ///
+    /// Equivalent to [`RoadGraph::sample_spawn_points`] with every road class
+    /// weighted equally.
+    pub fn sample_edges_by_length<R: Rng + ?Sized>(&self, n: usize, rng: &mut R) -> Vec<usize> {
+        self.sample_spawn_points(n, &HashMap::new(), rng)
+    }
+
+    /// Samples spawn edges weighted by road class and length.
+    ///
+    /// Each edge is selected with probability proportional to its length
+    /// multiplied by the weight of its highway class, so vehicles populate
+    /// the network in proportion to road importance instead of uniformly per
+    /// segment (which over-populates short service-road stubs). Classes that
+    /// are missing from `weights_by_highway` get a weight of `1.0`. Edges
+    /// without geometry, with zero length or closed to through traffic
+    /// (see [`Access`]) are never selected.
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - Number of edge indices to draw (with replacement)
+    /// * `weights_by_highway` - Relative weight per OSM highway type
+    /// * `rng` - Random number generator to draw from
+    ///
+    /// # Returns
+    ///
+    /// A vector of `n` edge indices, or an empty vector if the graph has no
+    /// selectable edges.
+    pub fn sample_spawn_points<R: Rng + ?Sized>(
+        &self,
+        n: usize,
+        weights_by_highway: &HashMap<String, f64>,
+        rng: &mut R,
+    ) -> Vec<usize> {
+        let weights = self.edges.iter().map(|road| {
+            if road.geometry.is_empty() || !road.access.allows_through_traffic() {
+                return 0.0;
+            }
+            let class_weight = weights_by_highway
+                .get(&road.highway_type)
+                .copied()
+                .unwrap_or(1.0);
+            (road.length * class_weight).max(0.0)
+        });
+
+        match WeightedIndex::new(weights) {
+            Ok(dist) => (0..n).map(|_| dist.sample(rng)).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Picks a random outgoing edge from the given node.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - OSM node ID to continue from
+    /// * `rng` - Random number generator to draw from
+    ///
+    /// # Returns
+    ///
+    /// The index of a uniformly selected outgoing edge, or `None` if the node
+    /// is a dead end. Edges closed to through traffic (see [`Access`]) are
+    /// only selected when there is no other way on.
+    pub fn random_out_edge<R: Rng + ?Sized>(&self, node: i64, rng: &mut R) -> Option<usize> {
+        self.random_open_out_edge(node, &ClosureSet::default(), rng)
+    }
+}
+
+/// Determines if a highway type is suitable for vehicle traffic.
+///
+/// # Arguments
+///
+/// * `highway_type` - OSM highway tag value
+///
+/// # Returns
+///
+/// `true` if the highway type allows regular vehicle traffic, `false` otherwise.
+/// Excludes footpaths, cycleways, and other non-drivable roads.
+fn is_drivable(highway_type: &str) -> bool {
+    matches!(
+        highway_type,
+        "motorway" | "trunk" | "primary" | "secondary" | "tertiary" | "residential" | "service" | "living_street"
+    )
+}
+
+/// Returns the default spawn weights per highway class.
+///
+/// Major roads carry far more traffic per meter than residential streets and
+/// service roads, so they are weighted up accordingly.
+pub fn default_highway_weights() -> HashMap<String, f64> {
+    [
+        ("motorway", 4.0),
+        ("trunk", 3.0),
+        ("primary", 2.5),
+        ("secondary", 2.0),
+        ("tertiary", 1.5),
+        ("residential", 1.0),
+        ("living_street", 0.5),
+        ("service", 0.2),
+    ]
+    .into_iter()
+    .map(|(class, weight)| (class.to_string(), weight))
+    .collect()
+}
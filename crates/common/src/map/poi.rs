@@ -0,0 +1,170 @@
+//! Points of interest relevant to traffic: parking, fuel and charging.
+//!
+//! OSM maps them as nodes tagged `amenity=parking`, `amenity=fuel` and
+//! `amenity=charging_station`. The map loader collects them on request
+//! ([`MapSource::load_pois`]) into a [`PoiSet`], which the simulator uses
+//! for trip destinations and EV charging stops.
+
+use anyhow::{ensure, Result};
+use glam::DVec2;
+use rstar::primitives::GeomWithData;
+use rstar::RTree;
+use serde::{Deserialize, Serialize};
+use bevy_ecs::prelude::Resource;
+use super::spatial::METERS_PER_DEGREE;
+use super::{BoundingBox, MapSource};
+
+/// A point of interest, tagged with its index in the set.
+type IndexedPoi = GeomWithData<[f64; 2], usize>;
+
+/// Kind of a point of interest, from its `amenity` tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PoiKind {
+    Parking,
+    Fuel,
+    ChargingStation,
+}
+
+impl PoiKind {
+    /// Reads the kind from an `amenity` value; other amenities are not
+    /// collected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use traffic_common::map::PoiKind;
+    ///
+    /// assert_eq!(PoiKind::from_amenity("charging_station"), Some(PoiKind::ChargingStation));
+    /// assert_eq!(PoiKind::from_amenity("bench"), None);
+    /// ```
+    pub fn from_amenity(amenity: &str) -> Option<Self> {
+        match amenity {
+            "parking" => Some(Self::Parking),
+            "fuel" => Some(Self::Fuel),
+            "charging_station" => Some(Self::ChargingStation),
+            _ => None,
+        }
+    }
+}
+
+/// A point of interest.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Poi {
+    /// OSM node ID (negative for GeoJSON features without one)
+    pub id: i64,
+    pub kind: PoiKind,
+    /// Position (longitude, latitude)
+    pub pos: DVec2,
+    /// Name from the `name` tag, e.g. "Parkhaus Alexa"
+    pub name: Option<String>,
+}
+
+/// Points of interest indexed for nearest-neighbour queries.
+///
+/// # Examples
+///
+/// ```
+/// use glam::DVec2;
+/// use traffic_common::map::{Poi, PoiKind, PoiSet};
+///
+/// let pois = PoiSet::new(vec![
+///     Poi { id: 1, kind: PoiKind::Fuel, pos: DVec2::new(13.40, 52.52), name: None },
+///     Poi { id: 2, kind: PoiKind::ChargingStation, pos: DVec2::new(13.45, 52.52), name: None },
+/// ]);
+///
+/// assert_eq!(pois.nearest(13.41, 52.52, Some(PoiKind::ChargingStation)).map(|poi| poi.id), Some(2));
+/// assert_eq!(pois.nearest(13.44, 52.52, None).map(|poi| poi.id), Some(2));
+/// assert_eq!(pois.within(13.40, 52.52, 500.0).count(), 1);
+/// assert_eq!(pois.of_kind(PoiKind::Parking).count(), 0);
+/// ```
+#[derive(Debug, Default, Resource)]
+pub struct PoiSet {
+    pois: Vec<Poi>,
+    tree: RTree<IndexedPoi>,
+    /// Cosine of the mean latitude, so distances in the tree are
+    /// proportional to ground distances
+    lon_scale: f64,
+}
+
+impl PoiSet {
+    /// Indexes points of interest.
+    pub fn new(pois: Vec<Poi>) -> Self {
+        let mean_lat = if pois.is_empty() {
+            0.0
+        } else {
+            pois.iter().map(|poi| poi.pos.y).sum::<f64>() / pois.len() as f64
+        };
+        let lon_scale = mean_lat.to_radians().cos();
+        let tree = RTree::bulk_load(
+            pois.iter()
+                .enumerate()
+                .map(|(index, poi)| GeomWithData::new([poi.pos.x * lon_scale, poi.pos.y], index))
+                .collect(),
+        );
+        Self { pois, tree, lon_scale }
+    }
+
+    /// Collects the points of interest of several map files.
+    ///
+    /// # Arguments
+    ///
+    /// * `paths` - Map files, in any supported format
+    /// * `bbox` - Optional bounding box applied to every file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `paths` is empty or any file fails to load.
+    pub fn load_many(paths: &[&str], bbox: Option<BoundingBox>) -> Result<Self> {
+        ensure!(!paths.is_empty(), "No map files given");
+
+        let mut pois = Vec::new();
+        for path in paths {
+            pois.extend(MapSource::from_path(path).load_pois(bbox)?.pois);
+        }
+        Ok(Self::new(pois))
+    }
+
+    /// Returns the point of interest closest to a coordinate.
+    ///
+    /// # Arguments
+    ///
+    /// * `lon`, `lat` - Coordinate in degrees
+    /// * `kind` - Only consider points of this kind; any kind when `None`
+    pub fn nearest(&self, lon: f64, lat: f64, kind: Option<PoiKind>) -> Option<&Poi> {
+        self.tree
+            .nearest_neighbor_iter(&[lon * self.lon_scale, lat])
+            .map(|indexed| &self.pois[indexed.data])
+            .find(|poi| kind.is_none_or(|kind| poi.kind == kind))
+    }
+
+    /// Returns the points of interest within `radius_m` of a coordinate,
+    /// nearest first.
+    pub fn within(&self, lon: f64, lat: f64, radius_m: f64) -> impl Iterator<Item = &Poi> + '_ {
+        let radius = radius_m / METERS_PER_DEGREE;
+        self.tree
+            .nearest_neighbor_iter_with_distance_2(&[lon * self.lon_scale, lat])
+            .take_while(move |(_, distance_2)| *distance_2 <= radius * radius)
+            .map(|(indexed, _)| &self.pois[indexed.data])
+    }
+
+    /// Returns all points of interest of one kind.
+    pub fn of_kind(&self, kind: PoiKind) -> impl Iterator<Item = &Poi> + '_ {
+        self.pois.iter().filter(move |poi| poi.kind == kind)
+    }
+
+    /// Returns all points of interest.
+    pub fn pois(&self) -> &[Poi] {
+        &self.pois
+    }
+
+    /// Returns the number of points of interest.
+    pub fn len(&self) -> usize {
+        self.pois.len()
+    }
+
+    /// Returns `true` if there are no points of interest.
+    pub fn is_empty(&self) -> bool {
+        self.pois.is_empty()
+    }
+}
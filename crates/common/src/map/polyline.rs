@@ -0,0 +1,103 @@
+//! Polyline simplification.
+//!
+//! OSM ways carry a vertex every few meters along curves, far more than a
+//! map needs to draw them at city scale. [`simplify_polyline`] drops the
+//! vertices that deviate from a straight line by less than a tolerance in
+//! meters (Douglas-Peucker), keeping both end points so simplified roads
+//! still meet at their intersections. [`simplify_polyline_indices`] does the
+//! same for points that carry more than a position, such as vehicle traces.
+
+use glam::DVec2;
+use super::LocalProjection;
+
+/// Simplifies a polyline with the Douglas-Peucker algorithm.
+///
+/// # Arguments
+///
+/// * `points` - Vertices as (longitude, latitude)
+/// * `tolerance_m` - Largest distance in meters a dropped vertex may have
+///   from the simplified line; 0 or less keeps every vertex
+///
+/// # Returns
+///
+/// The remaining vertices in their original order, always including the
+/// first and last.
+///
+/// # Examples
+///
+/// ```
+/// use glam::DVec2;
+/// use traffic_common::map::simplify_polyline;
+///
+/// // A kink of about 1 m in a 1.4 km line
+/// let line = [DVec2::new(13.40, 52.52), DVec2::new(13.41, 52.52001), DVec2::new(13.42, 52.52)];
+/// assert_eq!(simplify_polyline(&line, 5.0).len(), 2);
+/// assert_eq!(simplify_polyline(&line, 0.5).len(), 3);
+/// ```
+pub fn simplify_polyline(points: &[DVec2], tolerance_m: f64) -> Vec<DVec2> {
+    simplify_polyline_indices(points, tolerance_m)
+        .into_iter()
+        .map(|index| points[index])
+        .collect()
+}
+
+/// Simplifies a polyline like [`simplify_polyline`], returning the indices
+/// of the vertices to keep instead of the vertices themselves.
+///
+/// # Returns
+///
+/// Indices into `points` in ascending order, always including the first
+/// and last (none for an empty polyline).
+///
+/// # Examples
+///
+/// ```
+/// use glam::DVec2;
+/// use traffic_common::map::simplify_polyline_indices;
+///
+/// let line = [DVec2::new(13.40, 52.52), DVec2::new(13.41, 52.52001), DVec2::new(13.42, 52.52)];
+/// assert_eq!(simplify_polyline_indices(&line, 5.0), vec![0, 2]);
+/// ```
+pub fn simplify_polyline_indices(points: &[DVec2], tolerance_m: f64) -> Vec<usize> {
+    if points.len() <= 2 || tolerance_m <= 0.0 {
+        return (0..points.len()).collect();
+    }
+
+    let projection = LocalProjection::new(points[0]);
+    let local: Vec<DVec2> = points.iter().map(|&point| projection.to_local(point)).collect();
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+
+    // Ranges still to split, instead of recursion that long ways could
+    // run deep with
+    let mut ranges = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = ranges.pop() {
+        let farthest = (first + 1..last)
+            .map(|index| (index, distance_to_segment(local[index], local[first], local[last])))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((index, distance)) = farthest {
+            if distance > tolerance_m {
+                keep[index] = true;
+                ranges.push((first, index));
+                ranges.push((index, last));
+            }
+        }
+    }
+
+    keep.into_iter()
+        .enumerate()
+        .filter_map(|(index, keep)| keep.then_some(index))
+        .collect()
+}
+
+/// Returns the distance of `point` from the segment `start`-`end`.
+fn distance_to_segment(point: DVec2, start: DVec2, end: DVec2) -> f64 {
+    let segment = end - start;
+    let length_squared = segment.length_squared();
+    if length_squared == 0.0 {
+        return point.distance(start);
+    }
+    let t = ((point - start).dot(segment) / length_squared).clamp(0.0, 1.0);
+    point.distance(start + segment * t)
+}
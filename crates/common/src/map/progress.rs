@@ -0,0 +1,211 @@
+//! Progress reporting and non-blocking map loading.
+//!
+//! Parsing a city-sized extract takes tens of seconds. Run on an async
+//! runtime, that blocks every other task, and a service appears hung until
+//! it is done. The `_async` loaders move the work onto tokio's blocking
+//! pool and report which file they are on, what they are doing and how far
+//! they have read, so a service can answer health checks with its loading
+//! status meanwhile.
+
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use anyhow::{ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use super::{BoundingBox, MapSource, RoadGraph};
+
+/// Bytes read between two progress reports.
+const REPORT_INTERVAL_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Callback receiving progress reports; called from the loading thread.
+pub type ProgressFn = dyn Fn(&LoadProgress) + Send + Sync;
+
+/// What a map loader is doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadStage {
+    /// Reading a previously parsed graph from its cache file
+    ReadingCache,
+    /// Reading nodes and ways from the map file
+    Parsing,
+    /// Splitting ways into segments, cleaning and indexing the graph
+    Building,
+    /// All files are loaded
+    Done,
+}
+
+/// A progress report of a map loader.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoadProgress {
+    /// File being loaded
+    pub path: String,
+    /// Number of the file being loaded, starting at 1
+    pub file: usize,
+    /// Number of files to load
+    pub files: usize,
+    pub stage: LoadStage,
+    /// Bytes read from the file (or its cache) so far; PBF extracts are
+    /// read in several passes, so this may exceed `file_size`
+    pub bytes_read: u64,
+    /// Size of the file (or its cache) in bytes
+    pub file_size: u64,
+}
+
+/// Reports the progress of loading one file.
+pub(crate) struct Reporter<'a> {
+    callback: &'a ProgressFn,
+    file: usize,
+    files: usize,
+}
+
+impl<'a> Reporter<'a> {
+    /// Creates a reporter for file number `file` (from 1) of `files`.
+    pub(crate) fn new(callback: &'a ProgressFn, file: usize, files: usize) -> Self {
+        Self { callback, file, files }
+    }
+
+    /// Creates a reporter that reports nothing.
+    pub(crate) fn silent() -> Self {
+        Self::new(&|_| {}, 1, 1)
+    }
+
+    /// Reports a stage of loading `path`; stages that read nothing report
+    /// zero bytes.
+    pub(crate) fn report(&self, path: &str, stage: LoadStage, bytes_read: u64, file_size: u64) {
+        (self.callback)(&LoadProgress {
+            path: path.to_string(),
+            file: self.file,
+            files: self.files,
+            stage,
+            bytes_read,
+            file_size,
+        });
+    }
+
+    /// Opens a file whose reads are reported under `stage`.
+    pub(crate) fn open<'r>(&'r self, path: &'r str, stage: LoadStage) -> io::Result<ProgressReader<'r, fs::File>> {
+        let file = fs::File::open(path)?;
+        let file_size = file.metadata().map(|metadata| metadata.len()).unwrap_or_default();
+        self.report(path, stage, 0, file_size);
+        Ok(ProgressReader { inner: file, reporter: self, path, stage, file_size, bytes_read: 0, reported: 0 })
+    }
+}
+
+/// A reader reporting how many bytes have passed through it.
+pub(crate) struct ProgressReader<'a, R> {
+    inner: R,
+    reporter: &'a Reporter<'a>,
+    path: &'a str,
+    stage: LoadStage,
+    file_size: u64,
+    bytes_read: u64,
+    reported: u64,
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes_read += n as u64;
+        if self.bytes_read - self.reported >= REPORT_INTERVAL_BYTES {
+            self.reported = self.bytes_read;
+            self.reporter.report(self.path, self.stage, self.bytes_read, self.file_size);
+        }
+        Ok(n)
+    }
+}
+
+impl<R: Seek> Seek for ProgressReader<'_, R> {
+    // Rereading counts as reading again
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+impl RoadGraph {
+    /// Loads a road network from an OSM PBF extract without blocking the
+    /// async runtime.
+    ///
+    /// Parsing runs on tokio's blocking pool; `progress` is called from
+    /// there as the file is read and the graph built.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the .osm.pbf file
+    /// * `bbox` - Optional bounding box restricting the loaded area
+    /// * `progress` - Receives progress reports
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened or the PBF data is
+    /// malformed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use traffic_common::map::RoadGraph;
+    ///
+    /// # async fn run() -> anyhow::Result<()> {
+    /// let graph = RoadGraph::load_from_pbf_async("berlin.osm.pbf", None, |progress| {
+    ///     println!("{:?}: {} of {} bytes", progress.stage, progress.bytes_read, progress.file_size);
+    /// })
+    /// .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn load_from_pbf_async<F>(path: &str, bbox: Option<BoundingBox>, progress: F) -> Result<Self>
+    where
+        F: Fn(&LoadProgress) + Send + Sync + 'static,
+    {
+        let path = path.to_string();
+        tokio::task::spawn_blocking(move || {
+            let reporter = Reporter::new(&progress, 1, 1);
+            let graph = MapSource::Pbf(path.clone()).load_reporting(bbox, &reporter)?;
+            reporter.report(&path, LoadStage::Done, 0, 0);
+            Ok(graph)
+        })
+        .await
+        .context("Map loading task failed")?
+    }
+
+    /// Like [`RoadGraph::load_or_build_many`], reporting progress to a
+    /// callback.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `paths` is empty or any file fails to load.
+    pub fn load_or_build_many_with_progress(
+        paths: &[&str],
+        bbox: Option<BoundingBox>,
+        progress: &ProgressFn,
+    ) -> Result<Self> {
+        ensure!(!paths.is_empty(), "No map files given");
+
+        let graph = match paths {
+            [path] => Self::load_or_build_reporting(path, bbox, &Reporter::new(progress, 1, 1))?,
+            _ => Self::load_or_build_merged(paths, bbox, progress)?,
+        };
+        if let Some(meta) = &graph.meta {
+            tracing::info!("🔏 Map build {} ({}, traffic-common {})", meta.short_hash(), meta.source_path, meta.crate_version);
+        }
+        Reporter::new(progress, paths.len(), paths.len()).report(paths[paths.len() - 1], LoadStage::Done, 0, 0);
+        Ok(graph)
+    }
+
+    /// Like [`RoadGraph::load_or_build_many`], without blocking the async
+    /// runtime: loading runs on tokio's blocking pool and reports progress
+    /// to a callback.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `paths` is empty or any file fails to load.
+    pub async fn load_or_build_many_async<F>(paths: Vec<String>, bbox: Option<BoundingBox>, progress: F) -> Result<Self>
+    where
+        F: Fn(&LoadProgress) + Send + Sync + 'static,
+    {
+        tokio::task::spawn_blocking(move || {
+            let paths: Vec<&str> = paths.iter().map(String::as_str).collect();
+            Self::load_or_build_many_with_progress(&paths, bbox, &progress)
+        })
+        .await
+        .context("Map loading task failed")?
+    }
+}
@@ -0,0 +1,356 @@
+//! Shortest-path routing over the road graph.
+//!
+//! Implements Dijkstra's algorithm over the directed edge list. The cost of
+//! each edge is supplied by the caller; [`RoadGraph::shortest_path`] uses
+//! edge length in meters. Alternative routes between the same two nodes
+//! come from Yen's k-shortest-paths algorithm ([`RoadGraph::k_routes`]).
+//! Origin-destination cost matrices run one one-to-many search per origin,
+//! spread over all CPU cores ([`RoadGraph::travel_time_matrix`]).
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use super::RoadGraph;
+
+/// Priority queue entry ordered by ascending cost.
+#[derive(Debug, Clone, Copy)]
+struct QueueEntry {
+    cost: f64,
+    node: i64,
+}
+
+impl PartialEq for QueueEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost.total_cmp(&other.cost) == Ordering::Equal
+    }
+}
+
+impl Eq for QueueEntry {}
+
+impl PartialOrd for QueueEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueueEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed so that BinaryHeap (a max-heap) pops the cheapest entry first
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+impl RoadGraph {
+    /// Finds the shortest path between two nodes.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - OSM node ID to start from
+    /// * `to` - OSM node ID to reach
+    ///
+    /// # Returns
+    ///
+    /// The indices of the edges to traverse in order (empty if `from == to`),
+    /// or `None` if `to` is unreachable from `from`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use traffic_common::map::RoadGraph;
+    ///
+    /// let graph = RoadGraph::load_from_pbf("map.osm.pbf").unwrap();
+    /// if let Some(path) = graph.shortest_path(1, 2) {
+    ///     println!("Route uses {} segments", path.len());
+    /// }
+    /// ```
+    pub fn shortest_path(&self, from: i64, to: i64) -> Option<Vec<usize>> {
+        self.shortest_path_by(from, to, |edge_idx| self.edges[edge_idx].length)
+    }
+
+    /// Finds the cheapest path between two nodes under a custom edge cost.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - OSM node ID to start from
+    /// * `to` - OSM node ID to reach
+    /// * `edge_cost` - Non-negative cost of traversing an edge, by edge index;
+    ///   edges with an infinite cost are never used
+    ///
+    /// # Returns
+    ///
+    /// The indices of the edges to traverse in order (empty if `from == to`),
+    /// or `None` if `to` is unreachable from `from`.
+    pub fn shortest_path_by<F: Fn(usize) -> f64>(&self, from: i64, to: i64, edge_cost: F) -> Option<Vec<usize>> {
+        if from == to {
+            return Some(Vec::new());
+        }
+
+        let mut best: HashMap<i64, f64> = HashMap::new();
+        let mut via_edge: HashMap<i64, usize> = HashMap::new();
+        let mut queue = BinaryHeap::new();
+
+        best.insert(from, 0.0);
+        queue.push(QueueEntry { cost: 0.0, node: from });
+
+        while let Some(QueueEntry { cost, node }) = queue.pop() {
+            if node == to {
+                return Some(self.unwind_path(&via_edge, from, to));
+            }
+            // Skip stale queue entries
+            if cost > best.get(&node).copied().unwrap_or(f64::INFINITY) {
+                continue;
+            }
+
+            for &edge_idx in self.out_edges.get(&node).into_iter().flatten() {
+                let road = &self.edges[edge_idx];
+                let next_cost = cost + edge_cost(edge_idx);
+                if next_cost < best.get(&road.end).copied().unwrap_or(f64::INFINITY) {
+                    best.insert(road.end, next_cost);
+                    via_edge.insert(road.end, edge_idx);
+                    queue.push(QueueEntry { cost: next_cost, node: road.end });
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Finds up to `k` loopless alternative paths between two nodes, shortest
+    /// first.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - OSM node ID to start from
+    /// * `to` - OSM node ID to reach
+    /// * `k` - Maximum number of paths
+    ///
+    /// # Returns
+    ///
+    /// Distinct paths as edge index sequences, ordered by length; fewer than
+    /// `k` if the network offers no more alternatives, and none if `to` is
+    /// unreachable.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use traffic_common::map::RoadGraph;
+    ///
+    /// let graph = RoadGraph::load_from_pbf("map.osm.pbf").unwrap();
+    /// for (rank, path) in graph.k_routes(1, 2, 3).iter().enumerate() {
+    ///     println!("Route {} uses {} segments", rank + 1, path.len());
+    /// }
+    /// ```
+    pub fn k_routes(&self, from: i64, to: i64, k: usize) -> Vec<Vec<usize>> {
+        self.k_routes_by(from, to, k, |edge_idx| self.edges[edge_idx].length)
+    }
+
+    /// Finds up to `k` loopless alternative paths between two nodes under a
+    /// custom edge cost, cheapest first (Yen's algorithm).
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - OSM node ID to start from
+    /// * `to` - OSM node ID to reach
+    /// * `k` - Maximum number of paths
+    /// * `edge_cost` - Non-negative cost of traversing an edge, by edge index;
+    ///   edges with an infinite cost are never used
+    ///
+    /// # Returns
+    ///
+    /// Distinct paths as edge index sequences, ordered by cost.
+    pub fn k_routes_by<F: Fn(usize) -> f64>(&self, from: i64, to: i64, k: usize, edge_cost: F) -> Vec<Vec<usize>> {
+        let path_cost = |path: &[usize]| path.iter().map(|&edge_idx| edge_cost(edge_idx)).sum::<f64>();
+
+        let mut routes: Vec<Vec<usize>> = Vec::new();
+        match self.shortest_path_by(from, to, &edge_cost) {
+            Some(path) if k > 0 => routes.push(path),
+            _ => return routes,
+        }
+        let mut candidates: Vec<(f64, Vec<usize>)> = Vec::new();
+
+        while routes.len() < k {
+            let previous = &routes[routes.len() - 1];
+
+            // Deviate from the previous route at each of its nodes in turn
+            for spur in 0..previous.len() {
+                let root = &previous[..spur];
+                let spur_node = root.last().map_or(from, |&edge_idx| self.edges[edge_idx].end);
+
+                // Leave the root unchanged but take a different way on than
+                // every known route sharing it, without revisiting its nodes
+                let blocked_edges: HashSet<usize> = routes
+                    .iter()
+                    .filter(|route| route.len() > spur && route[..spur] == *root)
+                    .map(|route| route[spur])
+                    .collect();
+                let root_nodes: HashSet<i64> = root.iter().map(|&edge_idx| self.edges[edge_idx].start).collect();
+
+                let spur_path = self.shortest_path_by(spur_node, to, |edge_idx| {
+                    let road = &self.edges[edge_idx];
+                    if blocked_edges.contains(&edge_idx) || root_nodes.contains(&road.end) {
+                        f64::INFINITY
+                    } else {
+                        edge_cost(edge_idx)
+                    }
+                });
+                let Some(spur_path) = spur_path else { continue };
+
+                let mut candidate = root.to_vec();
+                candidate.extend(spur_path);
+                if !routes.contains(&candidate) && !candidates.iter().any(|(_, path)| *path == candidate) {
+                    candidates.push((path_cost(&candidate), candidate));
+                }
+            }
+
+            // The cheapest candidate becomes the next route (earliest on ties)
+            let Some(best) = candidates
+                .iter()
+                .enumerate()
+                .min_by(|(_, (a, _)), (_, (b, _))| a.total_cmp(b))
+                .map(|(index, _)| index)
+            else {
+                break;
+            };
+            routes.push(candidates.remove(best).1);
+        }
+
+        routes
+    }
+
+    /// Computes free-flow travel times between every origin and every
+    /// destination.
+    ///
+    /// Runs one one-to-many search per origin, which stops as soon as all
+    /// destinations are reached; origins are distributed over all available
+    /// CPU cores.
+    ///
+    /// # Arguments
+    ///
+    /// * `origins` - OSM node IDs of the origins
+    /// * `destinations` - OSM node IDs of the destinations
+    ///
+    /// # Returns
+    ///
+    /// One row per origin with one travel time in seconds per destination,
+    /// `None` where the destination is unreachable.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use traffic_common::map::RoadGraph;
+    ///
+    /// let graph = RoadGraph::load_from_pbf("map.osm.pbf").unwrap();
+    /// let matrix = graph.travel_time_matrix(&[1, 2], &[3, 4, 5]);
+    /// if let Some(seconds) = matrix[0][2] {
+    ///     println!("1 -> 5 takes {:.0} s", seconds);
+    /// }
+    /// ```
+    pub fn travel_time_matrix(&self, origins: &[i64], destinations: &[i64]) -> Vec<Vec<Option<f64>>> {
+        self.cost_matrix_by(origins, destinations, |edge_idx| self.free_flow_time(edge_idx))
+    }
+
+    /// Computes the cost of the cheapest path between every origin and
+    /// every destination under a custom edge cost.
+    ///
+    /// # Arguments
+    ///
+    /// * `origins` - OSM node IDs of the origins
+    /// * `destinations` - OSM node IDs of the destinations
+    /// * `edge_cost` - Non-negative cost of traversing an edge, by edge index;
+    ///   edges with an infinite cost are never used
+    ///
+    /// # Returns
+    ///
+    /// One row per origin with one cost per destination, `None` where the
+    /// destination is unreachable.
+    pub fn cost_matrix_by<F: Fn(usize) -> f64 + Sync>(
+        &self,
+        origins: &[i64],
+        destinations: &[i64],
+        edge_cost: F,
+    ) -> Vec<Vec<Option<f64>>> {
+        if origins.is_empty() {
+            return Vec::new();
+        }
+        let threads = std::thread::available_parallelism().map_or(1, usize::from).min(origins.len());
+        let chunk_size = origins.len().div_ceil(threads);
+
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = origins
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    let edge_cost = &edge_cost;
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|&origin| self.one_to_many_by(origin, destinations, edge_cost))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("matrix worker panicked"))
+                .collect()
+        })
+    }
+
+    /// Computes the cost of the cheapest path from one node to each of
+    /// several others.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - OSM node ID to start from
+    /// * `targets` - OSM node IDs to reach
+    /// * `edge_cost` - Non-negative cost of traversing an edge, by edge index;
+    ///   edges with an infinite cost are never used
+    ///
+    /// # Returns
+    ///
+    /// One cost per target (0 for `from` itself), `None` where the target is
+    /// unreachable.
+    pub fn one_to_many_by<F: Fn(usize) -> f64>(&self, from: i64, targets: &[i64], edge_cost: F) -> Vec<Option<f64>> {
+        let mut remaining: HashSet<i64> = targets.iter().copied().collect();
+        let mut best: HashMap<i64, f64> = HashMap::new();
+        let mut settled: HashMap<i64, f64> = HashMap::new();
+        let mut queue = BinaryHeap::new();
+
+        best.insert(from, 0.0);
+        queue.push(QueueEntry { cost: 0.0, node: from });
+
+        while let Some(QueueEntry { cost, node }) = queue.pop() {
+            // Skip stale queue entries
+            if cost > best.get(&node).copied().unwrap_or(f64::INFINITY) || settled.contains_key(&node) {
+                continue;
+            }
+            settled.insert(node, cost);
+            remaining.remove(&node);
+            if remaining.is_empty() {
+                break;
+            }
+
+            for &edge_idx in self.out_edges.get(&node).into_iter().flatten() {
+                let road = &self.edges[edge_idx];
+                let next_cost = cost + edge_cost(edge_idx);
+                if next_cost < best.get(&road.end).copied().unwrap_or(f64::INFINITY) {
+                    best.insert(road.end, next_cost);
+                    queue.push(QueueEntry { cost: next_cost, node: road.end });
+                }
+            }
+        }
+
+        targets.iter().map(|target| settled.get(target).copied()).collect()
+    }
+
+    /// Reconstructs the edge sequence from the predecessor map.
+    fn unwind_path(&self, via_edge: &HashMap<i64, usize>, from: i64, to: i64) -> Vec<usize> {
+        let mut path = Vec::new();
+        let mut node = to;
+        while node != from {
+            let edge_idx = via_edge[&node];
+            path.push(edge_idx);
+            node = self.edges[edge_idx].start;
+        }
+        path.reverse();
+        path
+    }
+}
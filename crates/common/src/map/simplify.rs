@@ -0,0 +1,156 @@
+//! Graph simplification.
+//!
+//! Nodes that merely shape a road (traffic passes straight through, in one
+//! direction or both) are not decision points for routing. The map loader
+//! never splits ways at them; graphs assembled otherwise, e.g. with one
+//! edge per consecutive node pair, can be brought into the same form by
+//! merging chains of them into single edges carrying the full polyline
+//! geometry.
+
+use std::collections::{HashMap, HashSet};
+use super::{Road, RoadGraph};
+
+impl RoadGraph {
+    /// Merges chains of shape-only nodes into single edges.
+    ///
+    /// A node is merged away when it is not a traffic signal and every
+    /// incoming edge continues into exactly one outgoing edge of the same
+    /// way, direction, class and speed limit: one edge in and one out on
+    /// one-way roads, two of each on two-way roads. Dead ends (where the
+    /// only way on leads back) are kept. Merged edges keep the
+    /// concatenated geometry and the summed length, so distances and
+    /// rendering are unchanged. Merged-away nodes are removed from `nodes`
+    /// and the derived indexes are rebuilt.
+    ///
+    /// # Returns
+    ///
+    /// The number of nodes merged away.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use traffic_common::map::RoadGraph;
+    ///
+    /// let mut graph = RoadGraph::load_from_pbf("map.osm.pbf").unwrap();
+    /// let merged = graph.simplify();
+    /// println!("Merged {} nodes, {} edges left", merged, graph.edges.len());
+    /// ```
+    pub fn simplify(&mut self) -> usize {
+        let mut incoming: HashMap<i64, Vec<usize>> = HashMap::new();
+        let mut outgoing: HashMap<i64, Vec<usize>> = HashMap::new();
+        for (index, road) in self.edges.iter().enumerate() {
+            incoming.entry(road.end).or_default().push(index);
+            outgoing.entry(road.start).or_default().push(index);
+        }
+
+        // Incoming edge -> the outgoing edge it continues into, at every
+        // mergeable node
+        let mut through: HashMap<usize, usize> = HashMap::new();
+        let mut mergeable: HashSet<i64> = HashSet::new();
+        for (node, ins) in &incoming {
+            let Some(outs) = outgoing.get(node) else { continue };
+            if self.signals.contains(node) {
+                continue;
+            }
+            if let Some(continuations) = self.continuations(ins, outs) {
+                mergeable.insert(*node);
+                through.extend(continuations);
+            }
+        }
+        if mergeable.is_empty() {
+            self.rebuild_indexes();
+            return 0;
+        }
+
+        let mut visited = vec![false; self.edges.len()];
+        let mut merged_edges = Vec::with_capacity(self.edges.len());
+        let mut anchors = HashSet::new();
+
+        // Chains start at an edge whose start node is kept
+        for head in 0..self.edges.len() {
+            if !visited[head] && !mergeable.contains(&self.edges[head].start) {
+                merged_edges.push(self.merge_chain(head, &through, &anchors, &mut visited));
+            }
+        }
+
+        // Edges left over form closed rings of mergeable nodes; break each
+        // ring at one node, in both directions if it is two-way
+        for head in 0..self.edges.len() {
+            if visited[head] {
+                continue;
+            }
+            let start = self.edges[head].start;
+            anchors.insert(start);
+            for &edge in &outgoing[&start] {
+                if !visited[edge] {
+                    merged_edges.push(self.merge_chain(edge, &through, &anchors, &mut visited));
+                }
+            }
+        }
+
+        let removed: Vec<i64> = mergeable.into_iter().filter(|node| !anchors.contains(node)).collect();
+        for node in &removed {
+            self.nodes.remove(node);
+        }
+        self.edges = merged_edges;
+        self.rebuild_indexes();
+        removed.len()
+    }
+
+    /// Pairs the edges at a node into straight continuations.
+    ///
+    /// Returns `None` if the node is a decision point: an incoming edge has
+    /// no or several possible continuations, or the node is a dead end.
+    fn continuations(&self, ins: &[usize], outs: &[usize]) -> Option<Vec<(usize, usize)>> {
+        if ins.len() != outs.len() || ins.len() > 2 {
+            return None;
+        }
+
+        let mut pairs = Vec::with_capacity(ins.len());
+        for &incoming in ins {
+            let before = &self.edges[incoming];
+            // Turning back where the edge came from is a U-turn, not a continuation
+            let mut candidates = outs.iter().copied().filter(|&outgoing| {
+                let after = &self.edges[outgoing];
+                outgoing != incoming && after.end != before.start && same_road(before, after)
+            });
+            let (Some(outgoing), None) = (candidates.next(), candidates.next()) else {
+                return None;
+            };
+            pairs.push((incoming, outgoing));
+        }
+
+        // Two-way roads: both directions must continue into distinct edges
+        if let [(in_a, out_a), (in_b, out_b)] = pairs[..] {
+            let opposite = self.edges[out_a].end == self.edges[in_b].start && self.edges[out_b].end == self.edges[in_a].start;
+            if out_a == out_b || !opposite {
+                return None;
+            }
+        }
+        Some(pairs)
+    }
+
+    /// Walks from `head` through mergeable nodes and returns the merged edge.
+    fn merge_chain(&self, head: usize, through: &HashMap<usize, usize>, anchors: &HashSet<i64>, visited: &mut [bool]) -> Road {
+        visited[head] = true;
+        let mut chain = self.edges[head].clone();
+        let mut last = head;
+        while let Some(&next) = through.get(&last) {
+            if anchors.contains(&chain.end) || visited[next] {
+                break;
+            }
+            visited[next] = true;
+            let road = &self.edges[next];
+            chain.geometry.extend(road.geometry.iter().skip(1).copied());
+            chain.length += road.length;
+            chain.end = road.end;
+            last = next;
+        }
+        chain
+    }
+}
+
+/// Returns `true` if two consecutive edges may be merged into one.
+fn same_road(a: &Road, b: &Road) -> bool {
+    a.id == b.id && a.direction == b.direction && a.highway_type == b.highway_type && a.speed_limit_mps == b.speed_limit_mps
+}
@@ -0,0 +1,762 @@
+//! Map input formats.
+//!
+//! Road graphs can be built from OSM PBF extracts, OSM XML files and GeoJSON
+//! line features. Every reader feeds the same [`GraphBuilder`], so
+//! coordinate validation, bounding-box filtering, cleaning and
+//! simplification behave identically regardless of the source format. On
+//! request the readers also collect points of interest (see [`PoiSet`]).
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use anyhow::{bail, Context, Result};
+use flate2::read::ZlibDecoder;
+use glam::DVec2;
+use osmpbfreader::fileformat::{Blob, BlobHeader};
+use osmpbfreader::osmformat::HeaderBlock;
+use osmpbfreader::{OsmObj, OsmPbfReader};
+use protobuf::Message;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde_json::Value;
+use crate::geo::{path_length, CoordinateCounters};
+use super::access::AccessTags;
+use super::direction::is_roundabout;
+use super::lanes::WayLanes;
+use super::layers::{node_levels, WayLevel};
+use super::progress::{LoadStage, Reporter};
+use super::turn_lanes::WayTurnLanes;
+use super::{
+    is_drivable, parse_maxspeed, travel_directions, Access, BoundingBox, CleanReport, Direction, MapMeta, Node, Poi, PoiKind, PoiSet,
+    Road, RoadGraph,
+};
+
+/// Highway class assumed for GeoJSON features without a `highway` property.
+const DEFAULT_GEOJSON_HIGHWAY: &str = "residential";
+
+/// A file a road graph can be loaded from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapSource {
+    /// OpenStreetMap PBF extract (`.osm.pbf`)
+    Pbf(String),
+    /// OpenStreetMap XML export (`.osm`, `.xml`)
+    OsmXml(String),
+    /// GeoJSON `FeatureCollection` of `LineString` roads (`.geojson`, `.json`)
+    GeoJson(String),
+}
+
+impl MapSource {
+    /// Picks the source format from a file's extension.
+    ///
+    /// Unknown extensions are treated as PBF, the format of the bundled maps.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use traffic_common::map::MapSource;
+    ///
+    /// assert_eq!(MapSource::from_path("berlin.osm.pbf"), MapSource::Pbf("berlin.osm.pbf".into()));
+    /// assert_eq!(MapSource::from_path("grid.geojson"), MapSource::GeoJson("grid.geojson".into()));
+    /// assert_eq!(MapSource::from_path("test.osm"), MapSource::OsmXml("test.osm".into()));
+    /// ```
+    pub fn from_path(path: &str) -> Self {
+        let lower = path.to_ascii_lowercase();
+        if lower.ends_with(".geojson") || lower.ends_with(".json") {
+            Self::GeoJson(path.to_string())
+        } else if lower.ends_with(".osm") || lower.ends_with(".xml") {
+            Self::OsmXml(path.to_string())
+        } else {
+            Self::Pbf(path.to_string())
+        }
+    }
+
+    /// Returns the path of the source file.
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Pbf(path) | Self::OsmXml(path) | Self::GeoJson(path) => path,
+        }
+    }
+
+    /// Loads the road graph from this source.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened or is malformed.
+    pub fn load(&self) -> Result<RoadGraph> {
+        self.load_bbox(None)
+    }
+
+    /// Loads the road graph, keeping only nodes inside `bbox` if given.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened or is malformed.
+    pub fn load_bbox(&self, bbox: Option<BoundingBox>) -> Result<RoadGraph> {
+        match &bbox {
+            Some(bbox) => tracing::info!("🗺️ Loading map from: {} (bbox {})", self.path(), bbox),
+            None => tracing::info!("🗺️ Loading map from: {}", self.path()),
+        }
+
+        self.load_reporting(bbox, &Reporter::silent())
+    }
+
+    /// [`MapSource::load_bbox`] reporting its progress.
+    pub(crate) fn load_reporting(&self, bbox: Option<BoundingBox>, reporter: &Reporter) -> Result<RoadGraph> {
+        let builder = self.parse(bbox, reporter)?;
+        reporter.report(self.path(), LoadStage::Building, 0, 0);
+        Ok(builder.build())
+    }
+
+    /// Reads the nodes and ways of this source without building the graph:
+    /// ways are not yet split into road segments and nothing is cleaned,
+    /// so several sources can be merged first (see [`GraphBuilder::merge`]).
+    pub(super) fn parse(&self, bbox: Option<BoundingBox>, reporter: &Reporter) -> Result<GraphBuilder> {
+        let meta = MapMeta::for_file(self.path(), bbox)?;
+        let meta = match self {
+            Self::Pbf(path) => meta.openstreetmap(pbf_replication_timestamp(path)),
+            Self::OsmXml(_) => meta.openstreetmap(None),
+            Self::GeoJson(_) => meta,
+        };
+        let mut builder = GraphBuilder::new(bbox);
+        builder.graph.meta = Some(meta);
+        self.read(&mut builder, reporter)?;
+        Ok(builder)
+    }
+
+    /// Collects the parking, fuel and charging stations of this source,
+    /// keeping only those inside `bbox` if given.
+    ///
+    /// The road network itself is not built.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened or is malformed.
+    pub fn load_pois(&self, bbox: Option<BoundingBox>) -> Result<PoiSet> {
+        let mut builder = GraphBuilder::new(bbox);
+        builder.pois = Some(Vec::new());
+        self.read(&mut builder, &Reporter::silent())?;
+
+        let pois = builder.pois.unwrap_or_default();
+        tracing::info!("📍 {} points of interest in {}", pois.len(), self.path());
+        Ok(PoiSet::new(pois))
+    }
+
+    /// Feeds the nodes and ways of this source to a builder.
+    fn read(&self, builder: &mut GraphBuilder, reporter: &Reporter) -> Result<()> {
+        let file = reporter.open(self.path(), LoadStage::Parsing).context("Could not open map file")?;
+        match self {
+            Self::Pbf(_) => read_pbf(file, builder),
+            Self::OsmXml(_) => read_osm_xml(file, builder),
+            Self::GeoJson(path) => read_geojson(path, file, builder),
+        }
+    }
+}
+
+/// A drivable way waiting for its nodes to be resolved.
+pub(super) struct PendingWay {
+    pub(super) id: i64,
+    pub(super) nodes: Vec<i64>,
+    highway: String,
+    directions: &'static [Direction],
+    speed_limit_mps: Option<f64>,
+    name: Option<String>,
+    ref_: Option<String>,
+    turn_lanes: WayTurnLanes,
+    lanes: WayLanes,
+    level: WayLevel,
+    roundabout: bool,
+    access: Access,
+}
+
+/// Tags of a way that are carried over to its road segments.
+struct WayTags<'a> {
+    highway: &'a str,
+    maxspeed: Option<&'a str>,
+    name: Option<&'a str>,
+    ref_: Option<&'a str>,
+    oneway: Option<&'a str>,
+    junction: Option<&'a str>,
+    turn_lanes: Option<&'a str>,
+    turn_lanes_forward: Option<&'a str>,
+    turn_lanes_backward: Option<&'a str>,
+    lanes: Option<&'a str>,
+    lanes_forward: Option<&'a str>,
+    lanes_backward: Option<&'a str>,
+    bridge: Option<&'a str>,
+    tunnel: Option<&'a str>,
+    layer: Option<&'a str>,
+    access: AccessTags<'a>,
+}
+
+/// Format-independent assembly of a road graph from nodes and ways.
+pub(super) struct GraphBuilder {
+    /// Nodes, signals and metadata read so far; no edges until built
+    pub(super) graph: RoadGraph,
+    bbox: Option<BoundingBox>,
+    pub(super) coordinates: CoordinateCounters,
+    pub(super) ways: Vec<PendingWay>,
+    /// Points of interest, if they are being collected
+    pois: Option<Vec<Poi>>,
+}
+
+impl GraphBuilder {
+    fn new(bbox: Option<BoundingBox>) -> Self {
+        Self {
+            graph: RoadGraph::default(),
+            bbox,
+            coordinates: CoordinateCounters::default(),
+            ways: Vec::new(),
+            pois: None,
+        }
+    }
+
+    /// Adds a node, unless its coordinate is invalid or outside the bbox.
+    fn add_node(&mut self, id: i64, lon: f64, lat: f64, signal: bool) {
+        // Drop nodes with unusable coordinates; their segments go with them
+        let Ok((lon, lat)) = self.coordinates.check(lon, lat) else {
+            return;
+        };
+        if self.bbox.is_some_and(|bbox| !bbox.contains(lon, lat)) {
+            return;
+        }
+        self.graph.nodes.insert(id, Node { id, pos: DVec2::new(lon, lat) });
+        if signal {
+            self.graph.signals.insert(id);
+        }
+    }
+
+    /// Collects a point of interest if points of interest are requested,
+    /// its `amenity` is one of the [`PoiKind`]s and it lies inside the bbox.
+    fn add_poi(&mut self, id: i64, lon: f64, lat: f64, amenity: Option<&str>, name: Option<&str>) {
+        let Some(pois) = &mut self.pois else { return };
+        let Some(kind) = amenity.and_then(PoiKind::from_amenity) else { return };
+        let Ok((lon, lat)) = self.coordinates.check(lon, lat) else { return };
+        if self.bbox.is_some_and(|bbox| !bbox.contains(lon, lat)) {
+            return;
+        }
+        pois.push(Poi { id, kind, pos: DVec2::new(lon, lat), name: name.map(str::to_string) });
+    }
+
+    /// Queues a way; ways of non-drivable classes and ways closed to cars
+    /// are ignored.
+    fn add_way(&mut self, id: i64, nodes: Vec<i64>, tags: WayTags) {
+        if !is_drivable(tags.highway) {
+            return;
+        }
+        let Some(access) = tags.access.car_access() else {
+            return;
+        };
+        let directions = travel_directions(tags.highway, tags.oneway, tags.junction);
+        self.ways.push(PendingWay {
+            id,
+            nodes,
+            highway: tags.highway.to_string(),
+            directions,
+            speed_limit_mps: tags.maxspeed.and_then(parse_maxspeed),
+            name: tags.name.map(str::to_string),
+            ref_: tags.ref_.map(str::to_string),
+            turn_lanes: WayTurnLanes::from_tags(
+                tags.turn_lanes,
+                tags.turn_lanes_forward,
+                tags.turn_lanes_backward,
+                directions,
+            ),
+            lanes: WayLanes::from_tags(tags.lanes, tags.lanes_forward, tags.lanes_backward, directions),
+            level: WayLevel::from_tags(tags.bridge, tags.tunnel, tags.layer),
+            roundabout: is_roundabout(tags.junction),
+            access,
+        });
+    }
+
+    /// Creates the road segments and runs the cleaning passes.
+    pub(super) fn build(mut self) -> RoadGraph {
+        if self.coordinates.rejected() > 0 || self.coordinates.normalized > 0 {
+            tracing::warn!("⚠️ Invalid node coordinates in map: {:?}", self.coordinates);
+        }
+
+        let mut graph = self.graph;
+
+        // Distinct nodes at the same coordinates and levels are one node (see
+        // `RoadGraph::clean`); collapse them before splitting so ways joined
+        // carelessly still meet, while overpasses stay apart
+        let levels = node_levels(self.ways.iter().map(|way| (way.level.level(), way.nodes.iter().copied())));
+        let duplicates = graph.duplicate_nodes(&levels);
+        for (duplicate, id) in &duplicates {
+            if graph.signals.contains(duplicate) {
+                graph.signals.insert(*id);
+            }
+        }
+        for way in &mut self.ways {
+            for node in &mut way.nodes {
+                *node = duplicates.get(node).copied().unwrap_or(*node);
+            }
+            way.nodes.dedup();
+        }
+
+        // How often each node is touched by a segment that is kept
+        let mut references: HashMap<i64, u32> = HashMap::new();
+        for way in &self.ways {
+            let present = |index: usize| way.nodes.get(index).is_some_and(|node| graph.nodes.contains_key(node));
+            for (index, node) in way.nodes.iter().enumerate() {
+                if present(index) && ((index > 0 && present(index - 1)) || present(index + 1)) {
+                    *references.entry(*node).or_default() += 1;
+                }
+            }
+        }
+
+        // Each way becomes one edge per span between decision points (nodes
+        // shared with another way or visited twice, signals, way ends) and
+        // permitted direction, carrying the geometry of the nodes between.
+        // Spans are also cut where a node is missing (invalid or outside the
+        // bbox).
+        let mut shape_nodes: Vec<i64> = Vec::new();
+        for way in self.ways.drain(..) {
+            let mut span: Vec<i64> = Vec::new();
+            for (index, &node) in way.nodes.iter().enumerate() {
+                if !graph.nodes.contains_key(&node) {
+                    emit_span(&mut graph, &way, &span, &mut shape_nodes);
+                    span.clear();
+                    continue;
+                }
+                span.push(node);
+                let decision = index + 1 == way.nodes.len()
+                    || references.get(&node).is_some_and(|&count| count > 1)
+                    || graph.signals.contains(&node);
+                if decision {
+                    emit_span(&mut graph, &way, &span, &mut shape_nodes);
+                    span = vec![node];
+                }
+            }
+            emit_span(&mut graph, &way, &span, &mut shape_nodes);
+        }
+
+        // Shape points live on in the geometry only
+        for node in shape_nodes {
+            graph.nodes.remove(&node);
+        }
+
+        // Drop degenerate geometry before it reaches the simulation
+        let cleaned = graph.clean();
+        if cleaned != CleanReport::default() {
+            tracing::info!(
+                "🧹 Cleaned map: merged {} duplicate nodes, dropped {} zero-length edges",
+                cleaned.duplicate_nodes,
+                cleaned.zero_length_edges
+            );
+        }
+
+        tracing::info!(
+            "✅ Map loaded: {} nodes, {} road segments.",
+            graph.nodes.len(),
+            graph.edges.len()
+        );
+        graph
+    }
+}
+
+/// Adds the edges of one span of a way, in every permitted direction.
+///
+/// Spans of fewer than two nodes are skipped. A span that closes on itself
+/// (a ring without other decision points) is cut in two at its middle node,
+/// so every edge connects two distinct nodes.
+fn emit_span(graph: &mut RoadGraph, way: &PendingWay, span: &[i64], shape_nodes: &mut Vec<i64>) {
+    if span.len() < 2 {
+        return;
+    }
+    if span.first() == span.last() && span.len() > 2 {
+        let middle = span.len() / 2;
+        emit_span(graph, way, &span[..=middle], shape_nodes);
+        emit_span(graph, way, &span[middle..], shape_nodes);
+        return;
+    }
+
+    let geometry: Vec<DVec2> = span.iter().map(|node| graph.nodes[node].pos).collect();
+    let length = path_length(&geometry);
+    shape_nodes.extend_from_slice(&span[1..span.len() - 1]);
+
+    let road = Road {
+        id: way.id,
+        direction: Direction::Forward,
+        start: span[0],
+        end: span[span.len() - 1],
+        length,
+        geometry,
+        highway_type: way.highway.clone(),
+        speed_limit_mps: way.speed_limit_mps,
+        name: way.name.clone(),
+        ref_: way.ref_.clone(),
+        grade: None,
+        turn_lanes: None,
+        lanes: None,
+        bridge: way.level.bridge,
+        tunnel: way.level.tunnel,
+        layer: way.level.layer,
+        roundabout: way.roundabout,
+        access: way.access,
+    };
+    for &direction in way.directions {
+        let mut edge = road.with_direction(direction);
+        edge.turn_lanes = way.turn_lanes.get(direction);
+        edge.lanes = way.lanes.get(direction);
+        graph.edges.push(edge);
+    }
+}
+
+/// Reads the replication timestamp from the header of an OSM PBF extract:
+/// the time of the last change included, in seconds since the Unix epoch.
+///
+/// Returns `None` if the header does not record one or cannot be read; the
+/// timestamp is informative only, so the map still loads.
+fn pbf_replication_timestamp(path: &str) -> Option<u64> {
+    let mut file = BufReader::new(File::open(path).ok()?);
+
+    // The header blob comes first: a big-endian length, a `BlobHeader`
+    // and the `Blob` it describes
+    let mut length = [0; 4];
+    file.read_exact(&mut length).ok()?;
+    let mut bytes = vec![0; u32::from_be_bytes(length) as usize];
+    file.read_exact(&mut bytes).ok()?;
+    let header = BlobHeader::parse_from_bytes(&bytes).ok()?;
+    if header.get_field_type() != "OSMHeader" {
+        return None;
+    }
+    let mut bytes = vec![0; usize::try_from(header.get_datasize()).ok()?];
+    file.read_exact(&mut bytes).ok()?;
+    let blob = Blob::parse_from_bytes(&bytes).ok()?;
+
+    let block = if blob.has_zlib_data() {
+        HeaderBlock::parse_from_reader(&mut ZlibDecoder::new(blob.get_zlib_data())).ok()?
+    } else {
+        HeaderBlock::parse_from_bytes(blob.get_raw()).ok()?
+    };
+    if !block.has_osmosis_replication_timestamp() {
+        return None;
+    }
+    u64::try_from(block.get_osmosis_replication_timestamp()).ok()
+}
+
+/// Reads nodes and highway ways from an OSM PBF extract.
+fn read_pbf(file: impl Read + Seek, builder: &mut GraphBuilder) -> Result<()> {
+    let mut pbf = OsmPbfReader::new(file);
+
+    // Extract nodes and ways that represent highways
+    let objs = pbf.get_objs_and_deps(|obj| {
+        obj.is_node() || (obj.is_way() && obj.tags().contains_key("highway"))
+    })?;
+
+    for obj in objs.values() {
+        match obj {
+            OsmObj::Node(n) => {
+                let signal = n.tags.get("highway").is_some_and(|v| v == "traffic_signals");
+                builder.add_node(n.id.0, n.lon(), n.lat(), signal);
+                let tag = |key: &str| n.tags.get(key).map(|s| s.as_str());
+                builder.add_poi(n.id.0, n.lon(), n.lat(), tag("amenity"), tag("name"));
+            }
+            OsmObj::Way(w) => {
+                let tag = |key: &str| w.tags.get(key).map(|s| s.as_str());
+                let tags = WayTags {
+                    highway: tag("highway").unwrap_or(""),
+                    maxspeed: tag("maxspeed"),
+                    name: tag("name"),
+                    ref_: tag("ref"),
+                    oneway: tag("oneway"),
+                    junction: tag("junction"),
+                    turn_lanes: tag("turn:lanes"),
+                    turn_lanes_forward: tag("turn:lanes:forward"),
+                    turn_lanes_backward: tag("turn:lanes:backward"),
+                    lanes: tag("lanes"),
+                    lanes_forward: tag("lanes:forward"),
+                    lanes_backward: tag("lanes:backward"),
+                    bridge: tag("bridge"),
+                    tunnel: tag("tunnel"),
+                    layer: tag("layer"),
+                    access: AccessTags {
+                        access: tag("access"),
+                        vehicle: tag("vehicle"),
+                        motor_vehicle: tag("motor_vehicle"),
+                        motorcar: tag("motorcar"),
+                        service: tag("service"),
+                    },
+                };
+                builder.add_way(w.id.0, w.nodes.iter().map(|n| n.0).collect(), tags);
+            }
+            OsmObj::Relation(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// An OSM XML element whose children are still being read.
+enum OpenElement {
+    Node { id: i64, lon: f64, lat: f64, signal: bool, amenity: Option<String>, name: Option<String> },
+    Way { id: i64, nodes: Vec<i64>, tags: HashMap<String, String> },
+}
+
+/// Reads nodes and ways from an OSM XML file (as exported by JOSM or the
+/// OSM API).
+fn read_osm_xml(file: impl Read, builder: &mut GraphBuilder) -> Result<()> {
+    let mut reader = Reader::from_reader(BufReader::new(file));
+    let mut buf = Vec::new();
+    let mut open: Option<OpenElement> = None;
+
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .with_context(|| format!("Malformed OSM XML at byte {}", reader.buffer_position()))?;
+        let self_closing = matches!(event, Event::Empty(_));
+        match event {
+            Event::Start(element) | Event::Empty(element) => {
+                match element.name().as_ref() {
+                    b"node" => {
+                        let node = OpenElement::Node {
+                            id: attribute(&element, "id")?,
+                            lon: attribute(&element, "lon")?,
+                            lat: attribute(&element, "lat")?,
+                            signal: false,
+                            amenity: None,
+                            name: None,
+                        };
+                        if self_closing {
+                            finish_element(node, builder);
+                        } else {
+                            open = Some(node);
+                        }
+                    }
+                    b"way" => {
+                        let way = OpenElement::Way {
+                            id: attribute(&element, "id")?,
+                            nodes: Vec::new(),
+                            tags: HashMap::new(),
+                        };
+                        if self_closing {
+                            finish_element(way, builder);
+                        } else {
+                            open = Some(way);
+                        }
+                    }
+                    b"nd" => {
+                        if let Some(OpenElement::Way { nodes, .. }) = &mut open {
+                            nodes.push(attribute(&element, "ref")?);
+                        }
+                    }
+                    b"tag" => {
+                        let key: String = attribute(&element, "k")?;
+                        let value: String = attribute(&element, "v")?;
+                        match &mut open {
+                            Some(OpenElement::Node { signal, amenity, name, .. }) => match key.as_str() {
+                                "highway" => *signal |= value == "traffic_signals",
+                                "amenity" => *amenity = Some(value),
+                                "name" => *name = Some(value),
+                                _ => {}
+                            },
+                            Some(OpenElement::Way { tags, .. }) => {
+                                tags.insert(key, value);
+                            }
+                            None => {}
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::End(element) if matches!(element.name().as_ref(), b"node" | b"way") => {
+                if let Some(finished) = open.take() {
+                    finish_element(finished, builder);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(())
+}
+
+/// Hands a completely read OSM XML element to the builder.
+fn finish_element(element: OpenElement, builder: &mut GraphBuilder) {
+    match element {
+        OpenElement::Node { id, lon, lat, signal, amenity, name } => {
+            builder.add_node(id, lon, lat, signal);
+            builder.add_poi(id, lon, lat, amenity.as_deref(), name.as_deref());
+        }
+        OpenElement::Way { id, nodes, tags } => {
+            let tag = |key: &str| tags.get(key).map(String::as_str);
+            let way_tags = WayTags {
+                highway: tag("highway").unwrap_or(""),
+                maxspeed: tag("maxspeed"),
+                name: tag("name"),
+                ref_: tag("ref"),
+                oneway: tag("oneway"),
+                junction: tag("junction"),
+                turn_lanes: tag("turn:lanes"),
+                turn_lanes_forward: tag("turn:lanes:forward"),
+                turn_lanes_backward: tag("turn:lanes:backward"),
+                lanes: tag("lanes"),
+                lanes_forward: tag("lanes:forward"),
+                lanes_backward: tag("lanes:backward"),
+                bridge: tag("bridge"),
+                tunnel: tag("tunnel"),
+                layer: tag("layer"),
+                access: AccessTags {
+                    access: tag("access"),
+                    vehicle: tag("vehicle"),
+                    motor_vehicle: tag("motor_vehicle"),
+                    motorcar: tag("motorcar"),
+                    service: tag("service"),
+                },
+            };
+            builder.add_way(id, nodes, way_tags);
+        }
+    }
+}
+
+/// Parses a required attribute of an OSM XML element.
+pub(super) fn attribute<T: std::str::FromStr>(element: &BytesStart, name: &str) -> Result<T> {
+    let attr = element
+        .try_get_attribute(name)?
+        .with_context(|| format!("OSM XML element is missing attribute '{}'", name))?;
+    let value = attr.unescape_value()?;
+    value
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid value '{}' for OSM XML attribute '{}'", value, name))
+}
+
+/// Reads roads from a GeoJSON `FeatureCollection`.
+///
+/// Every `LineString` or `MultiLineString` feature becomes a way. Its
+/// `highway`, `maxspeed`, `lanes`, `turn:lanes`, `bridge`, `tunnel`, `layer`
+/// and access properties are used like OSM tags (features without `highway` count as
+/// residential streets) and its numeric `id` (feature or property) becomes
+/// the way ID. Lines sharing a coordinate are
+/// connected there if one of them ends there or both are on the same level
+/// (see [`WayLevel`]), so overpasses drawn through a vertex of the road
+/// below stay apart. `Point` features with `highway=traffic_signals` mark the
+/// node at their coordinate as a signal; those with an `amenity` property
+/// are points of interest.
+fn read_geojson(path: &str, file: impl Read, builder: &mut GraphBuilder) -> Result<()> {
+    let root: Value = serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("{} is not valid JSON", path))?;
+    let Some(features) = root.get("features").and_then(Value::as_array) else {
+        bail!("{} is not a GeoJSON FeatureCollection", path);
+    };
+
+    // Line ends join every line through their coordinate, whatever its level
+    let coordinate_key = |coordinate: &Value| -> Option<(u64, u64)> {
+        Some((coordinate.get(0)?.as_f64()?.to_bits(), coordinate.get(1)?.as_f64()?.to_bits()))
+    };
+    let mut line_ends: HashSet<(u64, u64)> = HashSet::new();
+    for feature in features {
+        let Some(geometry) = feature.get("geometry") else { continue };
+        let coordinates = geometry.get("coordinates").unwrap_or(&Value::Null);
+        let lines: Vec<&Value> = match geometry.get("type").and_then(Value::as_str) {
+            Some("LineString") => vec![coordinates],
+            Some("MultiLineString") => coordinates.as_array().map(|l| l.iter().collect()).unwrap_or_default(),
+            _ => continue,
+        };
+        for points in lines.into_iter().filter_map(Value::as_array) {
+            line_ends.extend([points.first(), points.last()].into_iter().flatten().filter_map(coordinate_key));
+        }
+    }
+
+    // Nodes are identified by coordinate and, away from line ends, level;
+    // synthetic IDs are negative so they never collide with real OSM IDs
+    let mut node_ids: HashMap<(u64, u64, Option<i8>), i64> = HashMap::new();
+    let mut node_id = |builder: &mut GraphBuilder, coordinate: &Value, level: i8, signal: bool| -> Option<i64> {
+        let lon = coordinate.get(0)?.as_f64()?;
+        let lat = coordinate.get(1)?.as_f64()?;
+        let (x, y) = (lon.to_bits(), lat.to_bits());
+        let level = (!line_ends.contains(&(x, y))).then_some(level);
+        let next_id = -(node_ids.len() as i64) - 1;
+        let id = *node_ids.entry((x, y, level)).or_insert(next_id);
+        if id == next_id || signal {
+            builder.add_node(id, lon, lat, signal);
+        }
+        Some(id)
+    };
+
+    for (index, feature) in features.iter().enumerate() {
+        let properties = feature.get("properties").unwrap_or(&Value::Null);
+        let property = |key: &str| properties.get(key).and_then(Value::as_str);
+        let Some(geometry) = feature.get("geometry") else { continue };
+        let coordinates = geometry.get("coordinates").unwrap_or(&Value::Null);
+        let id = feature
+            .get("id")
+            .or_else(|| properties.get("id"))
+            .and_then(Value::as_i64)
+            .unwrap_or(-(index as i64) - 1);
+
+        let lines: Vec<&Value> = match geometry.get("type").and_then(Value::as_str) {
+            Some("LineString") => vec![coordinates],
+            Some("MultiLineString") => coordinates.as_array().map(|l| l.iter().collect()).unwrap_or_default(),
+            Some("Point") => {
+                if property("highway") == Some("traffic_signals") {
+                    node_id(builder, coordinates, 0, true);
+                }
+                if let (Some(lon), Some(lat)) = (
+                    coordinates.get(0).and_then(Value::as_f64),
+                    coordinates.get(1).and_then(Value::as_f64),
+                ) {
+                    builder.add_poi(id, lon, lat, property("amenity"), property("name"));
+                }
+                continue;
+            }
+            _ => continue,
+        };
+
+        let highway = property("highway").unwrap_or(DEFAULT_GEOJSON_HIGHWAY);
+        // maxspeed may be given as a plain number of km/h
+        let maxspeed = properties.get("maxspeed").and_then(|value| {
+            value.as_str().map(str::to_string).or_else(|| value.as_f64().map(|kmh| kmh.to_string()))
+        });
+        // oneway, bridge, tunnel and access may be given as booleans, layer as a number
+        let flag = |key: &str| {
+            properties.get(key).and_then(|value| {
+                value.as_str().or_else(|| value.as_bool().map(|set| if set { "yes" } else { "no" }))
+            })
+        };
+        let oneway = flag("oneway");
+        // Lane counts may be given as numbers
+        let count = |key: &str| {
+            properties.get(key).and_then(|value| {
+                value.as_str().map(str::to_string).or_else(|| value.as_u64().map(|lanes| lanes.to_string()))
+            })
+        };
+        let (lanes, lanes_forward, lanes_backward) = (count("lanes"), count("lanes:forward"), count("lanes:backward"));
+        let layer = properties.get("layer").and_then(|value| {
+            value.as_str().map(str::to_string).or_else(|| value.as_i64().map(|layer| layer.to_string()))
+        });
+        let level = WayLevel::from_tags(flag("bridge"), flag("tunnel"), layer.as_deref()).level();
+
+        for line in lines {
+            let nodes = line
+                .as_array()
+                .map(|points| points.iter().filter_map(|point| node_id(builder, point, level, false)).collect())
+                .unwrap_or_default();
+            let tags = WayTags {
+                highway,
+                maxspeed: maxspeed.as_deref(),
+                name: property("name"),
+                ref_: property("ref"),
+                oneway,
+                junction: property("junction"),
+                turn_lanes: property("turn:lanes"),
+                turn_lanes_forward: property("turn:lanes:forward"),
+                turn_lanes_backward: property("turn:lanes:backward"),
+                lanes: lanes.as_deref(),
+                lanes_forward: lanes_forward.as_deref(),
+                lanes_backward: lanes_backward.as_deref(),
+                bridge: flag("bridge"),
+                tunnel: flag("tunnel"),
+                layer: layer.as_deref(),
+                access: AccessTags {
+                    access: flag("access"),
+                    vehicle: flag("vehicle"),
+                    motor_vehicle: flag("motor_vehicle"),
+                    motorcar: flag("motorcar"),
+                    service: property("service"),
+                },
+            };
+            builder.add_way(id, nodes, tags);
+        }
+    }
+    Ok(())
+}
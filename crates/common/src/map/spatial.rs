@@ -0,0 +1,237 @@
+//! Spatial indexes over road geometry and nodes.
+//!
+//! Every straight piece of every road's polyline is stored in an R-tree so
+//! that arbitrary coordinates (e.g. incoming GPS points) can be snapped to
+//! the nearest road in O(log n) instead of scanning all edges. A second
+//! tree over the graph's nodes turns coordinates into routing endpoints.
+
+use rstar::primitives::{GeomWithData, Line};
+use rstar::{RTree, AABB};
+use super::{BoundingBox, RoadGraph};
+
+/// A geometry piece of a road, tagged with the index of its edge and the
+/// position of the piece within the edge's polyline.
+type IndexedSegment = GeomWithData<Line<[f64; 2]>, (usize, usize)>;
+
+/// A graph node, tagged with its OSM ID.
+type IndexedNode = GeomWithData<[f64; 2], i64>;
+
+/// Approximate meters per degree of latitude.
+pub(crate) const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// A road geometry piece near a queried coordinate.
+#[derive(Debug, Clone, Copy)]
+pub struct SegmentCandidate {
+    /// Index of the edge the piece belongs to
+    pub edge: usize,
+    /// Position of the piece within the edge's polyline
+    pub piece: usize,
+    /// Approximate distance from the query point to the piece in meters
+    pub distance_m: f64,
+    /// Closest point on the piece as (longitude, latitude)
+    pub closest: [f64; 2],
+    /// Direction of the piece in degrees clockwise from north
+    pub bearing: f64,
+}
+
+/// R-tree of road geometry pieces.
+///
+/// Coordinates are stored in a locally scaled space where longitudes are
+/// multiplied by the cosine of the network's mean latitude, so Euclidean
+/// distances in the tree are proportional to ground distances.
+#[derive(Debug, Default)]
+pub struct EdgeIndex {
+    tree: RTree<IndexedSegment>,
+    lon_scale: f64,
+}
+
+impl EdgeIndex {
+    /// Builds the index from all edges of a graph.
+    pub fn build(graph: &RoadGraph) -> Self {
+        let lat_sum: f64 = graph.nodes.values().map(|node| node.pos.y).sum();
+        let mean_lat = if graph.nodes.is_empty() { 0.0 } else { lat_sum / graph.nodes.len() as f64 };
+        let lon_scale = mean_lat.to_radians().cos();
+
+        let segments = graph.edges.iter()
+            .enumerate()
+            .flat_map(|(edge_idx, road)| {
+                road.geometry.windows(2).enumerate().map(move |(piece, pair)| {
+                    let a = [pair[0].x * lon_scale, pair[0].y];
+                    let b = [pair[1].x * lon_scale, pair[1].y];
+                    GeomWithData::new(Line::new(a, b), (edge_idx, piece))
+                })
+            })
+            .collect();
+
+        Self {
+            tree: RTree::bulk_load(segments),
+            lon_scale,
+        }
+    }
+
+    /// Estimates the heap memory of the index in bytes.
+    ///
+    /// Counts the indexed segments; the tree's inner nodes add a few
+    /// percent on top.
+    pub(crate) fn memory_bytes(&self) -> usize {
+        self.tree.size() * std::mem::size_of::<IndexedSegment>()
+    }
+
+    /// Returns the longitude scale of the index space.
+    pub(crate) fn lon_scale(&self) -> f64 {
+        self.lon_scale
+    }
+
+    /// Returns the index of the edge closest to the given coordinate.
+    pub fn nearest(&self, lon: f64, lat: f64) -> Option<usize> {
+        self.tree
+            .nearest_neighbor(&[lon * self.lon_scale, lat])
+            .map(|segment| segment.data.0)
+    }
+
+    /// Returns the indices of edges with geometry inside or crossing a box,
+    /// in ascending order.
+    pub fn edges_in(&self, bbox: &BoundingBox) -> Vec<usize> {
+        let envelope = AABB::from_corners(
+            [bbox.min_lon * self.lon_scale, bbox.min_lat],
+            [bbox.max_lon * self.lon_scale, bbox.max_lat],
+        );
+        let mut edges: Vec<usize> = self.tree
+            .locate_in_envelope_intersecting(&envelope)
+            .map(|segment| segment.data.0)
+            .collect();
+        edges.sort_unstable();
+        edges.dedup();
+        edges
+    }
+
+    /// Returns road pieces within `radius_m` of a coordinate, nearest first.
+    ///
+    /// If no piece is that close, only the single nearest piece is returned,
+    /// so the result is empty only for an empty index.
+    pub fn candidates(&self, lon: f64, lat: f64, radius_m: f64) -> Vec<SegmentCandidate> {
+        let query = [lon * self.lon_scale, lat];
+        let radius = radius_m / METERS_PER_DEGREE;
+        let mut found: Vec<SegmentCandidate> = self.tree
+            .nearest_neighbor_iter_with_distance_2(&query)
+            .take_while(|(_, distance_2)| *distance_2 <= radius * radius)
+            .map(|(segment, distance_2)| self.candidate(segment, &query, distance_2))
+            .collect();
+
+        if found.is_empty() {
+            if let Some((segment, distance_2)) = self.tree.nearest_neighbor_iter_with_distance_2(&query).next() {
+                found.push(self.candidate(segment, &query, distance_2));
+            }
+        }
+        found
+    }
+
+    /// Converts an indexed piece into a candidate in geographic terms.
+    fn candidate(&self, segment: &IndexedSegment, query: &[f64; 2], distance_2: f64) -> SegmentCandidate {
+        let line = segment.geom();
+        let closest = line.nearest_point(query);
+        let dx = line.to[0] - line.from[0];
+        let dy = line.to[1] - line.from[1];
+        SegmentCandidate {
+            edge: segment.data.0,
+            piece: segment.data.1,
+            distance_m: distance_2.sqrt() * METERS_PER_DEGREE,
+            closest: [closest[0] / self.lon_scale, closest[1]],
+            bearing: dx.atan2(dy).to_degrees().rem_euclid(360.0),
+        }
+    }
+}
+
+/// R-tree of graph nodes, in the same scaled space as [`EdgeIndex`].
+#[derive(Debug, Default)]
+pub(crate) struct NodeIndex {
+    tree: RTree<IndexedNode>,
+    lon_scale: f64,
+}
+
+impl NodeIndex {
+    /// Builds the index from all nodes of a graph, using the longitude
+    /// scale of its edge index.
+    pub(crate) fn build(graph: &RoadGraph, lon_scale: f64) -> Self {
+        let nodes = graph.nodes
+            .values()
+            .map(|node| GeomWithData::new([node.pos.x * lon_scale, node.pos.y], node.id))
+            .collect();
+        Self {
+            tree: RTree::bulk_load(nodes),
+            lon_scale,
+        }
+    }
+
+    /// Returns the ID of the node closest to the given coordinate.
+    pub(crate) fn nearest(&self, lon: f64, lat: f64) -> Option<i64> {
+        self.tree
+            .nearest_neighbor(&[lon * self.lon_scale, lat])
+            .map(|node| node.data)
+    }
+
+    /// Estimates the heap memory of the index in bytes.
+    pub(crate) fn memory_bytes(&self) -> usize {
+        self.tree.size() * std::mem::size_of::<IndexedNode>()
+    }
+}
+
+impl RoadGraph {
+    /// Finds the road segment closest to a coordinate.
+    ///
+    /// Backed by an R-tree built at load time, so lookups take O(log n).
+    ///
+    /// # Arguments
+    ///
+    /// * `lon` - Longitude in degrees
+    /// * `lat` - Latitude in degrees
+    ///
+    /// # Returns
+    ///
+    /// The index into `edges` of the nearest road, or `None` if the graph
+    /// has no edges.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use traffic_common::map::RoadGraph;
+    ///
+    /// let graph = RoadGraph::load_from_pbf("map.osm.pbf").unwrap();
+    /// if let Some(edge) = graph.nearest_edge(13.405, 52.52) {
+    ///     println!("Snapped to way {}", graph.edges[edge].id);
+    /// }
+    /// ```
+    pub fn nearest_edge(&self, lon: f64, lat: f64) -> Option<usize> {
+        self.edge_index.nearest(lon, lat)
+    }
+
+    /// Finds the graph node closest to a coordinate, e.g. to turn arbitrary
+    /// coordinates into routing origins and destinations.
+    ///
+    /// Backed by an R-tree built at load time, so lookups take O(log n).
+    /// The node may be a dead end (e.g. the end of a one-way street), so
+    /// a route from it is not guaranteed to exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `lon` - Longitude in degrees
+    /// * `lat` - Latitude in degrees
+    ///
+    /// # Returns
+    ///
+    /// The OSM ID of the nearest node, or `None` if the graph has no nodes.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use traffic_common::map::RoadGraph;
+    ///
+    /// let graph = RoadGraph::load_from_pbf("map.osm.pbf").unwrap();
+    /// let from = graph.nearest_node(13.377, 52.516).unwrap();
+    /// let to = graph.nearest_node(13.413, 52.522).unwrap();
+    /// let route = graph.shortest_path(from, to);
+    /// ```
+    pub fn nearest_node(&self, lon: f64, lat: f64) -> Option<i64> {
+        self.node_index.nearest(lon, lat)
+    }
+}
@@ -0,0 +1,93 @@
+//! Speed limits from OSM `maxspeed` tags.
+//!
+//! `maxspeed` values come in several forms: plain numbers in km/h
+//! (`"50"`), numbers with a unit (`"30 mph"`, `"10 knots"`), the special
+//! value `"walk"`, and implicit zone limits such as `"DE:urban"` or
+//! `"DE:zone30"`.
+
+/// Walking pace used for `maxspeed=walk`, in km/h.
+const WALK_KMH: f64 = 7.0;
+
+/// Kilometers per mile.
+const KM_PER_MILE: f64 = 1.609_344;
+
+/// Kilometers per nautical mile.
+const KM_PER_NAUTICAL_MILE: f64 = 1.852;
+
+/// Parses an OSM `maxspeed` value into a limit in m/s.
+///
+/// # Arguments
+///
+/// * `value` - Raw `maxspeed` tag value
+///
+/// # Returns
+///
+/// The limit in m/s, or `None` if the road has no fixed limit (`"none"`,
+/// `"signals"`, `"variable"`) or the value cannot be interpreted. For
+/// multi-valued tags (`"50;30"`) the first value is used.
+///
+/// # Examples
+///
+/// ```
+/// use traffic_common::map::parse_maxspeed;
+///
+/// assert_eq!(parse_maxspeed("36").map(f64::round), Some(10.0));
+/// assert!(parse_maxspeed("30 mph").is_some());
+/// assert!(parse_maxspeed("DE:urban").is_some());
+/// assert_eq!(parse_maxspeed("none"), None);
+/// ```
+pub fn parse_maxspeed(value: &str) -> Option<f64> {
+    let value = value.split(';').next()?.trim();
+
+    let kmh = if value.eq_ignore_ascii_case("walk") {
+        WALK_KMH
+    } else if let Some(zone) = value.split_once(':').map(|(_, zone)| zone) {
+        zone_limit_kmh(zone)?
+    } else if let Some(number) = value.strip_suffix("mph") {
+        number.trim().parse::<f64>().ok()? * KM_PER_MILE
+    } else if let Some(number) = value.strip_suffix("knots") {
+        number.trim().parse::<f64>().ok()? * KM_PER_NAUTICAL_MILE
+    } else {
+        value.strip_suffix("km/h").unwrap_or(value).trim().parse::<f64>().ok()?
+    };
+
+    (kmh.is_finite() && kmh > 0.0).then_some(kmh / 3.6)
+}
+
+/// Returns the limit implied by a zone value (the part after `CC:`), in km/h.
+///
+/// Uses the common European defaults; motorways without a general limit
+/// return `None`.
+fn zone_limit_kmh(zone: &str) -> Option<f64> {
+    // Zone limits such as "zone30" or "zone:30"
+    if let Some(number) = zone.strip_prefix("zone") {
+        return number.trim_start_matches(':').parse().ok();
+    }
+
+    match zone {
+        "urban" => Some(50.0),
+        "rural" => Some(100.0),
+        "trunk" => Some(100.0),
+        "living_street" => Some(WALK_KMH),
+        "bicycle_road" => Some(30.0),
+        "walk" => Some(WALK_KMH),
+        _ => None,
+    }
+}
+
+/// Returns the typical speed limit of a highway class in m/s.
+///
+/// Used where no explicit limit is known. Values follow common German urban
+/// limits (50 km/h in town, 30 km/h or less on minor roads).
+pub fn default_speed_limit_mps(highway_type: &str) -> f64 {
+    let kmh = match highway_type {
+        "motorway" => 130.0,
+        "trunk" => 100.0,
+        "primary" | "secondary" | "tertiary" => 50.0,
+        "residential" => 30.0,
+        "service" => 20.0,
+        "living_street" => 7.0,
+        _ => 50.0,
+    };
+    kmh / 3.6
+}
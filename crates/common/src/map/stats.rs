@@ -0,0 +1,132 @@
+//! Summary statistics of a loaded road graph.
+//!
+//! Gives operators a quick picture of what a map extract contains (how
+//! much of each road class, how large an area, how well connected) without
+//! opening it in a GIS tool.
+
+use std::collections::{BTreeMap, HashSet};
+use serde::Serialize;
+use super::{BoundingBox, RoadGraph};
+
+/// Meters per kilometer.
+const METERS_PER_KM: f64 = 1000.0;
+
+/// Counts and lengths of one highway class.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ClassStats {
+    /// Nodes touched by at least one segment of the class
+    pub nodes: usize,
+    /// Directed segments of the class
+    pub edges: usize,
+    /// Length of the class's roads in km, each two-way stretch counted once
+    pub km: f64,
+}
+
+/// How well the directed graph is connected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Connectivity {
+    /// Strongly connected components
+    pub components: usize,
+    /// Nodes of the largest strongly connected component
+    pub largest_component_nodes: usize,
+    /// Share of all nodes in the largest component (0 to 1)
+    pub largest_component_share: f64,
+    /// Nodes without outgoing segments, where vehicles get stuck
+    pub dead_ends: usize,
+    /// Nodes without any segment
+    pub isolated_nodes: usize,
+    /// Mean number of outgoing segments per node
+    pub avg_out_degree: f64,
+}
+
+/// Summary of a [`RoadGraph`], from [`RoadGraph::stats`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GraphStats {
+    /// Nodes in the graph
+    pub nodes: usize,
+    /// Directed road segments in the graph
+    pub edges: usize,
+    /// Signalized nodes
+    pub signals: usize,
+    /// Per highway class (e.g. "residential"), sorted by class name
+    pub classes: BTreeMap<String, ClassStats>,
+    /// Length of the road network in km, each two-way stretch counted once
+    pub total_km: f64,
+    /// Mean length of a directed segment in meters
+    pub avg_edge_length_m: f64,
+    /// Extent of all nodes, or `None` for an empty graph
+    pub bbox: Option<BoundingBox>,
+    /// Connectivity metrics
+    pub connectivity: Connectivity,
+}
+
+impl RoadGraph {
+    /// Computes summary statistics of the graph.
+    ///
+    /// Runs a strongly connected component search, so it takes about as
+    /// long as [`RoadGraph::strongly_connected_components`]; compute it once
+    /// after loading rather than per request.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use traffic_common::map::RoadGraph;
+    ///
+    /// let graph = RoadGraph::load_from_pbf("map.osm.pbf").unwrap();
+    /// let stats = graph.stats();
+    /// println!("{:.0} km of road, {} classes", stats.total_km, stats.classes.len());
+    /// ```
+    pub fn stats(&self) -> GraphStats {
+        let mut classes: BTreeMap<String, ClassStats> = BTreeMap::new();
+        let mut class_nodes: BTreeMap<&str, HashSet<i64>> = BTreeMap::new();
+        let mut total_m = 0.0;
+        let mut directed_m = 0.0;
+        for (edge, road) in self.edges.iter().enumerate() {
+            let class = classes.entry(road.highway_type.clone()).or_default();
+            class.edges += 1;
+            directed_m += road.length;
+            if self.is_drawn_edge(edge) {
+                class.km += road.length / METERS_PER_KM;
+                total_m += road.length;
+            }
+            let nodes = class_nodes.entry(&road.highway_type).or_default();
+            nodes.insert(road.start);
+            nodes.insert(road.end);
+        }
+        for (class, nodes) in class_nodes {
+            if let Some(stats) = classes.get_mut(class) {
+                stats.nodes = nodes.len();
+            }
+        }
+
+        let bbox = BoundingBox::enclosing(self.nodes.values().map(|node| (node.pos.x, node.pos.y)));
+
+        GraphStats {
+            nodes: self.nodes.len(),
+            edges: self.edges.len(),
+            signals: self.signals.len(),
+            classes,
+            total_km: total_m / METERS_PER_KM,
+            avg_edge_length_m: if self.edges.is_empty() { 0.0 } else { directed_m / self.edges.len() as f64 },
+            bbox,
+            connectivity: self.connectivity(),
+        }
+    }
+
+    /// Computes the connectivity part of [`RoadGraph::stats`].
+    fn connectivity(&self) -> Connectivity {
+        let components = self.strongly_connected_components();
+        let largest = components.first().map_or(0, Vec::len);
+        let out_degree = |id: &i64| self.out_edges.get(id).map_or(0, Vec::len);
+        let in_degree = |id: &i64| self.in_edges.get(id).map_or(0, Vec::len);
+
+        Connectivity {
+            components: components.len(),
+            largest_component_nodes: largest,
+            largest_component_share: if self.nodes.is_empty() { 0.0 } else { largest as f64 / self.nodes.len() as f64 },
+            dead_ends: self.nodes.keys().filter(|id| out_degree(id) == 0 && in_degree(id) > 0).count(),
+            isolated_nodes: self.nodes.keys().filter(|id| out_degree(id) == 0 && in_degree(id) == 0).count(),
+            avg_out_degree: if self.nodes.is_empty() { 0.0 } else { self.edges.len() as f64 / self.nodes.len() as f64 },
+        }
+    }
+}
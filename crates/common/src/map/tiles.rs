@@ -0,0 +1,354 @@
+//! Mapbox Vector Tile (MVT) encoding of the road network.
+//!
+//! Clients showing a whole city cannot afford to download every road as
+//! JSON up front. Vector tiles split the network into the standard web
+//! mercator `z/x/y` grid, so a map only fetches what is on screen, and
+//! minor roads are left out of tiles at low zoom levels.
+//!
+//! Tiles have a single `roads` layer with one line feature per road
+//! segment (two-way roads appear once, see [`RoadGraph::is_drawn_edge`]),
+//! carrying the attributes `edge` (index into
+//! [`RoadGraph::edges`]), `highway`, and, if known, `name`, `ref` and
+//! `maxspeed` (km/h).
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use glam::DVec2;
+use prost::Message;
+use super::{BoundingBox, Road, RoadGraph};
+use vector_tile::tile::{Feature, GeomType, Layer, Value};
+use vector_tile::Tile;
+
+/// Generated Mapbox Vector Tile protobuf types.
+#[allow(dead_code)]
+mod vector_tile {
+    include!(concat!(env!("OUT_DIR"), "/vector_tile.rs"));
+}
+
+/// Highest zoom level tiles are generated for.
+pub const MAX_TILE_ZOOM: u8 = 22;
+
+/// Name of the layer holding the road segments.
+pub const ROADS_LAYER: &str = "roads";
+
+/// Tile coordinate units per tile side.
+const EXTENT: u32 = 4096;
+
+/// Geometry kept beyond the tile edge, in tile units, so lines rendered
+/// with a width don't show seams between tiles.
+const BUFFER: f64 = 64.0;
+
+/// Latitude limit of the web mercator projection.
+const MAX_LATITUDE: f64 = 85.051_128_78;
+
+/// MVT version written into the layer.
+const MVT_VERSION: u32 = 2;
+
+/// Geometry command IDs.
+const MOVE_TO: u32 = 1;
+const LINE_TO: u32 = 2;
+
+/// Lowest zoom level at which roads of a highway class are included.
+///
+/// Motorways and trunk roads are visible from the region level, minor
+/// streets only once individual blocks can be told apart.
+pub fn min_zoom(highway: &str) -> u8 {
+    match highway.trim_end_matches("_link") {
+        "motorway" | "trunk" => 5,
+        "primary" => 8,
+        "secondary" => 10,
+        "tertiary" => 11,
+        _ => 13,
+    }
+}
+
+/// Returns `true` if `z/x/y` addresses an existing tile up to [`MAX_TILE_ZOOM`].
+pub fn is_valid_tile(z: u8, x: u32, y: u32) -> bool {
+    z <= MAX_TILE_ZOOM && x < 1 << z && y < 1 << z
+}
+
+/// Position of a tile in the web mercator grid.
+#[derive(Debug, Clone, Copy)]
+struct TileId {
+    x: u32,
+    y: u32,
+    /// Number of tiles per axis at the tile's zoom level
+    tiles: f64,
+}
+
+impl TileId {
+    /// Converts (longitude, latitude) into tile units relative to this tile.
+    fn project(&self, pos: DVec2) -> DVec2 {
+        let lat = pos.y.clamp(-MAX_LATITUDE, MAX_LATITUDE).to_radians();
+        let world_x = (pos.x + 180.0) / 360.0 * self.tiles;
+        let world_y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * self.tiles;
+        DVec2::new(world_x - self.x as f64, world_y - self.y as f64) * EXTENT as f64
+    }
+
+    /// Converts tile units relative to this tile back into (longitude, latitude).
+    fn unproject(&self, local: DVec2) -> DVec2 {
+        let world = local / EXTENT as f64 + DVec2::new(self.x as f64, self.y as f64);
+        let lon = world.x / self.tiles * 360.0 - 180.0;
+        let lat = (PI * (1.0 - 2.0 * world.y / self.tiles)).sinh().atan().to_degrees();
+        DVec2::new(lon, lat)
+    }
+
+    /// Geographic box covered by the tile and its buffer.
+    fn bounds(&self) -> BoundingBox {
+        let north_west = self.unproject(DVec2::splat(-BUFFER));
+        let south_east = self.unproject(DVec2::splat(EXTENT as f64 + BUFFER));
+        BoundingBox {
+            min_lon: north_west.x,
+            min_lat: south_east.y,
+            max_lon: south_east.x,
+            max_lat: north_west.y,
+        }
+    }
+}
+
+impl RoadGraph {
+    /// Encodes the road segments of a web mercator tile as a Mapbox Vector Tile.
+    ///
+    /// Only roads whose class is visible at zoom `z` (see [`min_zoom`]) are
+    /// included. Geometry is clipped to the tile plus a small buffer.
+    ///
+    /// # Arguments
+    ///
+    /// * `z` - Zoom level
+    /// * `x` - Tile column, counted eastwards from 180° W
+    /// * `y` - Tile row, counted southwards from 85.05° N
+    ///
+    /// # Returns
+    ///
+    /// The protobuf-encoded tile. Tiles without roads, and coordinates
+    /// rejected by [`is_valid_tile`], yield an empty tile (no bytes).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use traffic_common::map::RoadGraph;
+    ///
+    /// let graph = RoadGraph::load_from_pbf("berlin.osm.pbf").unwrap();
+    /// let tile = graph.tile(14, 8802, 5373);
+    /// std::fs::write("8802-5373.mvt", tile).unwrap();
+    /// ```
+    pub fn tile(&self, z: u8, x: u32, y: u32) -> Vec<u8> {
+        if !is_valid_tile(z, x, y) {
+            return Vec::new();
+        }
+        let id = TileId { x, y, tiles: f64::from(1u32 << z) };
+
+        let mut layer = LayerBuilder::default();
+        for edge in self.edge_index.edges_in(&id.bounds()) {
+            let road = &self.edges[edge];
+            if min_zoom(&road.highway_type) > z || !self.is_drawn_edge(edge) {
+                continue;
+            }
+            let points: Vec<DVec2> = road.geometry.iter().map(|&pos| id.project(pos)).collect();
+            let geometry = encode_lines(&clip_polyline(&points));
+            if !geometry.is_empty() {
+                layer.add(edge, road, geometry);
+            }
+        }
+
+        if layer.features.is_empty() {
+            return Vec::new();
+        }
+        Tile { layers: vec![layer.finish()] }.encode_to_vec()
+    }
+}
+
+/// Attribute value, hashable for deduplication within a layer.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum TagValue {
+    String(String),
+    Uint(u64),
+}
+
+/// Collects features and deduplicates their attribute keys and values.
+#[derive(Default)]
+struct LayerBuilder {
+    features: Vec<Feature>,
+    keys: Vec<String>,
+    key_index: HashMap<&'static str, u32>,
+    values: Vec<TagValue>,
+    value_index: HashMap<TagValue, u32>,
+}
+
+impl LayerBuilder {
+    /// Adds a road segment with already encoded geometry.
+    fn add(&mut self, edge: usize, road: &Road, geometry: Vec<u32>) {
+        let mut tags = Vec::new();
+        let mut tag = |builder: &mut Self, key: &'static str, value: TagValue| {
+            tags.push(builder.key(key));
+            tags.push(builder.value(value));
+        };
+        tag(self, "edge", TagValue::Uint(edge as u64));
+        tag(self, "highway", TagValue::String(road.highway_type.clone()));
+        if let Some(name) = &road.name {
+            tag(self, "name", TagValue::String(name.clone()));
+        }
+        if let Some(ref_) = &road.ref_ {
+            tag(self, "ref", TagValue::String(ref_.clone()));
+        }
+        if let Some(limit) = road.speed_limit_mps {
+            tag(self, "maxspeed", TagValue::Uint((limit * 3.6).round() as u64));
+        }
+
+        self.features.push(Feature {
+            // Negative IDs (synthetic ways) can't be represented
+            id: u64::try_from(road.id).ok(),
+            tags,
+            r#type: Some(GeomType::Linestring as i32),
+            geometry,
+        });
+    }
+
+    /// Returns the index of a key, adding it on first use.
+    fn key(&mut self, key: &'static str) -> u32 {
+        *self.key_index.entry(key).or_insert_with(|| {
+            self.keys.push(key.to_string());
+            self.keys.len() as u32 - 1
+        })
+    }
+
+    /// Returns the index of a value, adding it on first use.
+    fn value(&mut self, value: TagValue) -> u32 {
+        if let Some(&index) = self.value_index.get(&value) {
+            return index;
+        }
+        self.values.push(value.clone());
+        let index = self.values.len() as u32 - 1;
+        self.value_index.insert(value, index);
+        index
+    }
+
+    /// Builds the `roads` layer.
+    fn finish(self) -> Layer {
+        Layer {
+            version: MVT_VERSION,
+            name: ROADS_LAYER.to_string(),
+            features: self.features,
+            keys: self.keys,
+            values: self
+                .values
+                .into_iter()
+                .map(|value| match value {
+                    TagValue::String(s) => Value { string_value: Some(s), ..Default::default() },
+                    TagValue::Uint(n) => Value { uint_value: Some(n), ..Default::default() },
+                })
+                .collect(),
+            extent: Some(EXTENT),
+        }
+    }
+}
+
+/// Clips a polyline in tile units to the buffered tile.
+///
+/// # Returns
+///
+/// The parts of the polyline inside the buffered tile; a line leaving and
+/// re-entering the tile comes back as several parts.
+fn clip_polyline(points: &[DVec2]) -> Vec<Vec<DVec2>> {
+    let min = DVec2::splat(-BUFFER);
+    let max = DVec2::splat(EXTENT as f64 + BUFFER);
+
+    let mut parts: Vec<Vec<DVec2>> = Vec::new();
+    let mut current: Vec<DVec2> = Vec::new();
+    for pair in points.windows(2) {
+        let Some((a, b)) = clip_segment(pair[0], pair[1], min, max) else {
+            if current.len() > 1 {
+                parts.push(std::mem::take(&mut current));
+            }
+            current.clear();
+            continue;
+        };
+        if current.last() != Some(&a) {
+            if current.len() > 1 {
+                parts.push(std::mem::take(&mut current));
+            }
+            current = vec![a];
+        }
+        current.push(b);
+        // The segment left the tile, so the next one starts a new part
+        if b != pair[1] {
+            parts.push(std::mem::take(&mut current));
+        }
+    }
+    if current.len() > 1 {
+        parts.push(current);
+    }
+    parts
+}
+
+/// Clips a segment to a box (Liang-Barsky).
+fn clip_segment(a: DVec2, b: DVec2, min: DVec2, max: DVec2) -> Option<(DVec2, DVec2)> {
+    let delta = b - a;
+    let (mut t0, mut t1) = (0.0_f64, 1.0_f64);
+    for (p, q) in [
+        (-delta.x, a.x - min.x),
+        (delta.x, max.x - a.x),
+        (-delta.y, a.y - min.y),
+        (delta.y, max.y - a.y),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+        }
+    }
+    if t0 > t1 {
+        return None;
+    }
+    let start = if t0 > 0.0 { a + delta * t0 } else { a };
+    let end = if t1 < 1.0 { a + delta * t1 } else { b };
+    Some((start, end))
+}
+
+/// Encodes line parts as MVT geometry commands.
+///
+/// Points are rounded to whole tile units; parts that collapse to a single
+/// point are dropped.
+fn encode_lines(parts: &[Vec<DVec2>]) -> Vec<u32> {
+    let mut geometry = Vec::new();
+    let mut cursor = (0_i32, 0_i32);
+    for part in parts {
+        let mut points: Vec<(i32, i32)> = part.iter().map(|p| (p.x.round() as i32, p.y.round() as i32)).collect();
+        points.dedup();
+        if points.len() < 2 {
+            continue;
+        }
+
+        geometry.push(command(MOVE_TO, 1));
+        push_delta(&mut geometry, &mut cursor, points[0]);
+        geometry.push(command(LINE_TO, points.len() as u32 - 1));
+        for &point in &points[1..] {
+            push_delta(&mut geometry, &mut cursor, point);
+        }
+    }
+    geometry
+}
+
+/// Builds a command integer from its ID and repeat count.
+fn command(id: u32, count: u32) -> u32 {
+    (id & 0x7) | (count << 3)
+}
+
+/// Appends a point relative to the cursor and moves the cursor to it.
+fn push_delta(geometry: &mut Vec<u32>, cursor: &mut (i32, i32), point: (i32, i32)) {
+    geometry.push(zigzag(point.0 - cursor.0));
+    geometry.push(zigzag(point.1 - cursor.1));
+    *cursor = point;
+}
+
+/// Zigzag-encodes a signed parameter integer.
+fn zigzag(n: i32) -> u32 {
+    ((n << 1) ^ (n >> 31)) as u32
+}
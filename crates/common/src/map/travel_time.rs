@@ -0,0 +1,161 @@
+//! Travel-time prediction for routing.
+//!
+//! [`TravelTimeModel`] is the plug-in point for edge travel-time estimates
+//! used by [`RoadGraph::fastest_path`]. The default [`HeuristicTravelTimeModel`]
+//! derives travel times from live speeds or speed limits; with the
+//! `onnx` feature enabled, [`OnnxTravelTimeModel`] runs a trained model
+//! through ONNX Runtime instead.
+
+use super::{ClosureSet, DynamicWeights, Road, RoadGraph};
+
+/// Lowest speed used for travel-time estimates, in m/s, so stopped traffic
+/// yields a large but finite cost.
+pub(crate) const MIN_SPEED_MPS: f64 = 0.5;
+
+/// Predicts how long it takes to traverse a road.
+///
+/// Implementations must be cheap to call: routing queries call `predict`
+/// once per relaxed edge.
+pub trait TravelTimeModel: Send + Sync {
+    /// Predicts the travel time of a road in seconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `road` - Road to traverse
+    /// * `time_of_day_secs` - Seconds since midnight (UTC) at which the road
+    ///   is entered
+    /// * `live_speed_mps` - Currently observed speed on the road, if known
+    fn predict(&self, road: &Road, time_of_day_secs: f64, live_speed_mps: Option<f64>) -> f64;
+}
+
+/// Rule-based travel-time model used when no trained model is available.
+///
+/// Uses the live speed when one is known; otherwise assumes traffic flows at
+/// a fraction of the speed limit, slowed down further during the
+/// morning and evening peaks.
+#[derive(Debug, Clone)]
+pub struct HeuristicTravelTimeModel {
+    /// Free-flow speed as a fraction of the speed limit
+    pub free_flow_factor: f64,
+    /// Additional speed factor applied during peak hours
+    pub peak_factor: f64,
+}
+
+impl Default for HeuristicTravelTimeModel {
+    fn default() -> Self {
+        Self {
+            free_flow_factor: 0.8,
+            peak_factor: 0.7,
+        }
+    }
+}
+
+impl TravelTimeModel for HeuristicTravelTimeModel {
+    fn predict(&self, road: &Road, time_of_day_secs: f64, live_speed_mps: Option<f64>) -> f64 {
+        let speed = live_speed_mps.unwrap_or_else(|| {
+            let hour = (time_of_day_secs / 3600.0).rem_euclid(24.0);
+            let peak = (7.0..9.0).contains(&hour) || (16.0..19.0).contains(&hour);
+            let free_flow = road.effective_speed_limit_mps() * self.free_flow_factor;
+            if peak { free_flow * self.peak_factor } else { free_flow }
+        });
+        road.length / speed.max(MIN_SPEED_MPS)
+    }
+}
+
+/// Travel-time model backed by an ONNX model.
+///
+/// The model must take a single `float32` input of shape `[1, 4]` holding
+/// `[length_m, speed_limit_mps, hour_of_day, live_speed_mps]` (the live speed
+/// is `-1` when unknown) and produce the travel time in seconds as its first
+/// output. Inference errors fall back to the heuristic model.
+#[cfg(feature = "onnx")]
+pub struct OnnxTravelTimeModel {
+    session: std::sync::Mutex<ort::session::Session>,
+    fallback: HeuristicTravelTimeModel,
+}
+
+#[cfg(feature = "onnx")]
+impl OnnxTravelTimeModel {
+    /// Loads a model from an `.onnx` file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if ONNX Runtime cannot be loaded or the model file
+    /// is invalid.
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let session = ort::session::Session::builder()?.commit_from_file(path)?;
+        tracing::info!("🧠 Loaded travel-time model from {}", path);
+        Ok(Self {
+            session: std::sync::Mutex::new(session),
+            fallback: HeuristicTravelTimeModel::default(),
+        })
+    }
+
+    /// Runs the model for a single road.
+    fn infer(&self, road: &Road, time_of_day_secs: f64, live_speed_mps: Option<f64>) -> anyhow::Result<f64> {
+        let features = vec![
+            road.length as f32,
+            road.effective_speed_limit_mps() as f32,
+            (time_of_day_secs / 3600.0).rem_euclid(24.0) as f32,
+            live_speed_mps.map_or(-1.0, |s| s as f32),
+        ];
+        let input = ort::value::Tensor::from_array(([1usize, 4], features))?;
+
+        let mut session = self.session.lock().map_err(|_| anyhow::anyhow!("model session poisoned"))?;
+        let outputs = session.run(ort::inputs![input])?;
+        let (_, data) = outputs[0].try_extract_tensor::<f32>()?;
+        data.first()
+            .map(|&seconds| seconds as f64)
+            .ok_or_else(|| anyhow::anyhow!("model produced no output"))
+    }
+}
+
+#[cfg(feature = "onnx")]
+impl TravelTimeModel for OnnxTravelTimeModel {
+    fn predict(&self, road: &Road, time_of_day_secs: f64, live_speed_mps: Option<f64>) -> f64 {
+        match self.infer(road, time_of_day_secs, live_speed_mps) {
+            Ok(seconds) if seconds.is_finite() && seconds >= 0.0 => seconds,
+            _ => self.fallback.predict(road, time_of_day_secs, live_speed_mps),
+        }
+    }
+}
+
+impl RoadGraph {
+    /// Finds the fastest path between two nodes according to a travel-time
+    /// model.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - OSM node ID to start from
+    /// * `to` - OSM node ID to reach
+    /// * `model` - Model predicting per-edge travel times
+    /// * `time_of_day_secs` - Departure time in seconds since midnight (UTC)
+    /// * `live` - Observed speeds, passed to the model where known
+    /// * `closures` - Closed segments, which the path avoids
+    ///
+    /// Segments closed to through traffic are costed up by
+    /// [`Access::cost_factor`](super::Access::cost_factor), so the path only
+    /// uses them to start or finish.
+    ///
+    /// # Returns
+    ///
+    /// The indices of the edges to traverse in order, or `None` if `to` is
+    /// unreachable from `from` over open roads.
+    pub fn fastest_path(
+        &self,
+        from: i64,
+        to: i64,
+        model: &dyn TravelTimeModel,
+        time_of_day_secs: f64,
+        live: &DynamicWeights,
+        closures: &ClosureSet,
+    ) -> Option<Vec<usize>> {
+        self.shortest_path_by(from, to, |edge_idx| {
+            if closures.is_closed(edge_idx) {
+                return f64::INFINITY;
+            }
+            let road = &self.edges[edge_idx];
+            model.predict(road, time_of_day_secs, live.speed(edge_idx)) * road.access.cost_factor()
+        })
+    }
+}
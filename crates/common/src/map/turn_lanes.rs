@@ -0,0 +1,150 @@
+//! Turn lanes from OSM `turn:lanes` tags.
+//!
+//! `turn:lanes` lists, from left to right in the direction of travel, the
+//! turns each lane allows at the end of the way: lanes are separated by
+//! `|`, several turns of one lane by `;`, e.g. `left|through|through;right`.
+//! On two-way roads the lanes of each direction are tagged separately as
+//! `turn:lanes:forward` and `turn:lanes:backward`.
+
+use serde::{Deserialize, Serialize};
+use super::Direction;
+
+/// A turn marked on a lane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Turn {
+    Left,
+    SlightLeft,
+    SharpLeft,
+    Through,
+    Right,
+    SlightRight,
+    SharpRight,
+    /// U-turn
+    Reverse,
+    /// The lane ends and merges into the lane on its left
+    MergeToLeft,
+    /// The lane ends and merges into the lane on its right
+    MergeToRight,
+    /// No marking (usually straight on)
+    None,
+}
+
+impl Turn {
+    /// Parses one OSM turn value, e.g. `slight_left`.
+    fn parse(value: &str) -> Option<Self> {
+        Some(match value.trim() {
+            "left" => Self::Left,
+            "slight_left" => Self::SlightLeft,
+            "sharp_left" => Self::SharpLeft,
+            "through" => Self::Through,
+            "right" => Self::Right,
+            "slight_right" => Self::SlightRight,
+            "sharp_right" => Self::SharpRight,
+            "reverse" => Self::Reverse,
+            "merge_to_left" => Self::MergeToLeft,
+            "merge_to_right" => Self::MergeToRight,
+            "" | "none" => Self::None,
+            _ => return None,
+        })
+    }
+}
+
+/// Turns allowed per lane, from the leftmost lane to the rightmost.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TurnLanes(pub Vec<Vec<Turn>>);
+
+impl TurnLanes {
+    /// Returns the number of lanes.
+    pub fn lane_count(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns the indices (0 = leftmost) of the lanes allowing a turn.
+    pub fn lanes_for(&self, turn: Turn) -> impl Iterator<Item = usize> + '_ {
+        self.0
+            .iter()
+            .enumerate()
+            .filter(move |(_, turns)| turns.contains(&turn))
+            .map(|(lane, _)| lane)
+    }
+}
+
+/// Parses a `turn:lanes` value.
+///
+/// Unknown turn values are skipped; a lane left without any turn counts as
+/// unmarked ([`Turn::None`]), so the lane count always matches the tag.
+///
+/// # Returns
+///
+/// The lanes, or `None` for an empty value.
+///
+/// # Examples
+///
+/// ```
+/// use traffic_common::map::{parse_turn_lanes, Turn};
+///
+/// let lanes = parse_turn_lanes("left|through|through;right").unwrap();
+/// assert_eq!(lanes.lane_count(), 3);
+/// assert_eq!(lanes.0[2], [Turn::Through, Turn::Right]);
+/// assert_eq!(lanes.lanes_for(Turn::Through).collect::<Vec<_>>(), [1, 2]);
+/// assert_eq!(parse_turn_lanes("|right").unwrap().0[0], [Turn::None]);
+/// assert_eq!(parse_turn_lanes(" "), None);
+/// ```
+pub fn parse_turn_lanes(value: &str) -> Option<TurnLanes> {
+    if value.trim().is_empty() {
+        return None;
+    }
+    let lanes = value
+        .split('|')
+        .map(|lane| {
+            let turns: Vec<Turn> = lane.split(';').filter_map(Turn::parse).collect();
+            if turns.is_empty() { vec![Turn::None] } else { turns }
+        })
+        .collect();
+    Some(TurnLanes(lanes))
+}
+
+/// Turn lanes of a way, per direction of travel.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WayTurnLanes {
+    forward: Option<TurnLanes>,
+    backward: Option<TurnLanes>,
+}
+
+impl WayTurnLanes {
+    /// Reads the lanes of a way from its `turn:lanes`,
+    /// `turn:lanes:forward` and `turn:lanes:backward` tags.
+    ///
+    /// Plain `turn:lanes` only applies to one-way roads, where it describes
+    /// the direction of travel.
+    pub(crate) fn from_tags(
+        lanes: Option<&str>,
+        forward: Option<&str>,
+        backward: Option<&str>,
+        directions: &[Direction],
+    ) -> Self {
+        let oneway = match directions {
+            [direction] => lanes.map(|lanes| (*direction, lanes)),
+            _ => None,
+        };
+        let pick = |directional: Option<&str>, direction: Direction| {
+            directional
+                .or_else(|| oneway.filter(|(oneway, _)| *oneway == direction).map(|(_, lanes)| lanes))
+                .and_then(parse_turn_lanes)
+        };
+        Self {
+            forward: pick(forward, Direction::Forward),
+            backward: pick(backward, Direction::Backward),
+        }
+    }
+
+    /// Returns the lanes in one direction of travel.
+    pub(crate) fn get(&self, direction: Direction) -> Option<TurnLanes> {
+        match direction {
+            Direction::Forward => self.forward.clone(),
+            Direction::Backward => self.backward.clone(),
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Live edge weights for congestion-aware routing.
+//!
+//! Free-flow travel times (length over speed limit) ignore congestion.
+//! traffic-ingest therefore aggregates the speeds of map-matched positions
+//! per road and stores them in Redis under [`ROAD_SPEEDS_KEY`];
+//! [`DynamicWeights`] holds them as an overlay over the immutable graph.
+//! Routing asks a [`TravelTimeProvider`] for each edge's current travel time
+//! and falls back to free flow for roads nobody reported from
+//! ([`RoadGraph::quickest_path`]).
+
+use std::collections::HashMap;
+use bevy_ecs::prelude::Resource;
+use super::travel_time::MIN_SPEED_MPS;
+use super::{ClosureSet, RoadGraph};
+
+/// Redis key holding the recent average speed per road, as a JSON object
+/// mapping OSM way IDs to speeds in m/s.
+pub const ROAD_SPEEDS_KEY: &str = "roads:speeds";
+
+/// Source of current per-edge travel times.
+///
+/// Implementations must be cheap to call: routing queries call
+/// `travel_time` once per relaxed edge.
+pub trait TravelTimeProvider: Send + Sync {
+    /// Returns the current travel time of an edge in seconds, or `None` if
+    /// nothing is known about it.
+    fn travel_time(&self, graph: &RoadGraph, edge: usize) -> Option<f64>;
+}
+
+/// Observed speeds per edge, layered over the graph's free-flow speeds.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::collections::HashMap;
+/// use traffic_common::map::{ClosureSet, DynamicWeights, RoadGraph};
+///
+/// let graph = RoadGraph::load_from_pbf("map.osm.pbf").unwrap();
+/// let speeds: HashMap<i64, f64> = serde_json::from_str(r#"{"4045215": 3.5}"#).unwrap();
+///
+/// let mut weights = DynamicWeights::default();
+/// weights.update_from_ways(&graph, &speeds);
+/// let route = graph.quickest_path(1, 2, &weights, &ClosureSet::default());
+/// ```
+#[derive(Debug, Clone, Default, Resource)]
+pub struct DynamicWeights {
+    speeds: HashMap<usize, f64>,
+}
+
+impl DynamicWeights {
+    /// Records the observed speed on an edge, in m/s.
+    pub fn set_speed(&mut self, edge: usize, speed_mps: f64) {
+        self.speeds.insert(edge, speed_mps);
+    }
+
+    /// Returns the observed speed on an edge, in m/s.
+    pub fn speed(&self, edge: usize) -> Option<f64> {
+        self.speeds.get(&edge).copied()
+    }
+
+    /// Replaces all observed speeds with per-road speeds, as stored under
+    /// [`ROAD_SPEEDS_KEY`].
+    ///
+    /// Every segment of a way gets the way's speed; ways that are not in the
+    /// graph and speeds that are negative or not finite are skipped.
+    ///
+    /// # Returns
+    ///
+    /// The number of edges with an observed speed.
+    pub fn update_from_ways(&mut self, graph: &RoadGraph, way_speeds: &HashMap<i64, f64>) -> usize {
+        self.speeds.clear();
+        for (&way_id, &speed) in way_speeds {
+            if !speed.is_finite() || speed < 0.0 {
+                continue;
+            }
+            for &edge in graph.edges_for_way(way_id) {
+                self.speeds.insert(edge, speed);
+            }
+        }
+        self.speeds.len()
+    }
+
+    /// Forgets all observed speeds.
+    pub fn clear(&mut self) {
+        self.speeds.clear();
+    }
+
+    /// Returns the number of edges with an observed speed.
+    pub fn len(&self) -> usize {
+        self.speeds.len()
+    }
+
+    /// Returns `true` if no speed has been observed.
+    pub fn is_empty(&self) -> bool {
+        self.speeds.is_empty()
+    }
+}
+
+impl TravelTimeProvider for DynamicWeights {
+    fn travel_time(&self, graph: &RoadGraph, edge: usize) -> Option<f64> {
+        let speed = self.speed(edge)?;
+        Some(graph.edges[edge].length / speed.max(MIN_SPEED_MPS))
+    }
+}
+
+impl RoadGraph {
+    /// Returns the travel time of an edge at its speed limit, in seconds.
+    pub fn free_flow_time(&self, edge: usize) -> f64 {
+        let road = &self.edges[edge];
+        road.length / road.effective_speed_limit_mps().max(MIN_SPEED_MPS)
+    }
+
+    /// Finds the quickest path between two nodes under current travel times.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - OSM node ID to start from
+    /// * `to` - OSM node ID to reach
+    /// * `provider` - Current travel times; edges it knows nothing about are
+    ///   costed at free flow
+    /// * `closures` - Closed segments, which the path avoids
+    ///
+    /// # Returns
+    ///
+    /// The indices of the edges to traverse in order, or `None` if `to` is
+    /// unreachable from `from` over open roads.
+    pub fn quickest_path(
+        &self,
+        from: i64,
+        to: i64,
+        provider: &dyn TravelTimeProvider,
+        closures: &ClosureSet,
+    ) -> Option<Vec<usize>> {
+        self.shortest_path_by(from, to, |edge_idx| {
+            if closures.is_closed(edge_idx) {
+                return f64::INFINITY;
+            }
+            provider
+                .travel_time(self, edge_idx)
+                .unwrap_or_else(|| self.free_flow_time(edge_idx))
+        })
+    }
+}
@@ -13,6 +13,21 @@ pub struct VehiclePosition {
     pub speed: f64,
     #[prost(int64, tag = "5")]
     pub timestamp: i64,
+    /// True for keepalive frames emitted while the simulation is paused
+    #[prost(bool, tag = "6")]
+    pub paused: bool,
+    /// Smoothed heading in degrees clockwise from north
+    #[prost(double, tag = "7")]
+    pub heading: f64,
+    /// Instantaneous longitudinal acceleration in m/s^2
+    #[prost(double, tag = "8")]
+    pub acceleration: f64,
+    /// True for frames emitted during the simulation warm-up period
+    #[prost(bool, tag = "9")]
+    pub warmup: bool,
+    /// Priority tier of the vehicle
+    #[prost(enumeration = "VehiclePriority", tag = "10")]
+    pub priority: i32,
 }
 /// Traffic jam message (for analytics)
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -25,3 +40,33 @@ pub struct TrafficJamAlert {
     #[prost(int64, tag = "3")]
     pub timestamp: i64,
 }
+/// Importance of a vehicle; higher tiers are emitted more often
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum VehiclePriority {
+    PriorityCar = 0,
+    PriorityTransit = 1,
+    PriorityEmergency = 2,
+}
+impl VehiclePriority {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            VehiclePriority::PriorityCar => "PRIORITY_CAR",
+            VehiclePriority::PriorityTransit => "PRIORITY_TRANSIT",
+            VehiclePriority::PriorityEmergency => "PRIORITY_EMERGENCY",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "PRIORITY_CAR" => Some(Self::PriorityCar),
+            "PRIORITY_TRANSIT" => Some(Self::PriorityTransit),
+            "PRIORITY_EMERGENCY" => Some(Self::PriorityEmergency),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,289 @@
+//! Traffic signal phase plans.
+//!
+//! Signal timings are plain data so they can be loaded from a scenario file,
+//! replaced at runtime through the control topic, and iterated on by external
+//! optimizers. A plan cycles through its phases; each phase gives green to a
+//! set of approaches, identified by the OSM way ID of the incoming road.
+//! An approach shows amber for the last [`AMBER_SECONDS`] of its green
+//! when the next phase does not keep it green.
+
+use serde::{Deserialize, Serialize};
+
+/// Kafka topic carrying periodic per-intersection delay metrics.
+pub const SIGNAL_METRICS_TOPIC: &str = "signal-metrics";
+
+/// Kafka topic carrying the light states of intersections whenever they
+/// change.
+pub const SIGNAL_STATES_TOPIC: &str = "signal-states";
+
+/// Length of the amber light at the end of a green phase, in seconds.
+pub const AMBER_SECONDS: f64 = 3.0;
+
+/// Deceleration a driver accepts to stop for an amber light, in m/s².
+const STOPPING_DECELERATION: f64 = 3.0;
+
+/// Green time of each phase of a default plan, in seconds.
+const DEFAULT_GREEN_SECONDS: f64 = 30.0;
+
+/// All-red phase of default plans at crossings with a single axis of
+/// traffic, giving way to pedestrians, in seconds.
+const DEFAULT_CROSSING_SECONDS: f64 = 15.0;
+
+/// Light shown to an approach.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LightState {
+    Green,
+    Amber,
+    Red,
+}
+
+impl LightState {
+    /// Returns `true` if a vehicle has to stop at the line.
+    ///
+    /// Red always stops traffic. At amber, a vehicle stops only if it can
+    /// do so comfortably; one too close to the line drives on.
+    ///
+    /// # Arguments
+    ///
+    /// * `distance_m` - Distance to the stop line in meters
+    /// * `speed_mps` - Current speed in m/s
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use traffic_common::signals::LightState;
+    ///
+    /// assert!(LightState::Red.must_stop(0.0, 14.0));
+    /// assert!(LightState::Amber.must_stop(80.0, 14.0));
+    /// assert!(!LightState::Amber.must_stop(10.0, 14.0));
+    /// assert!(!LightState::Green.must_stop(80.0, 14.0));
+    /// ```
+    pub fn must_stop(self, distance_m: f64, speed_mps: f64) -> bool {
+        match self {
+            Self::Green => false,
+            Self::Amber => distance_m >= speed_mps * speed_mps / (2.0 * STOPPING_DECELERATION),
+            Self::Red => true,
+        }
+    }
+}
+
+/// Light state of every approach of an intersection, as published on
+/// [`SIGNAL_STATES_TOPIC`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignalState {
+    /// OSM node ID of the intersection
+    pub node_id: i64,
+    /// Simulation time of the change in seconds
+    pub sim_time: f64,
+    /// Light per approach
+    pub approaches: Vec<ApproachLight>,
+}
+
+/// Light shown to one approach of an intersection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApproachLight {
+    /// OSM way ID of the incoming road
+    pub way_id: i64,
+    pub state: LightState,
+}
+
+/// One phase of a signal cycle.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignalPhase {
+    /// Length of the phase in seconds
+    pub duration_seconds: f64,
+    /// OSM way IDs of the approaches that have green during this phase
+    pub green_ways: Vec<i64>,
+}
+
+/// Fixed-time signal plan for a single intersection.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignalPlan {
+    /// OSM node ID of the signalized intersection
+    pub node_id: i64,
+    /// Seconds into the cycle at simulation time zero (for coordination)
+    #[serde(default)]
+    pub offset_seconds: f64,
+    /// Phases in cycle order
+    pub phases: Vec<SignalPhase>,
+}
+
+impl SignalPlan {
+    /// Builds a fixed-time plan for an intersection from the directions of
+    /// its approaches.
+    ///
+    /// Approaches along the axis of the first one share a phase, the others
+    /// get the second; where all approaches share one axis (e.g. a
+    /// pedestrian crossing on a straight road), the second phase is all red.
+    ///
+    /// # Arguments
+    ///
+    /// * `node_id` - OSM node ID of the intersection
+    /// * `approaches` - OSM way ID of every incoming road with its bearing
+    ///   towards the intersection in degrees
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use traffic_common::signals::SignalPlan;
+    ///
+    /// // A crossroads: way 1 runs north-south, way 2 east-west
+    /// let plan = SignalPlan::two_phase(7, &[(1, 0.0), (1, 180.0), (2, 90.0), (2, 270.0)]);
+    /// assert_eq!(plan.phases.len(), 2);
+    /// assert!(plan.is_green(1, 0.0));
+    /// assert!(!plan.is_green(2, 0.0));
+    /// ```
+    pub fn two_phase(node_id: i64, approaches: &[(i64, f64)]) -> Self {
+        let mut main = Vec::new();
+        let mut cross = Vec::new();
+        if let Some(&(_, reference)) = approaches.first() {
+            for &(way_id, bearing) in approaches {
+                // Angle between the axes, ignoring the direction of travel
+                let angle = (bearing - reference).rem_euclid(180.0);
+                let group = if angle.min(180.0 - angle) < 45.0 { &mut main } else { &mut cross };
+                if !group.contains(&way_id) {
+                    group.push(way_id);
+                }
+            }
+        }
+        cross.retain(|way_id| !main.contains(way_id));
+
+        let crossing = cross.is_empty();
+        Self {
+            node_id,
+            offset_seconds: 0.0,
+            phases: vec![
+                SignalPhase { duration_seconds: DEFAULT_GREEN_SECONDS, green_ways: main },
+                SignalPhase {
+                    duration_seconds: if crossing { DEFAULT_CROSSING_SECONDS } else { DEFAULT_GREEN_SECONDS },
+                    green_ways: cross,
+                },
+            ],
+        }
+    }
+
+    /// Returns the total cycle length in seconds.
+    pub fn cycle_seconds(&self) -> f64 {
+        self.phases.iter().map(|phase| phase.duration_seconds).sum()
+    }
+
+    /// Returns the phase active at the given simulation time.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - Simulation time in seconds
+    pub fn active_phase(&self, time: f64) -> Option<&SignalPhase> {
+        self.phase_at(time).map(|(index, _)| &self.phases[index])
+    }
+
+    /// Returns the index of the phase active at the given simulation time
+    /// and the seconds left in it.
+    fn phase_at(&self, time: f64) -> Option<(usize, f64)> {
+        let cycle = self.cycle_seconds();
+        if cycle <= 0.0 {
+            return None;
+        }
+
+        let mut t = (time + self.offset_seconds).rem_euclid(cycle);
+        for (index, phase) in self.phases.iter().enumerate() {
+            if t < phase.duration_seconds {
+                return Some((index, phase.duration_seconds - t));
+            }
+            t -= phase.duration_seconds;
+        }
+        Some((self.phases.len() - 1, 0.0))
+    }
+
+    /// Returns the light shown to the approach from the given way.
+    ///
+    /// # Arguments
+    ///
+    /// * `way_id` - OSM way ID of the incoming road
+    /// * `time` - Simulation time in seconds
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use traffic_common::signals::{LightState, SignalPhase, SignalPlan};
+    ///
+    /// let plan = SignalPlan {
+    ///     node_id: 7,
+    ///     offset_seconds: 0.0,
+    ///     phases: vec![
+    ///         SignalPhase { duration_seconds: 30.0, green_ways: vec![1] },
+    ///         SignalPhase { duration_seconds: 30.0, green_ways: vec![2] },
+    ///     ],
+    /// };
+    /// assert_eq!(plan.light_state(1, 10.0), LightState::Green);
+    /// assert_eq!(plan.light_state(1, 28.0), LightState::Amber);
+    /// assert_eq!(plan.light_state(2, 28.0), LightState::Red);
+    /// ```
+    pub fn light_state(&self, way_id: i64, time: f64) -> LightState {
+        let Some((index, remaining)) = self.phase_at(time) else { return LightState::Red };
+        if !self.phases[index].green_ways.contains(&way_id) {
+            return LightState::Red;
+        }
+        let next = &self.phases[(index + 1) % self.phases.len()];
+        if remaining <= AMBER_SECONDS && !next.green_ways.contains(&way_id) {
+            LightState::Amber
+        } else {
+            LightState::Green
+        }
+    }
+
+    /// Returns `true` if the approach from the given way has green.
+    ///
+    /// # Arguments
+    ///
+    /// * `way_id` - OSM way ID of the incoming road
+    /// * `time` - Simulation time in seconds
+    pub fn is_green(&self, way_id: i64, time: f64) -> bool {
+        self.active_phase(time)
+            .is_some_and(|phase| phase.green_ways.contains(&way_id))
+    }
+
+    /// Checks that the plan is well-formed.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the problem if the plan has no phases or a
+    /// phase has a non-positive or non-finite duration.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.phases.is_empty() {
+            return Err(format!("signal plan for node {} has no phases", self.node_id));
+        }
+        if let Some(phase) = self.phases.iter().find(|p| !(p.duration_seconds.is_finite() && p.duration_seconds > 0.0)) {
+            return Err(format!(
+                "signal plan for node {} has an invalid phase duration {}",
+                self.node_id, phase.duration_seconds
+            ));
+        }
+        if !self.offset_seconds.is_finite() {
+            return Err(format!("signal plan for node {} has an invalid offset", self.node_id));
+        }
+        Ok(())
+    }
+}
+
+/// Accumulated delay at a single signalized intersection.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IntersectionDelay {
+    /// OSM node ID of the intersection
+    pub node_id: i64,
+    /// Vehicle-seconds spent waiting at red
+    pub total_delay_seconds: f64,
+    /// Number of vehicles that passed through the intersection
+    pub vehicles_served: u64,
+}
+
+impl IntersectionDelay {
+    /// Average waiting time per served vehicle, in seconds.
+    pub fn average_delay_seconds(&self) -> f64 {
+        if self.vehicles_served == 0 {
+            0.0
+        } else {
+            self.total_delay_seconds / self.vehicles_served as f64
+        }
+    }
+}
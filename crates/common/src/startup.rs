@@ -0,0 +1,164 @@
+//! Waiting for infrastructure dependencies at startup.
+//!
+//! Under docker-compose, services start as soon as their dependency
+//! containers exist, which is usually before Kafka, Redis and Postgres
+//! accept connections. Instead of failing on the first connection attempt,
+//! services probe each dependency until it answers or the startup timeout
+//! (`STARTUP_TIMEOUT_SECS`) runs out.
+
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{BaseConsumer, Consumer};
+use sqlx::postgres::PgConnectOptions;
+use sqlx::{ConnectOptions, Connection};
+use tokio::time::{sleep, timeout, Instant};
+use crate::error::{Result, TrafficError};
+
+/// Delay before the second attempt; doubled after every failure.
+const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Longest delay between two attempts.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Longest a single attempt may take.
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest a Kafka metadata request may block.
+const KAFKA_METADATA_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Retries `probe` until it succeeds or `wait` has passed.
+///
+/// Every failed attempt is logged with the next retry delay, so a service
+/// stuck at startup shows which dependency it is waiting for.
+///
+/// # Arguments
+///
+/// * `name` - Name of the dependency, for logs and errors
+/// * `wait` - Total time to keep retrying; zero means a single attempt
+/// * `probe` - Connection attempt
+///
+/// # Errors
+///
+/// Returns [`TrafficError::Unavailable`] with the last failure if the
+/// dependency did not answer in time.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use traffic_common::startup::wait_for;
+///
+/// # async fn example() -> traffic_common::Result<()> {
+/// let client = redis::Client::open("redis://localhost:6379")?;
+/// let con = wait_for("Redis", Duration::from_secs(30), || client.get_multiplexed_async_connection()).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn wait_for<T, E, F, Fut>(name: &str, wait: Duration, mut probe: F) -> Result<T>
+where
+    E: std::fmt::Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::result::Result<T, E>>,
+{
+    let started = Instant::now();
+    let deadline = started + wait;
+    let mut delay = INITIAL_RETRY_DELAY;
+    let mut attempt = 1;
+
+    loop {
+        let error = match timeout(ATTEMPT_TIMEOUT, probe()).await {
+            Ok(Ok(value)) => {
+                if attempt > 1 {
+                    tracing::info!("✅ {} reachable after {:.1}s", name, started.elapsed().as_secs_f64());
+                }
+                return Ok(value);
+            }
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => format!("no answer within {:?}", ATTEMPT_TIMEOUT),
+        };
+
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(TrafficError::Unavailable(format!(
+                "{} still unreachable after {} attempts in {:.1}s: {}",
+                name,
+                attempt,
+                started.elapsed().as_secs_f64(),
+                error
+            )));
+        }
+
+        let pause = delay.min(deadline - now);
+        tracing::warn!("⏳ Waiting for {} (attempt {}): {}; retrying in {:.1}s", name, attempt, error, pause.as_secs_f64());
+        sleep(pause).await;
+        delay = (delay * 2).min(MAX_RETRY_DELAY);
+        attempt += 1;
+    }
+}
+
+/// Waits until the Kafka brokers answer a metadata request.
+///
+/// # Arguments
+///
+/// * `kafka` - Connection settings, from [`crate::Config::kafka_client`]
+/// * `wait` - Total time to keep retrying
+///
+/// # Errors
+///
+/// Returns an error if the settings are invalid, or
+/// [`TrafficError::Unavailable`] if no broker answered in time.
+pub async fn wait_for_kafka(kafka: &ClientConfig, wait: Duration) -> Result<()> {
+    // Failures are reported by the retry loop; keep librdkafka's own
+    // connection errors out of the log
+    let consumer: BaseConsumer = kafka.clone().set("log_level", "0").create()?;
+    let consumer = Arc::new(consumer);
+
+    wait_for("Kafka", wait, || {
+        let consumer = consumer.clone();
+        async move {
+            // Metadata requests block, so they run off the async runtime
+            tokio::task::spawn_blocking(move || consumer.fetch_metadata(None, KAFKA_METADATA_TIMEOUT).map(|_| ()))
+                .await
+                .map_err(|e| TrafficError::Internal(e.to_string()))?
+                .map_err(TrafficError::from)
+        }
+    })
+    .await
+}
+
+/// Waits until Redis answers a `PING`.
+///
+/// # Arguments
+///
+/// * `client` - Redis client, from [`crate::Config::redis_client`]
+/// * `wait` - Total time to keep retrying
+///
+/// # Errors
+///
+/// Returns [`TrafficError::Unavailable`] if Redis did not answer in time.
+pub async fn wait_for_redis(client: &redis::Client, wait: Duration) -> Result<()> {
+    wait_for("Redis", wait, || async {
+        let mut con = client.get_multiplexed_async_connection().await?;
+        redis::cmd("PING").query_async::<_, ()>(&mut con).await
+    })
+    .await
+}
+
+/// Waits until Postgres accepts a connection.
+///
+/// # Errors
+///
+/// Returns an error if the URL is invalid, or [`TrafficError::Unavailable`]
+/// if Postgres did not accept a connection in time. Rejected credentials
+/// are retried too, as the server may still be running its init scripts.
+pub async fn wait_for_postgres(url: &str, wait: Duration) -> Result<()> {
+    let options = PgConnectOptions::from_str(url)?;
+
+    wait_for("Postgres", wait, || async {
+        options.connect().await?.close().await
+    })
+    .await
+}
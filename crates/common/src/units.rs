@@ -0,0 +1,73 @@
+//! Units of telemetry on the wire.
+//!
+//! Every producer (simulator, feed adapters, replays) publishes
+//! `VehiclePosition` messages in the same units, and every consumer relies
+//! on them:
+//!
+//! - `speed` in meters per second (never degrees per second)
+//! - `heading` in degrees clockwise from north
+//! - `acceleration` in meters per second squared
+//! - `timestamp` in Unix seconds
+//!
+//! Ingest checks speeds with [`validate_speed`] before storing them, so a
+//! producer sending the wrong unit or garbage shows up in its rejection
+//! counts instead of in historical data.
+
+use thiserror::Error;
+
+/// Fastest plausible speed of a road vehicle in m/s (360 km/h).
+pub const MAX_SPEED_MPS: f64 = 100.0;
+
+/// Kilometers per hour in one meter per second.
+pub const KMH_PER_MPS: f64 = 3.6;
+
+/// Reason a speed was rejected.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpeedError {
+    #[error("speed is NaN or infinite")]
+    NotFinite,
+
+    #[error("speed is negative")]
+    Negative,
+
+    #[error("speed exceeds {MAX_SPEED_MPS} m/s")]
+    TooFast,
+}
+
+/// Checks that a speed is a plausible value in m/s.
+///
+/// # Arguments
+///
+/// * `speed` - Speed in m/s
+///
+/// # Returns
+///
+/// The speed, unchanged.
+///
+/// # Errors
+///
+/// Returns the reason the speed is unusable.
+///
+/// # Examples
+///
+/// ```
+/// use traffic_common::units::{validate_speed, SpeedError, KMH_PER_MPS};
+///
+/// assert_eq!(validate_speed(50.0 / KMH_PER_MPS), Ok(50.0 / KMH_PER_MPS));
+/// assert_eq!(validate_speed(0.0), Ok(0.0));
+/// assert_eq!(validate_speed(-1.0), Err(SpeedError::Negative));
+/// assert_eq!(validate_speed(f64::NAN), Err(SpeedError::NotFinite));
+/// // 50 km/h mistakenly sent in km/h is still plausible, 500 km/h is not
+/// assert_eq!(validate_speed(500.0 / KMH_PER_MPS), Err(SpeedError::TooFast));
+/// ```
+pub fn validate_speed(speed: f64) -> Result<f64, SpeedError> {
+    if !speed.is_finite() {
+        Err(SpeedError::NotFinite)
+    } else if speed < 0.0 {
+        Err(SpeedError::Negative)
+    } else if speed > MAX_SPEED_MPS {
+        Err(SpeedError::TooFast)
+    } else {
+        Ok(speed)
+    }
+}
@@ -0,0 +1,65 @@
+//! Detour factors of trips.
+//!
+//! The detour factor is the distance actually driven divided by the length
+//! of the shortest path between the trip's endpoints. Values well above 1
+//! point at missing connections, closures or poor routing.
+
+use serde::Serialize;
+use traffic_common::map::RoadGraph;
+use crate::trips::Trip;
+
+/// Detour factor of a single trip.
+#[derive(Debug, Clone, Serialize)]
+pub struct DetourFactor {
+    /// Vehicle that made the trip
+    pub vehicle_id: String,
+    /// Unix timestamp of the trip start
+    pub start_time: f64,
+    /// Distance driven along the recorded positions, in meters
+    pub driven_m: f64,
+    /// Shortest-path distance between the trip's endpoints, in meters
+    pub shortest_m: f64,
+    /// `driven_m / shortest_m`
+    pub factor: f64,
+}
+
+/// Computes the detour factor of every trip.
+///
+/// Trip endpoints are snapped to their nearest roads; the shortest path runs
+/// from the start of the first road to the end of the last one. Trips whose
+/// endpoints are unreachable from each other or that snap to the same road
+/// are skipped.
+///
+/// # Arguments
+///
+/// * `graph` - Road network used for snapping and routing
+/// * `trips` - Reconstructed trips
+pub fn detour_factors(graph: &RoadGraph, trips: &[Trip]) -> Vec<DetourFactor> {
+    trips
+        .iter()
+        .filter_map(|trip| {
+            let (lon, lat) = trip.origin();
+            let first = graph.nearest_edge(lon, lat)?;
+            let (lon, lat) = trip.destination();
+            let last = graph.nearest_edge(lon, lat)?;
+            if first == last {
+                return None;
+            }
+
+            let path = graph.shortest_path(graph.edges[first].start, graph.edges[last].end)?;
+            let shortest_m: f64 = path.iter().map(|&edge| graph.edges[edge].length).sum();
+            if shortest_m <= 0.0 {
+                return None;
+            }
+
+            let driven_m = trip.driven_distance();
+            Some(DetourFactor {
+                vehicle_id: trip.vehicle_id.clone(),
+                start_time: trip.start_time,
+                driven_m,
+                shortest_m,
+                factor: driven_m / shortest_m,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,124 @@
+//! Training data export for travel-time prediction models.
+//!
+//! Trips are snapped to the road network and cut into road traversals. Each
+//! traversal becomes one sample joining map features (road class, speed
+//! limit, signal proximity) with temporal features (hour of day, day of
+//! week); the label is the observed travel time. Samples are written as
+//! Parquet so they can be loaded directly by common ML tooling.
+
+use anyhow::{Context, Result};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::record::RecordWriter;
+use parquet_derive::ParquetRecordWriter;
+use std::fs::File;
+use std::sync::Arc;
+use traffic_common::map::RoadGraph;
+use traffic_common::geo::haversine_distance;
+use crate::trips::Trip;
+
+/// Number of samples per Parquet row group.
+const ROW_GROUP_SIZE: usize = 100_000;
+
+/// One road traversal with its features and travel-time label.
+#[derive(Debug, Clone, ParquetRecordWriter)]
+pub struct TravelTimeSample {
+    /// Vehicle that made the traversal
+    pub vehicle_id: String,
+    /// OSM way ID of the road
+    pub road_id: i64,
+    /// OSM highway classification of the road
+    pub road_class: String,
+    /// Speed limit of the road in m/s
+    pub speed_limit_mps: f64,
+    /// Length of the road segment in meters
+    pub road_length_m: f64,
+    /// Distance covered during the traversal in meters
+    pub distance_m: f64,
+    /// Distance from the traversal start to a signal at the end of the road,
+    /// or null if the road does not end at a signal
+    pub distance_to_signal_m: Option<f64>,
+    /// Hour of the day (UTC) at the traversal start, 0-23
+    pub hour_of_day: i32,
+    /// Day of the week at the traversal start, 0 = Monday
+    pub day_of_week: i32,
+    /// Label: observed travel time in seconds
+    pub travel_time_s: f64,
+}
+
+/// Builds training samples from trips.
+///
+/// Consecutive positions snapped to the same road form one traversal. A
+/// traversal ends where the first position on the next road is recorded, so
+/// the final traversal of every trip (which has no observed end) is dropped.
+///
+/// # Arguments
+///
+/// * `graph` - Road network used for snapping and map features
+/// * `trips` - Reconstructed trips
+pub fn training_samples(graph: &RoadGraph, trips: &[Trip]) -> Vec<TravelTimeSample> {
+    let mut samples = Vec::new();
+
+    for trip in trips {
+        // (edge index, index of the first position on that edge)
+        let mut runs: Vec<(usize, usize)> = Vec::new();
+        for (i, &(lon, lat)) in trip.points.iter().enumerate() {
+            let Some(edge) = graph.nearest_edge(lon, lat) else { continue };
+            if runs.last().map(|&(last, _)| last) != Some(edge) {
+                runs.push((edge, i));
+            }
+        }
+
+        for pair in runs.windows(2) {
+            let (edge, start) = pair[0];
+            let (_, end) = pair[1];
+            let road = &graph.edges[edge];
+            let start_time = trip.timestamps[start];
+
+            let distance_to_signal_m = graph.signals.contains(&road.end).then(|| {
+                let end_pos = graph.nodes.get(&road.end).map(|n| (n.pos.x, n.pos.y));
+                end_pos.map_or(0.0, |pos| haversine_distance(trip.points[start], pos))
+            });
+
+            samples.push(TravelTimeSample {
+                vehicle_id: trip.vehicle_id.clone(),
+                road_id: road.id,
+                road_class: road.highway_type.clone(),
+                speed_limit_mps: road.effective_speed_limit_mps(),
+                road_length_m: road.length,
+                distance_m: trip.points[start..=end]
+                    .windows(2)
+                    .map(|p| haversine_distance(p[0], p[1]))
+                    .sum(),
+                distance_to_signal_m,
+                hour_of_day: ((start_time / 3600.0).floor() as i64).rem_euclid(24) as i32,
+                // 1970-01-01 was a Thursday
+                day_of_week: ((start_time / 86_400.0).floor() as i64 + 3).rem_euclid(7) as i32,
+                travel_time_s: trip.timestamps[end] - start_time,
+            });
+        }
+    }
+
+    samples
+}
+
+/// Writes samples to a Parquet file.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be created or written.
+pub fn write_parquet(samples: &[TravelTimeSample], path: &str) -> Result<()> {
+    let file = File::create(path).with_context(|| format!("Could not create {}", path))?;
+    let schema = samples.schema()?;
+    let props = Arc::new(WriterProperties::builder().build());
+    let mut writer = SerializedFileWriter::new(file, schema, props)?;
+
+    for chunk in samples.chunks(ROW_GROUP_SIZE) {
+        let mut row_group = writer.next_row_group()?;
+        chunk.write_to_row_group(&mut row_group)?;
+        row_group.close()?;
+    }
+
+    writer.close()?;
+    Ok(())
+}
@@ -0,0 +1,25 @@
+//! Offline analytics over the historical telemetry store.
+//!
+//! Reusable queries that turn raw vehicle positions from TimescaleDB into
+//! tabular results (speed percentiles per road and hour, origin-destination
+//! matrices, detour factors, speeding events). Every result is a plain list of rows that can
+//! be written as CSV with [`output::write_csv`], so heavyweight analysis runs
+//! as a batch job instead of inside the API. Training data for travel-time
+//! models is exported as Parquet by [`export`].
+
+// Loading positions from the historical store
+pub mod store;
+// Splitting position streams into trips
+pub mod trips;
+// Speed percentiles per road and hour of day
+pub mod speed;
+// Origin-destination estimation from trips
+pub mod od;
+// Detour factors of trips against shortest paths
+pub mod detour;
+// Positions above the posted speed limit
+pub mod speeding;
+// CSV output of result tables
+pub mod output;
+// Parquet export of travel-time training data
+pub mod export;
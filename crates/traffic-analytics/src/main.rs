@@ -0,0 +1,178 @@
+//! Traffic Analytics - batch queries over the historical telemetry store.
+//!
+//! Usage:
+//!
+//! ```text
+//! traffic-analytics <speed-percentiles|od|detours|speeding|training-data> [--from TS] [--to TS]
+//!                   [--out PATH] [--cell-size METERS] [--trip-gap SECONDS]
+//!                   [--tolerance FRACTION]
+//! ```
+//!
+//! Timestamps are Unix seconds; the window defaults to the last 24 hours.
+//! Results are written as CSV to `--out` or to stdout, except training data,
+//! which is written as Parquet and requires `--out`.
+
+use anyhow::{bail, Context, Result};
+use sqlx::postgres::PgPoolOptions;
+use std::fs::File;
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+use traffic_analytics::{detour, export, od, output, speed, speeding, store, trips};
+use traffic_common::map::RoadGraph;
+use traffic_common::{init_tracing, Config};
+
+/// Default query window when `--from` is omitted, in seconds.
+const DEFAULT_WINDOW_SECS: i64 = 24 * 3600;
+
+/// Default OD grid cell size in meters.
+const DEFAULT_CELL_SIZE_M: f64 = 500.0;
+
+/// Default longest reporting pause within one trip, in seconds.
+const DEFAULT_TRIP_GAP_SECS: f64 = 300.0;
+
+/// Default allowed excess over the speed limit before a position counts as
+/// speeding, as a fraction of the limit.
+const DEFAULT_SPEEDING_TOLERANCE: f64 = 0.1;
+
+/// Minimum number of positions for a trip to be analysed.
+const MIN_TRIP_POINTS: usize = 3;
+
+/// Analysis to run.
+enum Query {
+    SpeedPercentiles,
+    OriginDestination,
+    Detours,
+    Speeding,
+    TrainingData,
+}
+
+/// Parsed command line.
+struct Args {
+    query: Query,
+    from: Option<i64>,
+    to: Option<i64>,
+    out: Option<String>,
+    cell_size_m: f64,
+    trip_gap_secs: f64,
+    tolerance: f64,
+}
+
+impl Args {
+    /// Parses the command line (without the program name).
+    fn parse(args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut args = args;
+        let query = match args.next().as_deref() {
+            Some("speed-percentiles") => Query::SpeedPercentiles,
+            Some("od") => Query::OriginDestination,
+            Some("detours") => Query::Detours,
+            Some("speeding") => Query::Speeding,
+            Some("training-data") => Query::TrainingData,
+            Some(other) => bail!("unknown query '{}'", other),
+            None => bail!("usage: traffic-analytics <speed-percentiles|od|detours|speeding|training-data> [options]"),
+        };
+
+        let mut parsed = Self {
+            query,
+            from: None,
+            to: None,
+            out: None,
+            cell_size_m: DEFAULT_CELL_SIZE_M,
+            trip_gap_secs: DEFAULT_TRIP_GAP_SECS,
+            tolerance: DEFAULT_SPEEDING_TOLERANCE,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--from" => {
+                    let value = args.next().context("--from requires a value")?;
+                    parsed.from = Some(value.parse().context("--from must be a Unix timestamp")?);
+                }
+                "--to" => {
+                    let value = args.next().context("--to requires a value")?;
+                    parsed.to = Some(value.parse().context("--to must be a Unix timestamp")?);
+                }
+                "--out" => {
+                    parsed.out = Some(args.next().context("--out requires a path")?);
+                }
+                "--cell-size" => {
+                    let value = args.next().context("--cell-size requires a value")?;
+                    parsed.cell_size_m = value.parse().context("--cell-size must be a number")?;
+                }
+                "--trip-gap" => {
+                    let value = args.next().context("--trip-gap requires a value")?;
+                    parsed.trip_gap_secs = value.parse().context("--trip-gap must be a number")?;
+                }
+                "--tolerance" => {
+                    let value = args.next().context("--tolerance requires a value")?;
+                    parsed.tolerance = value.parse().context("--tolerance must be a number")?;
+                }
+                other => tracing::warn!("Ignoring unknown argument: {}", other),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    init_tracing("traffic-analytics");
+    let config = Config::from_env()?;
+    let args = Args::parse(std::env::args().skip(1))?;
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    let to = args.to.unwrap_or(now);
+    let from = args.from.unwrap_or(to - DEFAULT_WINDOW_SECS);
+    if from > to {
+        bail!("--from must not be after --to");
+    }
+    if matches!(args.query, Query::TrainingData) && args.out.is_none() {
+        bail!("training-data requires --out");
+    }
+
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&config.postgres_url)
+        .await
+        .context("Failed to connect to Postgres")?;
+    let graph = RoadGraph::load_or_build_many(&config.map_paths(), config.map_bbox()?)?;
+
+    let positions = store::load_positions(&pool, from, to).await?;
+    tracing::info!("📊 Loaded {} positions between {} and {}", positions.len(), from, to);
+
+    match args.query {
+        Query::SpeedPercentiles => {
+            output::write_csv(&speed::speed_percentiles(&graph, &positions), open_output(&args.out)?)?;
+        }
+        Query::OriginDestination => {
+            let trips = trips::split_trips(&positions, args.trip_gap_secs, MIN_TRIP_POINTS);
+            output::write_csv(&od::od_matrix(&trips, args.cell_size_m), open_output(&args.out)?)?;
+        }
+        Query::Detours => {
+            let trips = trips::split_trips(&positions, args.trip_gap_secs, MIN_TRIP_POINTS);
+            output::write_csv(&detour::detour_factors(&graph, &trips), open_output(&args.out)?)?;
+        }
+        Query::Speeding => {
+            let events = speeding::speeding_events(&graph, &positions, args.tolerance);
+            output::write_csv(&events, open_output(&args.out)?)?;
+        }
+        Query::TrainingData => {
+            let trips = trips::split_trips(&positions, args.trip_gap_secs, MIN_TRIP_POINTS);
+            let samples = export::training_samples(&graph, &trips);
+            export::write_parquet(&samples, args.out.as_deref().unwrap_or_default())?;
+            tracing::info!("🧠 Exported {} training samples", samples.len());
+        }
+    }
+
+    if let Some(path) = &args.out {
+        tracing::info!("✅ Results written to {}", path);
+    }
+    Ok(())
+}
+
+/// Opens the CSV destination: the `--out` file if given, stdout otherwise.
+fn open_output(out: &Option<String>) -> Result<Box<dyn Write>> {
+    Ok(match out {
+        Some(path) => Box::new(File::create(path).with_context(|| format!("Could not create {}", path))?),
+        None => Box::new(std::io::stdout()),
+    })
+}
@@ -0,0 +1,69 @@
+//! Origin-destination estimation.
+//!
+//! Trip origins and destinations are aggregated on a regular grid of square
+//! cells, producing an OD matrix in long (one row per cell pair) format.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use crate::trips::Trip;
+
+/// Meters per degree of latitude.
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// Grid cell coordinates as (column, row).
+type Cell = (i64, i64);
+
+/// Number of trips between two grid cells.
+#[derive(Debug, Clone, Serialize)]
+pub struct OdPair {
+    /// Latitude of the origin cell's south-west corner
+    pub origin_lat: f64,
+    /// Longitude of the origin cell's south-west corner
+    pub origin_lon: f64,
+    /// Latitude of the destination cell's south-west corner
+    pub destination_lat: f64,
+    /// Longitude of the destination cell's south-west corner
+    pub destination_lon: f64,
+    /// Number of trips from origin to destination cell
+    pub trips: usize,
+}
+
+/// Estimates an origin-destination matrix from trips.
+///
+/// # Arguments
+///
+/// * `trips` - Reconstructed trips
+/// * `cell_size_m` - Edge length of the grid cells in meters
+///
+/// # Returns
+///
+/// One row per origin/destination cell pair with at least one trip, ordered
+/// by descending trip count.
+pub fn od_matrix(trips: &[Trip], cell_size_m: f64) -> Vec<OdPair> {
+    let Some(first) = trips.first() else { return Vec::new() };
+
+    // A single longitude scale keeps cells square near the data's latitude
+    let lat_step = cell_size_m / METERS_PER_DEGREE;
+    let lon_step = lat_step / first.origin().1.to_radians().cos().max(0.01);
+    let cell = |(lon, lat): (f64, f64)| -> Cell {
+        ((lon / lon_step).floor() as i64, (lat / lat_step).floor() as i64)
+    };
+
+    let mut counts: BTreeMap<(Cell, Cell), usize> = BTreeMap::new();
+    for trip in trips {
+        *counts.entry((cell(trip.origin()), cell(trip.destination()))).or_default() += 1;
+    }
+
+    let mut pairs: Vec<OdPair> = counts
+        .into_iter()
+        .map(|((origin, destination), trips)| OdPair {
+            origin_lat: origin.1 as f64 * lat_step,
+            origin_lon: origin.0 as f64 * lon_step,
+            destination_lat: destination.1 as f64 * lat_step,
+            destination_lon: destination.0 as f64 * lon_step,
+            trips,
+        })
+        .collect();
+    pairs.sort_by_key(|pair| std::cmp::Reverse(pair.trips));
+    pairs
+}
@@ -0,0 +1,24 @@
+//! CSV output of result tables.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::io::Write;
+
+/// Writes result rows as CSV with a header derived from the row type.
+///
+/// # Arguments
+///
+/// * `rows` - Result rows
+/// * `writer` - Destination, e.g. a file or stdout
+///
+/// # Errors
+///
+/// Returns an error if a row cannot be serialized or written.
+pub fn write_csv<T: Serialize, W: Write>(rows: &[T], writer: W) -> Result<()> {
+    let mut csv = csv::Writer::from_writer(writer);
+    for row in rows {
+        csv.serialize(row).context("Could not write CSV row")?;
+    }
+    csv.flush()?;
+    Ok(())
+}
@@ -0,0 +1,69 @@
+//! Speed percentiles per road and hour of day.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use traffic_common::map::RoadGraph;
+use crate::store::PositionRecord;
+
+/// Speed distribution of one road during one hour of the day.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoadSpeedPercentiles {
+    /// OSM way ID of the road
+    pub road_id: i64,
+    /// Hour of the day (UTC), 0-23
+    pub hour: u32,
+    /// Number of positions snapped to the road in that hour
+    pub samples: usize,
+    /// Median speed in m/s
+    pub p50_mps: f64,
+    /// 85th percentile speed in m/s
+    pub p85_mps: f64,
+    /// 95th percentile speed in m/s
+    pub p95_mps: f64,
+}
+
+/// Computes speed percentiles per road and hour of day.
+///
+/// Every position is snapped to its nearest road and bucketed by the hour of
+/// day of its timestamp, so the result describes a typical day over the
+/// queried window.
+///
+/// # Arguments
+///
+/// * `graph` - Road network used to snap positions to roads
+/// * `positions` - Recorded positions
+///
+/// # Returns
+///
+/// One row per (road, hour) with at least one sample, ordered by road and
+/// hour.
+pub fn speed_percentiles(graph: &RoadGraph, positions: &[PositionRecord]) -> Vec<RoadSpeedPercentiles> {
+    let mut buckets: BTreeMap<(i64, u32), Vec<f64>> = BTreeMap::new();
+
+    for record in positions {
+        let Some(edge) = graph.nearest_edge(record.longitude, record.latitude) else { continue };
+        let hour = ((record.timestamp / 3600.0).floor() as i64).rem_euclid(24) as u32;
+        buckets.entry((graph.edges[edge].id, hour)).or_default().push(record.speed);
+    }
+
+    buckets
+        .into_iter()
+        .map(|((road_id, hour), mut speeds)| {
+            speeds.sort_by(f64::total_cmp);
+            RoadSpeedPercentiles {
+                road_id,
+                hour,
+                samples: speeds.len(),
+                p50_mps: percentile(&speeds, 0.50),
+                p85_mps: percentile(&speeds, 0.85),
+                p95_mps: percentile(&speeds, 0.95),
+            }
+        })
+        .collect()
+}
+
+/// Nearest-rank percentile of sorted, non-empty values.
+fn percentile(sorted: &[f64], fraction: f64) -> f64 {
+    let rank = (fraction * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
@@ -0,0 +1,48 @@
+//! Speeding detection against posted speed limits.
+
+use serde::Serialize;
+use traffic_common::map::RoadGraph;
+use crate::store::PositionRecord;
+
+/// A position recorded above the speed limit of its road.
+#[derive(Debug, Clone, Serialize)]
+pub struct SpeedingEvent {
+    /// Vehicle that was speeding
+    pub vehicle_id: String,
+    /// Unix timestamp of the position
+    pub timestamp: f64,
+    /// OSM way ID of the road
+    pub road_id: i64,
+    /// Recorded speed in m/s
+    pub speed_mps: f64,
+    /// Tagged speed limit of the road in m/s
+    pub limit_mps: f64,
+}
+
+/// Finds positions recorded above the tagged speed limit of their road.
+///
+/// Only roads with an explicit `maxspeed` tag are checked; class defaults
+/// are too coarse to accuse anyone of speeding.
+///
+/// # Arguments
+///
+/// * `graph` - Road network used to snap positions and look up limits
+/// * `positions` - Recorded positions
+/// * `tolerance` - Allowed excess as a fraction of the limit (e.g. `0.1`)
+pub fn speeding_events(graph: &RoadGraph, positions: &[PositionRecord], tolerance: f64) -> Vec<SpeedingEvent> {
+    positions
+        .iter()
+        .filter_map(|record| {
+            let edge = graph.nearest_edge(record.longitude, record.latitude)?;
+            let road = &graph.edges[edge];
+            let limit_mps = road.speed_limit_mps?;
+            (record.speed > limit_mps * (1.0 + tolerance)).then(|| SpeedingEvent {
+                vehicle_id: record.vehicle_id.clone(),
+                timestamp: record.timestamp,
+                road_id: road.id,
+                speed_mps: record.speed,
+                limit_mps,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,64 @@
+//! Access to the historical position store.
+
+use sqlx::PgPool;
+
+/// A recorded vehicle position.
+#[derive(Debug, Clone)]
+pub struct PositionRecord {
+    /// Vehicle identifier
+    pub vehicle_id: String,
+    /// Unix timestamp in seconds
+    pub timestamp: f64,
+    /// Latitude in degrees
+    pub latitude: f64,
+    /// Longitude in degrees
+    pub longitude: f64,
+    /// Speed in m/s
+    pub speed: f64,
+}
+
+/// Loads all positions recorded within a time window.
+///
+/// Rows without coordinates are skipped. Results are ordered by vehicle and
+/// then by time, which is the order expected by [`crate::trips::split_trips`].
+///
+/// # Arguments
+///
+/// * `pool` - Connection pool to TimescaleDB
+/// * `from` - Start of the window as a Unix timestamp
+/// * `to` - End of the window as a Unix timestamp
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn load_positions(pool: &PgPool, from: i64, to: i64) -> Result<Vec<PositionRecord>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT vehicle_id,
+               extract(epoch FROM time)::float8 AS "timestamp!",
+               latitude AS "latitude!",
+               longitude AS "longitude!",
+               speed
+        FROM vehicle_positions
+        WHERE time BETWEEN to_timestamp($1) AND to_timestamp($2)
+          AND latitude IS NOT NULL
+          AND longitude IS NOT NULL
+        ORDER BY vehicle_id, time
+        "#,
+        from as f64,
+        to as f64
+    )
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| PositionRecord {
+            vehicle_id: row.vehicle_id,
+            timestamp: row.timestamp,
+            latitude: row.latitude,
+            longitude: row.longitude,
+            speed: row.speed.unwrap_or(0.0),
+        })
+        .collect())
+}
@@ -0,0 +1,84 @@
+//! Trip segmentation.
+//!
+//! The store only holds positions, so trips are reconstructed by splitting
+//! each vehicle's position stream wherever reporting pauses for longer than a
+//! gap threshold.
+
+use traffic_common::geo::haversine_distance;
+use crate::store::PositionRecord;
+
+/// A continuous trip of one vehicle.
+#[derive(Debug, Clone)]
+pub struct Trip {
+    /// Vehicle that made the trip
+    pub vehicle_id: String,
+    /// Unix timestamp of the first position
+    pub start_time: f64,
+    /// Unix timestamp of the last position
+    pub end_time: f64,
+    /// Positions along the trip as (longitude, latitude)
+    pub points: Vec<(f64, f64)>,
+    /// Unix timestamp of each position in `points`
+    pub timestamps: Vec<f64>,
+}
+
+impl Trip {
+    /// First position of the trip as (longitude, latitude).
+    pub fn origin(&self) -> (f64, f64) {
+        self.points[0]
+    }
+
+    /// Last position of the trip as (longitude, latitude).
+    pub fn destination(&self) -> (f64, f64) {
+        self.points[self.points.len() - 1]
+    }
+
+    /// Distance driven along the recorded positions, in meters.
+    pub fn driven_distance(&self) -> f64 {
+        self.points.windows(2).map(|pair| haversine_distance(pair[0], pair[1])).sum()
+    }
+}
+
+/// Splits position streams into trips.
+///
+/// A new trip starts whenever the vehicle changes or consecutive positions
+/// are more than `max_gap_secs` apart. Trips with fewer than `min_points`
+/// positions are dropped as noise.
+///
+/// # Arguments
+///
+/// * `positions` - Positions ordered by vehicle and then by time
+/// * `max_gap_secs` - Longest reporting pause within a single trip
+/// * `min_points` - Minimum number of positions for a trip to be kept
+pub fn split_trips(positions: &[PositionRecord], max_gap_secs: f64, min_points: usize) -> Vec<Trip> {
+    let mut trips = Vec::new();
+    let mut current: Option<Trip> = None;
+
+    for record in positions {
+        let point = (record.longitude, record.latitude);
+
+        if let Some(trip) = current.as_mut() {
+            if trip.vehicle_id == record.vehicle_id && record.timestamp - trip.end_time <= max_gap_secs {
+                trip.points.push(point);
+                trip.timestamps.push(record.timestamp);
+                trip.end_time = record.timestamp;
+                continue;
+            }
+        }
+
+        if let Some(finished) = current.take() {
+            trips.push(finished);
+        }
+        current = Some(Trip {
+            vehicle_id: record.vehicle_id.clone(),
+            start_time: record.timestamp,
+            end_time: record.timestamp,
+            points: vec![point],
+            timestamps: vec![record.timestamp],
+        });
+    }
+    trips.extend(current);
+
+    trips.retain(|trip| trip.points.len() >= min_points.max(1));
+    trips
+}
@@ -0,0 +1,273 @@
+//! Operator endpoints for auditing the hot-path Redis state.
+//!
+//! The keyspace report scans every key in Redis, groups keys into patterns
+//! (e.g. `vehicle:*:meta`), and reports per-pattern counts, memory usage and
+//! TTL distribution. Patterns the system does not maintain are flagged as
+//! unknown, and geo index members without a live metadata key ("ghosts" of
+//! vehicles that stopped reporting) are counted separately. The memory
+//! report breaks down how much of the API process the road graph takes.
+
+use axum::{extract::State, http::StatusCode, Json};
+use common::map::GraphMemory;
+use redis::aio::MultiplexedConnection;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use tracing::error;
+use crate::AppState;
+
+/// Geo index of the latest vehicle positions, maintained by traffic-ingest.
+pub(crate) const GEO_INDEX_KEY: &str = "vehicles:current";
+
+/// Key patterns maintained by the system, with a short description each.
+const KNOWN_PATTERNS: &[(&str, &str)] = &[
+    (GEO_INDEX_KEY, "Geo index of the latest vehicle positions"),
+    ("vehicle:*:meta", "Latest speed, heading, timestamp and matched road per vehicle (60 s TTL)"),
+    (crate::zones::ZONE_STATS_KEY, "Latest per-zone vehicle counts and average speeds (60 s TTL)"),
+];
+
+/// Keys inspected per pipelined round trip.
+const PIPELINE_CHUNK: usize = 500;
+
+/// Upper bounds (exclusive, in seconds) of the TTL histogram buckets.
+const TTL_BUCKETS_SECS: [i64; 3] = [10, 60, 300];
+
+/// Keyspace layout of the Redis instance.
+#[derive(Serialize)]
+pub struct KeyspaceReport {
+    /// Number of keys found by the scan
+    pub scanned_keys: usize,
+    /// Per-pattern statistics, largest memory consumers first
+    pub patterns: Vec<PatternStats>,
+    /// Consistency of the geo index with the per-vehicle metadata keys
+    pub geo_index: GeoIndexHealth,
+}
+
+/// Statistics of all keys matching one pattern.
+#[derive(Serialize)]
+pub struct PatternStats {
+    /// Key pattern, with variable segments replaced by `*`
+    pub pattern: String,
+    /// Whether the system is expected to maintain keys of this pattern
+    pub known: bool,
+    /// What the keys hold, for known patterns
+    pub description: Option<&'static str>,
+    /// Redis types seen for the keys (normally exactly one)
+    pub key_types: Vec<String>,
+    /// Number of matching keys
+    pub count: usize,
+    /// Total memory used by the matching keys in bytes
+    pub memory_bytes: u64,
+    /// Distribution of remaining time-to-live
+    pub ttl: TtlHistogram,
+}
+
+/// Remaining time-to-live of a set of keys, bucketed.
+#[derive(Serialize, Default)]
+pub struct TtlHistogram {
+    /// Keys without an expiry
+    pub persistent: usize,
+    /// Keys expiring within 10 seconds
+    pub under_10s: usize,
+    /// Keys expiring within 10-60 seconds
+    pub under_60s: usize,
+    /// Keys expiring within 60-300 seconds
+    pub under_300s: usize,
+    /// Keys expiring after more than 300 seconds
+    pub longer: usize,
+}
+
+impl TtlHistogram {
+    /// Counts a key by its `PTTL` reply (`-1` means no expiry).
+    fn record(&mut self, pttl_ms: i64) {
+        if pttl_ms < 0 {
+            self.persistent += 1;
+            return;
+        }
+        let secs = pttl_ms / 1000;
+        match TTL_BUCKETS_SECS.iter().position(|&bound| secs < bound) {
+            Some(0) => self.under_10s += 1,
+            Some(1) => self.under_60s += 1,
+            Some(_) => self.under_300s += 1,
+            None => self.longer += 1,
+        }
+    }
+}
+
+/// Health of the vehicle geo index.
+#[derive(Serialize)]
+pub struct GeoIndexHealth {
+    /// Members of the geo index
+    pub members: usize,
+    /// Members whose metadata key has expired, i.e. vehicles that stopped
+    /// reporting but are still returned by proximity searches
+    pub without_meta: usize,
+}
+
+/// Memory used by the API process.
+#[derive(Serialize)]
+pub struct MemoryReport {
+    /// Resident set size of the process in bytes (Linux only)
+    pub resident_bytes: Option<u64>,
+    /// Estimated memory of the loaded road graph, per part
+    pub graph: GraphMemory,
+    /// Estimated total of `graph` in bytes
+    pub graph_total_bytes: usize,
+}
+
+/// Memory introspection endpoint handler.
+pub async fn memory(State(state): State<Arc<AppState>>) -> Json<MemoryReport> {
+    let graph = state.graph.memory_usage();
+    Json(MemoryReport {
+        resident_bytes: common::resident_memory_bytes(),
+        graph,
+        graph_total_bytes: graph.total(),
+    })
+}
+
+/// Keyspace introspection endpoint handler.
+///
+/// Scans the whole keyspace with `SCAN`, so it is meant for occasional
+/// operator use rather than for dashboards polling every few seconds.
+/// Responds with 503 if Redis is unreachable.
+pub async fn redis_keyspace(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<KeyspaceReport>, StatusCode> {
+    let report = build_report(&state.redis).await.map_err(|e| {
+        error!("❌ Redis keyspace scan failed: {}", e);
+        StatusCode::SERVICE_UNAVAILABLE
+    })?;
+    Ok(Json(report))
+}
+
+/// Scans Redis and aggregates the keyspace report.
+async fn build_report(client: &redis::Client) -> redis::RedisResult<KeyspaceReport> {
+    let mut con = client.get_multiplexed_async_connection().await?;
+    let keys = scan_keys(&mut con).await?;
+
+    let mut patterns: BTreeMap<String, PatternStats> = BTreeMap::new();
+    for chunk in keys.chunks(PIPELINE_CHUNK) {
+        let mut pipe = redis::pipe();
+        for key in chunk {
+            pipe.cmd("TYPE").arg(key);
+            pipe.cmd("PTTL").arg(key);
+            pipe.cmd("MEMORY").arg("USAGE").arg(key);
+        }
+        let replies: Vec<redis::Value> = pipe.query_async(&mut con).await?;
+
+        for (key, reply) in chunk.iter().zip(replies.chunks(3)) {
+            let key_type: String = redis::from_redis_value(&reply[0])?;
+            let pttl_ms: i64 = redis::from_redis_value(&reply[1])?;
+            let memory: Option<u64> = redis::from_redis_value(&reply[2])?;
+            let pattern = key_pattern(key);
+            let stats = patterns.entry(pattern.clone()).or_insert_with(|| {
+                let description = known_description(&pattern);
+                PatternStats {
+                    pattern,
+                    known: description.is_some(),
+                    description,
+                    key_types: Vec::new(),
+                    count: 0,
+                    memory_bytes: 0,
+                    ttl: TtlHistogram::default(),
+                }
+            });
+            stats.count += 1;
+            stats.memory_bytes += memory.unwrap_or(0);
+            stats.ttl.record(pttl_ms);
+            if !stats.key_types.contains(&key_type) {
+                stats.key_types.push(key_type);
+            }
+        }
+    }
+
+    let geo_index = geo_index_health(&mut con, &keys).await?;
+
+    let mut patterns: Vec<PatternStats> = patterns.into_values().collect();
+    patterns.sort_by_key(|stats| std::cmp::Reverse(stats.memory_bytes));
+
+    Ok(KeyspaceReport {
+        scanned_keys: keys.len(),
+        patterns,
+        geo_index,
+    })
+}
+
+/// Collects every key name with a cursor-based `SCAN`.
+async fn scan_keys(con: &mut MultiplexedConnection) -> redis::RedisResult<Vec<String>> {
+    let mut keys = Vec::new();
+    let mut cursor: u64 = 0;
+    loop {
+        let (next, batch): (u64, Vec<String>) = redis::cmd("SCAN")
+            .arg(cursor)
+            .arg("COUNT")
+            .arg(1000)
+            .query_async(con)
+            .await?;
+        keys.extend(batch);
+        if next == 0 {
+            break;
+        }
+        cursor = next;
+    }
+    // SCAN may return a key more than once
+    keys.sort_unstable();
+    keys.dedup();
+    Ok(keys)
+}
+
+/// Counts geo index members whose metadata key no longer exists.
+async fn geo_index_health(
+    con: &mut MultiplexedConnection,
+    keys: &[String],
+) -> redis::RedisResult<GeoIndexHealth> {
+    let members: Vec<String> = redis::cmd("ZRANGE")
+        .arg(GEO_INDEX_KEY)
+        .arg(0)
+        .arg(-1)
+        .query_async(con)
+        .await?;
+    let keys: HashSet<&str> = keys.iter().map(String::as_str).collect();
+    let without_meta = members
+        .iter()
+        .filter(|id| !keys.contains(format!("vehicle:{}:meta", id).as_str()))
+        .count();
+
+    Ok(GeoIndexHealth {
+        members: members.len(),
+        without_meta,
+    })
+}
+
+/// Derives the pattern of a key.
+///
+/// Known patterns win; otherwise every `:`-separated segment containing a
+/// digit is treated as an identifier and replaced by `*`.
+fn key_pattern(key: &str) -> String {
+    if let Some((pattern, _)) = KNOWN_PATTERNS.iter().find(|(pattern, _)| matches_pattern(pattern, key)) {
+        return pattern.to_string();
+    }
+    key.split(':')
+        .map(|segment| if segment.bytes().any(|b| b.is_ascii_digit()) { "*" } else { segment })
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Returns the description of a known pattern.
+fn known_description(pattern: &str) -> Option<&'static str> {
+    KNOWN_PATTERNS
+        .iter()
+        .find(|(known, _)| *known == pattern)
+        .map(|(_, description)| *description)
+}
+
+/// Matches a key against a pattern with at most one `*`, which matches any
+/// non-empty run of characters (vehicle IDs may themselves contain `:`).
+fn matches_pattern(pattern: &str, key: &str) -> bool {
+    match pattern.split_once('*') {
+        Some((prefix, suffix)) => {
+            key.len() > prefix.len() + suffix.len() && key.starts_with(prefix) && key.ends_with(suffix)
+        }
+        None => pattern == key,
+    }
+}
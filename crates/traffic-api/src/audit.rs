@@ -0,0 +1,198 @@
+//! Audit log of operational changes.
+//!
+//! Every call to the admin, control, dispatch and registry-changing routes
+//! is written to the `audit_log` table: who made it (the authenticated
+//! principal, or `anonymous` without authentication), what was called,
+//! when, with which result, and, for changes, the new and the previous
+//! value of the changed target. `GET /admin/audit` returns the log.
+//!
+//! Handlers describe their change by attaching an [`AuditChange`] to the
+//! response; the [`record`] middleware writes the entry after the handler
+//! ran. Calls without a change (e.g. admin reads, rejected requests) are
+//! recorded with action and status only.
+
+use axum::{
+    extract::{Query, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{error, warn};
+use crate::auth::{Principal, ANONYMOUS};
+use crate::AppState;
+
+/// Entries returned by `GET /admin/audit` when no limit is given.
+const DEFAULT_LIMIT: i64 = 100;
+
+/// Most entries returned by one `GET /admin/audit` call.
+const MAX_LIMIT: i64 = 1000;
+
+/// Change made by a handler, attached to its response as an extension.
+#[derive(Debug, Clone)]
+pub struct AuditChange {
+    /// What was changed, e.g. `signal_plan:42` or `vehicle:car_1`
+    pub target: String,
+    /// New value (`None` when the target was removed)
+    pub value: Option<Value>,
+    /// Value before the change, if known
+    pub previous: Option<Value>,
+}
+
+/// One entry of the audit log.
+#[derive(Serialize)]
+pub struct AuditEntry {
+    /// Entry identifier
+    id: i64,
+    /// Unix timestamp (seconds) of the call
+    timestamp: f64,
+    /// Principal who made the call
+    actor: String,
+    /// Role of the principal, if authenticated
+    role: Option<String>,
+    /// Method and path, e.g. `PUT /control/signals`
+    action: String,
+    /// HTTP status of the response
+    status: i16,
+    /// Changed target, if the call changed something
+    target: Option<String>,
+    /// New value of the target
+    value: Option<Value>,
+    /// Previous value of the target
+    previous: Option<Value>,
+}
+
+/// Query parameters of the audit endpoint.
+#[derive(Deserialize)]
+pub struct AuditParams {
+    /// Only entries at or after this Unix timestamp
+    from: Option<i64>,
+    /// Only entries at or before this Unix timestamp
+    to: Option<i64>,
+    /// Only entries of this actor
+    actor: Option<String>,
+    /// Only entries changing this target
+    target: Option<String>,
+    /// Maximum number of entries (default 100, at most 1000)
+    limit: Option<i64>,
+}
+
+/// Middleware writing an audit entry for every call it wraps.
+///
+/// Must run inside [`crate::auth::require_role`] so the principal is known.
+/// A failed write is logged but does not fail the call, which has already
+/// taken effect.
+pub async fn record(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let principal = request.extensions().get::<Principal>().cloned();
+    let action = format!("{} {}", request.method(), request.uri().path());
+
+    let response = next.run(request).await;
+
+    let change = response.extensions().get::<AuditChange>().cloned();
+    let (target, value, previous) = match change {
+        Some(change) => (Some(change.target), change.value, change.previous),
+        None => (None, None, None),
+    };
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO audit_log (actor, role, action, status, target, value, previous)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+        principal.as_ref().map_or(ANONYMOUS, |p| p.name.as_str()),
+        principal.as_ref().map(|p| p.role.as_str()),
+        action,
+        response.status().as_u16() as i16,
+        target,
+        value,
+        previous
+    )
+        .execute(&state.db)
+        .await;
+    if let Err(e) = result {
+        error!("❌ Failed to write audit entry for {}: {}", action, e);
+    }
+
+    response
+}
+
+/// Returns the value a target was last successfully set to through the API.
+///
+/// Used as the previous value of changes the API cannot read back (e.g.
+/// signal plans held by the simulator). `None` if the target was never
+/// changed, was removed, or the log cannot be read.
+pub async fn last_value(db: &PgPool, target: &str) -> Option<Value> {
+    let row = sqlx::query!(
+        r#"
+        SELECT value
+        FROM audit_log
+        WHERE target = $1 AND status BETWEEN 200 AND 299
+        ORDER BY time DESC, id DESC
+        LIMIT 1
+        "#,
+        target
+    )
+        .fetch_optional(db)
+        .await;
+    match row {
+        Ok(row) => row.and_then(|row| row.value),
+        Err(e) => {
+            warn!("⚠️ Could not read the previous value of {}: {}", target, e);
+            None
+        }
+    }
+}
+
+/// Audit log endpoint handler.
+///
+/// Returns entries newest first, optionally filtered by time window, actor
+/// and target. Responds with 503 if TimescaleDB is unreachable.
+pub async fn get_audit(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AuditParams>,
+) -> Result<Json<Vec<AuditEntry>>, StatusCode> {
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let rows = sqlx::query!(
+        r#"
+        SELECT id,
+               extract(epoch FROM time)::float8 AS "timestamp!",
+               actor, role, action, status, target, value, previous
+        FROM audit_log
+        WHERE ($1::float8 IS NULL OR time >= to_timestamp($1))
+          AND ($2::float8 IS NULL OR time <= to_timestamp($2))
+          AND ($3::text IS NULL OR actor = $3)
+          AND ($4::text IS NULL OR target = $4)
+        ORDER BY time DESC, id DESC
+        LIMIT $5
+        "#,
+        params.from.map(|from| from as f64),
+        params.to.map(|to| to as f64),
+        params.actor,
+        params.target,
+        limit
+    )
+        .fetch_all(&state.db)
+        .await
+        .map_err(|e| {
+            error!("❌ Failed to read the audit log: {}", e);
+            StatusCode::SERVICE_UNAVAILABLE
+        })?;
+
+    Ok(Json(rows
+        .into_iter()
+        .map(|row| AuditEntry {
+            id: row.id,
+            timestamp: row.timestamp,
+            actor: row.actor,
+            role: row.role,
+            action: row.action,
+            status: row.status,
+            target: row.target,
+            value: row.value,
+            previous: row.previous,
+        })
+        .collect()))
+}
@@ -0,0 +1,265 @@
+//! Authentication and role-based authorization.
+//!
+//! With `AUTH_ENABLED=true` every route except `/health` requires a bearer
+//! token, sent as `Authorization: Bearer <token>` or, on `/ws` only, for
+//! WebSocket clients that cannot set headers, as an `access_token` query
+//! parameter. Other routes ignore the parameter so keys do not end up in
+//! access logs. A token is either
+//!
+//! - a JWT signed with `JWT_SECRET` (HS256) carrying `sub`, `role` and
+//!   `exp` claims, issued by an external identity provider, or
+//! - an API key from the `api_keys` table, created with
+//!   `traffic-api create-api-key`.
+//!
+//! Each route group requires a minimum [`Role`]; higher roles include the
+//! powers of lower ones. Missing or invalid credentials are answered with
+//! 401, insufficient roles with 403. The authenticated [`Principal`] is
+//! attached to the request for handlers.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{error, warn};
+use common::Config;
+use crate::AppState;
+
+/// How long API key lookups are cached; revoked keys stay valid this long.
+const KEY_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// The only route accepting the token as a query parameter.
+const QUERY_TOKEN_PATH: &str = "/ws";
+
+/// Prefix of generated API keys, to recognize them in logs and configs.
+const API_KEY_PREFIX: &str = "tct_";
+
+/// Random bytes in a generated API key.
+const API_KEY_BYTES: usize = 32;
+
+/// Name recorded for callers when authentication is disabled.
+pub const ANONYMOUS: &str = "anonymous";
+
+/// Powers of an account, from least to most privileged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Live map, statistics and vehicle registry (read-only)
+    Viewer,
+    /// Viewer plus historical exports
+    Analyst,
+    /// Analyst plus simulation control, dispatch and registry changes
+    Operator,
+    /// Everything, including the admin endpoints
+    Admin,
+}
+
+impl Role {
+    /// Returns the name stored in the `api_keys` table and JWT claims.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Viewer => "viewer",
+            Self::Analyst => "analyst",
+            Self::Operator => "operator",
+            Self::Admin => "admin",
+        }
+    }
+
+    /// Parses a role name as stored in the `api_keys` table.
+    fn parse(name: &str) -> Option<Self> {
+        [Self::Viewer, Self::Analyst, Self::Operator, Self::Admin]
+            .into_iter()
+            .find(|role| role.as_str() == name)
+    }
+}
+
+/// The authenticated caller of a request.
+#[derive(Debug, Clone)]
+pub struct Principal {
+    /// JWT subject or API key name
+    pub name: String,
+    /// Granted role
+    pub role: Role,
+}
+
+/// Claims expected in a JWT; `exp` is checked by the decoder.
+#[derive(Deserialize)]
+struct Claims {
+    sub: String,
+    role: Role,
+}
+
+/// Verifies bearer tokens against `JWT_SECRET` and the `api_keys` table.
+pub struct Authenticator {
+    /// Whether credentials are required at all
+    enabled: bool,
+    /// Key and rules for JWTs, if `JWT_SECRET` is set
+    jwt: Option<(DecodingKey, Validation)>,
+    /// Recently verified API keys by key hash. Unknown keys are never
+    /// cached, so random tokens cannot grow it; expired entries are
+    /// evicted on every insert.
+    key_cache: RwLock<HashMap<String, (Principal, Instant)>>,
+}
+
+impl Authenticator {
+    /// Creates the authenticator from `AUTH_ENABLED` and `JWT_SECRET`.
+    pub fn new(config: &Config) -> Self {
+        let jwt = config
+            .jwt_secret
+            .as_ref()
+            .map(|secret| (DecodingKey::from_secret(secret.as_bytes()), Validation::new(Algorithm::HS256)));
+        if config.auth_enabled && jwt.is_none() {
+            warn!("⚠️ JWT_SECRET is not set, only API keys are accepted");
+        }
+        Self {
+            enabled: config.auth_enabled,
+            jwt,
+            key_cache: Default::default(),
+        }
+    }
+
+    /// Returns `true` if routes require credentials.
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Resolves a bearer token to its principal.
+    ///
+    /// # Errors
+    ///
+    /// Returns 401 for invalid, expired or revoked tokens and 503 if API
+    /// keys cannot be looked up.
+    async fn authenticate(&self, db: &PgPool, token: &str) -> Result<Principal, StatusCode> {
+        // JWTs have three dot-separated parts; API keys have none
+        if token.matches('.').count() == 2 {
+            let Some((key, validation)) = &self.jwt else {
+                return Err(StatusCode::UNAUTHORIZED);
+            };
+            return jsonwebtoken::decode::<Claims>(token, key, validation)
+                .map(|data| Principal { name: data.claims.sub, role: data.claims.role })
+                .map_err(|e| {
+                    warn!("Rejecting JWT: {}", e);
+                    StatusCode::UNAUTHORIZED
+                });
+        }
+
+        let hash = hash_api_key(token);
+        if let Some((principal, looked_up)) = self.key_cache.read().unwrap().get(&hash) {
+            if looked_up.elapsed() < KEY_CACHE_TTL {
+                return Ok(principal.clone());
+            }
+        }
+
+        let row = sqlx::query!(
+            "SELECT name, role FROM api_keys WHERE key_hash = $1 AND revoked_at IS NULL",
+            hash
+        )
+            .fetch_optional(db)
+            .await
+            .map_err(|e| {
+                error!("❌ API key lookup failed: {}", e);
+                StatusCode::SERVICE_UNAVAILABLE
+            })?;
+        let principal = row
+            .and_then(|row| Some(Principal { role: Role::parse(&row.role)?, name: row.name }))
+            .ok_or(StatusCode::UNAUTHORIZED)?;
+
+        let mut cache = self.key_cache.write().unwrap();
+        cache.retain(|_, (_, looked_up)| looked_up.elapsed() < KEY_CACHE_TTL);
+        cache.insert(hash, (principal.clone(), Instant::now()));
+        Ok(principal)
+    }
+}
+
+/// Middleware admitting only callers with at least the given role.
+///
+/// Used as a route layer with the application state and the route group's
+/// minimum role, e.g.
+/// `from_fn_with_state((state.clone(), Role::Admin), require_role)`.
+/// Passes every request through when authentication is disabled.
+pub async fn require_role(
+    State((state, required)): State<(Arc<AppState>, Role)>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    if !state.auth.enabled() {
+        return Ok(next.run(request).await);
+    }
+
+    let token = bearer_token(&request).ok_or(StatusCode::UNAUTHORIZED)?;
+    let principal = state.auth.authenticate(&state.db, &token).await?;
+    if principal.role < required {
+        warn!(
+            "Denying {} {} to {} (role {}, needs {})",
+            request.method(),
+            request.uri().path(),
+            principal.name,
+            principal.role.as_str(),
+            required.as_str()
+        );
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    request.extensions_mut().insert(principal);
+    Ok(next.run(request).await)
+}
+
+/// Extracts the token from the `Authorization` header or, on
+/// [`QUERY_TOKEN_PATH`], the `access_token` query parameter.
+fn bearer_token(request: &Request) -> Option<String> {
+    let header = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let query = || {
+        if request.uri().path() != QUERY_TOKEN_PATH {
+            return None;
+        }
+        request
+            .uri()
+            .query()?
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("access_token="))
+    };
+    header.or_else(query).map(str::trim).filter(|token| !token.is_empty()).map(str::to_string)
+}
+
+/// Generates a new random API key.
+pub fn generate_api_key() -> String {
+    let mut bytes = [0u8; API_KEY_BYTES];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("{}{}", API_KEY_PREFIX, hex)
+}
+
+/// Returns the hex SHA-256 hash under which an API key is stored.
+pub fn hash_api_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+/// Stores a new API key for an account.
+///
+/// # Errors
+///
+/// Returns an error if the key cannot be inserted.
+pub async fn create_api_key(db: &PgPool, name: &str, role: Role, key: &str) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "INSERT INTO api_keys (key_hash, name, role) VALUES ($1, $2, $3)",
+        hash_api_key(key),
+        name,
+        role.as_str()
+    )
+        .execute(db)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,83 @@
+//! Command line of the API server.
+//!
+//! ```text
+//! traffic-api [serve] [--dry-run] [--replay SESSION]
+//! traffic-api check-config
+//! traffic-api diff-map NEW_MAP...
+//! traffic-api create-api-key --name NAME --role ROLE
+//! ```
+//!
+//! Without a subcommand the server starts as before (`serve`).
+
+use std::path::PathBuf;
+use clap::{Args, Parser, Subcommand};
+use common::cli::CommonArgs;
+use crate::auth::Role;
+
+/// WebSocket and REST API server of the traffic control tower.
+#[derive(Debug, Parser)]
+#[command(name = "traffic-api", version, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(flatten)]
+    pub common: CommonArgs,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Options of `serve` when no subcommand is given
+    #[command(flatten)]
+    pub serve: ServeArgs,
+}
+
+impl Cli {
+    /// Returns the subcommand to execute, defaulting to `serve`.
+    pub fn command(self) -> Command {
+        self.command.unwrap_or(Command::Serve(self.serve))
+    }
+}
+
+/// Subcommands of the API server.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Serve the REST and WebSocket API (default)
+    Serve(ServeArgs),
+    /// Validate the configuration and the map, then exit
+    CheckConfig,
+    /// Compare the configured map with a newer extract and print the differences as JSON
+    DiffMap(DiffMapArgs),
+    /// Create an API key for an account and print it
+    CreateApiKey(CreateApiKeyArgs),
+}
+
+/// Options of `serve`.
+#[derive(Debug, Clone, Default, Args)]
+pub struct ServeArgs {
+    /// Serve the map only, without connecting to Kafka, Redis or TimescaleDB
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Replay a recorded session through /ws in a loop (demo mode, implies --dry-run)
+    #[arg(long, value_name = "SESSION")]
+    pub replay: Option<PathBuf>,
+}
+
+/// Options of `diff-map`.
+#[derive(Debug, Args)]
+pub struct DiffMapArgs {
+    /// Map files of the newer version, loaded with the configured bounding box
+    #[arg(required = true)]
+    pub paths: Vec<String>,
+    /// Also write the changed roads as a GeoJSON changeset to this file
+    #[arg(long, value_name = "FILE")]
+    pub geojson: Option<PathBuf>,
+}
+
+/// Options of `create-api-key`.
+#[derive(Debug, Args)]
+pub struct CreateApiKeyArgs {
+    /// Account or service the key belongs to
+    #[arg(long)]
+    pub name: String,
+    /// Role granted to the key
+    #[arg(long, value_enum)]
+    pub role: Role,
+}
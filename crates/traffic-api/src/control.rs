@@ -0,0 +1,164 @@
+//! Simulation control endpoints.
+//!
+//! Translates HTTP requests into [`SimCommand`]s produced to the control
+//! topic, where traffic-sim picks them up between ticks. Besides pausing,
+//! this lets external optimizers iterate signal plans against a running
+//! simulation. Each command is audited with the value it replaced.
+
+use axum::{extract::{Path, State}, http::StatusCode, Extension, Json};
+use common::control::{EmissionRates, SimCommand, CONTROL_TOPIC};
+use common::signals::SignalPlan;
+use rdkafka::producer::FutureRecord;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+use crate::audit::{last_value, AuditChange};
+use crate::AppState;
+
+/// Response payload for control endpoints.
+#[derive(Serialize)]
+pub struct ControlAck {
+    /// The command that was forwarded to the simulator
+    command: SimCommand,
+}
+
+/// Response of control endpoints, with the change for the audit log.
+type ControlResponse = Result<(Extension<AuditChange>, Json<ControlAck>), StatusCode>;
+
+/// Audit target of the simulation's run state.
+const SIMULATION_TARGET: &str = "simulation";
+
+/// Audit target of the telemetry emission rates.
+const EMISSION_TARGET: &str = "emission_rates";
+
+/// Returns the audit target of a road's closure.
+fn closure_target(way_id: i64) -> String {
+    format!("closure:{}", way_id)
+}
+
+/// Returns the audit target of an intersection's signal plan.
+fn signal_plan_target(node_id: i64) -> String {
+    format!("signal_plan:{}", node_id)
+}
+
+/// Pause endpoint handler.
+///
+/// Asks the simulator to freeze vehicle movement. Telemetry continues as
+/// low-rate keepalive frames flagged as paused.
+pub async fn pause(State(state): State<Arc<AppState>>) -> ControlResponse {
+    send_audited(&state, SimCommand::Pause, SIMULATION_TARGET.to_string(), Some(Value::from("paused"))).await
+}
+
+/// Resume endpoint handler.
+///
+/// Asks the simulator to resume vehicle movement after a pause.
+pub async fn resume(State(state): State<Arc<AppState>>) -> ControlResponse {
+    send_audited(&state, SimCommand::Resume, SIMULATION_TARGET.to_string(), Some(Value::from("running"))).await
+}
+
+/// Signal plan update handler.
+///
+/// Installs or replaces the fixed-time plan of the intersection named in the
+/// request body. Malformed plans are rejected before reaching the simulator.
+pub async fn set_signal_plan(
+    State(state): State<Arc<AppState>>,
+    Json(plan): Json<SignalPlan>,
+) -> ControlResponse {
+    if let Err(e) = plan.validate() {
+        warn!("Rejecting signal plan: {}", e);
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    let target = signal_plan_target(plan.node_id);
+    let value = serde_json::to_value(&plan).ok();
+    send_audited(&state, SimCommand::SetSignalPlan(plan), target, value).await
+}
+
+/// Signal plan removal handler.
+///
+/// Removes the plan of the given intersection, leaving it unsignalized.
+pub async fn clear_signal_plan(
+    State(state): State<Arc<AppState>>,
+    Path(node_id): Path<i64>,
+) -> ControlResponse {
+    send_audited(&state, SimCommand::ClearSignalPlan { node_id }, signal_plan_target(node_id), None).await
+}
+
+/// Emission rate update handler.
+///
+/// Changes how many ticks pass between telemetry frames of each vehicle
+/// priority tier, e.g. to shed ordinary cars under overload while keeping
+/// emergency vehicles fresh. Zero intervals are rejected.
+pub async fn set_emission_rates(
+    State(state): State<Arc<AppState>>,
+    Json(rates): Json<EmissionRates>,
+) -> ControlResponse {
+    if let Err(e) = rates.validate() {
+        warn!("Rejecting emission rates: {}", e);
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    let value = serde_json::to_value(rates).ok();
+    send_audited(&state, SimCommand::SetEmissionRates(rates), EMISSION_TARGET.to_string(), value).await
+}
+
+/// Road closure handler.
+///
+/// Closes a road (an OSM way, in both directions) in the running
+/// simulation: vehicles no longer turn into it and new fleet routes avoid
+/// it. Responds with 404 for ways that are not part of the map.
+pub async fn close_road(
+    State(state): State<Arc<AppState>>,
+    Path(way_id): Path<i64>,
+) -> ControlResponse {
+    if state.graph.edges_for_way(way_id).is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    send_audited(&state, SimCommand::CloseRoad { way_id }, closure_target(way_id), Some(Value::from("closed"))).await
+}
+
+/// Road reopening handler.
+///
+/// Reopens a road closed with [`close_road`].
+pub async fn reopen_road(
+    State(state): State<Arc<AppState>>,
+    Path(way_id): Path<i64>,
+) -> ControlResponse {
+    if state.graph.edges_for_way(way_id).is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    send_audited(&state, SimCommand::ReopenRoad { way_id }, closure_target(way_id), Some(Value::from("open"))).await
+}
+
+/// Sends a command that sets `target` to `value`, attaching the change
+/// (with the value last set through the API as previous value) for the
+/// audit log.
+async fn send_audited(state: &AppState, command: SimCommand, target: String, value: Option<Value>) -> ControlResponse {
+    let previous = last_value(&state.db, &target).await;
+    let ack = send_command(state, command).await?;
+    Ok((Extension(AuditChange { target, value, previous }), ack))
+}
+
+/// Produces a command to the control topic.
+///
+/// Responds with 503 if Kafka is unreachable or the API runs without it
+/// (`--dry-run`).
+pub(crate) async fn send_command(state: &AppState, command: SimCommand) -> Result<Json<ControlAck>, StatusCode> {
+    let Some(producer) = &state.producer else {
+        warn!("Rejecting control command in dry run: {:?}", command);
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    };
+    let payload = command.to_json();
+    let record = FutureRecord::<(), _>::to(CONTROL_TOPIC).payload(&payload);
+
+    match producer.send(record, Duration::from_secs(5)).await {
+        Ok(_) => {
+            info!("🎛️ Control command sent: {:?}", command);
+            Ok(Json(ControlAck { command }))
+        }
+        Err((e, _)) => {
+            error!("❌ Failed to send control command: {}", e);
+            Err(StatusCode::SERVICE_UNAVAILABLE)
+        }
+    }
+}
@@ -0,0 +1,372 @@
+//! Scheduled notification digests.
+//!
+//! Instead of one notification per event, operators receive one digest per
+//! period (`DIGEST_INTERVAL_SECS`, daily by default) summarizing the
+//! incidents and alerts reported, resolved and expired in it, the ones
+//! still open, and the traffic KPIs of the recorded telemetry. Periods are
+//! aligned to multiples of the interval since the Unix epoch, so daily
+//! digests cover midnight to midnight UTC.
+//!
+//! Digests are sent through the [`crate::webhooks::WebhookDispatcher`] as `digest` events
+//! and, if `SMTP_URL`, `DIGEST_EMAIL_FROM` and `DIGEST_EMAIL_TO` are set,
+//! emailed as plain text. `GET /admin/digest` previews the digest of any
+//! period without sending it; previews are cached per requested period
+//! (see [`crate::query_cache`]).
+
+use anyhow::Context;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+};
+use lettre::message::Mailbox;
+use lettre::transport::smtp::AsyncSmtpTransport;
+use lettre::{AsyncTransport, Message, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{error, info, warn};
+use common::units::KMH_PER_MPS;
+use common::Config;
+use crate::query_cache::{CacheScope, JsonBody};
+use crate::AppState;
+
+/// Most critical incidents listed by name in one digest.
+const MAX_LISTED_INCIDENTS: i64 = 10;
+
+/// Period previewed when digests are disabled and no `from` is given.
+const DEFAULT_PREVIEW_PERIOD: Duration = Duration::from_secs(24 * 3600);
+
+/// Summary of one period.
+#[derive(Serialize, Debug)]
+pub struct Digest {
+    /// Unix timestamp (seconds) of the start of the period
+    from: f64,
+    /// Unix timestamp (seconds) of the end of the period
+    to: f64,
+    /// Incidents and alerts
+    incidents: IncidentSummary,
+    /// Recorded telemetry
+    traffic: TrafficSummary,
+}
+
+/// Incident and alert counts of a period; deleted incidents are left out.
+#[derive(Serialize, Debug)]
+pub struct IncidentSummary {
+    /// Reported during the period
+    reported: i64,
+    /// Reported during the period, by kind ("incident", "alert")
+    by_kind: BTreeMap<String, i64>,
+    /// Reported during the period, by severity ("info", "warning", "critical")
+    by_severity: BTreeMap<String, i64>,
+    /// Resolved during the period
+    resolved: i64,
+    /// Expired during the period
+    expired: i64,
+    /// Open or acknowledged at the end of the period
+    still_open: i64,
+    /// Critical incidents reported during the period, newest first
+    critical: Vec<DigestIncident>,
+}
+
+/// An incident listed in a digest.
+#[derive(Serialize, Debug)]
+pub struct DigestIncident {
+    id: i64,
+    title: String,
+    status: String,
+}
+
+/// Telemetry KPIs of a period.
+#[derive(Serialize, Debug)]
+pub struct TrafficSummary {
+    /// Vehicles that reported at least one position
+    vehicles: i64,
+    /// Recorded positions
+    positions: i64,
+    /// Average recorded speed in km/h
+    avg_speed_kmh: Option<f64>,
+    /// Highest recorded speed in km/h
+    max_speed_kmh: Option<f64>,
+}
+
+/// Query parameters of the digest preview.
+#[derive(Deserialize)]
+pub struct DigestParams {
+    /// Start of the period (default: one digest interval, or a day, before `to`)
+    from: Option<i64>,
+    /// End of the period (default: now)
+    to: Option<i64>,
+}
+
+/// Sends digests by email over SMTP.
+pub struct Mailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+}
+
+impl Mailer {
+    /// Creates the mailer from `SMTP_URL`, `DIGEST_EMAIL_FROM` and
+    /// `DIGEST_EMAIL_TO`.
+    ///
+    /// # Returns
+    ///
+    /// `None` if `SMTP_URL` is not set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SMTP URL or an address is invalid, or the
+    /// sender or recipients are missing.
+    pub fn from_config(config: &Config) -> anyhow::Result<Option<Self>> {
+        let Some(url) = &config.smtp_url else { return Ok(None) };
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::from_url(url)
+            .context("Invalid SMTP_URL")?
+            .build();
+        let from = config
+            .digest_email_from
+            .as_deref()
+            .context("DIGEST_EMAIL_FROM is required with SMTP_URL")?
+            .parse()
+            .context("Invalid DIGEST_EMAIL_FROM")?;
+        let to = config
+            .digest_email_to()
+            .into_iter()
+            .map(|address| address.parse().with_context(|| format!("Invalid digest recipient '{}'", address)))
+            .collect::<anyhow::Result<Vec<Mailbox>>>()?;
+        if to.is_empty() {
+            anyhow::bail!("DIGEST_EMAIL_TO is required with SMTP_URL");
+        }
+        Ok(Some(Self { transport, from, to }))
+    }
+
+    /// Emails a plain-text message to all recipients.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message cannot be built or the SMTP server
+    /// rejects it.
+    pub async fn send(&self, subject: &str, body: String) -> anyhow::Result<()> {
+        let mut message = Message::builder().from(self.from.clone()).subject(subject);
+        for recipient in &self.to {
+            message = message.to(recipient.clone());
+        }
+        self.transport.send(message.body(body)?).await?;
+        Ok(())
+    }
+}
+
+/// Summarizes incidents and telemetry of a period.
+///
+/// # Arguments
+///
+/// * `db` - TimescaleDB pool
+/// * `from` - Unix timestamp of the start of the period (inclusive)
+/// * `to` - Unix timestamp of the end of the period (exclusive)
+///
+/// # Errors
+///
+/// Returns an error if a query fails.
+pub async fn build_digest(db: &PgPool, from: f64, to: f64) -> Result<Digest, sqlx::Error> {
+    let counts = sqlx::query!(
+        r#"
+        SELECT count(*) FILTER (WHERE created_at >= to_timestamp($1) AND created_at < to_timestamp($2)) AS "reported!",
+               count(*) FILTER (WHERE status = 'resolved'
+                                  AND resolved_at >= to_timestamp($1) AND resolved_at < to_timestamp($2)) AS "resolved!",
+               count(*) FILTER (WHERE status = 'expired'
+                                  AND expires_at >= to_timestamp($1) AND expires_at < to_timestamp($2)) AS "expired!",
+               count(*) FILTER (WHERE status IN ('open', 'acknowledged') AND created_at < to_timestamp($2)) AS "still_open!"
+        FROM incidents
+        WHERE deleted_at IS NULL
+        "#,
+        from,
+        to
+    )
+        .fetch_one(db)
+        .await?;
+
+    let groups = sqlx::query!(
+        r#"
+        SELECT kind, severity, count(*) AS "count!"
+        FROM incidents
+        WHERE deleted_at IS NULL AND created_at >= to_timestamp($1) AND created_at < to_timestamp($2)
+        GROUP BY kind, severity
+        "#,
+        from,
+        to
+    )
+        .fetch_all(db)
+        .await?;
+    let mut by_kind = BTreeMap::new();
+    let mut by_severity = BTreeMap::new();
+    for group in groups {
+        *by_kind.entry(group.kind).or_default() += group.count;
+        *by_severity.entry(group.severity).or_default() += group.count;
+    }
+
+    let critical = sqlx::query_as!(
+        DigestIncident,
+        r#"
+        SELECT id, title, status
+        FROM incidents
+        WHERE deleted_at IS NULL AND severity = 'critical'
+          AND created_at >= to_timestamp($1) AND created_at < to_timestamp($2)
+        ORDER BY created_at DESC, id DESC
+        LIMIT $3
+        "#,
+        from,
+        to,
+        MAX_LISTED_INCIDENTS
+    )
+        .fetch_all(db)
+        .await?;
+
+    let traffic = sqlx::query!(
+        r#"
+        SELECT count(DISTINCT vehicle_id) AS "vehicles!", count(*) AS "positions!",
+               avg(speed) AS avg_speed, max(speed) AS max_speed
+        FROM vehicle_positions
+        WHERE time >= to_timestamp($1) AND time < to_timestamp($2)
+        "#,
+        from,
+        to
+    )
+        .fetch_one(db)
+        .await?;
+
+    Ok(Digest {
+        from,
+        to,
+        incidents: IncidentSummary {
+            reported: counts.reported,
+            by_kind,
+            by_severity,
+            resolved: counts.resolved,
+            expired: counts.expired,
+            still_open: counts.still_open,
+            critical,
+        },
+        traffic: TrafficSummary {
+            vehicles: traffic.vehicles,
+            positions: traffic.positions,
+            avg_speed_kmh: traffic.avg_speed.map(|speed| speed * KMH_PER_MPS),
+            max_speed_kmh: traffic.max_speed.map(|speed| speed * KMH_PER_MPS),
+        },
+    })
+}
+
+impl Digest {
+    /// Returns the email subject, e.g. "Traffic digest 2024-05-01 00:00 – 2024-05-02 00:00 UTC".
+    pub fn subject(&self) -> String {
+        format!("Traffic digest {} – {} UTC", format_time(self.from), format_time(self.to))
+    }
+
+    /// Renders the digest as plain text for email.
+    pub fn to_text(&self) -> String {
+        let incidents = &self.incidents;
+        let traffic = &self.traffic;
+        let mut text = String::new();
+        let _ = writeln!(text, "{}\n", self.subject());
+
+        let _ = writeln!(text, "Incidents and alerts");
+        let _ = writeln!(text, "  reported:   {}", incidents.reported);
+        for (name, count) in incidents.by_kind.iter().chain(&incidents.by_severity) {
+            let _ = writeln!(text, "    {:<10} {}", format!("{}:", name), count);
+        }
+        let _ = writeln!(text, "  resolved:   {}", incidents.resolved);
+        let _ = writeln!(text, "  expired:    {}", incidents.expired);
+        let _ = writeln!(text, "  still open: {}", incidents.still_open);
+        if !incidents.critical.is_empty() {
+            let _ = writeln!(text, "\nCritical incidents");
+            for incident in &incidents.critical {
+                let _ = writeln!(text, "  #{} {} ({})", incident.id, incident.title, incident.status);
+            }
+        }
+
+        let _ = writeln!(text, "\nTraffic");
+        let _ = writeln!(text, "  vehicles:   {}", traffic.vehicles);
+        let _ = writeln!(text, "  positions:  {}", traffic.positions);
+        if let (Some(avg), Some(max)) = (traffic.avg_speed_kmh, traffic.max_speed_kmh) {
+            let _ = writeln!(text, "  speed:      {:.1} km/h average, {:.1} km/h max", avg, max);
+        }
+        text
+    }
+}
+
+/// Sends a digest at the end of every period, forever.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state with the database pool and webhooks
+/// * `interval` - Length of a period
+/// * `mailer` - Email delivery, if configured
+pub async fn run_digests(state: Arc<AppState>, interval: Duration, mailer: Option<Mailer>) {
+    let period = interval.as_secs().max(1);
+    loop {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let end = (now.as_secs() / period + 1) * period;
+        tokio::time::sleep(Duration::from_secs(end) - now).await;
+
+        let digest = match build_digest(&state.db, (end - period) as f64, end as f64).await {
+            Ok(digest) => digest,
+            Err(e) => {
+                error!("❌ Could not build the digest: {}", e);
+                continue;
+            }
+        };
+        info!(
+            "📰 Digest: {} incidents reported, {} still open, {} vehicles",
+            digest.incidents.reported, digest.incidents.still_open, digest.traffic.vehicles
+        );
+
+        if state.webhooks.is_enabled() {
+            state.webhooks.dispatch("digest", &digest).await;
+        }
+        if let Some(mailer) = &mailer {
+            if let Err(e) = mailer.send(&digest.subject(), digest.to_text()).await {
+                warn!("⚠️ Could not email the digest: {}", e);
+            }
+        }
+    }
+}
+
+/// Digest preview endpoint handler.
+///
+/// Returns the digest of the given period without sending it. Responds
+/// with 503 if TimescaleDB is unreachable.
+pub async fn get_digest(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DigestParams>,
+) -> Result<JsonBody, StatusCode> {
+    // Open-ended previews are keyed as such, so repeated refreshes share
+    // an entry while "now" moves on
+    let key = format!("from={:?}&to={:?}", params.from, params.to);
+    state
+        .query_cache
+        .get_or_compute(CacheScope::Digest, key, || preview_digest(&state, &params))
+        .await
+}
+
+/// Builds the digest previewed for the given parameters.
+async fn preview_digest(state: &AppState, params: &DigestParams) -> Result<Digest, StatusCode> {
+    let to = params
+        .to
+        .map(|to| to as f64)
+        .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64());
+    let period = state.digest_interval.unwrap_or(DEFAULT_PREVIEW_PERIOD);
+    let from = params.from.map_or(to - period.as_secs_f64(), |from| from as f64);
+
+    build_digest(&state.db, from, to).await.map_err(|e| {
+        error!("❌ Could not build the digest: {}", e);
+        StatusCode::SERVICE_UNAVAILABLE
+    })
+}
+
+/// Formats a Unix timestamp as "YYYY-MM-DD HH:MM" (UTC).
+fn format_time(timestamp: f64) -> String {
+    chrono::DateTime::from_timestamp(timestamp as i64, 0)
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,167 @@
+//! Dispatch endpoints bridging the simulator's fleet subsystem.
+//!
+//! New tasks are forwarded to traffic-sim through the control topic. Task
+//! lifecycle events coming back on the fleet events topic are kept in an
+//! in-memory task table (served by `GET /dispatch/tasks/{id}`) and streamed
+//! to WebSocket clients.
+
+use axum::{extract::{Path, State}, http::StatusCode, Extension, Json};
+use common::control::SimCommand;
+use common::fleet::{FleetKind, FleetTask, TaskEvent, TaskStatus, FLEET_EVENTS_TOPIC};
+use futures_util::StreamExt;
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{Consumer, StreamConsumer};
+use rdkafka::Message;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use tracing::{error, info, warn};
+use crate::audit::AuditChange;
+use crate::AppState;
+
+/// Sequence number for task IDs issued by this API instance.
+static NEXT_TASK_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Request body for creating a dispatch task.
+#[derive(Deserialize)]
+pub struct CreateTaskRequest {
+    /// Kind of vehicle that should serve the task
+    kind: FleetKind,
+    /// OSM node ID of the pickup location
+    pickup_node: i64,
+    /// OSM node ID of the drop-off location
+    dropoff_node: i64,
+}
+
+/// Latest known state of a task.
+#[derive(Serialize, Clone)]
+pub struct TaskRecord {
+    /// Task identifier
+    task_id: String,
+    /// Kind of vehicle serving the task
+    kind: FleetKind,
+    /// Latest lifecycle status
+    status: TaskStatus,
+    /// Vehicle serving the task, once assigned
+    vehicle_id: Option<String>,
+    /// Unix timestamp (seconds) of the latest transition
+    updated_at: i64,
+}
+
+/// In-memory table of task states keyed by task ID.
+pub type TaskTable = RwLock<HashMap<String, TaskRecord>>;
+
+/// Task creation endpoint handler.
+///
+/// Validates the locations against the loaded map and forwards the task to
+/// the simulator. The task starts out as `pending`; progress can be polled
+/// via `GET /dispatch/tasks/{id}` or followed over the WebSocket.
+pub async fn create_task(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<CreateTaskRequest>,
+) -> Result<(StatusCode, Extension<AuditChange>, Json<TaskRecord>), StatusCode> {
+    for node in [request.pickup_node, request.dropoff_node] {
+        if !state.graph.nodes.contains_key(&node) {
+            warn!("Rejecting dispatch task: unknown node {}", node);
+            return Err(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+    }
+
+    let task = FleetTask {
+        id: format!("dispatch_{}", NEXT_TASK_ID.fetch_add(1, Ordering::Relaxed)),
+        kind: request.kind,
+        pickup_node: request.pickup_node,
+        dropoff_node: request.dropoff_node,
+    };
+
+    let record = TaskRecord {
+        task_id: task.id.clone(),
+        kind: task.kind,
+        status: TaskStatus::Pending,
+        vehicle_id: None,
+        updated_at: chrono::Utc::now().timestamp(),
+    };
+
+    // Record the task before sending so early events from the sim aren't overwritten
+    state.tasks.write().unwrap().insert(record.task_id.clone(), record.clone());
+    if let Err(status) = crate::control::send_command(&state, SimCommand::CreateTask(task)).await {
+        state.tasks.write().unwrap().remove(&record.task_id);
+        return Err(status);
+    }
+
+    let change = AuditChange {
+        target: format!("task:{}", record.task_id),
+        value: serde_json::to_value(&record).ok(),
+        previous: None,
+    };
+    Ok((StatusCode::CREATED, Extension(change), Json(record)))
+}
+
+/// Task status endpoint handler.
+///
+/// Returns the latest known state of a task, or 404 if it is unknown.
+pub async fn get_task(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<TaskRecord>, StatusCode> {
+    state.tasks.read().unwrap()
+        .get(&id)
+        .cloned()
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Consumes fleet task events, updating the task table and WebSocket clients.
+///
+/// Each event is forwarded to WebSocket clients as a JSON object with
+/// `"type": "task_event"` so it can be told apart from vehicle updates.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state with the task table and broadcaster
+/// * `kafka` - Kafka connection settings
+pub async fn consume_task_events(state: Arc<AppState>, mut kafka: ClientConfig) {
+    let consumer: StreamConsumer = match kafka
+        .set("group.id", "traffic-api-dispatch")
+        .set("auto.offset.reset", "latest")
+        .create()
+    {
+        Ok(c) => c,
+        Err(e) => {
+            error!("❌ Failed to create fleet events consumer: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = consumer.subscribe(&[FLEET_EVENTS_TOPIC]) {
+        error!("❌ Failed to subscribe to '{}': {}", FLEET_EVENTS_TOPIC, e);
+        return;
+    }
+
+    info!("✅ Subscribed to '{}' for dispatch updates", FLEET_EVENTS_TOPIC);
+
+    let mut stream = consumer.stream();
+    while let Some(msg_result) = stream.next().await {
+        let Ok(msg) = msg_result else { continue };
+        let Some(payload) = msg.payload() else { continue };
+        let Ok(event) = serde_json::from_slice::<TaskEvent>(payload) else {
+            warn!("Ignoring malformed fleet event");
+            continue;
+        };
+
+        state.tasks.write().unwrap().insert(event.task_id.clone(), TaskRecord {
+            task_id: event.task_id.clone(),
+            kind: event.kind,
+            status: event.status,
+            vehicle_id: event.vehicle_id.clone(),
+            updated_at: event.timestamp,
+        });
+
+        let mut message = serde_json::to_value(&event).unwrap_or_default();
+        message["type"] = serde_json::Value::from("task_event");
+        let _ = state.tx.send(message.to_string());
+    }
+
+    error!("❌ Fleet events stream ended!");
+}
@@ -0,0 +1,129 @@
+//! Per-district congestion statistics.
+//!
+//! Ingest tags every stored position with its administrative district
+//! (`DISTRICTS_PATH`). `GET /districts/stats` aggregates the positions of a
+//! recent window per district and rates congestion by comparing recorded
+//! speeds with the speed limits of the matched roads. Responses are cached
+//! per window (see [`crate::query_cache`]).
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::error;
+use common::map::MIN_MATCH_CONFIDENCE;
+use crate::query_cache::{CacheScope, JsonBody};
+use crate::AppState;
+
+/// Window aggregated when none is given, in seconds.
+const DEFAULT_WINDOW_SECS: u32 = 300;
+
+/// Longest window that may be requested, in seconds.
+const MAX_WINDOW_SECS: u32 = 24 * 3600;
+
+/// Query parameters of the district statistics.
+#[derive(Deserialize)]
+pub struct DistrictParams {
+    /// Seconds before now to aggregate (default 300, at most one day)
+    window: Option<u32>,
+}
+
+/// Statistics of one district over the window.
+#[derive(Serialize, Debug)]
+pub struct DistrictStats {
+    /// District name
+    district: String,
+    /// Vehicles that reported from the district
+    vehicles: i64,
+    /// Positions recorded in the district
+    positions: i64,
+    /// Average recorded speed in m/s
+    avg_speed: f64,
+    /// 0 when traffic flows at the speed limit, 1 at a standstill; `None`
+    /// if no position could be matched to a road
+    congestion: Option<f64>,
+}
+
+/// District statistics endpoint handler.
+///
+/// Returns one entry per district with recorded positions, ordered by
+/// name. Responds with 503 if TimescaleDB is unreachable.
+pub async fn get_district_stats(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DistrictParams>,
+) -> Result<JsonBody, StatusCode> {
+    let window = params.window.unwrap_or(DEFAULT_WINDOW_SECS).clamp(1, MAX_WINDOW_SECS);
+    state
+        .query_cache
+        .get_or_compute(CacheScope::Districts, format!("window={}", window), || district_stats(&state, window))
+        .await
+}
+
+/// Aggregates the positions of the last `window` seconds per district.
+async fn district_stats(state: &AppState, window: u32) -> Result<Vec<DistrictStats>, StatusCode> {
+    let window = f64::from(window);
+
+    let totals = sqlx::query!(
+        r#"
+        SELECT district AS "district!", count(DISTINCT vehicle_id) AS "vehicles!", count(*) AS "positions!",
+               avg(speed) AS avg_speed
+        FROM vehicle_positions
+        WHERE district IS NOT NULL AND time >= now() - make_interval(secs => $1)
+        GROUP BY district
+        ORDER BY district
+        "#,
+        window
+    )
+        .fetch_all(&state.db)
+        .await
+        .map_err(|e| db_unavailable(&e))?;
+
+    let roads = sqlx::query!(
+        r#"
+        SELECT district AS "district!", road_id AS "road_id!", count(*) AS "positions!", avg(speed) AS avg_speed
+        FROM vehicle_positions
+        WHERE district IS NOT NULL AND road_id IS NOT NULL AND time >= now() - make_interval(secs => $1)
+            AND (match_confidence IS NULL OR match_confidence >= $2)
+        GROUP BY district, road_id
+        "#,
+        window,
+        MIN_MATCH_CONFIDENCE
+    )
+        .fetch_all(&state.db)
+        .await
+        .map_err(|e| db_unavailable(&e))?;
+
+    // Free-flow ratio per district, weighted by positions per road
+    let mut flow: BTreeMap<String, (f64, f64)> = BTreeMap::new();
+    for road in roads {
+        let Some(&edge) = state.graph.edges_for_way(road.road_id).first() else { continue };
+        let limit = state.graph.edges[edge].effective_speed_limit_mps();
+        let Some(speed) = road.avg_speed.filter(|_| limit > 0.0) else { continue };
+        let (weighted, weight) = flow.entry(road.district).or_default();
+        *weighted += (speed / limit).min(1.0) * road.positions as f64;
+        *weight += road.positions as f64;
+    }
+
+    Ok(totals
+        .into_iter()
+        .map(|row| DistrictStats {
+            congestion: flow
+                .get(&row.district)
+                .filter(|(_, weight)| *weight > 0.0)
+                .map(|(weighted, weight)| 1.0 - weighted / weight),
+            district: row.district,
+            vehicles: row.vehicles,
+            positions: row.positions,
+            avg_speed: row.avg_speed.unwrap_or(0.0),
+        })
+        .collect())
+}
+
+/// Logs a database error and maps it to 503.
+fn db_unavailable(e: &sqlx::Error) -> StatusCode {
+    error!("❌ District statistics query failed: {}", e);
+    StatusCode::SERVICE_UNAVAILABLE
+}
@@ -0,0 +1,337 @@
+//! Vehicle metadata registry.
+//!
+//! Telemetry only identifies vehicles by ID. Static metadata (license
+//! plate, operator, type, capacity) is registered through
+//! `/fleet/vehicles` and stored in the `vehicles` table. It is attached to
+//! live vehicle updates on the WebSocket (as `vehicle`) and to historical
+//! trace exports. Changes are audited with the metadata they replaced.
+//!
+//! Live updates are enriched from an in-memory copy of the registry, kept
+//! current by the endpoints of this instance and reloaded periodically to
+//! pick up changes made through other API instances.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{error, info, warn};
+use crate::audit::AuditChange;
+use crate::AppState;
+
+/// Interval between reloads of the in-memory registry.
+const REGISTRY_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Static metadata of a vehicle.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct VehicleFields {
+    /// License plate, e.g. "B-TX 1234"
+    license_plate: Option<String>,
+    /// Company or agency operating the vehicle
+    operator: Option<String>,
+    /// Vehicle type, e.g. "taxi", "van", "bus"
+    vehicle_type: Option<String>,
+    /// Passenger or load capacity
+    capacity: Option<i32>,
+}
+
+/// A registered vehicle.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Vehicle {
+    /// ID the vehicle reports telemetry under
+    vehicle_id: String,
+    /// Static metadata
+    #[serde(flatten)]
+    fields: VehicleFields,
+}
+
+/// In-memory copy of the registry keyed by vehicle ID.
+pub type VehicleRegistry = RwLock<HashMap<String, VehicleFields>>;
+
+/// Vehicle registration endpoint handler.
+///
+/// Responds with 201 and the stored vehicle, 409 if the ID is already
+/// registered, or 422 if the ID is empty or the capacity negative.
+pub async fn create_vehicle(
+    State(state): State<Arc<AppState>>,
+    Json(vehicle): Json<Vehicle>,
+) -> Result<(StatusCode, Extension<AuditChange>, Json<Vehicle>), StatusCode> {
+    if vehicle.vehicle_id.trim().is_empty() {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    validate(&vehicle.fields)?;
+
+    let fields = &vehicle.fields;
+    sqlx::query!(
+        r#"
+        INSERT INTO vehicles (vehicle_id, license_plate, operator, vehicle_type, capacity)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        vehicle.vehicle_id,
+        fields.license_plate,
+        fields.operator,
+        fields.vehicle_type,
+        fields.capacity
+    )
+        .execute(&state.db)
+        .await
+        .map_err(|e| match e.as_database_error() {
+            Some(db_error) if db_error.is_unique_violation() => StatusCode::CONFLICT,
+            _ => db_unavailable(&e),
+        })?;
+
+    info!("🚙 Registered vehicle {}", vehicle.vehicle_id);
+    state.vehicles.write().unwrap().insert(vehicle.vehicle_id.clone(), vehicle.fields.clone());
+    let change = AuditChange {
+        target: vehicle_target(&vehicle.vehicle_id),
+        value: serde_json::to_value(&vehicle.fields).ok(),
+        previous: None,
+    };
+    Ok((StatusCode::CREATED, Extension(change), Json(vehicle)))
+}
+
+/// Vehicle list endpoint handler.
+///
+/// Returns all registered vehicles ordered by ID.
+pub async fn list_vehicles(State(state): State<Arc<AppState>>) -> Result<Json<Vec<Vehicle>>, StatusCode> {
+    load_vehicles(&state.db).await.map(Json).map_err(|e| db_unavailable(&e))
+}
+
+/// Vehicle lookup endpoint handler.
+///
+/// Returns the vehicle, or 404 if it is not registered.
+pub async fn get_vehicle(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<Vehicle>, StatusCode> {
+    load_vehicle(&state.db, &id)
+        .await
+        .map_err(|e| db_unavailable(&e))?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Vehicle update endpoint handler.
+///
+/// Replaces the metadata of a registered vehicle. Responds with 404 if it
+/// is not registered, or 422 if the capacity is negative.
+pub async fn update_vehicle(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(fields): Json<VehicleFields>,
+) -> Result<(Extension<AuditChange>, Json<Vehicle>), StatusCode> {
+    validate(&fields)?;
+    let previous = load_vehicle(&state.db, &id).await.map_err(|e| db_unavailable(&e))?;
+
+    let updated = sqlx::query!(
+        r#"
+        UPDATE vehicles
+        SET license_plate = $2, operator = $3, vehicle_type = $4, capacity = $5, updated_at = now()
+        WHERE vehicle_id = $1
+        "#,
+        id,
+        fields.license_plate,
+        fields.operator,
+        fields.vehicle_type,
+        fields.capacity
+    )
+        .execute(&state.db)
+        .await
+        .map_err(|e| db_unavailable(&e))?;
+    if updated.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    state.vehicles.write().unwrap().insert(id.clone(), fields.clone());
+    let change = AuditChange {
+        target: vehicle_target(&id),
+        value: serde_json::to_value(&fields).ok(),
+        previous: previous.and_then(|vehicle| serde_json::to_value(vehicle.fields).ok()),
+    };
+    Ok((Extension(change), Json(Vehicle { vehicle_id: id, fields })))
+}
+
+/// Vehicle removal endpoint handler.
+///
+/// Responds with 204, or 404 if the vehicle is not registered. Recorded
+/// positions of the vehicle are kept.
+pub async fn delete_vehicle(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Response {
+    let deleted = sqlx::query!(
+        r#"
+        DELETE FROM vehicles
+        WHERE vehicle_id = $1
+        RETURNING license_plate, operator, vehicle_type, capacity
+        "#,
+        id
+    )
+        .fetch_optional(&state.db)
+        .await;
+
+    match deleted {
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Ok(Some(row)) => {
+            info!("🚙 Unregistered vehicle {}", id);
+            state.vehicles.write().unwrap().remove(&id);
+            let previous = VehicleFields {
+                license_plate: row.license_plate,
+                operator: row.operator,
+                vehicle_type: row.vehicle_type,
+                capacity: row.capacity,
+            };
+            let change = AuditChange {
+                target: vehicle_target(&id),
+                value: None,
+                previous: serde_json::to_value(previous).ok(),
+            };
+            (StatusCode::NO_CONTENT, Extension(change)).into_response()
+        }
+        Err(e) => db_unavailable(&e).into_response(),
+    }
+}
+
+/// Loads all registered vehicles ordered by ID.
+pub async fn load_vehicles(db: &PgPool) -> Result<Vec<Vehicle>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT vehicle_id, license_plate, operator, vehicle_type, capacity
+        FROM vehicles
+        ORDER BY vehicle_id
+        "#
+    )
+        .fetch_all(db)
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| Vehicle {
+            vehicle_id: row.vehicle_id,
+            fields: VehicleFields {
+                license_plate: row.license_plate,
+                operator: row.operator,
+                vehicle_type: row.vehicle_type,
+                capacity: row.capacity,
+            },
+        })
+        .collect())
+}
+
+/// Loads a single registered vehicle, if any.
+pub async fn load_vehicle(db: &PgPool, vehicle_id: &str) -> Result<Option<Vehicle>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT vehicle_id, license_plate, operator, vehicle_type, capacity
+        FROM vehicles
+        WHERE vehicle_id = $1
+        "#,
+        vehicle_id
+    )
+        .fetch_optional(db)
+        .await?;
+
+    Ok(row.map(|row| Vehicle {
+        vehicle_id: row.vehicle_id,
+        fields: VehicleFields {
+            license_plate: row.license_plate,
+            operator: row.operator,
+            vehicle_type: row.vehicle_type,
+            capacity: row.capacity,
+        },
+    }))
+}
+
+/// Keeps the in-memory registry in sync with the `vehicles` table.
+///
+/// Reloads the whole registry at startup and then periodically; failed
+/// reloads are logged and the previous copy is kept.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state with the registry and database pool
+pub async fn refresh_registry(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(REGISTRY_REFRESH_INTERVAL);
+    loop {
+        interval.tick().await;
+        match load_vehicles(&state.db).await {
+            Ok(vehicles) => {
+                let registry = vehicles.into_iter().map(|v| (v.vehicle_id, v.fields)).collect();
+                *state.vehicles.write().unwrap() = registry;
+            }
+            Err(e) => warn!("⚠️ Could not reload the vehicle registry: {}", e),
+        }
+    }
+}
+
+/// Adds the registered metadata to a live vehicle update.
+///
+/// The update is returned unchanged if the vehicle is not registered or the
+/// payload is not a JSON object with an `id`.
+///
+/// # Arguments
+///
+/// * `registry` - In-memory registry
+/// * `payload` - Vehicle update as published by traffic-ingest
+///
+/// # Returns
+///
+/// The update with a `vehicle` object holding the metadata.
+pub fn attach_metadata(registry: &VehicleRegistry, payload: String) -> String {
+    let registry = registry.read().unwrap();
+    if registry.is_empty() {
+        return payload;
+    }
+
+    let Ok(mut update) = serde_json::from_str::<Value>(&payload) else { return payload };
+    let Some(fields) = update.get("id").and_then(Value::as_str).and_then(|id| registry.get(id)) else {
+        return payload;
+    };
+    update["vehicle"] = serde_json::to_value(fields).unwrap_or_default();
+    update.to_string()
+}
+
+impl Vehicle {
+    /// Returns the vehicle's metadata.
+    pub fn fields(&self) -> &VehicleFields {
+        &self.fields
+    }
+}
+
+impl VehicleFields {
+    /// Returns a one-line human-readable summary, e.g. for GPX descriptions.
+    pub fn summary(&self) -> String {
+        [&self.license_plate, &self.vehicle_type, &self.operator]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Returns the audit target of a vehicle's metadata.
+fn vehicle_target(vehicle_id: &str) -> String {
+    format!("vehicle:{}", vehicle_id)
+}
+
+/// Rejects metadata with a negative capacity.
+fn validate(fields: &VehicleFields) -> Result<(), StatusCode> {
+    if fields.capacity.is_some_and(|capacity| capacity < 0) {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    Ok(())
+}
+
+/// Logs a database error and maps it to 503.
+fn db_unavailable(e: &sqlx::Error) -> StatusCode {
+    error!("❌ Vehicle registry query failed: {}", e);
+    StatusCode::SERVICE_UNAVAILABLE
+}
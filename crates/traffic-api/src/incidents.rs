@@ -0,0 +1,488 @@
+//! Incidents and alerts with a persistent lifecycle.
+//!
+//! Incidents (e.g. an accident blocking a road) and alerts (e.g. a
+//! congestion warning) are stored in the `incidents` table and move
+//! through
+//!
+//! ```text
+//! open -> acknowledged -> resolved
+//!   \__________\__________> expired   (once `expires_at` has passed)
+//! ```
+//!
+//! Alerts expire after [`DEFAULT_ALERT_TTL_SECS`] unless created with
+//! another `ttl_secs`; incidents only expire if given one. Deleting an
+//! incident only hides it (`deleted_at`), so the history stays complete.
+//!
+//! Every change is published to the Redis channel `incidents:update`, which
+//! all API instances forward to their WebSocket clients as an `incident`
+//! message carrying the full incident.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+use crate::audit::AuditChange;
+use crate::auth::{Principal, ANONYMOUS};
+use crate::query_cache::CacheScope;
+use crate::AppState;
+
+/// Redis channel on which incident changes are published.
+pub const INCIDENT_UPDATES_CHANNEL: &str = "incidents:update";
+
+/// Lifetime of alerts created without `ttl_secs`.
+pub const DEFAULT_ALERT_TTL_SECS: i64 = 3600;
+
+/// Interval between checks for expired incidents.
+const EXPIRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Kind of a reported event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IncidentKind {
+    /// Something happened on the network (accident, breakdown, road works)
+    Incident,
+    /// A warning raised by operators or monitoring (e.g. congestion)
+    Alert,
+}
+
+/// How urgent an incident is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+/// Lifecycle state of an incident.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IncidentStatus {
+    /// Reported, nobody has taken it on yet
+    Open,
+    /// An operator is handling it
+    Acknowledged,
+    /// Handled
+    Resolved,
+    /// Its TTL ran out before it was resolved
+    Expired,
+}
+
+/// Request body for reporting an incident.
+#[derive(Deserialize)]
+pub struct CreateIncidentRequest {
+    kind: IncidentKind,
+    severity: Severity,
+    title: String,
+    description: Option<String>,
+    /// OSM way ID of the affected road, if any
+    road_id: Option<i64>,
+    longitude: Option<f64>,
+    latitude: Option<f64>,
+    /// Seconds until the incident expires (default: alerts only, one hour)
+    ttl_secs: Option<i64>,
+}
+
+/// Query parameters of the incident list.
+#[derive(Deserialize)]
+pub struct IncidentParams {
+    /// Only incidents in this state
+    status: Option<IncidentStatus>,
+    /// Include deleted incidents (default: false)
+    #[serde(default)]
+    include_deleted: bool,
+}
+
+/// An incident with its full lifecycle; timestamps are Unix seconds.
+#[derive(Serialize, Clone, Debug)]
+pub struct Incident {
+    id: i64,
+    kind: IncidentKind,
+    severity: Severity,
+    title: String,
+    description: Option<String>,
+    road_id: Option<i64>,
+    longitude: Option<f64>,
+    latitude: Option<f64>,
+    status: IncidentStatus,
+    created_at: f64,
+    created_by: String,
+    acknowledged_at: Option<f64>,
+    acknowledged_by: Option<String>,
+    resolved_at: Option<f64>,
+    resolved_by: Option<String>,
+    expires_at: Option<f64>,
+    deleted_at: Option<f64>,
+}
+
+impl IncidentKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Incident => "incident",
+            Self::Alert => "alert",
+        }
+    }
+}
+
+impl Severity {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+impl IncidentStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Acknowledged => "acknowledged",
+            Self::Resolved => "resolved",
+            Self::Expired => "expired",
+        }
+    }
+}
+
+/// Parses a database column holding a serialized enum name.
+fn parse<T: serde::de::DeserializeOwned>(value: &str) -> Result<T, sqlx::Error> {
+    serde_json::from_value(serde_json::Value::from(value)).map_err(|e| sqlx::Error::Decode(e.into()))
+}
+
+/// Response of incident changes, with the change for the audit log.
+type IncidentResponse = Result<(Extension<AuditChange>, Json<Incident>), StatusCode>;
+
+/// Incident reporting endpoint handler.
+///
+/// Responds with 201 and the new incident, or 422 if the title is empty,
+/// the TTL not positive or only one coordinate is given.
+pub async fn create_incident(
+    State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
+    Json(request): Json<CreateIncidentRequest>,
+) -> Result<(StatusCode, Extension<AuditChange>, Json<Incident>), StatusCode> {
+    let ttl_secs = match request.kind {
+        IncidentKind::Alert => Some(request.ttl_secs.unwrap_or(DEFAULT_ALERT_TTL_SECS)),
+        IncidentKind::Incident => request.ttl_secs,
+    };
+    if request.title.trim().is_empty()
+        || ttl_secs.is_some_and(|ttl| ttl <= 0)
+        || request.longitude.is_some() != request.latitude.is_some()
+    {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO incidents (kind, severity, title, description, road_id, longitude, latitude, created_by, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, now() + make_interval(secs => $9::float8))
+        RETURNING id
+        "#,
+        request.kind.as_str(),
+        request.severity.as_str(),
+        request.title,
+        request.description,
+        request.road_id,
+        request.longitude,
+        request.latitude,
+        actor(&principal),
+        ttl_secs.map(|ttl| ttl as f64)
+    )
+        .fetch_one(&state.db)
+        .await
+        .map_err(|e| db_unavailable(&e))?;
+
+    info!("🚨 {} {} reported: {}", request.kind.as_str(), id, request.title);
+    let (change, incident) = changed(&state, id, None).await?;
+    Ok((StatusCode::CREATED, change, incident))
+}
+
+/// Incident list endpoint handler.
+///
+/// Returns incidents newest first, without deleted ones unless
+/// `include_deleted=true`.
+pub async fn list_incidents(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<IncidentParams>,
+) -> Result<Json<Vec<Incident>>, StatusCode> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, kind, severity, title, description, road_id, longitude, latitude, status, created_by,
+               acknowledged_by, resolved_by,
+               extract(epoch FROM created_at)::float8 AS "created_at!",
+               extract(epoch FROM acknowledged_at)::float8 AS acknowledged_at,
+               extract(epoch FROM resolved_at)::float8 AS resolved_at,
+               extract(epoch FROM expires_at)::float8 AS expires_at,
+               extract(epoch FROM deleted_at)::float8 AS deleted_at
+        FROM incidents
+        WHERE ($1::text IS NULL OR status = $1)
+          AND ($2 OR deleted_at IS NULL)
+        ORDER BY created_at DESC, id DESC
+        "#,
+        params.status.map(IncidentStatus::as_str),
+        params.include_deleted
+    )
+        .fetch_all(&state.db)
+        .await
+        .map_err(|e| db_unavailable(&e))?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(Incident {
+                id: row.id,
+                kind: parse(&row.kind)?,
+                severity: parse(&row.severity)?,
+                title: row.title,
+                description: row.description,
+                road_id: row.road_id,
+                longitude: row.longitude,
+                latitude: row.latitude,
+                status: parse(&row.status)?,
+                created_at: row.created_at,
+                created_by: row.created_by,
+                acknowledged_at: row.acknowledged_at,
+                acknowledged_by: row.acknowledged_by,
+                resolved_at: row.resolved_at,
+                resolved_by: row.resolved_by,
+                expires_at: row.expires_at,
+                deleted_at: row.deleted_at,
+            })
+        })
+        .collect::<Result<Vec<_>, sqlx::Error>>()
+        .map(Json)
+        .map_err(|e| db_unavailable(&e))
+}
+
+/// Incident lookup endpoint handler.
+///
+/// Returns the incident (deleted ones included), or 404.
+pub async fn get_incident(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<Json<Incident>, StatusCode> {
+    load_incident(&state.db, id)
+        .await
+        .map_err(|e| db_unavailable(&e))?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Acknowledge endpoint handler.
+///
+/// Moves an open incident to `acknowledged`. Responds with 404 if the
+/// incident does not exist or was deleted, and 409 if it is not open.
+pub async fn acknowledge_incident(
+    State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<i64>,
+) -> IncidentResponse {
+    let previous = load_active(&state, id).await?;
+    let updated = sqlx::query!(
+        r#"
+        UPDATE incidents
+        SET status = 'acknowledged', acknowledged_at = now(), acknowledged_by = $2
+        WHERE id = $1 AND status = 'open' AND deleted_at IS NULL
+        "#,
+        id,
+        actor(&principal)
+    )
+        .execute(&state.db)
+        .await
+        .map_err(|e| db_unavailable(&e))?;
+    if updated.rows_affected() == 0 {
+        return Err(StatusCode::CONFLICT);
+    }
+    changed(&state, id, Some(previous)).await
+}
+
+/// Resolve endpoint handler.
+///
+/// Moves an open or acknowledged incident to `resolved`. Responds with 404
+/// if the incident does not exist or was deleted, and 409 if it is already
+/// resolved or expired.
+pub async fn resolve_incident(
+    State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<i64>,
+) -> IncidentResponse {
+    let previous = load_active(&state, id).await?;
+    let updated = sqlx::query!(
+        r#"
+        UPDATE incidents
+        SET status = 'resolved', resolved_at = now(), resolved_by = $2
+        WHERE id = $1 AND status IN ('open', 'acknowledged') AND deleted_at IS NULL
+        "#,
+        id,
+        actor(&principal)
+    )
+        .execute(&state.db)
+        .await
+        .map_err(|e| db_unavailable(&e))?;
+    if updated.rows_affected() == 0 {
+        return Err(StatusCode::CONFLICT);
+    }
+    changed(&state, id, Some(previous)).await
+}
+
+/// Delete endpoint handler.
+///
+/// Hides the incident from listings and live views; the row is kept.
+/// Responds with 404 if the incident does not exist or was deleted.
+pub async fn delete_incident(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> IncidentResponse {
+    let previous = load_active(&state, id).await?;
+    sqlx::query!("UPDATE incidents SET deleted_at = now() WHERE id = $1 AND deleted_at IS NULL", id)
+        .execute(&state.db)
+        .await
+        .map_err(|e| db_unavailable(&e))?;
+    changed(&state, id, Some(previous)).await
+}
+
+/// Expires incidents whose TTL has run out, forever.
+///
+/// Runs on every API instance; each expired incident is claimed by exactly
+/// one of them, which publishes the change.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state with the database pool
+pub async fn expire_incidents(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(EXPIRY_INTERVAL);
+    loop {
+        interval.tick().await;
+        let expired = sqlx::query_scalar!(
+            r#"
+            UPDATE incidents
+            SET status = 'expired'
+            WHERE status IN ('open', 'acknowledged') AND expires_at <= now() AND deleted_at IS NULL
+            RETURNING id
+            "#
+        )
+            .fetch_all(&state.db)
+            .await;
+
+        match expired {
+            Ok(ids) => {
+                for id in ids {
+                    info!("⌛ Incident {} expired", id);
+                    match load_incident(&state.db, id).await {
+                        Ok(Some(incident)) => publish(&state, &incident).await,
+                        Ok(None) => {}
+                        Err(e) => warn!("⚠️ Could not load expired incident {}: {}", id, e),
+                    }
+                }
+            }
+            Err(e) => warn!("⚠️ Could not expire incidents: {}", e),
+        }
+    }
+}
+
+/// Loads an incident, deleted ones included.
+async fn load_incident(db: &PgPool, id: i64) -> Result<Option<Incident>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT id, kind, severity, title, description, road_id, longitude, latitude, status, created_by,
+               acknowledged_by, resolved_by,
+               extract(epoch FROM created_at)::float8 AS "created_at!",
+               extract(epoch FROM acknowledged_at)::float8 AS acknowledged_at,
+               extract(epoch FROM resolved_at)::float8 AS resolved_at,
+               extract(epoch FROM expires_at)::float8 AS expires_at,
+               extract(epoch FROM deleted_at)::float8 AS deleted_at
+        FROM incidents
+        WHERE id = $1
+        "#,
+        id
+    )
+        .fetch_optional(db)
+        .await?;
+
+    let Some(row) = row else { return Ok(None) };
+    Ok(Some(Incident {
+        id: row.id,
+        kind: parse(&row.kind)?,
+        severity: parse(&row.severity)?,
+        title: row.title,
+        description: row.description,
+        road_id: row.road_id,
+        longitude: row.longitude,
+        latitude: row.latitude,
+        status: parse(&row.status)?,
+        created_at: row.created_at,
+        created_by: row.created_by,
+        acknowledged_at: row.acknowledged_at,
+        acknowledged_by: row.acknowledged_by,
+        resolved_at: row.resolved_at,
+        resolved_by: row.resolved_by,
+        expires_at: row.expires_at,
+        deleted_at: row.deleted_at,
+    }))
+}
+
+/// Loads an incident that has not been deleted, or responds with 404.
+async fn load_active(state: &AppState, id: i64) -> Result<Incident, StatusCode> {
+    load_incident(&state.db, id)
+        .await
+        .map_err(|e| db_unavailable(&e))?
+        .filter(|incident| incident.deleted_at.is_none())
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Reloads a changed incident, publishes it and describes the change for
+/// the audit log.
+async fn changed(state: &AppState, id: i64, previous: Option<Incident>) -> IncidentResponse {
+    let incident = load_incident(&state.db, id)
+        .await
+        .map_err(|e| db_unavailable(&e))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    publish(state, &incident).await;
+
+    let change = AuditChange {
+        target: format!("incident:{}", id),
+        value: serde_json::to_value(&incident).ok(),
+        previous: previous.and_then(|previous| serde_json::to_value(previous).ok()),
+    };
+    Ok((Extension(change), Json(incident)))
+}
+
+/// Publishes an incident to all API instances and drops the cached
+/// digests that count it.
+///
+/// Falls back to this instance's WebSocket clients if Redis is
+/// unreachable.
+async fn publish(state: &AppState, incident: &Incident) {
+    state.query_cache.invalidate(CacheScope::Digest);
+
+    let mut message = serde_json::to_value(incident).unwrap_or_default();
+    message["type"] = serde_json::Value::from("incident");
+    let payload = message.to_string();
+
+    let published: redis::RedisResult<()> = async {
+        let mut con = state.redis.get_multiplexed_async_connection().await?;
+        redis::cmd("PUBLISH").arg(INCIDENT_UPDATES_CHANNEL).arg(&payload).query_async(&mut con).await
+    }
+    .await;
+    if let Err(e) = published {
+        warn!("⚠️ Could not publish incident {} to Redis, notifying local clients only: {}", incident.id, e);
+        let _ = state.tx.send(payload);
+    }
+}
+
+/// Returns the name recorded for the caller.
+fn actor(principal: &Option<Extension<Principal>>) -> &str {
+    principal.as_ref().map_or(ANONYMOUS, |Extension(principal)| principal.name.as_str())
+}
+
+/// Logs a database error and maps it to 503.
+fn db_unavailable(e: &sqlx::Error) -> StatusCode {
+    error!("❌ Incident query failed: {}", e);
+    StatusCode::SERVICE_UNAVAILABLE
+}
@@ -0,0 +1,69 @@
+//! Status endpoint while the map loads.
+//!
+//! Loading a large extract takes tens of seconds. Rather than refusing
+//! connections until then, the API listens right away: `/health` reports
+//! which file is being loaded and how far along it is, and every other
+//! route answers `503 Service Unavailable`. Once the map is ready the
+//! socket is handed over to the full router.
+
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use std::net::TcpListener;
+use tokio::sync::{oneshot, watch};
+use tokio::task::JoinHandle;
+use tracing::error;
+use common::map::LoadProgress;
+use crate::HealthStatus;
+
+/// Latest progress report of the map loader, if it has reported yet.
+pub type ProgressReceiver = watch::Receiver<Option<LoadProgress>>;
+
+/// The status server running while the map loads.
+pub struct LoadingServer {
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl LoadingServer {
+    /// Starts answering on a copy of `listener` with the loading status.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket cannot be shared with the runtime.
+    pub fn start(listener: &TcpListener, progress: ProgressReceiver) -> std::io::Result<Self> {
+        let listener = tokio::net::TcpListener::from_std(listener.try_clone()?)?;
+        let app = Router::new()
+            .route("/health", get(loading_health))
+            .fallback(|| async { StatusCode::SERVICE_UNAVAILABLE })
+            .with_state(progress);
+
+        let (shutdown, stop) = oneshot::channel();
+        let task = tokio::spawn(async move {
+            let stopped = async {
+                let _ = stop.await;
+            };
+            if let Err(e) = axum::serve(listener, app).with_graceful_shutdown(stopped).await {
+                error!("❌ Loading status server failed: {}", e);
+            }
+        });
+        Ok(Self { shutdown, task })
+    }
+
+    /// Stops answering once the requests in flight are done, freeing the
+    /// socket for the full router.
+    pub async fn stop(self) {
+        let _ = self.shutdown.send(());
+        let _ = self.task.await;
+    }
+}
+
+/// Health check endpoint handler while the map loads.
+async fn loading_health(State(progress): State<ProgressReceiver>) -> Json<HealthStatus> {
+    Json(HealthStatus {
+        status: "LOADING".to_string(),
+        map_loaded: false,
+        total_roads: 0,
+        visible_roads: 0,
+        map: None,
+        loading: progress.borrow().clone(),
+    })
+}
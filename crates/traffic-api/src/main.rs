@@ -1,24 +1,86 @@
 //! Traffic API service - WebSocket and REST API server.
 //!
 //! This service provides:
-//! - REST endpoints for health checks and map data
+//! - REST endpoints for health checks, map data (geometry simplified to a
+//!   tolerance), map statistics, map provenance and attribution, and
+//!   intersection markers
 //! - WebSocket connections for real-time vehicle updates
 //! - Redis pub/sub integration for broadcasting vehicle telemetry
+//! - Simulation control endpoints (pause/resume, signal plans, road closures) via the control topic
+//! - Dispatch endpoints for fleet tasks, with status updates over the WebSocket
+//! - Historical per-vehicle trace export (GPX/GeoJSON) from TimescaleDB
+//! - A registry of vehicle metadata (plate, operator, type, capacity),
+//!   joined into live updates and trace exports
+//! - Redis keyspace introspection for operators
+//! - Live per-zone vehicle counts and average speeds, over REST and the WebSocket
+//! - The road network as Mapbox Vector Tiles
+//! - Batch lookup of road details with field selection; see [`roads`]
+//! - Per-district congestion over a recent window; see [`districts`]
+//! - Optional bearer-token authentication with per-route-group roles; see [`auth`]
+//! - An audit log of admin, control, dispatch and registry changes; see [`audit`]
+//! - Scheduled digests of incidents and traffic KPIs via webhooks and email; see [`digest`]
+//! - Incidents and alerts with an open/acknowledged/resolved lifecycle and
+//!   TTL expiry, persisted in TimescaleDB and pushed over the WebSocket; see [`incidents`]
+//! - Short-lived caching of district statistics and digest previews; see [`query_cache`]
+//! - Recording of live WebSocket traffic and its replay in a demo mode
+//!   without Kafka or Redis; see [`sessions`]
+//!
+//! With `--dry-run` the API serves the map (`/map`, `/tiles`) without
+//! connecting to Kafka, Redis or TimescaleDB; `--replay FILE` does the same
+//! and plays a recorded session through `/ws`. `check-config` validates the
+//! configuration and the map without serving anything, and
+//! `create-api-key` issues API keys; see [`cli`]. While the map loads,
+//! `/health` reports the loader's progress; see [`loading`].
+
+mod admin;
+mod audit;
+mod auth;
+mod cli;
+mod control;
+mod digest;
+mod districts;
+mod dispatch;
+mod fleet;
+mod incidents;
+mod loading;
+mod query_cache;
+mod roads;
+mod sessions;
+mod signals;
+mod state;
+mod tiles;
+mod trace;
+mod webhooks;
+mod zones;
 
 use axum::{
-    extract::{State, WebSocketUpgrade, ws::{Message, WebSocket}},
+    extract::{Query, State, WebSocketUpgrade, ws::{Message, WebSocket}},
+    http::StatusCode,
+    middleware::from_fn_with_state,
     response::IntoResponse,
-    routing::get,
+    routing::{delete, get, post, put},
     Json, Router,
 };
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::broadcast;
+use std::time::Duration;
+use tokio::sync::{broadcast, watch};
 use tracing::{info, error, warn};
-use common::{telemetry, Config};
-use common::map::RoadGraph;
+use common::Config;
+use common::map::{simplify_polyline, BoundingBox, GraphStats, Intersection, LoadProgress, MapMeta, RoadGraph, TurnLanes};
+use common::startup::{wait_for_kafka, wait_for_postgres, wait_for_redis};
 use tower_http::cors::CorsLayer;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use futures_util::StreamExt;
+use rdkafka::consumer::BaseConsumer;
+use rdkafka::producer::FutureProducer;
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use clap::Parser;
+use crate::auth::{require_role, Authenticator, Role};
+use crate::cli::{Cli, Command, CreateApiKeyArgs, DiffMapArgs, ServeArgs};
+use crate::query_cache::CacheScope;
 
 /// Simplified road representation for frontend consumption.
 #[derive(Serialize, Clone)]
@@ -27,39 +89,259 @@ struct Road {
     id: u64,
     /// Sequence of [longitude, latitude] coordinates defining the road geometry
     geometry: Vec<[f64; 2]>,
+    /// Street name, if tagged
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    /// Route number (e.g. "A100"), if tagged
+    #[serde(rename = "ref", skip_serializing_if = "Option::is_none")]
+    ref_: Option<String>,
+    /// Turns allowed per lane (left to right) along the geometry, if tagged
+    #[serde(skip_serializing_if = "Option::is_none")]
+    turn_lanes: Option<TurnLanes>,
+    /// Turns allowed per lane against the geometry, on two-way roads
+    #[serde(skip_serializing_if = "Option::is_none")]
+    turn_lanes_backward: Option<TurnLanes>,
+    /// Number of lanes along the geometry, if tagged
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lanes: Option<u8>,
+    /// Number of lanes against the geometry, on two-way roads
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lanes_backward: Option<u8>,
+    /// Whether the road is a bridge
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    bridge: bool,
+    /// Whether the road runs through a tunnel
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    tunnel: bool,
+    /// Vertical level (0 = ground); roads drawn across each other only
+    /// connect if they share a node on the same level
+    #[serde(skip_serializing_if = "is_ground_level")]
+    layer: i8,
+}
+
+/// Largest geometry simplification tolerance in meters a `/map` request
+/// may ask for.
+const MAX_MAP_TOLERANCE_M: f64 = 1000.0;
+
+/// Query parameters of the map endpoint.
+#[derive(Deserialize)]
+struct MapParams {
+    /// Simplification tolerance in meters (default:
+    /// `MAP_SIMPLIFY_TOLERANCE_M`, 0 for the full geometry)
+    tolerance: Option<f64>,
+}
+
+/// Returns `true` for roads at ground level, whose layer is not sent.
+fn is_ground_level(layer: &i8) -> bool {
+    *layer == 0
 }
 
 /// Shared application state across all handlers.
 struct AppState {
     /// Broadcast channel for sending vehicle updates to WebSocket clients
     tx: broadcast::Sender<String>,
-    /// Pre-filtered road segments for the frontend
+    /// Pre-filtered road segments for the frontend, simplified with
+    /// `map_tolerance`
     map_points: Vec<Road>,
+    /// Default simplification tolerance of `/map` in meters
+    map_tolerance: f64,
     /// Total number of roads loaded from the map
     total_roads: usize,
+    /// Kafka producer for simulation control commands (`None` in dry runs)
+    producer: Option<FutureProducer>,
+    /// Full road network graph
+    graph: RoadGraph,
+    /// Summary statistics of the graph, computed once at startup
+    stats: GraphStats,
+    /// Intersections and their right-of-way rules, classified at startup
+    intersections: Vec<Intersection>,
+    /// Latest known state of fleet tasks
+    tasks: dispatch::TaskTable,
+    /// TimescaleDB pool for historical queries
+    db: PgPool,
+    /// In-memory copy of the vehicle metadata registry
+    vehicles: fleet::VehicleRegistry,
+    /// Redis client for pub/sub, fleet snapshots and admin introspection
+    redis: redis::Client,
+    /// Outgoing webhook notifications
+    webhooks: webhooks::WebhookDispatcher,
+    /// Period of each notification digest (`None` if digests are disabled)
+    digest_interval: Option<Duration>,
+    /// Verifies credentials of requests
+    auth: Authenticator,
+    /// Recent analytics responses
+    query_cache: query_cache::QueryCache,
+    /// Where recorded WebSocket sessions are stored
+    session_dir: PathBuf,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    telemetry::init_tracing("traffic-api");
-
-    // Load configuration from environment
-    let config = Config::from_env().unwrap_or_else(|e| {
-        warn!("Failed to load config: {}. Using defaults.", e);
-        Config {
-            kafka_brokers: "localhost:19092".to_string(),
-            postgres_url: "".to_string(),
-            redis_url: "redis://localhost:6379".to_string(),
-            log_level: "info".to_string(),
+    let cli = Cli::parse();
+    let common = cli.common.clone();
+
+    match cli.command() {
+        Command::Serve(args) => {
+            let config = common.init("traffic-api").unwrap_or_else(|e| {
+                warn!("Failed to load config: {}. Using defaults.", e);
+                Config::default()
+            });
+            serve(config, args).await
         }
-    });
+        Command::CheckConfig => {
+            let config = common.init("traffic-api")?;
+            check_config(&config)
+        }
+        Command::DiffMap(args) => {
+            let config = common.init("traffic-api")?;
+            diff_map(&config, &args)
+        }
+        Command::CreateApiKey(args) => {
+            let config = common.init("traffic-api")?;
+            create_api_key(&config, &args).await
+        }
+    }
+}
+
+/// Creates an API key and prints it; only its hash is stored.
+///
+/// # Errors
+///
+/// Returns an error if TimescaleDB is unreachable or the key cannot be stored.
+async fn create_api_key(config: &Config, args: &CreateApiKeyArgs) -> anyhow::Result<()> {
+    let db = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&config.postgres_url)
+        .await?;
+    let key = auth::generate_api_key();
+    auth::create_api_key(&db, &args.name, args.role, &key).await?;
+
+    info!("🔑 Created {} API key for {}; it cannot be shown again", args.role.as_str(), args.name);
+    println!("{}", key);
+    Ok(())
+}
+
+/// Validates the configuration and loads the map once.
+///
+/// Kafka and Redis clients are created from the broker settings without
+/// connecting, which catches unknown security options and unreadable
+/// certificates.
+///
+/// # Errors
+///
+/// Returns an error if `MAP_BBOX` is malformed, the broker settings are
+/// invalid or the map cannot be loaded.
+fn check_config(config: &Config) -> anyhow::Result<()> {
+    let _: BaseConsumer = config.kafka_client().create().context("Invalid Kafka configuration")?;
+    config.redis_client().context("Invalid Redis configuration")?;
+    info!("🔐 Kafka security protocol: {}", config.kafka_security_protocol());
+
+    let bbox = config.map_bbox()?;
+    info!("🔍 Map files: {:?}, bounding box: {:?}", config.map_paths(), bbox);
+
+    let graph = RoadGraph::load_or_build_many(&config.map_paths(), bbox)?;
+    info!("✅ Configuration is valid: {} nodes, {} roads", graph.nodes.len(), graph.edges.len());
+    let stats = graph.stats();
+    info!(
+        "📐 {:.1} km of road in {} classes, {} strongly connected components ({:.1}% of nodes in the largest)",
+        stats.total_km,
+        stats.classes.len(),
+        stats.connectivity.components,
+        stats.connectivity.largest_component_share * 100.0
+    );
+    info!("🧠 Road graph memory: {}", graph.memory_usage());
+    Ok(())
+}
+
+/// Compares the configured map with a newer version and prints the
+/// differences as JSON, writing a GeoJSON changeset if requested.
+///
+/// # Errors
+///
+/// Returns an error if `MAP_BBOX` is malformed, either map cannot be
+/// loaded or the changeset cannot be written.
+fn diff_map(config: &Config, args: &DiffMapArgs) -> anyhow::Result<()> {
+    let bbox = config.map_bbox()?;
+    let current = RoadGraph::load_or_build_many(&config.map_paths(), bbox)?;
+    let paths: Vec<&str> = args.paths.iter().map(String::as_str).collect();
+    let next = RoadGraph::load_or_build_many(&paths, bbox)?;
+
+    let diff = current.diff(&next);
+    info!(
+        "🗺️ {:?} -> {:?}: {} nodes added, {} removed, {} moved; {} roads added, {} removed, {} changed",
+        config.map_paths(),
+        paths,
+        diff.nodes_added.len(),
+        diff.nodes_removed.len(),
+        diff.nodes_moved.len(),
+        diff.edges_added.len(),
+        diff.edges_removed.len(),
+        diff.edges_changed.len()
+    );
+    if let Some(path) = &args.geojson {
+        let changeset = diff.to_geojson(&current, &next);
+        std::fs::write(path, serde_json::to_string(&changeset)?)
+            .with_context(|| format!("Could not write {}", path.display()))?;
+        info!("🗺️ Changeset written to {}", path.display());
+    }
+    println!("{}", serde_json::to_string_pretty(&diff)?);
+    Ok(())
+}
+
+/// Runs the API server until it fails.
+///
+/// # Arguments
+///
+/// * `config` - Service configuration
+/// * `args` - Whether to run without Kafka, Redis and TimescaleDB, and the
+///   session to replay in that case
+///
+/// # Errors
+///
+/// Returns an error if a required dependency is unreachable at startup, the
+/// session to replay cannot be read, or the listener cannot be bound.
+async fn serve(config: Config, args: ServeArgs) -> anyhow::Result<()> {
+    // Replaying a session is a demo without the backing services
+    let dry_run = args.dry_run || args.replay.is_some();
+    let replay = args.replay.as_deref().map(sessions::Session::load).transpose()?;
+    let redis = config.redis_client()?;
+    if dry_run {
+        // Serve the map only; live data, control and history are unavailable
+        info!("🧪 Dry run: not connecting to Kafka, Redis or TimescaleDB");
+    } else {
+        // Dependencies may still be booting (e.g. under docker-compose); the
+        // database is optional, so the API starts without it
+        wait_for_kafka(&config.kafka_client(), config.startup_timeout()).await?;
+        wait_for_redis(&redis, config.startup_timeout()).await?;
+        if let Err(e) = wait_for_postgres(&config.postgres_url, config.startup_timeout()).await {
+            warn!("⚠️ Starting without TimescaleDB, historical queries will fail: {}", e);
+        }
+    }
+
+    // Listen right away so /health reports progress while the map loads
+    let listener = std::net::TcpListener::bind("0.0.0.0:3000")?;
+    listener.set_nonblocking(true)?;
+    let (progress_tx, progress_rx) = watch::channel(None);
+    let loading_server = loading::LoadingServer::start(&listener, progress_rx)?;
 
     info!("🗺️ Loading map for API...");
 
-    // Load road network from OpenStreetMap data
-    let road_graph = match RoadGraph::load_from_pbf("crates/traffic-sim/assets/berlin.osm.pbf") {
+    // Load road network from OpenStreetMap data off the async runtime
+    let paths: Vec<String> = config.map_paths().into_iter().map(str::to_string).collect();
+    let loaded = match config.map_bbox() {
+        Ok(bbox) => {
+            RoadGraph::load_or_build_many_async(paths, bbox, move |progress| {
+                progress_tx.send_replace(Some(progress.clone()));
+            })
+            .await
+        }
+        Err(e) => Err(e),
+    };
+    loading_server.stop().await;
+    let road_graph = match loaded {
         Ok(graph) => {
             info!("✅ API Map loaded: {} roads", graph.edges.len());
+            info!("🧠 Road graph memory: {}", graph.memory_usage());
             graph
         },
         Err(e) => {
@@ -70,53 +352,150 @@ async fn main() -> anyhow::Result<()> {
 
     let total_roads = road_graph.edges.len();
 
-    // Filter and transform roads for frontend rendering
-    let map_points: Vec<Road> = road_graph.edges
-        .iter()
-        .filter(|road| {
-            matches!(
-                road.highway_type.as_str(),
-                "motorway" | "trunk" | "primary" | "secondary" | "tertiary" |
-                "residential" | "service" | "living_street"
-            )
-        })
-        .map(|road| Road {
-            id: road.id as u64,
-            geometry: road.geometry
-                .iter()
-                .map(|point| [point.x, point.y])
-                .collect(),
-        })
-        .collect();
+    let map_tolerance = config.map_simplify_tolerance_m.clamp(0.0, MAX_MAP_TOLERANCE_M);
+    let map_points = map_roads(&road_graph, map_tolerance);
 
     info!("📊 Prepared {} road segments for frontend", map_points.len());
 
     let (tx, _rx) = broadcast::channel(1000);
 
+    // Create Kafka producer for simulation control commands
+    let producer: Option<FutureProducer> = if dry_run {
+        None
+    } else {
+        Some(
+            config
+                .kafka_client()
+                .set("message.timeout.ms", "5000")
+                .create()?,
+        )
+    };
+
+    // Connect lazily so the API still serves live data while the DB is down
+    let db = PgPoolOptions::new()
+        .max_connections(5)
+        .connect_lazy(&config.postgres_url)?;
+
     let shared_state = Arc::new(AppState {
         tx: tx.clone(),
         map_points,
+        map_tolerance,
         total_roads,
+        producer,
+        stats: road_graph.stats(),
+        intersections: road_graph.classify_intersections(),
+        graph: road_graph,
+        tasks: Default::default(),
+        db,
+        vehicles: Default::default(),
+        redis,
+        webhooks: webhooks::WebhookDispatcher::new(&config.webhook_urls())?,
+        digest_interval: config.digest_interval(),
+        auth: Authenticator::new(&config),
+        query_cache: query_cache::QueryCache::new(config.analytics_cache_ttl()),
+        session_dir: PathBuf::from(&config.session_dir),
     });
+    if shared_state.auth.enabled() {
+        info!("🔒 Authentication required on all routes except /health");
+    }
 
-    // Start Redis pub/sub listener in background
-    let state_clone = shared_state.clone();
-    let redis_url = config.redis_url.clone();
-    tokio::spawn(async move {
-        subscribe_redis(state_clone, redis_url).await;
-    });
+    if let Some(session) = replay {
+        info!("📼 Demo mode: replaying {}", args.replay.unwrap_or_default().display());
+        tokio::spawn(session.replay(shared_state.clone()));
+    }
 
-    // Build and configure the HTTP router
-    let app = Router::new()
-        .route("/health", get(health_check))
+    if !dry_run {
+        // Start Redis pub/sub listener in background
+        let state_clone = shared_state.clone();
+        tokio::spawn(async move {
+            subscribe_redis(state_clone).await;
+        });
+
+        // Track fleet task events for the dispatch API
+        let state_clone = shared_state.clone();
+        let kafka = config.kafka_client();
+        tokio::spawn(async move {
+            dispatch::consume_task_events(state_clone, kafka).await;
+        });
+
+        // Stream traffic light changes to WebSocket clients
+        tokio::spawn(signals::consume_signal_states(shared_state.clone(), config.kafka_client()));
+
+        // Keep the vehicle registry current for enriching live updates
+        tokio::spawn(fleet::refresh_registry(shared_state.clone()));
+
+        // Expire incidents and alerts whose TTL has run out
+        tokio::spawn(incidents::expire_incidents(shared_state.clone()));
+
+        // Summarize each period in one digest instead of notifying per event
+        let mailer = digest::Mailer::from_config(&config)?;
+        match shared_state.digest_interval {
+            Some(interval) if shared_state.webhooks.is_enabled() || mailer.is_some() => {
+                info!("📰 Sending digests every {:?}", interval);
+                tokio::spawn(digest::run_digests(shared_state.clone(), interval, mailer));
+            }
+            _ => {}
+        }
+    }
+
+    // Build and configure the HTTP router; each group requires a minimum role
+    let viewer = Router::new()
         .route("/map", get(get_map))
+        .route("/map/stats", get(get_map_stats))
+        .route("/map/meta", get(get_map_meta))
+        .route("/map/intersections", get(get_intersections))
+        .route("/roads", get(roads::get_roads))
+        .route("/tiles/:z/:x/:y", get(tiles::get_tile))
         .route("/ws", get(ws_handler))
+        .route("/zones", get(zones::get_zones))
+        .route("/districts/stats", get(districts::get_district_stats))
+        .route("/dispatch/tasks/:id", get(dispatch::get_task))
+        .route("/fleet/vehicles", get(fleet::list_vehicles))
+        .route("/fleet/vehicles/:id", get(fleet::get_vehicle))
+        .route("/state", get(state::get_state))
+        .route("/incidents", get(incidents::list_incidents))
+        .route("/incidents/:id", get(incidents::get_incident))
+        .route_layer(from_fn_with_state((shared_state.clone(), Role::Viewer), require_role));
+    let analyst = Router::new()
+        .route("/vehicles/:id/trace", get(trace::get_trace))
+        .route_layer(from_fn_with_state((shared_state.clone(), Role::Analyst), require_role));
+    let operator = Router::new()
+        .route("/control/pause", post(control::pause))
+        .route("/control/resume", post(control::resume))
+        .route("/control/signals", put(control::set_signal_plan))
+        .route("/control/signals/:node_id", delete(control::clear_signal_plan))
+        .route("/control/emission", put(control::set_emission_rates))
+        .route("/control/closures/:way_id", put(control::close_road).delete(control::reopen_road))
+        .route("/dispatch/tasks", post(dispatch::create_task))
+        .route("/fleet/vehicles", post(fleet::create_vehicle))
+        .route("/fleet/vehicles/:id", put(fleet::update_vehicle).delete(fleet::delete_vehicle))
+        .route("/incidents", post(incidents::create_incident))
+        .route("/incidents/:id", delete(incidents::delete_incident))
+        .route("/incidents/:id/acknowledge", post(incidents::acknowledge_incident))
+        .route("/incidents/:id/resolve", post(incidents::resolve_incident))
+        .route_layer(from_fn_with_state(shared_state.clone(), audit::record))
+        .route_layer(from_fn_with_state((shared_state.clone(), Role::Operator), require_role));
+    let admin = Router::new()
+        .route("/admin/redis/keyspace", get(admin::redis_keyspace))
+        .route("/admin/memory", get(admin::memory))
+        .route("/admin/audit", get(audit::get_audit))
+        .route("/admin/digest", get(digest::get_digest))
+        .route("/admin/sessions", get(sessions::list_sessions))
+        .route("/admin/sessions/record", post(sessions::start_recording))
+        .route_layer(from_fn_with_state(shared_state.clone(), audit::record))
+        .route_layer(from_fn_with_state((shared_state.clone(), Role::Admin), require_role));
+
+    let app = Router::new()
+        .route("/health", get(health_check))
+        .merge(viewer)
+        .merge(analyst)
+        .merge(operator)
+        .merge(admin)
         .with_state(shared_state)
         .layer(CorsLayer::permissive());
 
     info!("🚀 API listening on 0.0.0.0:3000");
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await?;
-    axum::serve(listener, app).await?;
+    axum::serve(tokio::net::TcpListener::from_std(listener)?, app).await?;
 
     Ok(())
 }
@@ -130,26 +509,136 @@ struct HealthStatus {
     map_loaded: bool,
     total_roads: usize,
     visible_roads: usize,
+    /// Source file and checksum of the loaded map, to check that all
+    /// services run the same map build
+    map: Option<MapMeta>,
+    /// Progress of the map loader, while the map loads
+    #[serde(skip_serializing_if = "Option::is_none")]
+    loading: Option<LoadProgress>,
 }
 
 /// Health check endpoint handler.
 ///
-/// Returns the service status and map loading statistics.
+/// Returns the service status, map loading statistics and the map's
+/// provenance.
 async fn health_check(State(state): State<Arc<AppState>>) -> Json<HealthStatus> {
     Json(HealthStatus {
         status: "OK".to_string(),
         map_loaded: state.total_roads > 0,
         total_roads: state.total_roads,
         visible_roads: state.map_points.len(),
+        map: state.graph.meta.clone(),
+        loading: None,
     })
 }
 
+/// Filters and transforms roads for frontend rendering.
+///
+/// # Arguments
+///
+/// * `graph` - Road network
+/// * `tolerance_m` - Geometry simplification tolerance in meters (see
+///   [`simplify_polyline`])
+fn map_roads(graph: &RoadGraph, tolerance_m: f64) -> Vec<Road> {
+    graph.edges
+        .iter()
+        .enumerate()
+        .filter(|(edge, road)| {
+            // Two-way roads are sent once, not once per direction
+            graph.is_drawn_edge(*edge) && matches!(
+                road.highway_type.as_str(),
+                "motorway" | "trunk" | "primary" | "secondary" | "tertiary" |
+                "residential" | "service" | "living_street"
+            )
+        })
+        .map(|(edge, road)| Road {
+            id: road.id as u64,
+            geometry: simplify_polyline(&road.geometry, tolerance_m)
+                .iter()
+                .map(|point| [point.x, point.y])
+                .collect(),
+            name: road.name.clone(),
+            ref_: road.ref_.clone(),
+            turn_lanes: road.turn_lanes.clone(),
+            turn_lanes_backward: graph
+                .reverse_edge(edge)
+                .and_then(|reverse| graph.edges[reverse].turn_lanes.clone()),
+            lanes: road.lanes,
+            lanes_backward: graph.reverse_edge(edge).and_then(|reverse| graph.edges[reverse].lanes),
+            bridge: road.bridge,
+            tunnel: road.tunnel,
+            layer: road.level(),
+        })
+        .collect()
+}
+
 /// Map data endpoint handler.
 ///
-/// Returns all pre-filtered road segments for rendering on the frontend.
-async fn get_map(State(state): State<Arc<AppState>>) -> Json<Vec<Road>> {
-    info!("📍 Map requested, sending {} road segments", state.map_points.len());
-    Json(state.map_points.clone())
+/// Returns all pre-filtered road segments for rendering on the frontend,
+/// with vertices closer than `tolerance` meters to the simplified line
+/// dropped. Low-zoom clients pass a larger tolerance to download less.
+///
+/// # Errors
+///
+/// Returns `400 Bad Request` if the tolerance is negative, not a number
+/// or above [`MAX_MAP_TOLERANCE_M`].
+async fn get_map(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<MapParams>,
+) -> Result<Json<Vec<Road>>, StatusCode> {
+    let roads = match params.tolerance {
+        Some(tolerance) if !(0.0..=MAX_MAP_TOLERANCE_M).contains(&tolerance) => {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        Some(tolerance) if tolerance != state.map_tolerance => map_roads(&state.graph, tolerance),
+        _ => state.map_points.clone(),
+    };
+    info!("📍 Map requested, sending {} road segments", roads.len());
+    Ok(Json(roads))
+}
+
+/// Map statistics endpoint handler.
+///
+/// Returns node/edge counts per highway class, network length, extent and
+/// connectivity of the loaded road graph.
+async fn get_map_stats(State(state): State<Arc<AppState>>) -> Json<GraphStats> {
+    Json(state.stats.clone())
+}
+
+/// Where the loaded map comes from and what it contains.
+#[derive(Serialize)]
+struct MapInfo {
+    /// Source file, checksum, data source, extract time, licence and
+    /// attribution, if the map was loaded from a file
+    #[serde(flatten)]
+    meta: Option<MapMeta>,
+    /// Extent of the road network, or `None` for an empty map
+    extent: Option<BoundingBox>,
+    /// Directed road segments per highway class
+    roads: BTreeMap<String, usize>,
+}
+
+/// Map metadata endpoint handler.
+///
+/// Returns the provenance of the loaded map with the licence and the
+/// attribution to display alongside it, its extent and the number of road
+/// segments per highway class, so clients can credit the data without
+/// hard-coding it.
+async fn get_map_meta(State(state): State<Arc<AppState>>) -> Json<MapInfo> {
+    Json(MapInfo {
+        meta: state.graph.meta.clone(),
+        extent: state.stats.bbox,
+        roads: state.stats.classes.iter().map(|(class, stats)| (class.clone(), stats.edges)).collect(),
+    })
+}
+
+/// Intersections endpoint handler.
+///
+/// Returns every intersection with its position, approach count and how
+/// right of way is decided (signals, roundabout, priority road or none),
+/// for rendering intersection markers.
+async fn get_intersections(State(state): State<Arc<AppState>>) -> Json<Vec<Intersection>> {
+    Json(state.intersections.clone())
 }
 
 /// WebSocket upgrade handler.
@@ -184,30 +673,29 @@ async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>) {
 
 /// Subscribes to Redis pub/sub and broadcasts messages to WebSocket clients.
 ///
-/// Listens to the "vehicles:update" channel and forwards all received
-/// messages to connected WebSocket clients via the broadcast channel.
+/// Listens to the "vehicles:update", "zones:update", "congestion:trend" and
+/// "incidents:update" channels and forwards all received messages to
+/// connected WebSocket clients via the broadcast channel. Zone snapshots
+/// carry `"type": "zone_stats"`, trends `"type": "congestion.trend"` and
+/// incidents `"type": "incident"` so clients can tell them apart from
+/// vehicle updates. Updates of registered vehicles get
+/// their metadata attached (see [`fleet::attach_metadata`]). Messages on
+/// [`query_cache::INVALIDATE_CHANNEL`] drop cached analytics responses
+/// instead of being forwarded.
 ///
 /// # Arguments
 ///
-/// * `state` - Shared application state with the broadcast sender
-/// * `redis_url` - Redis connection URL
+/// * `state` - Shared application state with the broadcast sender and
+///   the Redis client
 ///
 /// # Behavior
 ///
 /// Runs indefinitely until the Redis connection is lost. Errors are logged
 /// but the function does not panic, allowing graceful degradation.
-async fn subscribe_redis(state: Arc<AppState>, redis_url: String) {
-    info!("🔌 Connecting to Redis at: {}", redis_url);
+async fn subscribe_redis(state: Arc<AppState>) {
+    info!("🔌 Connecting to Redis at: {}", state.redis.get_connection_info().addr);
 
-    let client = match redis::Client::open(redis_url.as_str()) {
-        Ok(c) => c,
-        Err(e) => {
-            error!("❌ Failed to create Redis client: {}", e);
-            return;
-        }
-    };
-
-    let con = match client.get_async_connection().await {
+    let con = match state.redis.get_async_connection().await {
         Ok(c) => c,
         Err(e) => {
             error!("❌ Failed to connect to Redis: {}", e);
@@ -216,12 +704,24 @@ async fn subscribe_redis(state: Arc<AppState>, redis_url: String) {
     };
 
     let mut pubsub = con.into_pubsub();
-    if let Err(e) = pubsub.subscribe("vehicles:update").await {
+    if let Err(e) = pubsub.subscribe(&[
+        "vehicles:update",
+        zones::ZONE_UPDATES_CHANNEL,
+        zones::CONGESTION_TREND_CHANNEL,
+        incidents::INCIDENT_UPDATES_CHANNEL,
+        query_cache::INVALIDATE_CHANNEL,
+    ]).await {
         error!("❌ Failed to subscribe to channel: {}", e);
         return;
     }
 
-    info!("✅ Successfully subscribed to 'vehicles:update'. Waiting for messages...");
+    info!(
+        "✅ Successfully subscribed to 'vehicles:update', '{}', '{}', '{}' and '{}'. Waiting for messages...",
+        zones::ZONE_UPDATES_CHANNEL,
+        zones::CONGESTION_TREND_CHANNEL,
+        incidents::INCIDENT_UPDATES_CHANNEL,
+        query_cache::INVALIDATE_CHANNEL
+    );
 
     while let Some(msg) = pubsub.on_message().next().await {
         let payload: String = match msg.get_payload() {
@@ -232,6 +732,21 @@ async fn subscribe_redis(state: Arc<AppState>, redis_url: String) {
             }
         };
 
+        let payload = match msg.get_channel_name() {
+            "vehicles:update" => fleet::attach_metadata(&state.vehicles, payload),
+            query_cache::INVALIDATE_CHANNEL => {
+                // Internal; not forwarded to clients
+                state.query_cache.handle_invalidation(&payload);
+                continue;
+            }
+            incidents::INCIDENT_UPDATES_CHANNEL => {
+                // Incidents changed on another instance are counted in digests
+                state.query_cache.invalidate(CacheScope::Digest);
+                payload
+            }
+            _ => payload,
+        };
+
         // Broadcast to WebSocket clients (ignore error if no subscribers)
         let _ = state.tx.send(payload);
     }
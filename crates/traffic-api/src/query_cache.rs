@@ -0,0 +1,152 @@
+//! Caching of heavy analytics responses.
+//!
+//! Dashboards refresh `/districts/stats` and `/admin/digest` every few
+//! seconds with the same parameters, and each refresh re-ran the same
+//! aggregation over `vehicle_positions`. [`QueryCache`] keeps the
+//! serialized response per endpoint and normalized query for
+//! `ANALYTICS_CACHE_TTL_SECS`; concurrent identical requests share a single
+//! query.
+//!
+//! Entries are dropped before their TTL when the data behind them changes:
+//! ingest publishes the affected [`CacheScope`] (or `*` for all) on
+//! [`INVALIDATE_CHANNEL`] after rewriting history with a backfill, and
+//! every incident change invalidates the digests.
+
+use axum::body::Bytes;
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use moka::future::Cache;
+use serde::Serialize;
+use std::future::Future;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// Redis channel on which the aggregation pipeline announces changed data.
+pub const INVALIDATE_CHANNEL: &str = "analytics:invalidate";
+
+/// Most responses kept at once, across all endpoints.
+const MAX_ENTRIES: u64 = 1000;
+
+/// Endpoint a cached response belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CacheScope {
+    /// `GET /districts/stats`
+    Districts,
+    /// `GET /admin/digest`
+    Digest,
+}
+
+impl CacheScope {
+    /// Reads a scope from its name in invalidation messages.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "districts" => Some(Self::Districts),
+            "digest" => Some(Self::Digest),
+            _ => None,
+        }
+    }
+}
+
+/// A serialized JSON response body.
+#[derive(Debug, Clone)]
+pub struct JsonBody(Bytes);
+
+impl IntoResponse for JsonBody {
+    fn into_response(self) -> Response {
+        ([(header::CONTENT_TYPE, "application/json")], self.0).into_response()
+    }
+}
+
+/// Analytics responses by endpoint and normalized query.
+pub struct QueryCache {
+    /// `None` if caching is disabled
+    cache: Option<Cache<(CacheScope, String), Bytes>>,
+}
+
+impl QueryCache {
+    /// Creates a cache whose entries live for `ttl`; `None` disables
+    /// caching.
+    pub fn new(ttl: Option<Duration>) -> Self {
+        Self {
+            cache: ttl.map(|ttl| {
+                Cache::builder()
+                    .max_capacity(MAX_ENTRIES)
+                    .time_to_live(ttl)
+                    .support_invalidation_closures()
+                    .build()
+            }),
+        }
+    }
+
+    /// Returns the cached response for a query, or computes, caches and
+    /// returns it.
+    ///
+    /// Errors are passed through and not cached.
+    ///
+    /// # Arguments
+    ///
+    /// * `scope` - Endpoint of the query
+    /// * `key` - Query parameters after defaults and clamping, so
+    ///   equivalent requests share an entry
+    /// * `compute` - Runs the query
+    ///
+    /// # Errors
+    ///
+    /// Returns the status of a failed query, or 500 if the response cannot
+    /// be serialized.
+    pub async fn get_or_compute<T, F, Fut>(
+        &self,
+        scope: CacheScope,
+        key: String,
+        compute: F,
+    ) -> Result<JsonBody, StatusCode>
+    where
+        T: Serialize,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, StatusCode>>,
+    {
+        let serialize = |value: T| {
+            serde_json::to_vec(&value).map(Bytes::from).map_err(|e| {
+                error!("❌ Could not serialize {:?} response: {}", scope, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })
+        };
+
+        let Some(cache) = &self.cache else {
+            return serialize(compute().await?).map(JsonBody);
+        };
+        cache
+            .try_get_with((scope, key), async { serialize(compute().await?) })
+            .await
+            .map(JsonBody)
+            .map_err(|status| *status)
+    }
+
+    /// Drops all cached responses of one endpoint.
+    pub fn invalidate(&self, scope: CacheScope) {
+        if let Some(cache) = &self.cache {
+            if let Err(e) = cache.invalidate_entries_if(move |(entry_scope, _), _| *entry_scope == scope) {
+                warn!("⚠️ Could not invalidate cached {:?} responses: {}", scope, e);
+            }
+        }
+    }
+
+    /// Handles a message on [`INVALIDATE_CHANNEL`]: a scope name
+    /// (`districts`, `digest`) or `*` for all.
+    pub fn handle_invalidation(&self, message: &str) {
+        let Some(cache) = &self.cache else { return };
+        match message.trim() {
+            "*" => {
+                info!("🧹 Analytics cache cleared");
+                cache.invalidate_all();
+            }
+            name => match CacheScope::from_name(name) {
+                Some(scope) => {
+                    info!("🧹 Cached {:?} responses invalidated", scope);
+                    self.invalidate(scope);
+                }
+                None => warn!("⚠️ Ignoring invalidation of unknown analytics cache '{}'", name),
+            },
+        }
+    }
+}
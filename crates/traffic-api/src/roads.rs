@@ -0,0 +1,162 @@
+//! Batch lookup of road details.
+//!
+//! `/map` and the tiles carry just enough to draw the network. When the
+//! frontend needs more about the roads in view, `GET /roads` returns the
+//! details of many OSM ways in one request, limited to the fields asked
+//! for:
+//!
+//! ```text
+//! GET /roads?ids=4045215,23775633&fields=name,speed_limit
+//! [{"id":4045215,"name":"Unter den Linden","speed_limit":13.9}, ...]
+//! ```
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::sync::Arc;
+use common::map::RoadGraph;
+use crate::AppState;
+
+/// Most ways that may be looked up in one request.
+const MAX_IDS: usize = 500;
+
+/// Query parameters of the road lookup.
+#[derive(Deserialize)]
+pub struct RoadParams {
+    /// Comma-separated OSM way IDs
+    ids: String,
+    /// Comma-separated fields to return besides `id` (default: all)
+    fields: Option<String>,
+}
+
+/// A detail of a road that can be requested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RoadField {
+    /// Polylines of [longitude, latitude], one per drawn segment
+    Geometry,
+    Name,
+    Ref,
+    /// OSM highway class, e.g. "residential"
+    Highway,
+    /// Speed limit in m/s, the typical limit of the class if not tagged
+    SpeedLimit,
+    /// Length in meters
+    Length,
+    /// Whether the road can only be travelled in one direction
+    Oneway,
+    Bridge,
+    Tunnel,
+    /// Vertical level (0 = ground)
+    Layer,
+}
+
+impl RoadField {
+    const ALL: [RoadField; 10] = [
+        Self::Geometry,
+        Self::Name,
+        Self::Ref,
+        Self::Highway,
+        Self::SpeedLimit,
+        Self::Length,
+        Self::Oneway,
+        Self::Bridge,
+        Self::Tunnel,
+        Self::Layer,
+    ];
+
+    /// Returns the field's name in requests and responses.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Geometry => "geometry",
+            Self::Name => "name",
+            Self::Ref => "ref",
+            Self::Highway => "highway",
+            Self::SpeedLimit => "speed_limit",
+            Self::Length => "length",
+            Self::Oneway => "oneway",
+            Self::Bridge => "bridge",
+            Self::Tunnel => "tunnel",
+            Self::Layer => "layer",
+        }
+    }
+
+    /// Reads a field from its name.
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|field| field.name() == name)
+    }
+}
+
+/// Road lookup endpoint handler.
+///
+/// Returns one object per requested way found in the map, in request
+/// order, with `id` and the selected fields; unknown ways are left out.
+///
+/// # Errors
+///
+/// Returns `400 Bad Request` if an ID is not a number, a field is unknown,
+/// or more than [`MAX_IDS`] ways are requested.
+pub async fn get_roads(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<RoadParams>,
+) -> Result<Json<Vec<Value>>, StatusCode> {
+    let ids = split(&params.ids)
+        .map(str::parse)
+        .collect::<Result<Vec<i64>, _>>()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    if ids.len() > MAX_IDS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let fields = match &params.fields {
+        Some(fields) => split(fields)
+            .map(|name| RoadField::from_name(name).ok_or(StatusCode::BAD_REQUEST))
+            .collect::<Result<Vec<_>, _>>()?,
+        None => RoadField::ALL.to_vec(),
+    };
+
+    Ok(Json(ids
+        .into_iter()
+        .filter_map(|id| road_details(&state.graph, id, &fields))
+        .collect()))
+}
+
+/// Collects the selected fields of one way, or `None` if it is not in the
+/// map.
+fn road_details(graph: &RoadGraph, way_id: i64, fields: &[RoadField]) -> Option<Value> {
+    let edges = graph.edges_for_way(way_id);
+    let first = &graph.edges[*edges.first()?];
+    // Each stretch of road once, not once per direction
+    let drawn: Vec<usize> = edges.iter().copied().filter(|&edge| graph.is_drawn_edge(edge)).collect();
+
+    let mut road = Map::new();
+    road.insert("id".to_string(), Value::from(way_id));
+    for &field in fields {
+        let value = match field {
+            RoadField::Geometry => Value::from(
+                drawn
+                    .iter()
+                    .map(|&edge| graph.edges[edge].geometry.iter().map(|point| vec![point.x, point.y]).collect())
+                    .collect::<Vec<Vec<Vec<f64>>>>(),
+            ),
+            RoadField::Name => Value::from(first.name.clone()),
+            RoadField::Ref => Value::from(first.ref_.clone()),
+            RoadField::Highway => Value::from(first.highway_type.clone()),
+            RoadField::SpeedLimit => Value::from(first.effective_speed_limit_mps()),
+            RoadField::Length => Value::from(drawn.iter().map(|&edge| graph.edges[edge].length).sum::<f64>()),
+            RoadField::Oneway => Value::from(edges.iter().all(|&edge| graph.reverse_edge(edge).is_none())),
+            RoadField::Bridge => Value::from(first.bridge),
+            RoadField::Tunnel => Value::from(first.tunnel),
+            RoadField::Layer => Value::from(first.level()),
+        };
+        road.insert(field.name().to_string(), value);
+    }
+    Some(Value::Object(road))
+}
+
+/// Splits a comma-separated parameter, ignoring empty entries.
+fn split(values: &str) -> impl Iterator<Item = &str> {
+    values.split(',').map(str::trim).filter(|value| !value.is_empty())
+}
@@ -0,0 +1,286 @@
+//! Recording and replay of live WebSocket traffic for demos.
+//!
+//! Demo environments should look alive without running the simulation,
+//! Kafka and Redis. An admin records a window of what `/ws` clients receive
+//! with `POST /admin/sessions/record`; the messages are written with their
+//! time offsets to a gzip-compressed JSON lines file in `SESSION_DIR`:
+//!
+//! ```text
+//! {"format":"traffic-session","version":1,"recorded_at":1760000000,"duration_secs":300,"map":{...}}
+//! [0,"{\"id\":\"v1\",\"lat\":52.52,...}"]
+//! [12,"{\"type\":\"zone_stats\",...}"]
+//! ```
+//!
+//! `traffic-api serve --replay FILE` serves the map as in a dry run and
+//! plays the file through `/ws` in a loop with the original timing.
+
+use axum::{extract::State, http::StatusCode, Json};
+use anyhow::{bail, Context};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+use tokio::time::Instant;
+use tracing::{error, info, warn};
+use common::map::MapMeta;
+use crate::AppState;
+
+/// Value of the header's `format` field.
+const SESSION_FORMAT: &str = "traffic-session";
+
+/// Version of the file layout.
+const SESSION_VERSION: u32 = 1;
+
+/// File name suffix of recorded sessions.
+const SESSION_EXTENSION: &str = ".jsonl.gz";
+
+/// Longest window that can be recorded at once.
+const MAX_DURATION_SECS: u64 = 3600;
+
+/// First line of a session file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionHeader {
+    /// Always "traffic-session"
+    format: String,
+    version: u32,
+    /// Unix timestamp at which recording started
+    recorded_at: u64,
+    /// Length of the recorded window in seconds
+    duration_secs: u64,
+    /// Map the API served while recording
+    map: Option<MapMeta>,
+}
+
+/// Request body for recording a session.
+#[derive(Deserialize)]
+pub struct RecordRequest {
+    /// File name without extension (letters, digits, `-` and `_`)
+    name: String,
+    /// Length of the window to record, at most an hour
+    duration_secs: u64,
+}
+
+/// A recording that was started.
+#[derive(Serialize)]
+pub struct RecordingStarted {
+    name: String,
+    path: String,
+    duration_secs: u64,
+}
+
+/// A recorded session in `SESSION_DIR`.
+#[derive(Serialize)]
+pub struct SessionInfo {
+    name: String,
+    size_bytes: u64,
+    #[serde(flatten)]
+    header: SessionHeader,
+}
+
+/// Session recording endpoint handler.
+///
+/// Starts recording everything sent to WebSocket clients for the requested
+/// window and returns immediately; the file is complete once the window
+/// has passed.
+///
+/// # Errors
+///
+/// Returns `400 Bad Request` for an invalid name or duration, `409
+/// Conflict` if a session of that name exists, and `500 Internal Server
+/// Error` if the file cannot be created.
+pub async fn start_recording(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<RecordRequest>,
+) -> Result<(StatusCode, Json<RecordingStarted>), StatusCode> {
+    if !is_valid_name(&request.name) || !(1..=MAX_DURATION_SECS).contains(&request.duration_secs) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let path = state.session_dir.join(format!("{}{}", request.name, SESSION_EXTENSION));
+    let header = SessionHeader {
+        format: SESSION_FORMAT.to_string(),
+        version: SESSION_VERSION,
+        recorded_at: chrono::Utc::now().timestamp().max(0) as u64,
+        duration_secs: request.duration_secs,
+        map: state.graph.meta.clone(),
+    };
+    let writer = create_session(&path, &header).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::AlreadyExists {
+            return StatusCode::CONFLICT;
+        }
+        error!("❌ Could not create session file {}: {}", path.display(), e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    info!("🎬 Recording session '{}' for {} s", request.name, request.duration_secs);
+    let duration = Duration::from_secs(request.duration_secs);
+    tokio::spawn(record(state.tx.subscribe(), writer, request.name.clone(), duration));
+
+    Ok((StatusCode::ACCEPTED, Json(RecordingStarted {
+        name: request.name,
+        path: path.display().to_string(),
+        duration_secs: request.duration_secs,
+    })))
+}
+
+/// Session list endpoint handler.
+///
+/// Returns the sessions in `SESSION_DIR` with their headers, by name;
+/// files that cannot be read are left out.
+pub async fn list_sessions(State(state): State<Arc<AppState>>) -> Json<Vec<SessionInfo>> {
+    let Ok(entries) = std::fs::read_dir(&state.session_dir) else {
+        return Json(Vec::new());
+    };
+
+    let mut sessions: Vec<SessionInfo> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let file_name = entry.file_name().into_string().ok()?;
+            let name = file_name.strip_suffix(SESSION_EXTENSION)?.to_string();
+            let size_bytes = entry.metadata().ok()?.len();
+            match read_header(&entry.path()) {
+                Ok((header, _)) => Some(SessionInfo { name, size_bytes, header }),
+                Err(e) => {
+                    warn!("⚠️ Skipping unreadable session {}: {:#}", file_name, e);
+                    None
+                }
+            }
+        })
+        .collect();
+    sessions.sort_by(|a, b| a.name.cmp(&b.name));
+    Json(sessions)
+}
+
+/// Returns `true` for names that are safe as file names.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Creates a session file and writes its header; fails if it exists.
+fn create_session(path: &Path, header: &SessionHeader) -> std::io::Result<GzEncoder<BufWriter<File>>> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let file = File::options().write(true).create_new(true).open(path)?;
+    let mut writer = GzEncoder::new(BufWriter::new(file), Compression::default());
+    serde_json::to_writer(&mut writer, header)?;
+    writer.write_all(b"\n")?;
+    Ok(writer)
+}
+
+/// Writes the broadcast messages of one window to a session file.
+async fn record(
+    mut rx: Receiver<String>,
+    mut writer: GzEncoder<BufWriter<File>>,
+    name: String,
+    duration: Duration,
+) {
+    let started = Instant::now();
+    let mut messages = 0u64;
+    loop {
+        let message = match tokio::time::timeout_at(started + duration, rx.recv()).await {
+            Ok(Ok(message)) => message,
+            Ok(Err(RecvError::Lagged(skipped))) => {
+                warn!("⚠️ Session '{}' missed {} messages", name, skipped);
+                continue;
+            }
+            // Window over, or the server is shutting down
+            Err(_) | Ok(Err(RecvError::Closed)) => break,
+        };
+
+        let offset_ms = started.elapsed().as_millis() as u64;
+        let written = serde_json::to_writer(&mut writer, &(offset_ms, &message))
+            .map_err(std::io::Error::from)
+            .and_then(|_| writer.write_all(b"\n"));
+        if let Err(e) = written {
+            error!("❌ Recording session '{}' failed: {}", name, e);
+            return;
+        }
+        messages += 1;
+    }
+
+    match writer.finish().and_then(|mut file| file.flush()) {
+        Ok(()) => info!("🎬 Session '{}' recorded: {} messages", name, messages),
+        Err(e) => error!("❌ Could not finish session '{}': {}", name, e),
+    }
+}
+
+/// A session loaded for replay.
+pub struct Session {
+    header: SessionHeader,
+    /// Messages with their offset from the start in milliseconds
+    messages: Vec<(u64, String)>,
+}
+
+impl Session {
+    /// Reads a session file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, is not a session file
+    /// of a supported version, or holds no messages.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let (header, lines) = read_header(path)?;
+        let messages = lines
+            .enumerate()
+            .map(|(index, line)| {
+                let line = line?;
+                serde_json::from_str(&line).with_context(|| format!("Malformed message on line {}", index + 2))
+            })
+            .collect::<anyhow::Result<Vec<(u64, String)>>>()
+            .with_context(|| format!("Could not read session {}", path.display()))?;
+        if messages.is_empty() {
+            bail!("Session {} holds no messages", path.display());
+        }
+        Ok(Self { header, messages })
+    }
+
+    /// Plays the session to WebSocket clients over and over, with the
+    /// original spacing between messages.
+    pub async fn replay(self, state: Arc<AppState>) {
+        let recorded_map = self.header.map.as_ref().map(|meta| meta.file_hash.as_str());
+        let served_map = state.graph.meta.as_ref().map(|meta| meta.file_hash.as_str());
+        if recorded_map != served_map {
+            warn!("⚠️ Session was recorded on a different map, vehicles may appear off-road");
+        }
+
+        // Clients join at any time; pause between passes like a real gap
+        let pass_length = Duration::from_secs(self.header.duration_secs)
+            .max(Duration::from_millis(self.messages.last().map_or(0, |(offset, _)| *offset)));
+        loop {
+            info!("▶️ Replaying {} recorded messages", self.messages.len());
+            let started = Instant::now();
+            for (offset_ms, message) in &self.messages {
+                tokio::time::sleep_until(started + Duration::from_millis(*offset_ms)).await;
+                // No receivers is fine: nobody is watching right now
+                let _ = state.tx.send(message.clone());
+            }
+            tokio::time::sleep_until(started + pass_length).await;
+        }
+    }
+}
+
+/// Opens a session file and reads its header.
+///
+/// # Returns
+///
+/// The header and the remaining lines.
+fn read_header(path: &Path) -> anyhow::Result<(SessionHeader, std::io::Lines<BufReader<GzDecoder<File>>>)> {
+    let file = File::open(path).with_context(|| format!("Could not open session {}", path.display()))?;
+    let mut lines = BufReader::new(GzDecoder::new(file)).lines();
+    let first = lines.next().context("Session file is empty")??;
+    let header: SessionHeader = serde_json::from_str(&first).context("Not a session file")?;
+    if header.format != SESSION_FORMAT || header.version != SESSION_VERSION {
+        bail!("Unsupported session format {} version {}", header.format, header.version);
+    }
+    Ok((header, lines))
+}
@@ -0,0 +1,60 @@
+//! Live traffic light states.
+//!
+//! traffic-sim publishes the lights of an intersection whenever one of them
+//! changes; they are streamed to WebSocket clients next to the vehicle
+//! updates so the map can draw the lights.
+
+use common::signals::{SignalState, SIGNAL_STATES_TOPIC};
+use futures_util::StreamExt;
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{Consumer, StreamConsumer};
+use rdkafka::Message;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use crate::AppState;
+
+/// Consumes light state changes and forwards them to WebSocket clients.
+///
+/// Each change is forwarded as the [`SignalState`] JSON object with
+/// `"type": "signal_state"` so it can be told apart from vehicle updates.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state with the broadcaster
+/// * `kafka` - Kafka connection settings
+pub async fn consume_signal_states(state: Arc<AppState>, mut kafka: ClientConfig) {
+    let consumer: StreamConsumer = match kafka
+        .set("group.id", "traffic-api-signals")
+        .set("auto.offset.reset", "latest")
+        .create()
+    {
+        Ok(c) => c,
+        Err(e) => {
+            error!("❌ Failed to create signal states consumer: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = consumer.subscribe(&[SIGNAL_STATES_TOPIC]) {
+        error!("❌ Failed to subscribe to '{}': {}", SIGNAL_STATES_TOPIC, e);
+        return;
+    }
+
+    info!("✅ Subscribed to '{}' for traffic lights", SIGNAL_STATES_TOPIC);
+
+    let mut stream = consumer.stream();
+    while let Some(msg_result) = stream.next().await {
+        let Ok(msg) = msg_result else { continue };
+        let Some(payload) = msg.payload() else { continue };
+        let Ok(signal) = serde_json::from_slice::<SignalState>(payload) else {
+            warn!("Ignoring malformed signal state");
+            continue;
+        };
+
+        let mut message = serde_json::to_value(&signal).unwrap_or_default();
+        message["type"] = serde_json::Value::from("signal_state");
+        let _ = state.tx.send(message.to_string());
+    }
+
+    error!("❌ Signal states stream ended!");
+}
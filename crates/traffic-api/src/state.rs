@@ -0,0 +1,209 @@
+//! Fleet snapshots, current and past.
+//!
+//! `GET /state` returns the latest position of every vehicle that is
+//! reporting, read from the Redis geo index and per-vehicle metadata kept
+//! by traffic-ingest. `GET /state?at=<timestamp>` reconstructs the same
+//! snapshot for a past instant from TimescaleDB: the last position each
+//! vehicle reported at or before it. In both, a vehicle that has been
+//! silent for longer than [`STALE_AFTER_SECS`] is no longer part of the
+//! fleet, just as its metadata expires from Redis.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use redis::aio::MultiplexedConnection;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::error;
+use crate::admin::GEO_INDEX_KEY;
+use crate::AppState;
+
+/// How long a vehicle stays in a snapshot after its last position, in
+/// seconds; matches the lifetime of vehicle metadata in Redis.
+const STALE_AFTER_SECS: i64 = 60;
+
+/// Vehicles read per pipelined round trip.
+const PIPELINE_CHUNK: usize = 500;
+
+/// Query parameters of the state endpoint.
+#[derive(Deserialize)]
+pub struct StateParams {
+    /// Instant to reconstruct as a Unix timestamp (default: now, from Redis)
+    at: Option<i64>,
+}
+
+/// Positions of the whole fleet at one instant.
+#[derive(Serialize)]
+pub struct FleetState {
+    /// Instant of the snapshot as a Unix timestamp
+    pub at: i64,
+    /// Whether the snapshot is the live one (`false`: reconstructed from
+    /// history)
+    pub live: bool,
+    /// Vehicles reporting at the instant, by ID
+    pub vehicles: Vec<VehicleState>,
+}
+
+/// Last known position of a vehicle.
+#[derive(Serialize)]
+pub struct VehicleState {
+    /// Vehicle identifier
+    pub id: String,
+    pub lat: f64,
+    pub lon: f64,
+    /// Speed in m/s
+    pub speed: f64,
+    /// Heading in degrees clockwise from north, if reported
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heading: Option<f64>,
+    /// OSM way ID of the matched road
+    pub road_id: Option<i64>,
+    /// When the position was reported, as a Unix timestamp
+    pub timestamp: i64,
+}
+
+/// Position as returned by `GEOPOS`: `(longitude, latitude)`, or nothing
+/// for vehicles no longer in the index.
+type GeoPosition = Option<(f64, f64)>;
+
+/// Vehicle metadata as written to `vehicle:{id}:meta` by traffic-ingest.
+#[derive(Deserialize)]
+struct VehicleMeta {
+    speed: f64,
+    #[serde(default)]
+    heading: Option<f64>,
+    timestamp: i64,
+    #[serde(default)]
+    road_id: Option<i64>,
+}
+
+/// Fleet snapshot endpoint handler.
+///
+/// Without `at`, returns the live positions from Redis. With `at`, returns
+/// the positions recorded in TimescaleDB as they were at that instant, in
+/// the same shape. Responds with 400 if `at` lies in the future.
+pub async fn get_state(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<StateParams>,
+) -> Result<Json<FleetState>, StatusCode> {
+    let now = chrono::Utc::now().timestamp();
+    let snapshot = match params.at {
+        Some(at) if at > now => return Err(StatusCode::BAD_REQUEST),
+        Some(at) => FleetState {
+            at,
+            live: false,
+            vehicles: load_history(&state, at).await.map_err(|e| {
+                error!("❌ Failed to reconstruct fleet state at {}: {}", at, e);
+                StatusCode::SERVICE_UNAVAILABLE
+            })?,
+        },
+        None => FleetState {
+            at: now,
+            live: true,
+            vehicles: load_live(&state.redis).await.map_err(|e| {
+                error!("❌ Failed to read live fleet state: {}", e);
+                StatusCode::SERVICE_UNAVAILABLE
+            })?,
+        },
+    };
+    Ok(Json(snapshot))
+}
+
+/// Reads the current position and metadata of every vehicle in the geo
+/// index, ordered by ID.
+///
+/// Vehicles whose metadata has expired stopped reporting and are left out.
+async fn load_live(client: &redis::Client) -> redis::RedisResult<Vec<VehicleState>> {
+    let mut con = client.get_multiplexed_async_connection().await?;
+    let mut ids: Vec<String> = redis::cmd("ZRANGE")
+        .arg(GEO_INDEX_KEY)
+        .arg(0)
+        .arg(-1)
+        .query_async(&mut con)
+        .await?;
+    ids.sort();
+
+    let mut vehicles = Vec::with_capacity(ids.len());
+    for chunk in ids.chunks(PIPELINE_CHUNK) {
+        vehicles.extend(load_live_chunk(&mut con, chunk).await?);
+    }
+    Ok(vehicles)
+}
+
+/// Reads the positions and metadata of some vehicles in one round trip.
+async fn load_live_chunk(
+    con: &mut MultiplexedConnection,
+    ids: &[String],
+) -> redis::RedisResult<Vec<VehicleState>> {
+    let keys: Vec<String> = ids.iter().map(|id| format!("vehicle:{}:meta", id)).collect();
+    let (positions, metadata): (Vec<GeoPosition>, Vec<Option<String>>) = redis::pipe()
+        .cmd("GEOPOS")
+        .arg(GEO_INDEX_KEY)
+        .arg(ids)
+        .cmd("MGET")
+        .arg(&keys)
+        .query_async(con)
+        .await?;
+
+    Ok(ids
+        .iter()
+        .zip(positions)
+        .zip(metadata)
+        .filter_map(|((id, position), metadata)| {
+            let (lon, lat) = position?;
+            let meta: VehicleMeta = serde_json::from_str(&metadata?).ok()?;
+            Some(VehicleState {
+                id: id.clone(),
+                lat,
+                lon,
+                speed: meta.speed,
+                heading: meta.heading,
+                road_id: meta.road_id,
+                timestamp: meta.timestamp,
+            })
+        })
+        .collect())
+}
+
+/// Reconstructs the fleet at a past instant: the last recorded position of
+/// every vehicle that reported within [`STALE_AFTER_SECS`] before `at`,
+/// ordered by ID.
+async fn load_history(state: &AppState, at: i64) -> Result<Vec<VehicleState>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT DISTINCT ON (vehicle_id)
+               vehicle_id,
+               extract(epoch FROM time)::float8 AS "timestamp!",
+               latitude AS "latitude!",
+               longitude AS "longitude!",
+               speed,
+               heading,
+               road_id
+        FROM vehicle_positions
+        WHERE time <= to_timestamp($1)
+          AND time > to_timestamp($1) - make_interval(secs => $2)
+          AND latitude IS NOT NULL
+          AND longitude IS NOT NULL
+        ORDER BY vehicle_id, time DESC
+        "#,
+        at as f64,
+        STALE_AFTER_SECS as f64
+    )
+        .fetch_all(&state.db)
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| VehicleState {
+            id: row.vehicle_id,
+            lat: row.latitude,
+            lon: row.longitude,
+            speed: row.speed.unwrap_or(0.0),
+            heading: row.heading,
+            road_id: row.road_id,
+            timestamp: row.timestamp as i64,
+        })
+        .collect())
+}
@@ -0,0 +1,51 @@
+//! Road network as Mapbox Vector Tiles.
+//!
+//! Serves `/tiles/{z}/{x}/{y}.mvt` from the loaded road graph, so map
+//! clients fetch only the roads on screen instead of the whole `/map`
+//! payload. Tiles have a single `roads` layer (see
+//! [`common::map::RoadGraph::tile`]).
+
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use common::map::is_valid_tile;
+use std::sync::Arc;
+use tracing::error;
+use crate::AppState;
+
+/// Media type of Mapbox Vector Tiles.
+const MVT_CONTENT_TYPE: &str = "application/vnd.mapbox-vector-tile";
+
+/// Returns one vector tile of the road network.
+///
+/// The `.mvt` extension on the row is optional. Tiles without roads are
+/// returned as empty `200` responses, as MVT clients expect.
+///
+/// # Errors
+///
+/// Returns `400 Bad Request` for malformed or out-of-range tile coordinates.
+pub async fn get_tile(
+    State(state): State<Arc<AppState>>,
+    Path((z, x, y)): Path<(u8, u32, String)>,
+) -> Result<Response, StatusCode> {
+    let y: u32 = y
+        .strip_suffix(".mvt")
+        .unwrap_or(&y)
+        .parse()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    if !is_valid_tile(z, x, y) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // Low-zoom tiles of large maps take a while to encode
+    let tile = tokio::task::spawn_blocking(move || state.graph.tile(z, x, y))
+        .await
+        .map_err(|e| {
+            error!("❌ Tile {}/{}/{} failed: {}", z, x, y, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(([(header::CONTENT_TYPE, MVT_CONTENT_TYPE)], tile).into_response())
+}
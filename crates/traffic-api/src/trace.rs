@@ -0,0 +1,276 @@
+//! Per-vehicle trace export.
+//!
+//! Builds a single vehicle's historical track from TimescaleDB and renders
+//! it as GPX or GeoJSON so it can be opened directly in GIS tools. The
+//! vehicle's registered metadata, if any, is included. With
+//! `?matched=true` the positions are snapped onto the road network and
+//! carry the OSM way they were matched to.
+//!
+//! Long windows are thinned out on the server: in time, by keeping one
+//! position per interval so no trace exceeds [`MAX_TRACE_POINTS`], and on
+//! request in space, by dropping positions that deviate less than
+//! `?tolerance=` meters from the simplified track (Douglas-Peucker).
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use common::map::{simplify_polyline_indices, RoadGraph};
+use geo::Point;
+use glam::DVec2;
+use serde::Deserialize;
+use std::fmt::Write;
+use std::sync::Arc;
+use tracing::error;
+use crate::fleet::{load_vehicle, Vehicle};
+use crate::AppState;
+
+/// Default trace window when `from` is omitted, in seconds.
+const DEFAULT_WINDOW_SECS: i64 = 3600;
+
+/// Most positions loaded for a trace; longer windows keep one position per
+/// `window / MAX_TRACE_POINTS` seconds.
+const MAX_TRACE_POINTS: i64 = 10_000;
+
+/// Largest simplification tolerance in meters a trace request may ask for.
+const MAX_TRACE_TOLERANCE_M: f64 = 1000.0;
+
+/// Export format of a trace.
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceFormat {
+    /// GPS Exchange Format (XML)
+    Gpx,
+    /// GeoJSON Feature with a LineString geometry
+    #[default]
+    Geojson,
+}
+
+/// Query parameters of the trace endpoint.
+#[derive(Deserialize)]
+pub struct TraceParams {
+    /// Start of the window as a Unix timestamp (default: one hour before `to`)
+    from: Option<i64>,
+    /// End of the window as a Unix timestamp (default: now)
+    to: Option<i64>,
+    /// Output format (default: GeoJSON)
+    #[serde(default)]
+    format: TraceFormat,
+    /// Simplification tolerance in meters (default: 0, every loaded
+    /// position)
+    tolerance: Option<f64>,
+    /// Whether to snap the positions onto the road network (default: no)
+    #[serde(default)]
+    matched: bool,
+}
+
+/// A single recorded position of a trace.
+pub struct TracePoint {
+    /// Unix timestamp in seconds
+    pub timestamp: f64,
+    /// Latitude in degrees
+    pub latitude: f64,
+    /// Longitude in degrees
+    pub longitude: f64,
+    /// Speed in m/s
+    pub speed: f64,
+    /// OSM way ID of the road the position was snapped to, in map-matched
+    /// traces
+    pub road_id: Option<i64>,
+}
+
+/// Trace export endpoint handler.
+///
+/// Returns the positions recorded for the vehicle within `[from, to]`,
+/// ordered by time and downsampled as described in the module docs, as GPX
+/// or GeoJSON. Responds with 404 if no positions were recorded in the
+/// window.
+pub async fn get_trace(
+    State(state): State<Arc<AppState>>,
+    Path(vehicle_id): Path<String>,
+    Query(params): Query<TraceParams>,
+) -> Result<Response, StatusCode> {
+    let to = params.to.unwrap_or_else(|| chrono::Utc::now().timestamp());
+    let from = params.from.unwrap_or(to - DEFAULT_WINDOW_SECS);
+    if from > to {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut points = load_trace(&state, &vehicle_id, from, to).await.map_err(|e| {
+        error!("❌ Failed to load trace for {}: {}", vehicle_id, e);
+        StatusCode::SERVICE_UNAVAILABLE
+    })?;
+
+    if points.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    if params.matched {
+        match_trace(&state.graph, &mut points);
+    }
+    let tolerance_m = params.tolerance.unwrap_or(0.0).clamp(0.0, MAX_TRACE_TOLERANCE_M);
+    let points = simplify_trace(points, tolerance_m);
+
+    // Metadata is optional; an unregistered vehicle still has a trace
+    let vehicle = load_vehicle(&state.db, &vehicle_id).await.unwrap_or_else(|e| {
+        error!("❌ Failed to load metadata of {}: {}", vehicle_id, e);
+        None
+    });
+
+    let response = match params.format {
+        TraceFormat::Gpx => (
+            [(header::CONTENT_TYPE, "application/gpx+xml")],
+            to_gpx(&vehicle_id, vehicle.as_ref(), &points),
+        ).into_response(),
+        TraceFormat::Geojson => (
+            [(header::CONTENT_TYPE, "application/geo+json")],
+            to_geojson(&vehicle_id, vehicle.as_ref(), &points).to_string(),
+        ).into_response(),
+    };
+    Ok(response)
+}
+
+/// Loads a vehicle's positions within a time window, ordered by time.
+///
+/// Windows longer than [`MAX_TRACE_POINTS`] seconds keep the first
+/// position of every `window / MAX_TRACE_POINTS` seconds.
+pub async fn load_trace(
+    state: &AppState,
+    vehicle_id: &str,
+    from: i64,
+    to: i64,
+) -> Result<Vec<TracePoint>, sqlx::Error> {
+    // Positions carry whole seconds, so one-second buckets keep them all
+    let bucket_secs = ((to - from) as f64 / MAX_TRACE_POINTS as f64).max(1.0);
+    let rows = sqlx::query!(
+        r#"
+        SELECT DISTINCT ON (floor(extract(epoch FROM time)::float8 / $4::float8))
+               extract(epoch FROM time)::float8 AS "timestamp!",
+               latitude AS "latitude!",
+               longitude AS "longitude!",
+               speed
+        FROM vehicle_positions
+        WHERE vehicle_id = $1
+          AND time BETWEEN to_timestamp($2) AND to_timestamp($3)
+          AND latitude IS NOT NULL
+          AND longitude IS NOT NULL
+        ORDER BY floor(extract(epoch FROM time)::float8 / $4::float8), time
+        "#,
+        vehicle_id,
+        from as f64,
+        to as f64,
+        bucket_secs
+    )
+        .fetch_all(&state.db)
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| TracePoint {
+            timestamp: row.timestamp,
+            latitude: row.latitude,
+            longitude: row.longitude,
+            speed: row.speed.unwrap_or(0.0),
+            road_id: None,
+        })
+        .collect())
+}
+
+/// Snaps the positions of a trace onto the road network, in driving
+/// order so each position follows on from the road of the previous one.
+///
+/// Leaves the trace as recorded if the map is not loaded.
+fn match_trace(graph: &RoadGraph, points: &mut [TracePoint]) {
+    let trace: Vec<Point> = points.iter().map(|p| Point::new(p.longitude, p.latitude)).collect();
+    for (point, matched) in points.iter_mut().zip(graph.match_trace(&trace)) {
+        let [longitude, latitude] = matched.snapped;
+        point.longitude = longitude;
+        point.latitude = latitude;
+        point.road_id = Some(matched.road_id);
+    }
+}
+
+/// Drops the positions of a trace that deviate less than `tolerance_m`
+/// meters from the simplified track; 0 keeps every position.
+fn simplify_trace(points: Vec<TracePoint>, tolerance_m: f64) -> Vec<TracePoint> {
+    let track: Vec<DVec2> = points.iter().map(|p| DVec2::new(p.longitude, p.latitude)).collect();
+    let keep = simplify_polyline_indices(&track, tolerance_m);
+    if keep.len() == points.len() {
+        return points;
+    }
+
+    let mut keep = keep.into_iter().peekable();
+    points
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| keep.next_if_eq(index).is_some())
+        .map(|(_, point)| point)
+        .collect()
+}
+
+/// Renders a trace as a GPX 1.1 document with a single track segment.
+///
+/// The vehicle's metadata goes into the track description. GPX 1.1 has no
+/// speed element, so speeds go into the widely supported Garmin
+/// `TrackPointExtension` of each point.
+fn to_gpx(vehicle_id: &str, vehicle: Option<&Vehicle>, points: &[TracePoint]) -> String {
+    let mut gpx = String::new();
+    gpx.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    gpx.push_str(concat!(
+        "<gpx version=\"1.1\" creator=\"traffic-control-tower\" xmlns=\"http://www.topografix.com/GPX/1/1\"",
+        " xmlns:gpxtpx=\"http://www.garmin.com/xmlschemas/TrackPointExtension/v2\">\n",
+    ));
+    let _ = writeln!(gpx, "  <trk>\n    <name>{}</name>", xml_escape(vehicle_id));
+    if let Some(vehicle) = vehicle {
+        let _ = writeln!(gpx, "    <desc>{}</desc>", xml_escape(&vehicle.fields().summary()));
+    }
+    gpx.push_str("    <trkseg>\n");
+
+    for point in points {
+        let time = chrono::DateTime::from_timestamp(point.timestamp as i64, 0)
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default();
+        let _ = writeln!(
+            gpx,
+            "      <trkpt lat=\"{}\" lon=\"{}\"><time>{}</time><extensions><gpxtpx:TrackPointExtension>\
+             <gpxtpx:speed>{}</gpxtpx:speed></gpxtpx:TrackPointExtension></extensions></trkpt>",
+            point.latitude, point.longitude, time, point.speed
+        );
+    }
+
+    gpx.push_str("    </trkseg>\n  </trk>\n</gpx>\n");
+    gpx
+}
+
+/// Renders a trace as a GeoJSON Feature with a LineString geometry.
+///
+/// Per-point timestamps and speeds (and, for map-matched traces, road IDs)
+/// are kept as parallel arrays in the feature properties, next to the
+/// vehicle's metadata (`null` if the vehicle is not registered).
+fn to_geojson(vehicle_id: &str, vehicle: Option<&Vehicle>, points: &[TracePoint]) -> serde_json::Value {
+    let mut feature = serde_json::json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "LineString",
+            "coordinates": points.iter().map(|p| [p.longitude, p.latitude]).collect::<Vec<_>>(),
+        },
+        "properties": {
+            "vehicle_id": vehicle_id,
+            "vehicle": vehicle.map(Vehicle::fields),
+            "timestamps": points.iter().map(|p| p.timestamp).collect::<Vec<_>>(),
+            "speeds": points.iter().map(|p| p.speed).collect::<Vec<_>>(),
+        },
+    });
+    if points.iter().any(|p| p.road_id.is_some()) {
+        feature["properties"]["road_ids"] = points.iter().map(|p| p.road_id).collect();
+    }
+    feature
+}
+
+/// Escapes the XML special characters of a text node.
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
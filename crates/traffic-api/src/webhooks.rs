@@ -0,0 +1,85 @@
+//! Outgoing webhook notifications.
+//!
+//! Notifications are POSTed as JSON to every URL in `WEBHOOK_URLS`:
+//!
+//! ```json
+//! {"event": "digest", "sent_at": 1700000000.0, "data": { ... }}
+//! ```
+//!
+//! Delivery is best effort: failed endpoints are logged and skipped, so one
+//! unreachable receiver does not hold up the others.
+
+use serde::Serialize;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// Longest time one webhook request may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Envelope of every webhook notification.
+#[derive(Serialize)]
+struct Notification<'a, T: Serialize> {
+    /// Kind of notification, e.g. "digest"
+    event: &'a str,
+    /// Unix timestamp (seconds) of sending
+    sent_at: f64,
+    /// Event-specific payload
+    data: &'a T,
+}
+
+/// Sends notifications to the configured webhook endpoints.
+pub struct WebhookDispatcher {
+    client: reqwest::Client,
+    urls: Vec<String>,
+}
+
+impl WebhookDispatcher {
+    /// Creates a dispatcher for the given endpoint URLs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be initialized.
+    pub fn new(urls: &[&str]) -> reqwest::Result<Self> {
+        Ok(Self {
+            client: reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build()?,
+            urls: urls.iter().map(|url| url.to_string()).collect(),
+        })
+    }
+
+    /// Returns `true` if at least one endpoint is configured.
+    pub fn is_enabled(&self) -> bool {
+        !self.urls.is_empty()
+    }
+
+    /// Sends a notification to every endpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - Kind of notification
+    /// * `data` - Payload, sent as the `data` field
+    ///
+    /// # Returns
+    ///
+    /// The number of endpoints that accepted the notification (2xx).
+    pub async fn dispatch<T: Serialize>(&self, event: &str, data: &T) -> usize {
+        let sent_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64();
+        let notification = Notification { event, sent_at, data };
+
+        let mut delivered = 0;
+        for url in &self.urls {
+            let result = self
+                .client
+                .post(url)
+                .json(&notification)
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status);
+            match result {
+                Ok(_) => delivered += 1,
+                Err(e) => warn!("⚠️ Webhook {} to {} failed: {}", event, url, e),
+            }
+        }
+        info!("📣 Sent {} webhook to {}/{} endpoints", event, delivered, self.urls.len());
+        delivered
+    }
+}
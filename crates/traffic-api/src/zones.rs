@@ -0,0 +1,75 @@
+//! Live per-zone statistics.
+//!
+//! traffic-ingest aggregates vehicle counts and average speeds per named
+//! zone (e.g. district) every few seconds, stores the latest snapshot under
+//! `zones:stats` and publishes it to `zones:update`, which is forwarded to
+//! WebSocket clients as a `zone_stats` message.
+
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+use tracing::error;
+use crate::AppState;
+
+/// Redis key holding the latest per-zone snapshot, maintained by traffic-ingest.
+pub const ZONE_STATS_KEY: &str = "zones:stats";
+
+/// Redis channel on which traffic-ingest publishes per-zone snapshots.
+pub const ZONE_UPDATES_CHANNEL: &str = "zones:update";
+
+/// Redis channel on which traffic-ingest publishes `congestion.trend`
+/// events (rapid speed drops in a zone or on a road).
+pub const CONGESTION_TREND_CHANNEL: &str = "congestion:trend";
+
+/// Query parameters of the zones endpoint.
+#[derive(Deserialize)]
+pub struct ZoneParams {
+    /// Only return the zone with this official identifier (e.g. a district code)
+    id: Option<String>,
+}
+
+/// Returns the latest per-zone statistics.
+///
+/// The response is a JSON array of `{zone, id, vehicle_count, avg_speed}`
+/// objects (`id` only for zones loaded with an official identifier); it is
+/// empty if no zones are configured or ingest has not published recently.
+///
+/// # Errors
+///
+/// Returns `503 Service Unavailable` if Redis cannot be reached, and
+/// `500 Internal Server Error` if the stored snapshot is malformed.
+pub async fn get_zones(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ZoneParams>,
+) -> Result<Response, StatusCode> {
+    let stats: Option<String> = async {
+        let mut con = state.redis.get_multiplexed_async_connection().await?;
+        redis::cmd("GET").arg(ZONE_STATS_KEY).query_async(&mut con).await
+    }
+    .await
+    .map_err(|e: redis::RedisError| {
+        error!("❌ Failed to read zone statistics: {}", e);
+        StatusCode::SERVICE_UNAVAILABLE
+    })?;
+
+    let stats = stats.unwrap_or_else(|| "[]".to_string());
+    let Some(id) = params.id else {
+        return Ok(([(header::CONTENT_TYPE, "application/json")], stats).into_response());
+    };
+
+    let zones: Vec<Value> = serde_json::from_str(&stats).map_err(|e| {
+        error!("❌ Malformed zone statistics: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let matching: Vec<Value> = zones
+        .into_iter()
+        .filter(|zone| zone.get("id").and_then(Value::as_str) == Some(id.as_str()))
+        .collect();
+    Ok(Json(matching).into_response())
+}
@@ -0,0 +1,29 @@
+use thiserror::Error;
+
+// Custom Result type alias for convenient use across the client
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("Invalid URL: {0}")]
+    Url(String),
+
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("API returned {status}: {body}")]
+    Status { status: reqwest::StatusCode, body: String },
+
+    #[error("WebSocket error: {0}")]
+    WebSocket(Box<tokio_tungstenite::tungstenite::Error>),
+
+    #[error("Subscription closed: {0}")]
+    Disconnected(String),
+}
+
+// Unboxed, the WebSocket error would triple the size of every result
+impl From<tokio_tungstenite::tungstenite::Error> for ClientError {
+    fn from(e: tokio_tungstenite::tungstenite::Error) -> Self {
+        Self::WebSocket(Box::new(e))
+    }
+}
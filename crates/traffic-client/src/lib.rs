@@ -0,0 +1,51 @@
+//! Client for the traffic-api REST and WebSocket interfaces.
+//!
+//! Rust consumers of the API (dashboards, integrations, load generators)
+//! share this client instead of hand-rolling HTTP requests and WebSocket
+//! parsing:
+//!
+//! - [`Client`] wraps the REST endpoints with typed responses and bearer
+//!   authentication.
+//! - [`SubscriptionBuilder`] selects the live messages a consumer wants
+//!   from `/ws` and returns a [`Subscription`] that reconnects with
+//!   backoff when the connection drops.
+//! - [`LiveState`] reassembles the current picture (vehicles, zones, open
+//!   incidents) from a REST snapshot and the stream of deltas.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use traffic_client::{Client, LiveState};
+//!
+//! # async fn run() -> traffic_client::Result<()> {
+//! let client = Client::new("http://localhost:3000")?.with_token("tct_...");
+//! let mut state = LiveState::from_snapshot(&client).await?;
+//! let mut subscription = client.subscribe().vehicles().incidents().connect().await?;
+//!
+//! loop {
+//!     let message = subscription.next().await?;
+//!     state.apply(&message);
+//!     println!("{} vehicles live", state.vehicles().len());
+//! }
+//! # }
+//! ```
+
+// Error handling types
+pub mod error;
+pub use error::{ClientError, Result};
+
+// Messages pushed over the WebSocket
+pub mod messages;
+pub use messages::{CongestionTrend, Incident, MessageKind, ServerMessage, VehicleUpdate, ZoneStats};
+
+// REST endpoints
+pub mod rest;
+pub use rest::{Client, FleetState, Health, TracePoint};
+
+// Live subscriptions with reconnection
+pub mod subscription;
+pub use subscription::{Subscription, SubscriptionBuilder};
+
+// Snapshot and delta reassembly
+pub mod live;
+pub use live::LiveState;
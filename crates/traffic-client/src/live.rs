@@ -0,0 +1,126 @@
+//! Reassembly of the current picture from a snapshot and live deltas.
+//!
+//! Zone statistics arrive as complete snapshots and replace the previous
+//! ones; vehicle updates, incident changes and traffic light changes are
+//! deltas applied to what is known. The REST API offers snapshots of
+//! vehicles, zones and incidents to start from
+//! ([`LiveState::from_snapshot`]); vehicles that join later appear with
+//! their first update, and all disappear after [`LiveState::prune`] once
+//! they stop reporting.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use traffic_common::signals::SignalState;
+use crate::error::Result;
+use crate::messages::{Incident, ServerMessage, VehicleUpdate, ZoneStats};
+use crate::rest::Client;
+
+/// Current vehicles, zones and active incidents.
+#[derive(Debug, Clone, Default)]
+pub struct LiveState {
+    /// Latest update per vehicle ID and when it was received
+    vehicles: HashMap<String, (VehicleUpdate, Instant)>,
+    zones: Vec<ZoneStats>,
+    /// Incidents that are neither resolved, expired nor deleted, by ID
+    incidents: HashMap<i64, Incident>,
+    /// Latest lights per intersection (OSM node ID) that changed since the
+    /// start
+    signals: HashMap<i64, SignalState>,
+}
+
+impl LiveState {
+    /// Starts from the current vehicle positions, zone statistics and
+    /// active incidents.
+    ///
+    /// Subscribe before taking the snapshot and apply the messages
+    /// afterwards, so changes made in between are not lost; applying a
+    /// change twice is harmless.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any snapshot cannot be fetched.
+    pub async fn from_snapshot(client: &Client) -> Result<Self> {
+        let received = Instant::now();
+        let vehicles = client
+            .state(None)
+            .await?
+            .vehicles
+            .into_iter()
+            .map(|update| (update.id.clone(), (update, received)))
+            .collect();
+        let zones = client.zones().await?;
+        let incidents = client
+            .incidents(None)
+            .await?
+            .into_iter()
+            .filter(Incident::is_active)
+            .map(|incident| (incident.id, incident))
+            .collect();
+        Ok(Self {
+            vehicles,
+            zones,
+            incidents,
+            signals: HashMap::new(),
+        })
+    }
+
+    /// Applies a live message.
+    pub fn apply(&mut self, message: &ServerMessage) {
+        match message {
+            ServerMessage::Vehicle(update) => {
+                self.vehicles.insert(update.id.clone(), (update.clone(), Instant::now()));
+            }
+            ServerMessage::ZoneStats(zones) => self.zones = zones.clone(),
+            ServerMessage::Incident(incident) if incident.is_active() => {
+                self.incidents.insert(incident.id, incident.clone());
+            }
+            ServerMessage::Incident(incident) => {
+                self.incidents.remove(&incident.id);
+            }
+            ServerMessage::SignalState(signal) => {
+                self.signals.insert(signal.node_id, signal.clone());
+            }
+            ServerMessage::CongestionTrend(_) | ServerMessage::Other(_) => {}
+        }
+    }
+
+    /// Forgets vehicles without an update for longer than `max_age`.
+    ///
+    /// # Returns
+    ///
+    /// The number of vehicles removed.
+    pub fn prune(&mut self, max_age: Duration) -> usize {
+        let before = self.vehicles.len();
+        self.vehicles.retain(|_, (_, received)| received.elapsed() <= max_age);
+        before - self.vehicles.len()
+    }
+
+    /// Returns the latest update of a vehicle.
+    pub fn vehicle(&self, id: &str) -> Option<&VehicleUpdate> {
+        self.vehicles.get(id).map(|(update, _)| update)
+    }
+
+    /// Returns the latest update of every known vehicle, in no particular
+    /// order.
+    pub fn vehicles(&self) -> Vec<&VehicleUpdate> {
+        self.vehicles.values().map(|(update, _)| update).collect()
+    }
+
+    /// Returns the latest zone statistics.
+    pub fn zones(&self) -> &[ZoneStats] {
+        &self.zones
+    }
+
+    /// Returns the lights of an intersection, if they changed since the
+    /// start.
+    pub fn signal(&self, node_id: i64) -> Option<&SignalState> {
+        self.signals.get(&node_id)
+    }
+
+    /// Returns the active incidents, newest first.
+    pub fn incidents(&self) -> Vec<&Incident> {
+        let mut incidents: Vec<&Incident> = self.incidents.values().collect();
+        incidents.sort_by(|a, b| b.created_at.total_cmp(&a.created_at));
+        incidents
+    }
+}
@@ -0,0 +1,190 @@
+//! Messages pushed to WebSocket clients.
+//!
+//! The API forwards what the pipeline publishes without an envelope:
+//! vehicle updates are bare objects with an `id`, everything else carries
+//! a `type` (`zone_stats`, `congestion.trend`, `incident`, `signal_state`).
+//! [`ServerMessage::parse`] tells them apart; messages this version of the
+//! client does not know are kept as [`ServerMessage::Other`] so newer
+//! servers do not break older consumers.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use traffic_common::signals::SignalState;
+
+/// Kind of a live message, for selecting subscriptions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageKind {
+    Vehicle,
+    ZoneStats,
+    CongestionTrend,
+    Incident,
+    SignalState,
+    /// Messages of a type unknown to this client
+    Other,
+}
+
+/// Latest position and state of a vehicle.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VehicleUpdate {
+    /// Vehicle identifier
+    pub id: String,
+    pub lat: f64,
+    pub lon: f64,
+    /// Speed in m/s
+    pub speed: f64,
+    /// Heading in degrees clockwise from north
+    #[serde(default)]
+    pub heading: f64,
+    /// Acceleration in m/s²
+    #[serde(default)]
+    pub acceleration: f64,
+    #[serde(default)]
+    pub paused: bool,
+    /// Whether the simulation is still warming up
+    #[serde(default)]
+    pub warmup: bool,
+    /// Dispatch priority, e.g. "PRIORITY_EMERGENCY"
+    #[serde(default)]
+    pub priority: Option<String>,
+    /// Vehicle class, e.g. "VEHICLE_CLASS_TRUCK"
+    #[serde(default)]
+    pub vehicle_class: Option<String>,
+    #[serde(default)]
+    pub convoy_id: Option<String>,
+    /// Lane the vehicle drives in, counted from 1 at the leftmost lane
+    #[serde(default)]
+    pub lane: Option<u32>,
+    /// OSM way ID of the matched road
+    #[serde(default)]
+    pub road_id: Option<i64>,
+    /// District the vehicle is in
+    #[serde(default)]
+    pub district: Option<String>,
+    /// Fleet metadata of registered vehicles
+    #[serde(default)]
+    pub vehicle: Option<Value>,
+}
+
+/// Statistics of one zone.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ZoneStats {
+    /// Zone name
+    pub zone: String,
+    /// Official zone identifier, e.g. a district code
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Vehicles currently inside the zone
+    pub vehicle_count: usize,
+    /// Average speed of those vehicles in m/s
+    pub avg_speed: f64,
+}
+
+/// A rapid drop of the average speed of a zone or road.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CongestionTrend {
+    /// "zone" or "road"
+    pub scope: String,
+    /// Zone name or OSM way ID
+    pub target: String,
+    /// Street name of a road, if tagged
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Best average speed within the window, in m/s
+    pub baseline_speed: f64,
+    /// Current average speed, in m/s
+    pub current_speed: f64,
+    /// Relative drop from the baseline (e.g. 0.4 for 40 %)
+    pub drop: f64,
+    pub window_secs: u64,
+}
+
+/// An incident with its lifecycle; timestamps are Unix seconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Incident {
+    pub id: i64,
+    /// "incident" or "alert"
+    pub kind: String,
+    /// "info", "warning" or "critical"
+    pub severity: String,
+    pub title: String,
+    pub description: Option<String>,
+    /// OSM way ID of the affected road
+    pub road_id: Option<i64>,
+    pub longitude: Option<f64>,
+    pub latitude: Option<f64>,
+    /// "open", "acknowledged", "resolved" or "expired"
+    pub status: String,
+    pub created_at: f64,
+    pub created_by: String,
+    pub acknowledged_at: Option<f64>,
+    pub acknowledged_by: Option<String>,
+    pub resolved_at: Option<f64>,
+    pub resolved_by: Option<String>,
+    pub expires_at: Option<f64>,
+    pub deleted_at: Option<f64>,
+}
+
+impl Incident {
+    /// Returns `true` while the incident still needs attention: not
+    /// resolved, expired or deleted.
+    pub fn is_active(&self) -> bool {
+        self.deleted_at.is_none() && matches!(self.status.as_str(), "open" | "acknowledged")
+    }
+}
+
+/// A message received over the WebSocket.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerMessage {
+    Vehicle(VehicleUpdate),
+    /// Statistics of all zones; replaces the previous snapshot
+    ZoneStats(Vec<ZoneStats>),
+    CongestionTrend(CongestionTrend),
+    /// An incident was reported or changed state
+    Incident(Incident),
+    /// The lights of an intersection changed
+    SignalState(SignalState),
+    /// A message this client does not understand
+    Other(Value),
+}
+
+/// Zone snapshot as published by ingest.
+#[derive(Deserialize)]
+struct ZoneStatsMessage {
+    zones: Vec<ZoneStats>,
+}
+
+impl ServerMessage {
+    /// Parses a text frame.
+    ///
+    /// # Returns
+    ///
+    /// The typed message, [`ServerMessage::Other`] for unknown or
+    /// malformed messages of a known type, or `None` if the frame is not
+    /// JSON at all.
+    pub fn parse(text: &str) -> Option<Self> {
+        let value: Value = serde_json::from_str(text).ok()?;
+        let typed = match value.get("type").and_then(Value::as_str) {
+            None if value.get("id").is_some() => serde_json::from_value(value.clone()).map(Self::Vehicle).ok(),
+            Some("zone_stats") => serde_json::from_value::<ZoneStatsMessage>(value.clone())
+                .map(|message| Self::ZoneStats(message.zones))
+                .ok(),
+            Some("congestion.trend") => serde_json::from_value(value.clone()).map(Self::CongestionTrend).ok(),
+            Some("incident") => serde_json::from_value(value.clone()).map(Self::Incident).ok(),
+            Some("signal_state") => serde_json::from_value(value.clone()).map(Self::SignalState).ok(),
+            _ => None,
+        };
+        Some(typed.unwrap_or(Self::Other(value)))
+    }
+
+    /// Returns the kind of the message.
+    pub fn kind(&self) -> MessageKind {
+        match self {
+            Self::Vehicle(_) => MessageKind::Vehicle,
+            Self::ZoneStats(_) => MessageKind::ZoneStats,
+            Self::CongestionTrend(_) => MessageKind::CongestionTrend,
+            Self::Incident(_) => MessageKind::Incident,
+            Self::SignalState(_) => MessageKind::SignalState,
+            Self::Other(_) => MessageKind::Other,
+        }
+    }
+}
@@ -0,0 +1,264 @@
+//! Typed access to the REST endpoints.
+//!
+//! Responses that have a stable shape are deserialized into the types of
+//! [`crate::messages`]; the remaining endpoints are reachable through
+//! [`Client::get_json`] with a type of the caller's choosing.
+
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use traffic_common::map::{LoadProgress, MapMeta};
+use crate::error::{ClientError, Result};
+use crate::messages::{Incident, VehicleUpdate, ZoneStats};
+use crate::subscription::SubscriptionBuilder;
+
+/// Service status from `GET /health`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Health {
+    pub status: String,
+    pub map_loaded: bool,
+    pub total_roads: usize,
+    pub visible_roads: usize,
+    /// Source file and checksum of the loaded map
+    #[serde(default)]
+    pub map: Option<MapMeta>,
+    /// Progress of the map loader while the service starts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loading: Option<LoadProgress>,
+}
+
+/// A recorded position from `GET /vehicles/:id/trace`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TracePoint {
+    /// Unix timestamp in seconds
+    pub timestamp: f64,
+    pub lon: f64,
+    pub lat: f64,
+    /// Speed in m/s
+    pub speed: f64,
+}
+
+/// Positions of the whole fleet at one instant, from `GET /state`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FleetState {
+    /// Instant of the snapshot as a Unix timestamp
+    pub at: i64,
+    /// Whether the snapshot is the live one (`false`: reconstructed from
+    /// history)
+    pub live: bool,
+    /// Vehicles reporting at the instant, by ID; fields the snapshot does
+    /// not record (acceleration, lane, ...) have their defaults
+    pub vehicles: Vec<VehicleUpdate>,
+}
+
+/// Trace as served in GeoJSON: coordinates with per-point properties in
+/// parallel arrays.
+#[derive(Deserialize)]
+struct TraceFeature {
+    geometry: TraceGeometry,
+    properties: TraceProperties,
+}
+
+#[derive(Deserialize)]
+struct TraceGeometry {
+    coordinates: Vec<[f64; 2]>,
+}
+
+#[derive(Deserialize)]
+struct TraceProperties {
+    timestamps: Vec<f64>,
+    speeds: Vec<f64>,
+}
+
+/// Client for one traffic-api instance.
+///
+/// Cheap to clone; clones share the connection pool.
+#[derive(Debug, Clone)]
+pub struct Client {
+    base_url: Url,
+    token: Option<String>,
+    http: reqwest::Client,
+}
+
+impl Client {
+    /// Creates a client for the API at `base_url`, e.g.
+    /// `http://localhost:3000`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `base_url` is not an absolute HTTP(S) URL.
+    pub fn new(base_url: &str) -> Result<Self> {
+        let mut base_url = Url::parse(base_url).map_err(|e| ClientError::Url(format!("{}: {}", base_url, e)))?;
+        if !matches!(base_url.scheme(), "http" | "https") {
+            return Err(ClientError::Url(format!("{}: expected http or https", base_url)));
+        }
+        // Join paths below the base, not next to its last segment
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+        Ok(Self {
+            base_url,
+            token: None,
+            http: reqwest::Client::new(),
+        })
+    }
+
+    /// Authenticates all requests with an API key or JWT.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Starts building a live subscription on this API.
+    pub fn subscribe(&self) -> SubscriptionBuilder {
+        SubscriptionBuilder::new(self)
+    }
+
+    /// Returns the service status and the map it runs on.
+    pub async fn health(&self) -> Result<Health> {
+        self.get_json("health", &[]).await
+    }
+
+    /// Returns the latest statistics of all zones.
+    pub async fn zones(&self) -> Result<Vec<ZoneStats>> {
+        self.get_json("zones", &[]).await
+    }
+
+    /// Returns the incidents, newest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - Only incidents in this state, e.g. "open"
+    pub async fn incidents(&self, status: Option<&str>) -> Result<Vec<Incident>> {
+        let query: Vec<(&str, String)> = status.map(|status| ("status", status.to_string())).into_iter().collect();
+        self.get_json("incidents", &query).await
+    }
+
+    /// Returns the positions recorded for a vehicle, ordered by time; long
+    /// windows are thinned out to at most 10,000 positions.
+    ///
+    /// # Arguments
+    ///
+    /// * `vehicle_id` - Vehicle identifier
+    /// * `from` - Start of the window as a Unix timestamp (default: one
+    ///   hour before `to`)
+    /// * `to` - End of the window as a Unix timestamp (default: now)
+    /// * `tolerance_m` - Drop positions deviating less than this many meters
+    ///   from the simplified track (default: keep every position)
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClientError::Status`] with 404 if nothing was recorded in
+    /// the window.
+    pub async fn trace(
+        &self,
+        vehicle_id: &str,
+        from: Option<i64>,
+        to: Option<i64>,
+        tolerance_m: Option<f64>,
+    ) -> Result<Vec<TracePoint>> {
+        let mut query = vec![("format", "geojson".to_string())];
+        query.extend(from.map(|from| ("from", from.to_string())));
+        query.extend(to.map(|to| ("to", to.to_string())));
+        query.extend(tolerance_m.map(|tolerance| ("tolerance", tolerance.to_string())));
+        let path = format!("vehicles/{}/trace", urlencode(vehicle_id));
+        let trace: TraceFeature = self.get_json(&path, &query).await?;
+
+        Ok(trace
+            .geometry
+            .coordinates
+            .into_iter()
+            .zip(trace.properties.timestamps)
+            .zip(trace.properties.speeds)
+            .map(|(([lon, lat], timestamp), speed)| TracePoint { timestamp, lon, lat, speed })
+            .collect())
+    }
+
+    /// Returns the position of every reporting vehicle.
+    ///
+    /// # Arguments
+    ///
+    /// * `at` - Unix timestamp of a past instant to reconstruct from the
+    ///   recorded history (default: now)
+    pub async fn state(&self, at: Option<i64>) -> Result<FleetState> {
+        let query: Vec<(&str, String)> = at.map(|at| ("at", at.to_string())).into_iter().collect();
+        self.get_json("state", &query).await
+    }
+
+    /// Looks up details of roads by OSM way ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - OSM way IDs; unknown ways are left out of the response
+    /// * `fields` - Fields to return besides `id`, e.g. `["name", "speed_limit"]`;
+    ///   empty for all
+    pub async fn roads(&self, ids: &[i64], fields: &[&str]) -> Result<Vec<Value>> {
+        let mut query = vec![("ids", join(ids.iter()))];
+        if !fields.is_empty() {
+            query.push(("fields", join(fields.iter())));
+        }
+        self.get_json("roads", &query).await
+    }
+
+    /// Sends a GET request and deserializes the JSON response.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path relative to the base URL, e.g. `districts/stats`
+    /// * `query` - Query parameters
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClientError::Status`] with the response body for non-2xx
+    /// answers, and [`ClientError::Http`] if the request fails or the body
+    /// does not match `T`.
+    pub async fn get_json<T: DeserializeOwned>(&self, path: &str, query: &[(&str, String)]) -> Result<T> {
+        let url = self.url(path)?;
+        let mut request = self.http.get(url).query(query);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(ClientError::Status { status, body });
+        }
+        Ok(response.json().await?)
+    }
+
+    /// Returns the WebSocket URL of the live stream, with the token as
+    /// query parameter since WebSocket clients cannot always set headers.
+    pub(crate) fn ws_url(&self) -> Result<Url> {
+        let mut url = self.url("ws")?;
+        let scheme = if url.scheme() == "https" { "wss" } else { "ws" };
+        url.set_scheme(scheme).map_err(|_| ClientError::Url(format!("{}: cannot switch to {}", url, scheme)))?;
+        if let Some(token) = &self.token {
+            url.query_pairs_mut().append_pair("access_token", token);
+        }
+        Ok(url)
+    }
+
+    /// Resolves a path relative to the base URL.
+    fn url(&self, path: &str) -> Result<Url> {
+        self.base_url.join(path).map_err(|e| ClientError::Url(format!("{}: {}", path, e)))
+    }
+}
+
+/// Joins values into a comma-separated parameter.
+fn join<T: ToString>(values: impl Iterator<Item = T>) -> String {
+    values.map(|value| value.to_string()).collect::<Vec<_>>().join(",")
+}
+
+/// Percent-encodes a path segment.
+fn urlencode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
@@ -0,0 +1,271 @@
+//! Live subscriptions to `/ws` with automatic reconnection.
+//!
+//! The server pushes every live message to every client, so selection
+//! happens here: a [`SubscriptionBuilder`] names the message kinds,
+//! vehicles and area of interest, and the resulting [`Subscription`] only
+//! yields matching messages. When the connection drops it reconnects with
+//! exponential backoff; messages sent in the meantime are lost, which
+//! [`Subscription::reconnects`] lets consumers notice.
+
+use futures_util::StreamExt;
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tracing::{info, warn};
+use reqwest::Url;
+use traffic_common::map::BoundingBox;
+use traffic_common::VehicleClass;
+use crate::error::{ClientError, Result};
+use crate::messages::{MessageKind, ServerMessage};
+use crate::rest::Client;
+
+/// Initial delay before reconnecting.
+const DEFAULT_MIN_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Longest delay between reconnection attempts.
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Which live messages a subscription yields.
+#[derive(Debug, Clone, Default)]
+struct Filter {
+    /// Message kinds; empty for all
+    kinds: HashSet<MessageKind>,
+    /// Only updates of these vehicles
+    vehicle_ids: Option<HashSet<String>>,
+    /// Only updates of vehicles of these classes, by protobuf name
+    classes: Option<HashSet<String>>,
+    /// Only vehicle updates inside this area
+    bbox: Option<BoundingBox>,
+}
+
+impl Filter {
+    /// Returns `true` if the message is wanted.
+    fn matches(&self, message: &ServerMessage) -> bool {
+        if !self.kinds.is_empty() && !self.kinds.contains(&message.kind()) {
+            return false;
+        }
+        let ServerMessage::Vehicle(update) = message else { return true };
+        // Updates without a class come from producers predating classes,
+        // which only had cars
+        let class = update.vehicle_class.as_deref().unwrap_or(VehicleClass::Car.as_str_name());
+        self.vehicle_ids.as_ref().is_none_or(|ids| ids.contains(&update.id))
+            && self.classes.as_ref().is_none_or(|classes| classes.contains(class))
+            && self.bbox.is_none_or(|bbox| bbox.contains(update.lon, update.lat))
+    }
+}
+
+/// Builds a [`Subscription`].
+///
+/// Without any kind selected, all messages are yielded.
+///
+/// # Examples
+///
+/// ```no_run
+/// use traffic_client::Client;
+/// use traffic_common::map::BoundingBox;
+///
+/// # async fn run() -> traffic_client::Result<()> {
+/// let client = Client::new("http://localhost:3000")?;
+/// let mitte = BoundingBox { min_lon: 13.36, min_lat: 52.50, max_lon: 13.43, max_lat: 52.54 };
+/// let mut subscription = client.subscribe().vehicles().within(mitte).connect().await?;
+/// let update = subscription.next().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SubscriptionBuilder {
+    client: Client,
+    filter: Filter,
+    reconnect: bool,
+    min_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl SubscriptionBuilder {
+    /// Starts a subscription on the client's API, see [`Client::subscribe`].
+    pub fn new(client: &Client) -> Self {
+        Self {
+            client: client.clone(),
+            filter: Filter::default(),
+            reconnect: true,
+            min_backoff: DEFAULT_MIN_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+        }
+    }
+
+    /// Yields vehicle updates.
+    pub fn vehicles(self) -> Self {
+        self.kind(MessageKind::Vehicle)
+    }
+
+    /// Yields zone statistics snapshots.
+    pub fn zones(self) -> Self {
+        self.kind(MessageKind::ZoneStats)
+    }
+
+    /// Yields congestion trends.
+    pub fn trends(self) -> Self {
+        self.kind(MessageKind::CongestionTrend)
+    }
+
+    /// Yields incident changes.
+    pub fn incidents(self) -> Self {
+        self.kind(MessageKind::Incident)
+    }
+
+    /// Yields traffic light changes.
+    pub fn signals(self) -> Self {
+        self.kind(MessageKind::SignalState)
+    }
+
+    /// Yields messages of the given kind.
+    pub fn kind(mut self, kind: MessageKind) -> Self {
+        self.filter.kinds.insert(kind);
+        self
+    }
+
+    /// Yields updates of these vehicles only.
+    pub fn vehicle_ids<I, S>(mut self, ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.filter.vehicle_ids = Some(ids.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Yields updates of vehicles of these classes only.
+    pub fn classes<I>(mut self, classes: I) -> Self
+    where
+        I: IntoIterator<Item = VehicleClass>,
+    {
+        self.filter.classes = Some(classes.into_iter().map(|class| class.as_str_name().to_string()).collect());
+        self
+    }
+
+    /// Yields updates of vehicles inside this area only.
+    pub fn within(mut self, bbox: BoundingBox) -> Self {
+        self.filter.bbox = Some(bbox);
+        self
+    }
+
+    /// Sets whether to reconnect when the connection drops (default: yes).
+    pub fn reconnect(mut self, reconnect: bool) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+
+    /// Sets the delay before the first reconnection attempt and the
+    /// longest delay it doubles up to.
+    pub fn backoff(mut self, min: Duration, max: Duration) -> Self {
+        self.min_backoff = min;
+        self.max_backoff = max.max(min);
+        self
+    }
+
+    /// Connects to the live stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the first connection fails, so misconfigured
+    /// URLs and tokens surface immediately instead of being retried.
+    pub async fn connect(self) -> Result<Subscription> {
+        let url = self.client.ws_url()?;
+        let (socket, _) = tokio_tungstenite::connect_async(url.as_str()).await?;
+        info!("🔌 Subscribed to {}", redacted(&url));
+        Ok(Subscription {
+            url,
+            socket: Some(socket),
+            filter: self.filter,
+            reconnect: self.reconnect,
+            min_backoff: self.min_backoff,
+            max_backoff: self.max_backoff,
+            reconnects: 0,
+        })
+    }
+}
+
+/// A live stream of messages from the API.
+pub struct Subscription {
+    url: Url,
+    /// `None` while disconnected
+    socket: Option<Socket>,
+    filter: Filter,
+    reconnect: bool,
+    min_backoff: Duration,
+    max_backoff: Duration,
+    reconnects: u64,
+}
+
+impl Subscription {
+    /// Waits for the next matching message, reconnecting as needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClientError::Disconnected`] if the connection drops and
+    /// reconnection is disabled.
+    pub async fn next(&mut self) -> Result<ServerMessage> {
+        loop {
+            let Some(socket) = &mut self.socket else {
+                self.socket = Some(self.reconnect().await);
+                continue;
+            };
+
+            let reason = match socket.next().await {
+                Some(Ok(Message::Text(text))) => {
+                    match ServerMessage::parse(&text) {
+                        Some(message) if self.filter.matches(&message) => return Ok(message),
+                        Some(_) => {}
+                        None => warn!("⚠️ Ignoring non-JSON message: {}", text),
+                    }
+                    continue;
+                }
+                // Pings are answered by the socket on the next read
+                Some(Ok(Message::Close(frame))) => format!("closed by server ({:?})", frame),
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => e.to_string(),
+                None => "connection ended".to_string(),
+            };
+
+            self.socket = None;
+            if !self.reconnect {
+                return Err(ClientError::Disconnected(reason));
+            }
+            warn!("⚠️ Live stream lost: {}, reconnecting", reason);
+        }
+    }
+
+    /// Returns how often the connection was re-established; messages
+    /// sent while disconnected were missed.
+    pub fn reconnects(&self) -> u64 {
+        self.reconnects
+    }
+
+    /// Connects again, doubling the delay after every failed attempt.
+    async fn reconnect(&mut self) -> Socket {
+        let mut delay = self.min_backoff;
+        loop {
+            tokio::time::sleep(delay).await;
+            match tokio_tungstenite::connect_async(self.url.as_str()).await {
+                Ok((socket, _)) => {
+                    self.reconnects += 1;
+                    info!("🔌 Live stream reconnected");
+                    return socket;
+                }
+                Err(e) => {
+                    warn!("⚠️ Reconnection failed, retrying in {:?}: {}", delay, e);
+                    delay = (delay * 2).min(self.max_backoff);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the URL without the token, for logs.
+fn redacted(url: &Url) -> String {
+    format!("{}://{}{}", url.scheme(), url.host_str().unwrap_or_default(), url.path())
+}
@@ -0,0 +1,13 @@
+//! Build script for compiling the GTFS Realtime definitions.
+//!
+//! Generates Rust types for the subset of gtfs-realtime.proto used by the
+//! GTFS-RT feed adapter.
+
+fn main() {
+    prost_build::Config::new()
+        .compile_protos(
+            &["../../proto/gtfs-realtime.proto"],
+            &["../../proto/"],
+        )
+        .expect("Failed to compile protos");
+}
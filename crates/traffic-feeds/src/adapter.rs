@@ -0,0 +1,96 @@
+//! The feed adapter abstraction and the loop that drives adapters.
+
+use anyhow::Result;
+use prost::Message;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use std::future::Future;
+use std::time::Duration;
+use tracing::{error, info, warn};
+use traffic_common::VehiclePosition;
+
+/// Kafka topic carrying raw vehicle telemetry.
+pub const TELEMETRY_TOPIC: &str = "raw-telemetry";
+
+/// Pause before retrying a subscription that failed, in seconds.
+const RECONNECT_DELAY_SECS: u64 = 5;
+
+/// How an adapter obtains new data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedMode {
+    /// The source is a snapshot that must be fetched periodically
+    Poll {
+        /// Time between two fetches
+        interval: Duration,
+    },
+    /// The source pushes data; `next_batch` waits until some arrives
+    Subscribe,
+}
+
+/// A source of vehicle positions from outside the platform.
+///
+/// Adapters normalize whatever the source delivers into `VehiclePosition`
+/// messages: coordinates in degrees, speed in m/s, heading in degrees from
+/// north and timestamps in Unix seconds.
+pub trait FeedAdapter: Send {
+    /// Short name of the feed, used in logs.
+    fn name(&self) -> &str;
+
+    /// Whether the adapter is polled or streams data.
+    fn mode(&self) -> FeedMode;
+
+    /// Returns the next batch of positions.
+    ///
+    /// For polled feeds this fetches the current snapshot; for subscribed
+    /// feeds it waits until new positions arrive. An error ends the current
+    /// fetch or connection; the runner retries.
+    fn next_batch(&mut self) -> impl Future<Output = Result<Vec<VehiclePosition>>> + Send;
+}
+
+/// Runs an adapter forever, producing its positions to Kafka.
+///
+/// Fetch errors are logged and retried: polled feeds on their next interval,
+/// subscribed feeds after a short delay.
+///
+/// # Arguments
+///
+/// * `adapter` - Feed adapter to drive
+/// * `producer` - Kafka producer for the telemetry topic
+pub async fn run_adapter<A: FeedAdapter>(mut adapter: A, producer: FutureProducer) {
+    info!("📡 Starting feed adapter '{}' ({:?})", adapter.name(), adapter.mode());
+
+    loop {
+        match adapter.next_batch().await {
+            Ok(positions) => {
+                for position in &positions {
+                    publish(&producer, position);
+                }
+                if !positions.is_empty() {
+                    info!("📤 '{}': forwarded {} positions", adapter.name(), positions.len());
+                }
+            }
+            Err(e) => {
+                error!("❌ Feed '{}' failed: {:#}", adapter.name(), e);
+                if adapter.mode() == FeedMode::Subscribe {
+                    tokio::time::sleep(Duration::from_secs(RECONNECT_DELAY_SECS)).await;
+                }
+            }
+        }
+
+        if let FeedMode::Poll { interval } = adapter.mode() {
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
+
+/// Produces a position to the telemetry topic (fire and forget).
+fn publish(producer: &FutureProducer, position: &VehiclePosition) {
+    let payload = position.encode_to_vec();
+    let key = position.vehicle_id.clone();
+    let producer = producer.clone();
+    tokio::spawn(async move {
+        let record = FutureRecord::to(TELEMETRY_TOPIC).payload(&payload).key(&key);
+        if let Err((e, _)) = producer.send(record, Duration::from_secs(0)).await {
+            warn!("Failed to produce feed position for {}: {}", key, e);
+        }
+    });
+}
@@ -0,0 +1,100 @@
+//! JSON-over-HTTP position feeds.
+//!
+//! Many fleet and city APIs expose current positions as a JSON array. The
+//! adapter accepts the common field name variants (`lat`/`latitude`,
+//! `lon`/`lng`/`longitude`, ...), so most such feeds work without custom code.
+
+use anyhow::Result;
+use serde::Deserialize;
+use std::time::Duration;
+use traffic_common::VehiclePosition;
+use crate::adapter::{FeedAdapter, FeedMode};
+
+/// A position as found in typical JSON feeds.
+#[derive(Debug, Deserialize)]
+pub struct JsonPosition {
+    /// Vehicle identifier (string or number)
+    #[serde(alias = "vehicle_id", alias = "vehicleId")]
+    pub id: serde_json::Value,
+    /// Latitude in degrees
+    #[serde(alias = "latitude")]
+    pub lat: f64,
+    /// Longitude in degrees
+    #[serde(alias = "lng", alias = "longitude")]
+    pub lon: f64,
+    /// Speed in m/s
+    #[serde(default)]
+    pub speed: f64,
+    /// Heading in degrees clockwise from north
+    #[serde(default, alias = "bearing", alias = "course")]
+    pub heading: f64,
+    /// Unix timestamp in seconds (defaults to the time of the fetch)
+    #[serde(default, alias = "time", alias = "ts")]
+    pub timestamp: Option<i64>,
+}
+
+/// Polls an HTTP endpoint returning a JSON array of positions.
+pub struct JsonFeedAdapter {
+    client: reqwest::Client,
+    url: String,
+    interval: Duration,
+    id_prefix: String,
+}
+
+impl JsonFeedAdapter {
+    /// Creates an adapter for a feed URL.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - URL returning the JSON array
+    /// * `interval` - Time between two fetches
+    /// * `id_prefix` - Prefix added to vehicle IDs to keep feeds apart
+    pub fn new(url: String, interval: Duration, id_prefix: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+            interval,
+            id_prefix,
+        }
+    }
+}
+
+impl FeedAdapter for JsonFeedAdapter {
+    fn name(&self) -> &str {
+        &self.url
+    }
+
+    fn mode(&self) -> FeedMode {
+        FeedMode::Poll { interval: self.interval }
+    }
+
+    async fn next_batch(&mut self) -> Result<Vec<VehiclePosition>> {
+        let records: Vec<JsonPosition> = self.client
+            .get(&self.url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let now = chrono::Utc::now().timestamp();
+        Ok(records
+            .into_iter()
+            .map(|record| {
+                let id = match record.id {
+                    serde_json::Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                VehiclePosition {
+                    vehicle_id: format!("{}{}", self.id_prefix, id),
+                    latitude: record.lat,
+                    longitude: record.lon,
+                    speed: record.speed,
+                    heading: record.heading,
+                    timestamp: record.timestamp.unwrap_or(now),
+                    ..Default::default()
+                }
+            })
+            .collect())
+    }
+}
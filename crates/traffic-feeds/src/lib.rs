@@ -0,0 +1,26 @@
+//! Adapters for external real-time vehicle feeds.
+//!
+//! Each [`FeedAdapter`] pulls positions from an outside source (a city's
+//! GTFS-Realtime endpoint, a JSON API, a raw NMEA stream), normalizes them
+//! into [`VehiclePosition`](traffic_common::VehiclePosition) and hands them
+//! to [`run_adapter`], which produces them to the same Kafka topic the
+//! simulator writes to. Downstream services can't tell real and simulated
+//! vehicles apart.
+
+// FeedAdapter trait and the adapter runner
+pub mod adapter;
+pub use adapter::{run_adapter, FeedAdapter, FeedMode};
+
+// GTFS-Realtime vehicle positions
+pub mod gtfs_rt;
+
+// JSON-over-HTTP position feeds
+pub mod json;
+
+// NMEA 0183 sentence streams
+pub mod nmea;
+
+/// Generated GTFS Realtime protobuf types.
+pub mod transit_realtime {
+    include!(concat!(env!("OUT_DIR"), "/transit_realtime.rs"));
+}
@@ -0,0 +1,74 @@
+//! Traffic Feeds - bridges an external real-time vehicle feed into Kafka.
+//!
+//! Runs a single adapter selected through environment variables:
+//!
+//! - `FEED_KIND`: `gtfs-rt`, `json` or `nmea`
+//! - `FEED_URL`: feed URL (`gtfs-rt`, `json`) or `host:port` (`nmea`)
+//! - `FEED_POLL_INTERVAL_SECS`: fetch interval of polled feeds (default: 10)
+//! - `FEED_ID_PREFIX`: prefix for vehicle IDs of polled feeds (default: none)
+//! - `FEED_VEHICLE_ID`: vehicle ID of an NMEA stream (default: "nmea_1")
+//!
+//! Kafka settings come from the shared configuration (`KAFKA_BROKERS`).
+
+use anyhow::{Context, Result};
+use rdkafka::producer::FutureProducer;
+use serde::Deserialize;
+use std::time::Duration;
+use traffic_common::{init_tracing, Config};
+use traffic_feeds::gtfs_rt::GtfsRealtimeAdapter;
+use traffic_feeds::json::JsonFeedAdapter;
+use traffic_feeds::nmea::NmeaAdapter;
+use traffic_feeds::run_adapter;
+
+/// Supported feed types.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum FeedKind {
+    GtfsRt,
+    Json,
+    Nmea,
+}
+
+/// Feed selection, read from `FEED_*` environment variables.
+#[derive(Debug, Deserialize)]
+struct FeedConfig {
+    kind: FeedKind,
+    url: String,
+    #[serde(default = "default_poll_interval_secs")]
+    poll_interval_secs: u64,
+    #[serde(default)]
+    id_prefix: String,
+    #[serde(default = "default_vehicle_id")]
+    vehicle_id: String,
+}
+
+fn default_poll_interval_secs() -> u64 {
+    10
+}
+
+fn default_vehicle_id() -> String {
+    "nmea_1".to_string()
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    init_tracing("traffic-feeds");
+    let config = Config::from_env()?;
+    let feed: FeedConfig = envy::prefixed("FEED_")
+        .from_env()
+        .context("Failed to load feed configuration from FEED_* variables")?;
+
+    let producer: FutureProducer = config
+        .kafka_client()
+        .set("message.timeout.ms", "5000")
+        .create()?;
+
+    let interval = Duration::from_secs(feed.poll_interval_secs.max(1));
+    match feed.kind {
+        FeedKind::GtfsRt => run_adapter(GtfsRealtimeAdapter::new(feed.url, interval, feed.id_prefix), producer).await,
+        FeedKind::Json => run_adapter(JsonFeedAdapter::new(feed.url, interval, feed.id_prefix), producer).await,
+        FeedKind::Nmea => run_adapter(NmeaAdapter::new(feed.url, feed.vehicle_id), producer).await,
+    }
+
+    Ok(())
+}
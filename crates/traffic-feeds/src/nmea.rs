@@ -0,0 +1,140 @@
+//! NMEA 0183 position streams.
+//!
+//! GPS trackers and AVL gateways commonly emit NMEA sentences over a TCP
+//! socket. Each stream carries a single vehicle; positions are taken from
+//! `RMC` (recommended minimum) sentences, all other sentences are ignored.
+
+use anyhow::{bail, Context, Result};
+use chrono::{NaiveDate, NaiveTime};
+use tokio::io::{AsyncBufReadExt, BufReader, Lines};
+use tokio::net::TcpStream;
+use traffic_common::VehiclePosition;
+use crate::adapter::{FeedAdapter, FeedMode};
+
+/// Meters per second in one knot.
+const KNOTS_TO_MPS: f64 = 0.514_444;
+
+/// Subscribes to a TCP stream of NMEA sentences from one vehicle.
+pub struct NmeaAdapter {
+    address: String,
+    vehicle_id: String,
+    lines: Option<Lines<BufReader<TcpStream>>>,
+}
+
+impl NmeaAdapter {
+    /// Creates an adapter for an NMEA stream.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - `host:port` of the NMEA TCP server
+    /// * `vehicle_id` - ID assigned to the vehicle carrying the receiver
+    pub fn new(address: String, vehicle_id: String) -> Self {
+        Self { address, vehicle_id, lines: None }
+    }
+}
+
+impl FeedAdapter for NmeaAdapter {
+    fn name(&self) -> &str {
+        &self.address
+    }
+
+    fn mode(&self) -> FeedMode {
+        FeedMode::Subscribe
+    }
+
+    async fn next_batch(&mut self) -> Result<Vec<VehiclePosition>> {
+        if self.lines.is_none() {
+            let stream = TcpStream::connect(&self.address).await
+                .with_context(|| format!("Could not connect to {}", self.address))?;
+            self.lines = Some(BufReader::new(stream).lines());
+        }
+        let Some(lines) = self.lines.as_mut() else { return Ok(Vec::new()) };
+
+        loop {
+            let line = match lines.next_line().await {
+                Ok(Some(line)) => line,
+                Ok(None) => {
+                    self.lines = None;
+                    bail!("NMEA stream closed");
+                }
+                Err(e) => {
+                    self.lines = None;
+                    return Err(e.into());
+                }
+            };
+
+            if let Some(mut position) = parse_rmc(&line) {
+                position.vehicle_id = self.vehicle_id.clone();
+                return Ok(vec![position]);
+            }
+        }
+    }
+}
+
+/// Parses an `RMC` sentence into a position without a vehicle ID.
+///
+/// Returns `None` for other sentence types, sentences with a bad checksum,
+/// fixes flagged as invalid and malformed fields.
+///
+/// # Examples
+///
+/// ```
+/// use traffic_feeds::nmea::parse_rmc;
+///
+/// let position = parse_rmc("$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W").unwrap();
+/// assert!((position.latitude - 48.1173).abs() < 1e-4);
+///
+/// // Truncated or non-ASCII coordinates from a broken feed are skipped
+/// assert!(parse_rmc("$GPRMC,123519,A,4,N,01131.000,E,022.4,084.4,230394").is_none());
+/// assert!(parse_rmc("$GPRMC,123519,A,4é07.038,N,01131.000,E,022.4,084.4,230394").is_none());
+/// ```
+pub fn parse_rmc(sentence: &str) -> Option<VehiclePosition> {
+    let body = sentence.trim().strip_prefix('$')?;
+    let body = match body.split_once('*') {
+        Some((body, checksum)) => {
+            let expected = u8::from_str_radix(checksum, 16).ok()?;
+            if body.bytes().fold(0, |acc, b| acc ^ b) != expected {
+                return None;
+            }
+            body
+        }
+        None => body,
+    };
+
+    let fields: Vec<&str> = body.split(',').collect();
+    if fields.len() < 10 || !fields[0].ends_with("RMC") || fields[2] != "A" {
+        return None;
+    }
+
+    let latitude = parse_coordinate(fields[3], fields[4], 2)?;
+    let longitude = parse_coordinate(fields[5], fields[6], 3)?;
+    let speed = fields[7].parse::<f64>().unwrap_or(0.0) * KNOTS_TO_MPS;
+    let heading = fields[8].parse::<f64>().unwrap_or(0.0);
+
+    let time = NaiveTime::parse_from_str(fields[1], "%H%M%S%.f").ok()?;
+    let date = NaiveDate::parse_from_str(fields[9], "%d%m%y").ok()?;
+
+    Some(VehiclePosition {
+        latitude,
+        longitude,
+        speed,
+        heading,
+        timestamp: date.and_time(time).and_utc().timestamp(),
+        ..Default::default()
+    })
+}
+
+/// Converts an NMEA `(d)ddmm.mmmm` coordinate and hemisphere to degrees.
+///
+/// Returns `None` for short or malformed values instead of panicking, since
+/// feeds are untrusted; splitting inside a multi-byte character fails too.
+fn parse_coordinate(value: &str, hemisphere: &str, degree_digits: usize) -> Option<f64> {
+    let degrees: f64 = value.get(..degree_digits)?.parse().ok()?;
+    let minutes: f64 = value.get(degree_digits..)?.parse().ok()?;
+    let coordinate = degrees + minutes / 60.0;
+    match hemisphere {
+        "N" | "E" => Some(coordinate),
+        "S" | "W" => Some(-coordinate),
+        _ => None,
+    }
+}
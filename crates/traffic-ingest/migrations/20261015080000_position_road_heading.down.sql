@@ -0,0 +1,8 @@
+-- position_road_heading.down.sql
+
+DROP INDEX IF EXISTS idx_road_id;
+
+ALTER TABLE vehicle_positions
+    DROP COLUMN IF EXISTS ingest_latency_ms,
+    DROP COLUMN IF EXISTS road_id,
+    DROP COLUMN IF EXISTS heading;
@@ -0,0 +1,10 @@
+-- position_road_heading.up.sql
+-- Heading and matched road as seen by ingest, and how long the position
+-- took from the producer to ingest
+
+ALTER TABLE vehicle_positions
+    ADD COLUMN IF NOT EXISTS heading DOUBLE PRECISION,
+    ADD COLUMN IF NOT EXISTS road_id BIGINT,
+    ADD COLUMN IF NOT EXISTS ingest_latency_ms DOUBLE PRECISION;
+
+CREATE INDEX IF NOT EXISTS idx_road_id ON vehicle_positions (road_id, time DESC) WHERE road_id IS NOT NULL;
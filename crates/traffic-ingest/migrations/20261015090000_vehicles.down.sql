@@ -0,0 +1,3 @@
+-- vehicles.down.sql
+
+DROP TABLE IF EXISTS vehicles;
@@ -0,0 +1,15 @@
+-- vehicles.up.sql
+-- Static metadata of registered vehicles, keyed by the ID they report
+-- telemetry under
+
+CREATE TABLE IF NOT EXISTS vehicles (
+    vehicle_id TEXT PRIMARY KEY,
+    license_plate TEXT,
+    operator TEXT,
+    vehicle_type TEXT,
+    capacity INTEGER CHECK (capacity >= 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_vehicles_operator ON vehicles (operator);
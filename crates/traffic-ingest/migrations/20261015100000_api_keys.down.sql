@@ -0,0 +1,3 @@
+-- api_keys.down.sql
+
+DROP TABLE IF EXISTS api_keys;
@@ -0,0 +1,18 @@
+-- audit_log.up.sql
+-- Every admin, control, dispatch and registry call made through the API:
+-- who did what and when, and what the changed value was before
+
+CREATE TABLE IF NOT EXISTS audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    time TIMESTAMPTZ NOT NULL DEFAULT now(),
+    actor TEXT NOT NULL,
+    role TEXT,
+    action TEXT NOT NULL,
+    status SMALLINT NOT NULL,
+    target TEXT,
+    value JSONB,
+    previous JSONB
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_time ON audit_log (time DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_target ON audit_log (target, time DESC) WHERE target IS NOT NULL;
@@ -0,0 +1,3 @@
+-- incidents.down.sql
+
+DROP TABLE IF EXISTS incidents;
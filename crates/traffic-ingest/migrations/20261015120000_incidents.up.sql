@@ -0,0 +1,27 @@
+-- incidents.up.sql
+-- Incidents and alerts with their lifecycle (open -> acknowledged ->
+-- resolved, or expired once their TTL runs out); deleted rows are kept
+-- with deleted_at set
+
+CREATE TABLE IF NOT EXISTS incidents (
+    id BIGSERIAL PRIMARY KEY,
+    kind TEXT NOT NULL CHECK (kind IN ('incident', 'alert')),
+    severity TEXT NOT NULL CHECK (severity IN ('info', 'warning', 'critical')),
+    title TEXT NOT NULL,
+    description TEXT,
+    road_id BIGINT,
+    longitude DOUBLE PRECISION,
+    latitude DOUBLE PRECISION,
+    status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'acknowledged', 'resolved', 'expired')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    created_by TEXT NOT NULL,
+    acknowledged_at TIMESTAMPTZ,
+    acknowledged_by TEXT,
+    resolved_at TIMESTAMPTZ,
+    resolved_by TEXT,
+    expires_at TIMESTAMPTZ,
+    deleted_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_incidents_active ON incidents (expires_at)
+    WHERE status IN ('open', 'acknowledged') AND deleted_at IS NULL;
@@ -0,0 +1,6 @@
+-- position_district.down.sql
+
+DROP INDEX IF EXISTS idx_district;
+
+ALTER TABLE vehicle_positions
+    DROP COLUMN IF EXISTS district;
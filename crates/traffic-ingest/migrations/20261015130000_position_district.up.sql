@@ -0,0 +1,7 @@
+-- position_district.up.sql
+-- Administrative district each position lies in, as tagged by ingest
+
+ALTER TABLE vehicle_positions
+    ADD COLUMN IF NOT EXISTS district TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_district ON vehicle_positions (district, time DESC) WHERE district IS NOT NULL;
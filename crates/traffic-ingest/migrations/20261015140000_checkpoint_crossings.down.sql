@@ -0,0 +1,3 @@
+-- checkpoint_crossings.down.sql
+
+DROP TABLE IF EXISTS checkpoint_crossings;
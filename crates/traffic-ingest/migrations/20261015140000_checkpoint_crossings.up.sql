@@ -0,0 +1,14 @@
+-- checkpoint_crossings.up.sql
+-- Vehicles passing virtual checkpoints (toll gantries, ANPR cameras)
+
+CREATE TABLE IF NOT EXISTS checkpoint_crossings (
+    time TIMESTAMPTZ NOT NULL,
+    checkpoint_id TEXT NOT NULL,
+    vehicle_id TEXT NOT NULL,
+    way_id BIGINT NOT NULL,
+    direction TEXT NOT NULL CHECK (direction IN ('forward', 'backward'))
+);
+
+SELECT create_hypertable('checkpoint_crossings', 'time', if_not_exists => TRUE);
+
+CREATE INDEX IF NOT EXISTS idx_checkpoint_crossings ON checkpoint_crossings (checkpoint_id, time DESC);
@@ -0,0 +1,4 @@
+-- position_match_confidence.down.sql
+
+ALTER TABLE vehicle_positions
+    DROP COLUMN IF EXISTS match_confidence;
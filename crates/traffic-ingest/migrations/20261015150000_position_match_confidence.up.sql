@@ -0,0 +1,5 @@
+-- position_match_confidence.up.sql
+-- Confidence of the map match that set road_id, from 0 to 1
+
+ALTER TABLE vehicle_positions
+    ADD COLUMN IF NOT EXISTS match_confidence DOUBLE PRECISION;
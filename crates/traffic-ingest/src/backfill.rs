@@ -0,0 +1,214 @@
+//! Backfill of TimescaleDB from Kafka (`traffic-ingest backfill`).
+//!
+//! Re-reads the telemetry of a time window from the topic (as far as Kafka
+//! still retains it) and writes it to `vehicle_positions`, e.g. after the
+//! database was restored from an older backup. Only the cold path is
+//! replayed: Redis holds live state only and is left alone, except that
+//! the API is told to drop its cached analytics once rows were written
+//! (`analytics:invalidate`). Positions are
+//! map-matched like in a live run, so their `road_id` is filled in; their
+//! ingest latency is unknown and left empty.
+//!
+//! The backfill consumes in its own consumer group and commits nothing, so
+//! the offsets of the running ingest service are never touched. It stops
+//! at the end of the window, or at the end of each partition as it was
+//! when the backfill started.
+
+use anyhow::{bail, Context, Result};
+use futures::StreamExt;
+use geo::Point;
+use prost::Message as ProstMessage;
+use rdkafka::consumer::{Consumer, StreamConsumer};
+use rdkafka::{Message, Offset, TopicPartitionList};
+use sqlx::postgres::PgPoolOptions;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::signal;
+use traffic_common::geo::CoordinateCounters;
+use traffic_common::units::validate_speed;
+use traffic_common::startup::{wait_for_kafka, wait_for_postgres};
+use traffic_common::{Config, VehiclePosition};
+use crate::batch::{BatchLimits, BatchWriter, PartitionKey, PositionRow};
+use crate::cli::BackfillArgs;
+use crate::consumer::TELEMETRY_TOPIC;
+use crate::{load_districts, load_graph};
+
+/// Consumer group of backfills, separate from the real ingest group.
+const BACKFILL_GROUP_ID: &str = "ingest-backfill";
+
+/// Positions per database transaction.
+const BACKFILL_BATCH_SIZE: usize = 1000;
+
+/// Longest wait for topic metadata and offset lookups.
+const KAFKA_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Positions between two progress log lines.
+const PROGRESS_INTERVAL: u64 = 10_000;
+
+/// Redis channel on which the API is told to drop cached analytics
+/// responses; `*` invalidates all of them.
+const ANALYTICS_INVALIDATE_CHANNEL: &str = "analytics:invalidate";
+
+/// Writes the telemetry of a time window from Kafka to TimescaleDB.
+///
+/// # Errors
+///
+/// Returns an error if the window is inverted, already has rows in
+/// TimescaleDB (unless `--allow-duplicates` is given), or Kafka or
+/// TimescaleDB fail.
+pub async fn run(config: &Config, args: &BackfillArgs) -> Result<()> {
+    if let Some(to) = args.to {
+        if to <= args.from {
+            bail!("--from must be before --to");
+        }
+    }
+
+    wait_for_kafka(&config.kafka_client(), config.startup_timeout()).await?;
+    wait_for_postgres(&config.postgres_url, config.startup_timeout()).await?;
+
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&config.postgres_url)
+        .await
+        .context("Failed to connect to Postgres")?;
+
+    // The table has no unique key, so writing a window twice duplicates it
+    let existing = sqlx::query_scalar!(
+        r#"
+        SELECT count(*) AS "count!"
+        FROM vehicle_positions
+        WHERE time >= to_timestamp($1) AND ($2::float8 IS NULL OR time <= to_timestamp($2))
+        "#,
+        args.from as f64,
+        args.to.map(|to| to as f64)
+    )
+    .fetch_one(&pool)
+    .await?;
+    if existing > 0 && !args.allow_duplicates {
+        bail!(
+            "vehicle_positions already has {} rows in the window; pass --allow-duplicates to write anyway",
+            existing
+        );
+    }
+
+    let consumer: StreamConsumer = config
+        .kafka_client()
+        .set("group.id", BACKFILL_GROUP_ID)
+        .set("enable.auto.commit", "false")
+        .create()
+        .context("Failed to create Kafka consumer")?;
+
+    // Offset of the first message at or after `--from`, and the end of each
+    // partition at startup, so messages produced meanwhile are left to ingest
+    let metadata = consumer.fetch_metadata(Some(TELEMETRY_TOPIC), KAFKA_TIMEOUT)?;
+    let Some(topic) = metadata.topics().iter().find(|t| t.name() == TELEMETRY_TOPIC) else {
+        bail!("Topic {} does not exist", TELEMETRY_TOPIC);
+    };
+    let mut times = TopicPartitionList::new();
+    for partition in topic.partitions() {
+        times.add_partition_offset(TELEMETRY_TOPIC, partition.id(), Offset::Offset(args.from * 1000))?;
+    }
+    let starts = consumer.offsets_for_times(times, KAFKA_TIMEOUT)?;
+
+    let mut assignment = TopicPartitionList::new();
+    let mut ends: HashMap<i32, i64> = HashMap::new();
+    for element in starts.elements() {
+        let (_, high) = consumer.fetch_watermarks(TELEMETRY_TOPIC, element.partition(), KAFKA_TIMEOUT)?;
+        if let Offset::Offset(start) = element.offset() {
+            if start < high {
+                assignment.add_partition_offset(TELEMETRY_TOPIC, element.partition(), Offset::Offset(start))?;
+                ends.insert(element.partition(), high);
+            }
+        }
+    }
+    if ends.is_empty() {
+        tracing::info!("✅ Nothing to backfill: Kafka holds no telemetry after {}", args.from);
+        return Ok(());
+    }
+    consumer.assign(&assignment)?;
+    tracing::info!("⏮️ Backfilling {} partitions of {} from {}", ends.len(), TELEMETRY_TOPIC, args.from);
+
+    let graph = load_graph(config)?;
+    let districts = load_districts(config)?;
+    let writer = BatchWriter::new(vec![pool], BatchLimits::fixed(BACKFILL_BATCH_SIZE));
+    let mut last_matches: HashMap<String, (Point, usize)> = HashMap::new();
+    let mut coordinates = CoordinateCounters::default();
+    let mut written: u64 = 0;
+    let mut stream = consumer.stream();
+
+    let completed = tokio::select! {
+        result = async {
+            while !ends.is_empty() {
+                let Some(msg) = stream.next().await else { break };
+                let msg = msg?;
+                let partition = msg.partition();
+                let Some(&end) = ends.get(&partition) else { continue };
+
+                let past_window = args.to.is_some_and(|to| msg.timestamp().to_millis().is_some_and(|ms| ms > to * 1000));
+                if msg.offset() >= end || past_window {
+                    ends.remove(&partition);
+                    continue;
+                }
+
+                if let Some(mut position) = msg.payload().and_then(|payload| VehiclePosition::decode(payload).ok()) {
+                    let valid = coordinates.check(position.longitude, position.latitude).ok()
+                        .filter(|_| validate_speed(position.speed).is_ok());
+                    if let Some((lon, lat)) = valid {
+                        position.longitude = lon;
+                        position.latitude = lat;
+                        let point = Point::new(lon, lat);
+                        let previous = last_matches.get(&position.vehicle_id).copied();
+                        let matched = graph.match_point(point, previous.map(|p| p.0), previous.map(|p| p.1));
+                        if let Some(matched) = &matched {
+                            last_matches.insert(position.vehicle_id.clone(), (point, matched.edge));
+                        }
+
+                        let key: PartitionKey = (TELEMETRY_TOPIC.to_string(), partition);
+                        let district = districts.district_of(lon, lat).map(|zone| zone.name.clone());
+                        let row = PositionRow {
+                            position,
+                            road_id: matched.map(|m| m.road_id),
+                            match_confidence: matched.map(|m| m.confidence),
+                            district,
+                            ingest_latency_ms: None,
+                        };
+                        writer.add(&key, msg.offset(), row).await?;
+                        written += 1;
+                        if written.is_multiple_of(PROGRESS_INTERVAL) {
+                            tracing::info!("⏮️ {} positions backfilled", written);
+                        }
+                    }
+                }
+                if msg.offset() + 1 >= end {
+                    ends.remove(&partition);
+                }
+            }
+            Ok::<_, anyhow::Error>(())
+        } => {
+            result?;
+            true
+        },
+        _ = signal::ctrl_c() => false,
+    };
+
+    writer.flush().await?;
+    if written > 0 {
+        if let Err(e) = invalidate_analytics(config).await {
+            tracing::warn!("⚠️ Could not invalidate cached analytics, the API may serve them until they expire: {}", e);
+        }
+    }
+    if completed {
+        tracing::info!("✅ Backfill finished: {} positions written ({:?})", written, coordinates);
+    } else {
+        tracing::warn!("⚠️ Backfill interrupted after {} positions; the window is only partly written", written);
+    }
+    Ok(())
+}
+
+/// Tells the API instances that the history behind their cached analytics
+/// changed.
+async fn invalidate_analytics(config: &Config) -> Result<()> {
+    let mut con = config.redis_client()?.get_multiplexed_async_connection().await?;
+    redis::cmd("PUBLISH").arg(ANALYTICS_INVALIDATE_CHANNEL).arg("*").query_async::<_, ()>(&mut con).await?;
+    Ok(())
+}
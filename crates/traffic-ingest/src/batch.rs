@@ -1,70 +1,327 @@
+use futures::future::try_join_all;
 use sqlx::PgPool;
 use traffic_common::{VehiclePosition, Result};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 
-pub struct BatchWriter {
-    pool: PgPool,
-    buffer: Arc<Mutex<Vec<VehiclePosition>>>,
+// Flush interval as a multiple of the recent flush latency: the database
+// spends at most about a quarter of the time writing a partition's batches
+const INTERVAL_PER_FLUSH_LATENCY: u32 = 4;
+
+// Weight of the newest sample in the throughput and latency averages
+const SMOOTHING: f64 = 0.3;
+
+// Throughput is measured over windows of this length
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+// Kafka (topic, partition) a buffer belongs to
+pub type PartitionKey = (String, i32);
+
+// Next offset to commit for a partition after a flush
+pub type CommitOffset = (PartitionKey, i64);
+
+// A position together with what ingest derived from it, as stored in TimescaleDB
+#[derive(Debug, Clone)]
+pub struct PositionRow {
+    pub position: VehiclePosition,
+    // OSM way the position was matched onto, if any
+    pub road_id: Option<i64>,
+    // Confidence of the match that set road_id, from 0 to 1
+    pub match_confidence: Option<f64>,
+    // Administrative district the position lies in, if districts are configured
+    pub district: Option<String>,
+    // Milliseconds from the producer sending the position to ingest receiving it
+    pub ingest_latency_ms: Option<f64>,
+}
+
+// Buffered positions of one partition
+#[derive(Default)]
+struct PartitionBuffer {
+    positions: Vec<PositionRow>,
+    // Offset after the last message seen, if not yet committed
+    pending_offset: Option<i64>,
+    // When the oldest buffered position or skipped message arrived
+    since: Option<Instant>,
+}
+
+// Range the batch size and flush interval adapt within
+#[derive(Debug, Clone, Copy)]
+pub struct BatchLimits {
+    pub min_size: usize,
+    pub max_size: usize,
+    pub min_interval: Duration,
+    pub max_interval: Duration,
+}
+
+impl BatchLimits {
+    // Constant batch size, flushed by size only
+    pub fn fixed(size: usize) -> Self {
+        Self {
+            min_size: size,
+            max_size: size,
+            min_interval: Duration::MAX,
+            max_interval: Duration::MAX,
+        }
+    }
+}
+
+// Current batch size and flush interval, derived from the measured load.
+//
+// A batch is flushed when it is full or its oldest position has waited for
+// the flush interval. The interval follows the recent flush latency: a slow
+// database gets fewer, larger batches. The size is what one partition
+// receives within an interval, so at low traffic batches are flushed by
+// time (low latency) and during bursts by size (efficient large inserts).
+#[derive(Debug)]
+struct BatchTuning {
+    limits: BatchLimits,
     batch_size: usize,
+    interval: Duration,
+    // Smoothed positions per second over all partitions
+    rate: f64,
+    // Smoothed duration of a flush
+    flush_latency: Duration,
+    window_start: Instant,
+    window_rows: usize,
+}
+
+impl BatchTuning {
+    fn new(limits: BatchLimits) -> Self {
+        Self {
+            limits,
+            batch_size: limits.min_size,
+            interval: limits.min_interval,
+            rate: 0.0,
+            flush_latency: Duration::ZERO,
+            window_start: Instant::now(),
+            window_rows: 0,
+        }
+    }
+
+    // Count an added position; retunes once per measurement window
+    fn record_add(&mut self, partitions: usize) {
+        self.window_rows += 1;
+        let elapsed = self.window_start.elapsed();
+        if elapsed >= RATE_WINDOW {
+            let rate = self.window_rows as f64 / elapsed.as_secs_f64();
+            self.rate += SMOOTHING * (rate - self.rate);
+            self.window_start = Instant::now();
+            self.window_rows = 0;
+            self.retune(partitions);
+        }
+    }
+
+    // Record how long a flush took
+    fn record_flush(&mut self, latency: Duration, partitions: usize) {
+        self.flush_latency = self.flush_latency.mul_f64(1.0 - SMOOTHING) + latency.mul_f64(SMOOTHING);
+        self.retune(partitions);
+    }
+
+    fn retune(&mut self, partitions: usize) {
+        let limits = self.limits;
+        let interval = (self.flush_latency * INTERVAL_PER_FLUSH_LATENCY).clamp(limits.min_interval, limits.max_interval);
+        let per_partition = self.rate / partitions.max(1) as f64;
+        let batch_size = ((per_partition * interval.as_secs_f64()) as usize).clamp(limits.min_size, limits.max_size);
+
+        if batch_size != self.batch_size || interval != self.interval {
+            tracing::debug!(
+                "📦 Batching {} positions or {:?} ({:.0} positions/s, flushes take {:?})",
+                batch_size, interval, self.rate, self.flush_latency
+            );
+        }
+        self.batch_size = batch_size;
+        self.interval = interval;
+    }
+}
+
+// Writes batches of positions to TimescaleDB.
+//
+// With several write shards, each owns a hash range of vehicle IDs and its
+// own connection pool: a batch is split by shard and the shards insert
+// their part concurrently, each in its own transaction. A vehicle always
+// maps to the same shard, so its positions are still written in order.
+#[derive(Clone)]
+pub struct BatchWriter {
+    // One connection pool per write shard
+    pools: Arc<Vec<PgPool>>,
+    // One buffer per partition, so partitions can be flushed and committed independently
+    buffers: Arc<Mutex<HashMap<PartitionKey, PartitionBuffer>>>,
+    // Batch size and flush interval, adapted to the load
+    tuning: Arc<std::sync::Mutex<BatchTuning>>,
 }
 
 impl BatchWriter {
-    pub fn new(pool: PgPool, batch_size: usize) -> Self {
+    // Create a writer with one write shard per pool
+    pub fn new(pools: Vec<PgPool>, limits: BatchLimits) -> Self {
+        assert!(!pools.is_empty(), "BatchWriter needs at least one connection pool");
         Self {
-            pool,
-            buffer: Arc::new(Mutex::new(Vec::with_capacity(batch_size))),
-            batch_size,
+            pools: Arc::new(pools),
+            buffers: Arc::new(Mutex::new(HashMap::new())),
+            tuning: Arc::new(std::sync::Mutex::new(BatchTuning::new(limits))),
         }
     }
 
-    // Add a position to the buffer
-    pub async fn add(&self, position: VehiclePosition) -> Result<()> {
-        let mut buffer = self.buffer.lock().await;
-        buffer.push(position);
+    // Current batch size and flush interval
+    pub fn current(&self) -> (usize, Duration) {
+        let tuning = self.tuning.lock().unwrap_or_else(|e| e.into_inner());
+        (tuning.batch_size, tuning.interval)
+    }
+
+    // Connection pool of the first write shard, shared with other writers
+    pub fn pool(&self) -> &PgPool {
+        &self.pools[0]
+    }
+
+    // Add a position read at `offset` of `partition` to that partition's buffer.
+    // Returns the offset to commit if the partition's buffer was flushed.
+    pub async fn add(&self, partition: &PartitionKey, offset: i64, row: PositionRow) -> Result<Option<i64>> {
+        let mut buffers = self.buffers.lock().await;
+        let partitions = buffers.len().max(1);
+        let buffer = buffers.entry(partition.clone()).or_default();
+        buffer.positions.push(row);
+        buffer.pending_offset = Some(offset + 1);
+        buffer.since.get_or_insert_with(Instant::now);
 
-        // If the buffer is full — flush it to the DB
-        if buffer.len() >= self.batch_size {
-            self.flush_locked(&mut buffer).await?;
+        let (batch_size, interval) = {
+            let mut tuning = self.tuning.lock().unwrap_or_else(|e| e.into_inner());
+            tuning.record_add(partitions);
+            (tuning.batch_size, tuning.interval)
+        };
+
+        // If the buffer is full or has waited long enough — flush it to the DB
+        if buffer.positions.len() >= batch_size || buffer.since.is_some_and(|since| since.elapsed() >= interval) {
+            return self.flush_buffer(buffer, partitions).await;
         }
-        Ok(())
+        Ok(None)
+    }
+
+    // Flush the partitions whose oldest position has waited for the flush
+    // interval (e.g. when traffic stops). Returns the offsets to commit.
+    pub async fn flush_due(&self) -> Result<Vec<CommitOffset>> {
+        let (_, interval) = self.current();
+        let mut buffers = self.buffers.lock().await;
+        let partitions = buffers.len();
+        let mut offsets = Vec::new();
+
+        for (partition, buffer) in buffers.iter_mut() {
+            if buffer.since.is_some_and(|since| since.elapsed() >= interval) {
+                if let Some(offset) = self.flush_buffer(buffer, partitions).await? {
+                    offsets.push((partition.clone(), offset));
+                }
+            }
+        }
+        Ok(offsets)
+    }
+
+    // Mark a message without a position (e.g. undecodable) as consumed,
+    // so its offset is committed with the partition's next flush; starts
+    // the flush interval, so partitions seeing only such messages commit too
+    pub async fn skip(&self, partition: &PartitionKey, offset: i64) {
+        let mut buffers = self.buffers.lock().await;
+        let buffer = buffers.entry(partition.clone()).or_default();
+        buffer.pending_offset = Some(offset + 1);
+        buffer.since.get_or_insert_with(Instant::now);
+    }
+
+    // Flush the given partitions and forget their buffers (e.g. on revoke).
+    // Returns the offsets to commit for them.
+    pub async fn flush_partitions(&self, partitions: &[PartitionKey]) -> Result<Vec<CommitOffset>> {
+        let mut buffers = self.buffers.lock().await;
+        let count = buffers.len();
+        let mut offsets = Vec::new();
+
+        for partition in partitions {
+            let Some(buffer) = buffers.get_mut(partition) else { continue };
+            if let Some(offset) = self.flush_buffer(buffer, count).await? {
+                offsets.push((partition.clone(), offset));
+            }
+            buffers.remove(partition);
+        }
+        Ok(offsets)
+    }
+
+    // Forced flush of every partition (e.g., on shutdown)
+    pub async fn flush(&self) -> Result<Vec<CommitOffset>> {
+        let partitions: Vec<PartitionKey> = self.buffers.lock().await.keys().cloned().collect();
+        self.flush_partitions(&partitions).await
+    }
+
+    // Write one partition's buffer; returns its offset to commit
+    async fn flush_buffer(&self, buffer: &mut PartitionBuffer, partitions: usize) -> Result<Option<i64>> {
+        if !buffer.positions.is_empty() {
+            let started = Instant::now();
+            self.write(&buffer.positions).await?;
+            self.tuning
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .record_flush(started.elapsed(), partitions);
+        }
+        buffer.positions.clear();
+        buffer.since = None;
+        Ok(buffer.pending_offset.take())
     }
 
     // Internal write logic
-    async fn flush_locked(&self, buffer: &mut Vec<VehiclePosition>) -> Result<()> {
-        if buffer.is_empty() {
+    async fn write(&self, positions: &[PositionRow]) -> Result<()> {
+        if positions.is_empty() {
             return Ok(());
         }
 
         // The log we expect
-        tracing::info!("Saved {} positions to DB", buffer.len());
-
-        let mut tx = self.pool.begin().await?;
-
-        for pos in buffer.iter() {
-            sqlx::query!(
-                r#"
-                INSERT INTO vehicle_positions (time, vehicle_id, latitude, longitude, speed)
-                VALUES (to_timestamp($1), $2, $3, $4, $5)
-                "#,
-                pos.timestamp as f64,
-                pos.vehicle_id,
-                pos.latitude,
-                pos.longitude,
-                pos.speed
-            )
-                .execute(&mut *tx)
-                .await?;
-        }
+        tracing::info!("Saved {} positions to DB", positions.len());
 
-        tx.commit().await?;
-        buffer.clear();
+        let mut shards: Vec<Vec<&PositionRow>> = vec![Vec::new(); self.pools.len()];
+        for row in positions {
+            shards[shard_of(&row.position.vehicle_id, self.pools.len())].push(row);
+        }
+        try_join_all(
+            shards
+                .iter()
+                .zip(self.pools.iter())
+                .filter(|(rows, _)| !rows.is_empty())
+                .map(|(rows, pool)| write_shard(pool, rows)),
+        )
+            .await?;
         Ok(())
     }
+}
+
+// Shard owning a vehicle: its position in the range of vehicle ID hashes
+fn shard_of(vehicle_id: &str, shards: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    vehicle_id.hash(&mut hasher);
+    ((u128::from(hasher.finish()) * shards as u128) >> 64) as usize
+}
 
-    // Forced flush (e.g., on shutdown)
-    pub async fn flush(&self) -> Result<()> {
-        let mut buffer = self.buffer.lock().await;
-        self.flush_locked(&mut buffer).await
+// Insert one shard's part of a batch in a single transaction
+async fn write_shard(pool: &PgPool, positions: &[&PositionRow]) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    for row in positions {
+        let pos = &row.position;
+        sqlx::query!(
+            r#"
+            INSERT INTO vehicle_positions (time, vehicle_id, latitude, longitude, speed, heading, road_id, match_confidence, district, ingest_latency_ms)
+            VALUES (to_timestamp($1), $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            "#,
+            pos.timestamp as f64,
+            pos.vehicle_id,
+            pos.latitude,
+            pos.longitude,
+            pos.speed,
+            pos.heading,
+            row.road_id,
+            row.match_confidence,
+            row.district,
+            row.ingest_latency_ms
+        )
+            .execute(&mut *tx)
+            .await?;
     }
-}
\ No newline at end of file
+
+    tx.commit().await?;
+    Ok(())
+}
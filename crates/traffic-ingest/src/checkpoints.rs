@@ -0,0 +1,81 @@
+//! Storage of checkpoint crossings.
+//!
+//! The simulator publishes a [`CheckpointEvent`] to the
+//! [`CHECKPOINT_EVENTS_TOPIC`] topic for every vehicle passing a virtual
+//! checkpoint. A background task stores them in the `checkpoint_crossings`
+//! table, one row per crossing. Crossings are low-volume compared to
+//! positions, so they are written as they arrive rather than batched.
+
+use anyhow::{Context, Result};
+use futures::StreamExt;
+use rdkafka::consumer::{Consumer, StreamConsumer};
+use rdkafka::Message;
+use sqlx::PgPool;
+use traffic_common::checkpoints::{CheckpointEvent, CHECKPOINT_EVENTS_TOPIC};
+use traffic_common::map::Direction;
+use traffic_common::Config;
+
+/// Consumer group of the checkpoint writer.
+const GROUP_ID: &str = "ingest-checkpoints";
+
+/// Starts a background task storing checkpoint crossings.
+///
+/// Malformed events are logged and skipped; failed inserts are logged and
+/// the task keeps consuming.
+///
+/// # Errors
+///
+/// Returns an error if the Kafka consumer cannot be created or subscribed.
+pub fn spawn_checkpoint_writer(config: &Config, pool: PgPool) -> Result<()> {
+    let consumer: StreamConsumer = config
+        .kafka_client()
+        .set("group.id", GROUP_ID)
+        .set("auto.offset.reset", "earliest")
+        .create()
+        .context("Failed to create checkpoint consumer")?;
+    consumer.subscribe(&[CHECKPOINT_EVENTS_TOPIC])?;
+
+    tokio::spawn(async move {
+        let mut stream = consumer.stream();
+        while let Some(msg_result) = stream.next().await {
+            let Ok(msg) = msg_result else { continue };
+            let Some(payload) = msg.payload() else { continue };
+
+            let event: CheckpointEvent = match serde_json::from_slice(payload) {
+                Ok(event) => event,
+                Err(e) => {
+                    tracing::warn!("Ignoring malformed checkpoint event: {}", e);
+                    continue;
+                }
+            };
+            if let Err(e) = store(&pool, &event).await {
+                tracing::error!("❌ Failed to store checkpoint crossing: {}", e);
+            }
+        }
+    });
+
+    tracing::info!("✅ Subscribed to '{}' for checkpoint crossings", CHECKPOINT_EVENTS_TOPIC);
+    Ok(())
+}
+
+/// Inserts a single crossing.
+async fn store(pool: &PgPool, event: &CheckpointEvent) -> Result<()> {
+    let direction = match event.direction {
+        Direction::Forward => "forward",
+        Direction::Backward => "backward",
+    };
+    sqlx::query!(
+        r#"
+        INSERT INTO checkpoint_crossings (time, checkpoint_id, vehicle_id, way_id, direction)
+        VALUES (to_timestamp($1), $2, $3, $4, $5)
+        "#,
+        event.timestamp_ms as f64 / 1000.0,
+        event.checkpoint_id,
+        event.vehicle_id,
+        event.way_id,
+        direction
+    )
+        .execute(pool)
+        .await?;
+    Ok(())
+}
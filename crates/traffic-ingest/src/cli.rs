@@ -0,0 +1,69 @@
+//! Command line of the ingest service.
+//!
+//! ```text
+//! traffic-ingest [run] [--dry-run]
+//! traffic-ingest backfill --from TS [--to TS] [--allow-duplicates]
+//! traffic-ingest migrate
+//! ```
+//!
+//! Without a subcommand the service runs as before (`run`).
+
+use clap::{Args, Parser, Subcommand};
+use traffic_common::cli::CommonArgs;
+
+/// Kafka consumer writing vehicle telemetry to TimescaleDB and Redis.
+#[derive(Debug, Parser)]
+#[command(name = "traffic-ingest", version, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(flatten)]
+    pub common: CommonArgs,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Options of `run` when no subcommand is given
+    #[command(flatten)]
+    pub run: RunArgs,
+}
+
+impl Cli {
+    /// Returns the subcommand to execute, defaulting to `run`.
+    pub fn command(self) -> Command {
+        self.command.unwrap_or(Command::Run(self.run))
+    }
+}
+
+/// Subcommands of the ingest service.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Consume live telemetry into TimescaleDB and Redis (default)
+    Run(RunArgs),
+    /// Re-read a time window of telemetry from Kafka into TimescaleDB
+    Backfill(BackfillArgs),
+    /// Apply pending database migrations and exit
+    Migrate,
+}
+
+/// Options of `run`.
+#[derive(Debug, Clone, Default, Args)]
+pub struct RunArgs {
+    /// Validate telemetry without writing to TimescaleDB or Redis
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// Options of `backfill`.
+#[derive(Debug, Clone, Args)]
+pub struct BackfillArgs {
+    /// Start of the window as a Unix timestamp
+    #[arg(long, value_name = "TS")]
+    pub from: i64,
+
+    /// End of the window as a Unix timestamp (default: the end of the topic)
+    #[arg(long, value_name = "TS")]
+    pub to: Option<i64>,
+
+    /// Write positions even if the window already has rows in TimescaleDB
+    #[arg(long)]
+    pub allow_duplicates: bool,
+}
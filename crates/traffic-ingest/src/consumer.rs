@@ -0,0 +1,132 @@
+//! Kafka consumer setup with rebalance-safe offset handling.
+//!
+//! Offsets are committed only after the positions they cover have been
+//! written to TimescaleDB, so a crash or rebalance never skips data. Buffers
+//! are kept per partition, so a revocation flushes and commits only the
+//! partitions being handed over. The consumer uses cooperative sticky
+//! rebalancing (only moved partitions are revoked, the rest keep flowing)
+//! and, when `KAFKA_GROUP_INSTANCE_ID` is set, static group membership so
+//! restarts within the session timeout don't trigger a rebalance at all.
+
+use anyhow::{Context, Result};
+use rdkafka::consumer::{CommitMode, Consumer, ConsumerContext, Rebalance, StreamConsumer};
+use rdkafka::{ClientContext, Offset, TopicPartitionList};
+use std::sync::{Arc, OnceLock, Weak};
+use tokio::runtime::Handle;
+use traffic_common::Config;
+use crate::batch::{BatchWriter, CommitOffset, PartitionKey};
+
+/// Topic the simulator and feed adapters publish positions to.
+pub const TELEMETRY_TOPIC: &str = "raw-telemetry";
+
+/// Consumer group shared by all ingest instances.
+const GROUP_ID: &str = "ingest-group-final";
+
+/// Kafka consumer used by the ingest service.
+pub type IngestConsumer = StreamConsumer<IngestContext>;
+
+/// Consumer context that flushes and commits before partitions are revoked.
+pub struct IngestContext {
+    /// Writer whose buffer must be flushed before offsets are committed
+    writer: BatchWriter,
+    /// Back-reference to the owning consumer, used to commit on revoke
+    consumer: OnceLock<Weak<IngestConsumer>>,
+    /// Runtime used to run the async flush from the rebalance callback
+    runtime: Handle,
+}
+
+impl IngestContext {
+    /// Commits offsets returned by the batch writer after a flush.
+    pub fn commit(&self, consumer: &IngestConsumer, offsets: &[CommitOffset], mode: CommitMode) {
+        if offsets.is_empty() {
+            return;
+        }
+
+        let mut list = TopicPartitionList::new();
+        for ((topic, partition), offset) in offsets {
+            if let Err(e) = list.add_partition_offset(topic, *partition, Offset::Offset(*offset)) {
+                tracing::error!("Invalid offset for {}/{}: {}", topic, partition, e);
+            }
+        }
+
+        if let Err(e) = consumer.commit(&list, mode) {
+            tracing::error!("❌ Offset commit failed: {}", e);
+        }
+    }
+}
+
+impl ClientContext for IngestContext {}
+
+impl ConsumerContext for IngestContext {
+    fn pre_rebalance(&self, rebalance: &Rebalance<'_>) {
+        let Rebalance::Revoke(partitions) = rebalance else { return };
+        let revoked: Vec<PartitionKey> = partitions
+            .elements()
+            .iter()
+            .map(|element| (element.topic().to_string(), element.partition()))
+            .collect();
+        tracing::info!("🔄 Revoking {} partitions; flushing before handing them over", revoked.len());
+
+        // Rebalance callbacks are synchronous; block this worker until the
+        // revoked partitions' positions are safely in the database.
+        let flushed = tokio::task::block_in_place(|| {
+            self.runtime.block_on(self.writer.flush_partitions(&revoked))
+        });
+        let offsets = match flushed {
+            Ok(offsets) => offsets,
+            Err(e) => {
+                // Leave offsets uncommitted so the new owner re-reads the batch
+                tracing::error!("❌ Flush before revoke failed: {}", e);
+                return;
+            }
+        };
+
+        if let Some(consumer) = self.consumer.get().and_then(Weak::upgrade) {
+            self.commit(&consumer, &offsets, CommitMode::Sync);
+        }
+    }
+
+    fn post_rebalance(&self, rebalance: &Rebalance<'_>) {
+        if let Rebalance::Assign(partitions) = rebalance {
+            tracing::info!("✅ Assigned {} partitions", partitions.count());
+        }
+    }
+}
+
+/// Creates the ingest consumer and subscribes it to the telemetry topic.
+///
+/// # Arguments
+///
+/// * `config` - Application configuration with broker and group settings
+/// * `writer` - Batch writer flushed on partition revocation
+///
+/// # Errors
+///
+/// Returns an error if the consumer cannot be created or subscribed.
+pub fn create_consumer(config: &Config, writer: BatchWriter) -> Result<Arc<IngestConsumer>> {
+    let context = IngestContext {
+        writer,
+        consumer: OnceLock::new(),
+        runtime: Handle::current(),
+    };
+
+    let mut client_config = config.kafka_client();
+    client_config
+        .set("group.id", GROUP_ID)
+        .set("auto.offset.reset", "earliest")
+        .set("enable.auto.commit", "false")
+        .set("partition.assignment.strategy", "cooperative-sticky");
+    if let Some(instance_id) = &config.kafka_group_instance_id {
+        client_config.set("group.instance.id", instance_id);
+        tracing::info!("Using static group membership as '{}'", instance_id);
+    }
+
+    let consumer: IngestConsumer = client_config
+        .create_with_context(context)
+        .context("Failed to create Kafka consumer")?;
+    let consumer = Arc::new(consumer);
+    let _ = consumer.context().consumer.set(Arc::downgrade(&consumer));
+
+    consumer.subscribe(&[TELEMETRY_TOPIC])?;
+    Ok(consumer)
+}
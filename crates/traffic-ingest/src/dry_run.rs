@@ -0,0 +1,112 @@
+//! Dry-run mode of the ingest service.
+//!
+//! With `--dry-run`, ingest reads telemetry from Kafka, decodes, validates
+//! and map-matches every position exactly like a normal run, but writes
+//! nothing to TimescaleDB or Redis and commits no offsets (it consumes in
+//! its own consumer group). Periodic statistics show whether producers
+//! send well-formed data and whether it fits the configured map.
+
+use anyhow::{Context, Result};
+use futures::StreamExt;
+use geo::Point;
+use prost::Message as ProstMessage;
+use rdkafka::consumer::{Consumer, StreamConsumer};
+use rdkafka::Message;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::signal;
+use traffic_common::geo::CoordinateCounters;
+use traffic_common::map::MIN_MATCH_CONFIDENCE;
+use traffic_common::units::validate_speed;
+use traffic_common::{Config, VehiclePosition};
+use crate::consumer::TELEMETRY_TOPIC;
+use crate::load_graph;
+
+/// Consumer group of dry runs, separate from the real ingest group so its
+/// offsets are never touched.
+const DRY_RUN_GROUP_ID: &str = "ingest-dry-run";
+
+/// Interval between two statistics log lines.
+const LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Outcome counts of a dry run.
+#[derive(Debug, Default)]
+struct DryRunStats {
+    /// Messages received from Kafka
+    received: u64,
+    /// Messages without payload or with an undecodable payload
+    undecodable: u64,
+    /// Positions with valid coordinates but an implausible speed
+    bad_speed: u64,
+    /// Positions that passed validation and would have been written
+    accepted: u64,
+    /// Accepted positions snapped onto the road graph
+    matched: u64,
+    /// Matched positions too ambiguous to count towards road speeds
+    low_confidence: u64,
+}
+
+/// Consumes telemetry without writing anything until Ctrl-C.
+///
+/// # Errors
+///
+/// Returns an error if `MAP_BBOX` is malformed or the Kafka consumer
+/// cannot be created.
+pub async fn run(config: &Config) -> Result<()> {
+    let graph = load_graph(config)?;
+
+    let consumer: StreamConsumer = config
+        .kafka_client()
+        .set("group.id", DRY_RUN_GROUP_ID)
+        .set("auto.offset.reset", "latest")
+        .set("enable.auto.commit", "false")
+        .create()
+        .context("Failed to create Kafka consumer")?;
+    consumer.subscribe(&[TELEMETRY_TOPIC])?;
+    tracing::info!("🧪 Dry run: validating telemetry from {}, nothing will be written", TELEMETRY_TOPIC);
+
+    let mut stats = DryRunStats::default();
+    let mut coordinates = CoordinateCounters::default();
+    let mut last_matches: HashMap<String, (Point, usize)> = HashMap::new();
+    let mut last_log = Instant::now();
+    let mut stream = consumer.stream();
+
+    tokio::select! {
+        _ = async {
+            while let Some(msg_result) = stream.next().await {
+                let Ok(msg) = msg_result else { continue };
+                stats.received += 1;
+
+                let Some(position) = msg.payload().and_then(|payload| VehiclePosition::decode(payload).ok()) else {
+                    stats.undecodable += 1;
+                    continue;
+                };
+                let valid = coordinates.check(position.longitude, position.latitude);
+                if valid.is_ok() && validate_speed(position.speed).is_err() {
+                    stats.bad_speed += 1;
+                } else if let Ok((lon, lat)) = valid {
+                    stats.accepted += 1;
+
+                    let point = Point::new(lon, lat);
+                    let previous = last_matches.get(&position.vehicle_id).copied();
+                    if let Some(matched) = graph.match_point(point, previous.map(|p| p.0), previous.map(|p| p.1)) {
+                        stats.matched += 1;
+                        if matched.confidence < MIN_MATCH_CONFIDENCE {
+                            stats.low_confidence += 1;
+                        }
+                        last_matches.insert(position.vehicle_id, (point, matched.edge));
+                    }
+                }
+
+                if last_log.elapsed() >= LOG_INTERVAL {
+                    last_log = Instant::now();
+                    tracing::info!("🧪 Dry run: {:?}, coordinates: {:?}", stats, coordinates);
+                }
+            }
+        } => {},
+        _ = signal::ctrl_c() => {}
+    }
+
+    tracing::info!("🧪 Dry run finished: {:?}, coordinates: {:?}", stats, coordinates);
+    Ok(())
+}
@@ -5,20 +5,70 @@
 //! - **Cold Path**: Batches data to TimescaleDB for historical analysis
 //! - **Hot Path**: Updates Redis with real-time vehicle locations and publishes
 //!   updates to connected clients via pub/sub
+//!
+//! Every position is map-matched onto the road graph so the hot path can
+//! carry the OSM way the vehicle is driving on. If `ZONES_PATH` is set,
+//! live vehicle counts and average speeds per zone (e.g. district) are
+//! published as well. If `DISTRICTS_PATH` is set, every stored position is
+//! tagged with the administrative district it lies in. Rapid speed drops in
+//! a zone or on a road are published as `congestion.trend` events.
+//! Vehicles passing virtual checkpoints are stored in
+//! `checkpoint_crossings`. `--dry-run` validates incoming telemetry without
+//! writing anything.
+//!
+//! Besides the service itself (`run`), the binary can backfill a time
+//! window from Kafka (`backfill`) and apply database migrations
+//! (`migrate`); see [`cli`].
 
+mod backfill;
 mod batch;
+mod checkpoints;
+mod cli;
+mod consumer;
+mod dry_run;
+mod metadata;
+mod trends;
+mod zones;
 
-use traffic_common::{Config, VehiclePosition, init_tracing};
-use rdkafka::consumer::{Consumer, StreamConsumer, CommitMode};
-use rdkafka::config::ClientConfig;
+use traffic_common::{Config, VehiclePosition};
+use traffic_common::geo::{CoordinateCounters, Districts};
+use traffic_common::units::validate_speed;
+use traffic_common::map::{RoadGraph, MIN_MATCH_CONFIDENCE, ROAD_SPEEDS_KEY};
+use traffic_common::startup::{wait_for_kafka, wait_for_postgres, wait_for_redis};
+use geo::Point;
+use std::collections::HashMap;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use rdkafka::consumer::{CommitMode, Consumer};
 use rdkafka::Message;
 use futures::StreamExt;
-use anyhow::{Context, Result};
+use anyhow::{ensure, Context, Result};
 use prost::Message as ProstMessage;
 use tokio::signal;
 use sqlx::PgPool;
-use crate::batch::BatchWriter;
+use crate::batch::{BatchLimits, BatchWriter, PartitionKey, PositionRow};
+use crate::checkpoints::spawn_checkpoint_writer;
+use crate::consumer::create_consumer;
+use crate::metadata::{MetadataBuffer, FLUSH_INTERVAL as METADATA_FLUSH_INTERVAL};
+use crate::trends::TrendDetector;
+use crate::zones::ZoneTracker;
 use redis::AsyncCommands;
+use clap::Parser;
+use crate::cli::{Cli, Command};
+
+/// How often batches are checked for having waited for their flush
+/// interval while no positions arrive.
+const BATCH_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Rejected coordinates (or speeds) between two warnings in the log.
+const REJECTION_LOG_INTERVAL: u64 = 1000;
+
+/// Interval between two publications of per-zone statistics and
+/// evaluations of congestion trends.
+const ZONE_STATS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Vehicles not heard from for this long are matched afresh, and their
+/// last match is forgotten at the next periodic publication.
+const MATCH_STALE_AFTER: Duration = Duration::from_secs(60);
 
 /// Main ingestion service handling both database writes and Redis updates.
 struct IngestService {
@@ -26,6 +76,25 @@ struct IngestService {
     batch_writer: BatchWriter,
     /// Redis connection for real-time geospatial indexing and pub/sub
     redis: redis::aio::ConnectionManager,
+    /// Vehicle metadata waiting to be written to Redis
+    metadata: MetadataBuffer,
+    /// Outcomes of coordinate validation since startup
+    coordinates: CoordinateCounters,
+    /// Positions rejected for an implausible speed since startup
+    rejected_speeds: u64,
+    /// Road network used for map matching (empty if the map failed to load)
+    graph: RoadGraph,
+    /// Last position, matched edge and time of the match per vehicle, for
+    /// heading-aware matching
+    last_matches: HashMap<String, (Point, usize, Instant)>,
+    /// Administrative districts positions are tagged with (empty if not configured)
+    districts: Districts,
+    /// Per-zone live statistics (`None` if no zones are configured)
+    zones: Option<ZoneTracker>,
+    /// Detector of rapid speed drops per zone and road
+    trends: TrendDetector,
+    /// When per-zone statistics were last published
+    last_zone_publish: Instant,
 }
 
 impl IngestService {
@@ -43,44 +112,158 @@ impl IngestService {
     ///
     /// Returns an error if:
     /// - PostgreSQL connection fails
+    /// - The batch size or interval range is empty, or `WRITE_SHARDS` is 0
     /// - Redis connection cannot be established
+    /// - `MAP_BBOX` is malformed
+    /// - `ZONES_PATH` is set but the zones file cannot be loaded
+    /// - `DISTRICTS_PATH` is set but the districts cannot be loaded
     async fn new(config: &Config) -> Result<Self> {
-        // Connect to Postgres
-        let pool = PgPool::connect(&config.postgres_url).await
-            .context("Failed to connect to Postgres")?;
-        // Batch size 100 for testing (to see logs quicker); in production use 1000+
-        let batch_writer = BatchWriter::new(pool, 100);
+        // Connect to Postgres, with separate connections per write shard
+        ensure!(config.write_shards > 0, "WRITE_SHARDS must be positive");
+        let mut pools = Vec::with_capacity(config.write_shards);
+        for _ in 0..config.write_shards {
+            pools.push(PgPool::connect(&config.postgres_url).await
+                .context("Failed to connect to Postgres")?);
+        }
+        // Batches adapt to the load within the configured range
+        ensure!(
+            0 < config.batch_min_size && config.batch_min_size <= config.batch_max_size,
+            "BATCH_MIN_SIZE must be positive and at most BATCH_MAX_SIZE"
+        );
+        ensure!(
+            config.batch_min_interval_ms <= config.batch_max_interval_ms,
+            "BATCH_MIN_INTERVAL_MS must be at most BATCH_MAX_INTERVAL_MS"
+        );
+        let batch_writer = BatchWriter::new(pools, BatchLimits {
+            min_size: config.batch_min_size,
+            max_size: config.batch_max_size,
+            min_interval: Duration::from_millis(config.batch_min_interval_ms),
+            max_interval: Duration::from_millis(config.batch_max_interval_ms),
+        });
 
         // Connect to Redis
-        let client = redis::Client::open(config.redis_url.as_str())
-            .context("Invalid Redis URL")?;
-        let redis = client.get_tokio_connection_manager().await
+        let client = config.redis_client()
+            .context("Invalid Redis configuration")?;
+        let redis = client.get_connection_manager().await
             .context("Failed to connect to Redis")?;
 
-        Ok(Self { batch_writer, redis })
+        let graph = load_graph(config)?;
+        let districts = load_districts(config)?;
+        let zones = config.zones_path.as_deref().map(ZoneTracker::load).transpose()?;
+
+        Ok(Self {
+            batch_writer,
+            redis,
+            metadata: MetadataBuffer::new(),
+            coordinates: CoordinateCounters::default(),
+            rejected_speeds: 0,
+            graph,
+            last_matches: HashMap::new(),
+            districts,
+            zones,
+            trends: TrendDetector::new(config.trend_drop_percent, Duration::from_secs(config.trend_window_secs)),
+            last_zone_publish: Instant::now(),
+        })
     }
 
     /// Processes a single vehicle position through both cold and hot paths.
     ///
+    /// Positions with invalid coordinates (NaN, out of range, `0,0`) or a
+    /// speed that is not a plausible value in m/s are counted and skipped
+    /// before touching either path; longitudes outside `[-180, 180]` are
+    /// wrapped.
+    ///
     /// # Cold Path (Historical Storage)
-    /// - Adds position to batch buffer for TimescaleDB
+    /// - Adds position, matched road and ingest latency to the batch buffer
+    ///   for TimescaleDB
     /// - Data is flushed periodically for efficient bulk inserts
     ///
     /// # Hot Path (Real-Time Updates)
     /// - Updates Redis geospatial index for proximity queries
-    /// - Stores vehicle metadata (speed, timestamp) with TTL
+    /// - Stages vehicle metadata (speed, heading, timestamp, matched road), written
+    ///   with a TTL every 250 ms with the latest value per vehicle
     /// - Publishes update to "vehicles:update" channel for WebSocket clients
+    /// - Every 5 seconds, stores per-zone statistics under "zones:stats" and
+    ///   publishes them to "zones:update" (if zones are configured), and
+    ///   publishes rapid speed drops to "congestion:trend"
     ///
     /// # Arguments
     ///
     /// * `position` - Vehicle position telemetry data
+    /// * `partition` - Kafka partition the position was read from
+    /// * `offset` - Kafka offset of the position's message
+    /// * `latency_ms` - Time from the producer sending the position to ingest
+    ///   receiving it, if known
+    ///
+    /// # Returns
+    ///
+    /// The offset to commit for `partition` if its batch buffer was flushed
+    /// to TimescaleDB.
     ///
     /// # Errors
     ///
     /// Returns an error if database or Redis operations fail.
-    async fn process(&mut self, position: VehiclePosition) -> Result<()> {
+    async fn process(
+        &mut self,
+        mut position: VehiclePosition,
+        partition: &PartitionKey,
+        offset: i64,
+        latency_ms: Option<f64>,
+    ) -> Result<Option<i64>> {
+        // 0. Validation: never let a bad coordinate reach GEOADD or the DB
+        match self.coordinates.check(position.longitude, position.latitude) {
+            Ok((lon, lat)) => {
+                position.longitude = lon;
+                position.latitude = lat;
+            }
+            Err(e) => {
+                // Log the first rejection and then every 1000th to avoid flooding
+                if self.coordinates.rejected() % REJECTION_LOG_INTERVAL == 1 {
+                    tracing::warn!(
+                        "⚠️ Rejected position of {}: {} (totals: {:?})",
+                        position.vehicle_id, e, self.coordinates
+                    );
+                }
+                self.batch_writer.skip(partition, offset).await;
+                return Ok(None);
+            }
+        }
+        if let Err(e) = validate_speed(position.speed) {
+            self.rejected_speeds += 1;
+            if self.rejected_speeds % REJECTION_LOG_INTERVAL == 1 {
+                tracing::warn!(
+                    "⚠️ Rejected position of {}: {} ({} speeds rejected so far)",
+                    position.vehicle_id, e, self.rejected_speeds
+                );
+            }
+            self.batch_writer.skip(partition, offset).await;
+            return Ok(None);
+        }
+
+        // Snap onto the road graph, continuing from the vehicle's last match
+        let point = Point::new(position.longitude, position.latitude);
+        let previous = self
+            .last_matches
+            .get(&position.vehicle_id)
+            .copied()
+            .filter(|&(_, _, matched_at)| matched_at.elapsed() < MATCH_STALE_AFTER);
+        let matched = self.graph.match_point(point, previous.map(|p| p.0), previous.map(|p| p.1));
+        if let Some(matched) = &matched {
+            self.last_matches.insert(position.vehicle_id.clone(), (point, matched.edge, Instant::now()));
+        }
+        let road_id = matched.map(|m| m.road_id);
+        let match_confidence = matched.map(|m| m.confidence);
+        let district = self.districts.district_of(position.longitude, position.latitude).map(|zone| zone.name.clone());
+
         // 1. Cold Path: Accumulate batch for TimescaleDB
-        self.batch_writer.add(position.clone()).await?;
+        let row = PositionRow {
+            position: position.clone(),
+            road_id,
+            match_confidence,
+            district: district.clone(),
+            ingest_latency_ms: latency_ms,
+        };
+        let flushed = self.batch_writer.add(partition, offset, row).await?;
 
         // 2. Hot Path: Update Redis Geo Index for proximity searches
         let _: () = self.redis.geo_add(
@@ -88,77 +271,253 @@ impl IngestService {
             (position.longitude, position.latitude, &position.vehicle_id)
         ).await?;
 
-        // 3. Store metadata (speed) with 60-second TTL
+        // 3. Stage metadata (speed) for the next write-behind flush
         let metadata = serde_json::json!({
             "speed": position.speed,
-            "timestamp": position.timestamp
+            "heading": position.heading,
+            "timestamp": position.timestamp,
+            "road_id": road_id
         });
-
-        let _: () = self.redis.set_ex(
-            format!("vehicle:{}:meta", position.vehicle_id),
-            metadata.to_string(),
-            60
-        ).await?;
+        self.metadata.stage(&position.vehicle_id, metadata.to_string());
 
         // 4. Publish update to WebSocket clients via Redis pub/sub
         let payload = serde_json::json!({
             "id": position.vehicle_id,
             "lat": position.latitude,
             "lon": position.longitude,
-            "speed": position.speed
+            "speed": position.speed,
+            "heading": position.heading,
+            "acceleration": position.acceleration,
+            "paused": position.paused,
+            "warmup": position.warmup,
+            "priority": position.priority().as_str_name(),
+            "vehicle_class": position.vehicle_class().as_str_name(),
+            "convoy_id": Some(&position.convoy_id).filter(|id| !id.is_empty()),
+            "lane": Some(position.lane).filter(|lane| *lane > 0),
+            "road_id": road_id,
+            "district": district
         }).to_string();
 
         let _: () = self.redis.publish("vehicles:update", payload).await?;
 
+        if self.metadata.is_due() {
+            self.metadata.flush(&mut self.redis).await?;
+        }
+
+        // 5. Per-zone statistics and congestion trends
+        if let Some(zones) = &mut self.zones {
+            zones.record(&position);
+        }
+        // Ambiguous matches (parallel roads, far off any road) would skew road speeds
+        if let Some(matched) = matched.filter(|m| m.confidence >= MIN_MATCH_CONFIDENCE) {
+            self.trends.record_road(matched.road_id, position.speed);
+        }
+        if self.last_zone_publish.elapsed() >= ZONE_STATS_INTERVAL {
+            self.last_zone_publish = Instant::now();
+            // Vehicles that stopped reporting would otherwise be kept forever
+            self.last_matches.retain(|_, &mut (_, _, matched_at)| matched_at.elapsed() < MATCH_STALE_AFTER);
+            self.publish_zone_stats().await?;
+        }
+
+        Ok(flushed)
+    }
+
+    /// Stores the current per-zone statistics in Redis, publishes them to
+    /// WebSocket clients and publishes any new congestion trends. Also
+    /// stores the average speed per road for congestion-aware routing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if Redis operations fail.
+    async fn publish_zone_stats(&mut self) -> Result<()> {
+        let snapshot = self.zones.as_mut().map(ZoneTracker::snapshot).unwrap_or_default();
+        if self.zones.is_some() {
+            let stats = serde_json::to_string(&snapshot)?;
+
+            // Expires if ingest stops, so the API never serves stale statistics
+            let _: () = self.redis.set_ex("zones:stats", &stats, 60).await?;
+
+            let payload = format!(r#"{{"type":"zone_stats","zones":{}}}"#, stats);
+            let _: () = self.redis.publish("zones:update", payload).await?;
+        }
+
+        let road_speeds = serde_json::to_string(&self.trends.road_averages())?;
+        let _: () = self.redis.set_ex(ROAD_SPEEDS_KEY, road_speeds, 60).await?;
+
+        for event in self.trends.evaluate(&snapshot, &self.graph) {
+            tracing::warn!("📉 Congestion trend: {:?}", event);
+            let _: () = self.redis.publish("congestion:trend", serde_json::to_string(&event)?).await?;
+        }
         Ok(())
     }
 }
 
+/// Loads the road network for map matching.
+///
+/// Ingest keeps running without a map, so a map that fails to load yields
+/// an empty graph (and a warning) rather than an error.
+///
+/// # Errors
+///
+/// Returns an error if `MAP_BBOX` is malformed.
+fn load_graph(config: &Config) -> Result<RoadGraph> {
+    let graph = match RoadGraph::load_or_build_many(&config.map_paths(), config.map_bbox()?) {
+        Ok(graph) => {
+            tracing::info!("🧠 Road graph memory: {}", graph.memory_usage());
+            graph
+        }
+        Err(e) => {
+            tracing::warn!("⚠️ Map unavailable, positions will not be map-matched: {}", e);
+            RoadGraph::default()
+        }
+    };
+    Ok(graph)
+}
+
+/// Loads the administrative districts positions are tagged with.
+///
+/// # Errors
+///
+/// Returns an error if `DISTRICTS_PATH` is set but cannot be loaded.
+fn load_districts(config: &Config) -> Result<Districts> {
+    let Some(path) = &config.districts_path else { return Ok(Districts::default()) };
+    let districts = Districts::load(path)?;
+    tracing::info!("🏛️ Loaded {} districts from {}", districts.len(), path);
+    Ok(districts)
+}
+
+/// Milliseconds between a position being sent and ingest receiving it.
+///
+/// Uses the Kafka message timestamp (set by the producer) and falls back to
+/// the position's own timestamp, which only has second precision.
+fn ingest_latency_ms(msg: &impl Message, position: &VehiclePosition) -> Option<f64> {
+    let sent_ms = msg.timestamp().to_millis().unwrap_or(position.timestamp * 1000);
+    let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_millis() as i64;
+    Some((now_ms - sent_ms).max(0) as f64)
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    init_tracing("traffic-ingest");
-    let config = Config::from_env()?;
+    let cli = Cli::parse();
+    let config = cli.common.init("traffic-ingest")?;
+
+    match cli.command() {
+        Command::Run(args) if args.dry_run => {
+            wait_for_kafka(&config.kafka_client(), config.startup_timeout()).await?;
+            dry_run::run(&config).await
+        }
+        Command::Run(_) => run(&config).await,
+        Command::Backfill(args) => backfill::run(&config, &args).await,
+        Command::Migrate => migrate(&config).await,
+    }
+}
+
+/// Applies the migrations in `migrations/` that the database lacks.
+///
+/// # Errors
+///
+/// Returns an error if Postgres is unreachable or a migration fails.
+async fn migrate(config: &Config) -> Result<()> {
+    wait_for_postgres(&config.postgres_url, config.startup_timeout()).await?;
+    let pool = PgPool::connect(&config.postgres_url).await
+        .context("Failed to connect to Postgres")?;
+
+    sqlx::migrate!("./migrations").run(&pool).await
+        .context("Failed to apply migrations")?;
+    tracing::info!("✅ Database schema is up to date");
+    Ok(())
+}
+
+/// Runs the ingest service until Ctrl-C.
+///
+/// # Errors
+///
+/// Returns an error if a dependency is unreachable at startup or the
+/// service cannot be set up.
+async fn run(config: &Config) -> Result<()> {
+    // Dependencies may still be booting (e.g. under docker-compose)
+    wait_for_kafka(&config.kafka_client(), config.startup_timeout()).await?;
+    wait_for_postgres(&config.postgres_url, config.startup_timeout()).await?;
+    wait_for_redis(&config.redis_client()?, config.startup_timeout()).await?;
 
-    let mut service = IngestService::new(&config).await?;
+    let mut service = IngestService::new(config).await?;
 
-    // Configure Kafka consumer
-    let consumer: StreamConsumer = ClientConfig::new()
-        .set("bootstrap.servers", &config.kafka_brokers)
-        .set("group.id", "ingest-group-final")
-        .set("auto.offset.reset", "earliest")
-        .set("enable.auto.commit", "false")
-        .create()
-        .context("Failed to create Kafka consumer")?;
+    // Configure Kafka consumer; offsets are committed only after the
+    // positions they cover have been flushed to the database
+    let consumer = create_consumer(config, service.batch_writer.clone())?;
+    tracing::info!(
+        "Ingest Service Started: Writing to DB (Batch={}-{}, every {}-{} ms, {} shards) & Redis",
+        config.batch_min_size, config.batch_max_size, config.batch_min_interval_ms, config.batch_max_interval_ms,
+        config.write_shards
+    );
 
-    consumer.subscribe(&["raw-telemetry"])?;
-    tracing::info!("Ingest Service Started: Writing to DB (Batch=100) & Redis");
+    // Store vehicles passing virtual checkpoints
+    spawn_checkpoint_writer(config, service.batch_writer.pool().clone())?;
 
     let mut stream = consumer.stream();
     let shutdown = signal::ctrl_c();
+    let mut metadata_ticker = tokio::time::interval(METADATA_FLUSH_INTERVAL);
+    let mut batch_ticker = tokio::time::interval(BATCH_CHECK_INTERVAL);
 
     // Main processing loop with graceful shutdown
     tokio::select! {
         _ = async {
-            while let Some(msg_result) = stream.next().await {
-                if let Ok(msg) = msg_result {
-                    if let Some(payload) = msg.payload() {
-                        if let Ok(pos) = VehiclePosition::decode(payload) {
-                            // Process vehicle position
-                            if let Err(e) = service.process(pos).await {
-                                tracing::error!("Processing error: {}", e);
+            loop {
+                let msg_result = tokio::select! {
+                    next = stream.next() => match next {
+                        Some(msg_result) => msg_result,
+                        None => break,
+                    },
+                    // Write staged metadata even while no positions arrive
+                    _ = metadata_ticker.tick() => {
+                        if service.metadata.is_due() {
+                            if let Err(e) = service.metadata.flush(&mut service.redis).await {
+                                tracing::error!("Metadata flush error: {}", e);
                             }
-                            // Acknowledge message processing
-                            let _ = consumer.commit_message(&msg, CommitMode::Async);
                         }
+                        continue;
+                    }
+                    // Flush batches that waited long enough even while no positions arrive
+                    _ = batch_ticker.tick() => {
+                        match service.batch_writer.flush_due().await {
+                            Ok(offsets) => consumer.context().commit(&consumer, &offsets, CommitMode::Async),
+                            Err(e) => tracing::error!("Flush error: {}", e),
+                        }
+                        continue;
                     }
+                };
+                let Ok(msg) = msg_result else { continue };
+                let partition: PartitionKey = (msg.topic().to_string(), msg.partition());
+                let position = msg.payload().and_then(|payload| VehiclePosition::decode(payload).ok());
+
+                let Some(pos) = position else {
+                    // Nothing to store, but the offset still has to be committed
+                    service.batch_writer.skip(&partition, msg.offset()).await;
+                    continue;
+                };
+
+                // Process vehicle position; acknowledge the partition once its batch is in the database
+                let latency_ms = ingest_latency_ms(&msg, &pos);
+                match service.process(pos, &partition, msg.offset(), latency_ms).await {
+                    Ok(Some(offset)) => {
+                        consumer.context().commit(&consumer, &[(partition, offset)], CommitMode::Async);
+                    }
+                    Ok(None) => {}
+                    Err(e) => tracing::error!("Processing error: {}", e),
                 }
             }
         } => {},
         _ = shutdown => {
             tracing::info!("Shutdown signal received. Flushing DB buffer...");
-            if let Err(e) = service.batch_writer.flush().await {
-                tracing::error!("Flush error: {}", e);
+            match service.batch_writer.flush().await {
+                Ok(offsets) => consumer.context().commit(&consumer, &offsets, CommitMode::Sync),
+                Err(e) => tracing::error!("Flush error: {}", e),
+            }
+            if let Err(e) = service.metadata.flush(&mut service.redis).await {
+                tracing::error!("Metadata flush error: {}", e);
             }
+            tracing::info!("Coordinate validation totals: {:?}", service.coordinates);
+            tracing::info!("Speeds rejected: {}", service.rejected_speeds);
             tracing::info!("Shutdown complete.");
         }
     }
@@ -0,0 +1,67 @@
+//! Write-behind buffer for per-vehicle metadata.
+//!
+//! The hot path writes three things to Redis for every position: the
+//! position itself (`GEOADD`), the vehicle's metadata (`SETEX`) and the
+//! update for WebSocket clients (`PUBLISH`). The map only needs the first
+//! and last to stay fresh; the metadata is read on demand and tolerates a
+//! short delay. It is therefore staged here, keeping only the latest value
+//! per vehicle, and written in one pipeline every [`FLUSH_INTERVAL`]. Under
+//! high-frequency telemetry most updates of a vehicle within an interval
+//! are coalesced away.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use anyhow::Result;
+
+/// How often staged metadata is written to Redis.
+pub const FLUSH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Lifetime of a vehicle's metadata in Redis, in seconds.
+const METADATA_TTL_SECS: u64 = 60;
+
+/// Latest metadata per vehicle, not yet written to Redis.
+pub struct MetadataBuffer {
+    pending: HashMap<String, String>,
+    last_flush: Instant,
+}
+
+impl MetadataBuffer {
+    /// Creates an empty buffer.
+    pub fn new() -> Self {
+        Self {
+            pending: HashMap::new(),
+            last_flush: Instant::now(),
+        }
+    }
+
+    /// Stages a vehicle's metadata, replacing any value not yet written.
+    pub fn stage(&mut self, vehicle_id: &str, metadata: String) {
+        self.pending.insert(vehicle_id.to_string(), metadata);
+    }
+
+    /// Returns `true` if the flush interval has elapsed.
+    pub fn is_due(&self) -> bool {
+        self.last_flush.elapsed() >= FLUSH_INTERVAL
+    }
+
+    /// Writes all staged metadata to `vehicle:{id}:meta` keys in a single
+    /// pipeline.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the Redis pipeline fails; the staged values are
+    /// dropped either way, as newer positions will replace them.
+    pub async fn flush(&mut self, redis: &mut redis::aio::ConnectionManager) -> Result<()> {
+        self.last_flush = Instant::now();
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut pipe = redis::pipe();
+        for (vehicle_id, metadata) in self.pending.drain() {
+            pipe.set_ex(format!("vehicle:{}:meta", vehicle_id), metadata, METADATA_TTL_SECS).ignore();
+        }
+        let _: () = pipe.query_async(redis).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+//! Early warning of deteriorating traffic.
+//!
+//! Absolute speed levels say little on their own: 20 km/h is free flow in
+//! a residential street and a jam on an arterial. A sharp *drop* is a
+//! better early warning, so every statistics interval the average speed of
+//! each zone and each road is compared with the best average seen within
+//! the trend window. When it has fallen by more than the configured share,
+//! a `congestion.trend` event is emitted, once per episode: the series is
+//! re-armed once the drop from the (sliding) baseline is less than half
+//! the threshold again.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use traffic_common::map::RoadGraph;
+use crate::zones::ZoneStats;
+
+/// Fewest samples (vehicles in a zone, positions on a road) in one interval
+/// for its average speed to count.
+const MIN_SAMPLES: usize = 3;
+
+/// Slowest baseline in m/s from which a drop is reported; below it traffic
+/// is already at a crawl.
+const MIN_BASELINE_SPEED: f64 = 2.0;
+
+/// What a trend was detected on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrendScope {
+    Zone,
+    Road,
+}
+
+/// A rapid drop of the average speed of a zone or road.
+#[derive(Debug, Serialize)]
+pub struct TrendEvent {
+    /// Always "congestion.trend"
+    #[serde(rename = "type")]
+    kind: &'static str,
+    scope: TrendScope,
+    /// Zone name or OSM way ID
+    target: String,
+    /// Street name of a road, if tagged
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    /// Best average speed within the window, in m/s
+    baseline_speed: f64,
+    /// Current average speed, in m/s
+    current_speed: f64,
+    /// Relative drop from the baseline (e.g. 0.4 for 40 %)
+    drop: f64,
+    /// Length of the trend window in seconds
+    window_secs: u64,
+}
+
+/// Recent average speeds of one zone or road.
+#[derive(Default)]
+struct Series {
+    samples: VecDeque<(Instant, f64)>,
+    last_seen: Option<Instant>,
+    /// Whether the current drop was already reported
+    alerted: bool,
+}
+
+/// Detects rapid speed drops on zones and roads.
+pub struct TrendDetector {
+    /// Relative drop that triggers an event (e.g. 0.3)
+    drop: f64,
+    /// How far back the baseline reaches
+    window: Duration,
+    series: HashMap<(TrendScope, String), Series>,
+    /// Speed sum and count per road since the last evaluation
+    road_speeds: HashMap<i64, (f64, usize)>,
+}
+
+impl TrendDetector {
+    /// Creates a detector.
+    ///
+    /// # Arguments
+    ///
+    /// * `drop_percent` - Drop in percent of the baseline that triggers an event
+    /// * `window` - How far back the baseline reaches
+    pub fn new(drop_percent: f64, window: Duration) -> Self {
+        Self {
+            drop: (drop_percent / 100.0).clamp(0.0, 1.0),
+            window,
+            series: HashMap::new(),
+            road_speeds: HashMap::new(),
+        }
+    }
+
+    /// Records the speed of a position matched onto a road.
+    pub fn record_road(&mut self, road_id: i64, speed: f64) {
+        let (sum, count) = self.road_speeds.entry(road_id).or_default();
+        *sum += speed;
+        *count += 1;
+    }
+
+    /// Returns the average speed per road in the current interval, for
+    /// roads with enough samples.
+    pub fn road_averages(&self) -> HashMap<i64, f64> {
+        self.road_speeds
+            .iter()
+            .filter(|(_, (_, count))| *count >= MIN_SAMPLES)
+            .map(|(&road_id, &(sum, count))| (road_id, sum / count as f64))
+            .collect()
+    }
+
+    /// Evaluates the interval that just ended.
+    ///
+    /// # Arguments
+    ///
+    /// * `zones` - Current per-zone statistics (empty without zones)
+    /// * `graph` - Road network, for street names
+    ///
+    /// # Returns
+    ///
+    /// The trends that started in this interval.
+    pub fn evaluate(&mut self, zones: &[ZoneStats], graph: &RoadGraph) -> Vec<TrendEvent> {
+        let now = Instant::now();
+        let mut events = Vec::new();
+
+        for zone in zones.iter().filter(|zone| zone.vehicle_count >= MIN_SAMPLES) {
+            if let Some(event) = self.observe(TrendScope::Zone, zone.zone.clone(), zone.avg_speed, now) {
+                events.push(event);
+            }
+        }
+
+        for (road_id, (sum, count)) in std::mem::take(&mut self.road_speeds) {
+            if count < MIN_SAMPLES {
+                continue;
+            }
+            if let Some(mut event) = self.observe(TrendScope::Road, road_id.to_string(), sum / count as f64, now) {
+                event.name = graph
+                    .edges_for_way(road_id)
+                    .first()
+                    .and_then(|&edge| graph.edges[edge].name.clone());
+                events.push(event);
+            }
+        }
+
+        // Forget zones and roads nobody reported from within the window
+        let window = self.window;
+        self.series
+            .retain(|_, series| series.last_seen.is_some_and(|seen| now.duration_since(seen) < window));
+        events
+    }
+
+    /// Adds an interval average to a series and checks it for a drop.
+    fn observe(&mut self, scope: TrendScope, target: String, speed: f64, now: Instant) -> Option<TrendEvent> {
+        let series = self.series.entry((scope, target.clone())).or_default();
+        while series.samples.front().is_some_and(|(time, _)| now.duration_since(*time) > self.window) {
+            series.samples.pop_front();
+        }
+        let baseline = series.samples.iter().map(|(_, speed)| *speed).fold(f64::NAN, f64::max);
+        series.samples.push_back((now, speed));
+        series.last_seen = Some(now);
+        if baseline.is_nan() || baseline < MIN_BASELINE_SPEED {
+            return None;
+        }
+
+        let drop = 1.0 - speed / baseline;
+        if series.alerted {
+            series.alerted = drop > self.drop / 2.0;
+            return None;
+        }
+        if drop < self.drop {
+            return None;
+        }
+        series.alerted = true;
+        Some(TrendEvent {
+            kind: "congestion.trend",
+            scope,
+            target,
+            name: None,
+            baseline_speed: baseline,
+            current_speed: speed,
+            drop,
+            window_secs: self.window.as_secs(),
+        })
+    }
+}
@@ -0,0 +1,106 @@
+//! Live per-zone statistics.
+//!
+//! Every position is assigned to the named zones (e.g. districts) it lies
+//! in. The tracker remembers the latest sample per vehicle and periodically
+//! aggregates vehicle counts and average speeds per zone, which the hot path
+//! stores in Redis and publishes to WebSocket clients.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use serde::Serialize;
+use traffic_common::geo::ZoneSet;
+use traffic_common::VehiclePosition;
+
+/// Vehicles not heard from for this long no longer count towards any zone.
+const STALE_AFTER: Duration = Duration::from_secs(60);
+
+/// Latest sample of one vehicle.
+struct VehicleSample {
+    /// Indices of the zones containing the vehicle
+    zones: Vec<usize>,
+    /// Speed in m/s
+    speed: f64,
+    /// When the sample was recorded
+    seen: Instant,
+}
+
+/// Aggregated live statistics of one zone.
+#[derive(Debug, Serialize)]
+pub struct ZoneStats {
+    /// Zone name
+    pub zone: String,
+    /// Official zone identifier, e.g. a district code
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Vehicles currently inside the zone
+    pub vehicle_count: usize,
+    /// Average speed of those vehicles in m/s (0 if the zone is empty)
+    pub avg_speed: f64,
+}
+
+/// Tracks which zone each vehicle is in.
+pub struct ZoneTracker {
+    zones: ZoneSet,
+    vehicles: HashMap<String, VehicleSample>,
+}
+
+impl ZoneTracker {
+    /// Loads zone definitions from a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - JSON array of zones, or GeoJSON district boundaries
+    ///   (see [`ZoneSet::load`])
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn load(path: &str) -> Result<Self> {
+        let zones = ZoneSet::load(path)?;
+        tracing::info!("🗺️ Loaded {} zones from {}", zones.len(), path);
+
+        Ok(Self { zones, vehicles: HashMap::new() })
+    }
+
+    /// Records the latest position of a vehicle.
+    pub fn record(&mut self, position: &VehiclePosition) {
+        let zones = self.zones
+            .indices_containing(position.longitude, position.latitude)
+            .collect();
+        self.vehicles.insert(position.vehicle_id.clone(), VehicleSample {
+            zones,
+            speed: position.speed,
+            seen: Instant::now(),
+        });
+    }
+
+    /// Aggregates statistics for every zone, forgetting stale vehicles.
+    ///
+    /// # Returns
+    ///
+    /// One entry per zone, in definition order, including empty zones.
+    pub fn snapshot(&mut self) -> Vec<ZoneStats> {
+        self.vehicles.retain(|_, sample| sample.seen.elapsed() < STALE_AFTER);
+
+        let mut totals = vec![(0usize, 0.0f64); self.zones.len()];
+        for sample in self.vehicles.values() {
+            for &zone in &sample.zones {
+                totals[zone].0 += 1;
+                totals[zone].1 += sample.speed;
+            }
+        }
+
+        self.zones
+            .zones()
+            .iter()
+            .zip(totals)
+            .map(|(zone, (count, speed_sum))| ZoneStats {
+                zone: zone.name.clone(),
+                id: zone.id.clone(),
+                vehicle_count: count,
+                avg_speed: if count > 0 { speed_sum / count as f64 } else { 0.0 },
+            })
+            .collect()
+    }
+}
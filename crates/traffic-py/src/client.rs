@@ -0,0 +1,101 @@
+//! `traffic_tower.Client`: blocking wrapper of the API client.
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use std::future::Future;
+use tokio::runtime::Runtime;
+use traffic_client::ClientError;
+use crate::to_python;
+
+/// Client for one traffic-api instance.
+///
+/// Calls block until the API answers; the GIL is released meanwhile.
+#[pyclass(module = "traffic_tower", frozen)]
+pub struct Client {
+    inner: traffic_client::Client,
+    runtime: Runtime,
+}
+
+#[pymethods]
+impl Client {
+    /// Creates a client for the API at `base_url`, authenticating with an
+    /// API key or JWT if given.
+    #[new]
+    #[pyo3(signature = (base_url, token = None))]
+    fn new(base_url: &str, token: Option<String>) -> PyResult<Self> {
+        let mut inner = traffic_client::Client::new(base_url).map_err(to_py_err)?;
+        if let Some(token) = token {
+            inner = inner.with_token(token);
+        }
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(Self { inner, runtime })
+    }
+
+    /// Returns the service status and the map it runs on.
+    fn health(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let health = self.block_on(py, self.inner.health())?;
+        to_python(py, &health)
+    }
+
+    /// Returns the latest statistics of all zones.
+    fn zones(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let zones = self.block_on(py, self.inner.zones())?;
+        to_python(py, &zones)
+    }
+
+    /// Returns the incidents, newest first, optionally only those in one
+    /// state (e.g. "open").
+    #[pyo3(signature = (status = None))]
+    fn incidents(&self, py: Python<'_>, status: Option<&str>) -> PyResult<PyObject> {
+        let incidents = self.block_on(py, self.inner.incidents(status))?;
+        to_python(py, &incidents)
+    }
+
+    /// Looks up road details by OSM way ID, limited to `fields` if given.
+    #[pyo3(signature = (ids, fields = None))]
+    fn roads(&self, py: Python<'_>, ids: Vec<i64>, fields: Option<Vec<String>>) -> PyResult<PyObject> {
+        let fields = fields.unwrap_or_default();
+        let fields: Vec<&str> = fields.iter().map(String::as_str).collect();
+        let roads = self.block_on(py, self.inner.roads(&ids, &fields))?;
+        to_python(py, &roads)
+    }
+
+    /// Returns the positions recorded for a vehicle between two Unix
+    /// timestamps (default: the last hour), as dicts with `timestamp`,
+    /// `lon`, `lat` and `speed`, simplified to `tolerance` meters if given.
+    #[pyo3(signature = (vehicle_id, start = None, end = None, tolerance = None))]
+    fn trace(
+        &self,
+        py: Python<'_>,
+        vehicle_id: &str,
+        start: Option<i64>,
+        end: Option<i64>,
+        tolerance: Option<f64>,
+    ) -> PyResult<PyObject> {
+        let trace = self.block_on(py, self.inner.trace(vehicle_id, start, end, tolerance))?;
+        to_python(py, &trace)
+    }
+}
+
+impl Client {
+    /// Runs a request to completion without holding the GIL.
+    fn block_on<T, F>(&self, py: Python<'_>, request: F) -> PyResult<T>
+    where
+        T: Send,
+        F: Future<Output = traffic_client::Result<T>> + Send,
+    {
+        py.allow_threads(|| self.runtime.block_on(request)).map_err(to_py_err)
+    }
+}
+
+/// Maps client errors to Python exceptions: `ValueError` for bad URLs,
+/// `RuntimeError` otherwise.
+fn to_py_err(e: ClientError) -> PyErr {
+    match e {
+        ClientError::Url(_) => PyValueError::new_err(e.to_string()),
+        e => PyRuntimeError::new_err(e.to_string()),
+    }
+}
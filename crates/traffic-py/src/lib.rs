@@ -0,0 +1,58 @@
+//! Python bindings (`traffic_tower` package).
+//!
+//! Notebooks use the same code as the services instead of
+//! re-implementing it in Python:
+//!
+//! - `traffic_tower.Client` pulls live state and vehicle history from the
+//!   API through [`traffic_client`].
+//! - `traffic_tower.RoadNetwork` loads a map with the services' loader and
+//!   cache, snaps points and traces with the ingest map matcher, and
+//!   computes network distances with the router.
+//! - `traffic_tower.simplify_polyline` thins out geometry like `/map` does.
+//!
+//! Results are returned as plain dicts and lists. Build the wheel with
+//! `maturin build --release` in this directory:
+//!
+//! ```text
+//! >>> import traffic_tower
+//! >>> network = traffic_tower.RoadNetwork.load("berlin.osm.pbf")
+//! >>> network.snap(13.405, 52.52)["road_id"]
+//! 4045215
+//! >>> client = traffic_tower.Client("http://localhost:3000", token="tct_...")
+//! >>> trace = client.trace("vehicle-42")
+//! >>> network.snap_trace([(p["lon"], p["lat"]) for p in trace])
+//! ```
+
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use serde::Serialize;
+
+mod client;
+mod network;
+
+/// Converts a serializable value into the equivalent Python dicts, lists
+/// and scalars.
+pub(crate) fn to_python<T: Serialize>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    let json = serde_json::to_string(value).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+    Ok(py.import("json")?.call_method1("loads", (json,))?.unbind())
+}
+
+/// Simplifies a polyline of `(lon, lat)` points, dropping vertices closer
+/// than `tolerance_m` meters to the simplified line.
+#[pyfunction]
+fn simplify_polyline(points: Vec<(f64, f64)>, tolerance_m: f64) -> Vec<(f64, f64)> {
+    let points: Vec<glam::DVec2> = points.into_iter().map(|(lon, lat)| glam::DVec2::new(lon, lat)).collect();
+    traffic_common::map::simplify_polyline(&points, tolerance_m)
+        .into_iter()
+        .map(|point| (point.x, point.y))
+        .collect()
+}
+
+/// The `traffic_tower` Python module.
+#[pymodule]
+fn traffic_tower(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<client::Client>()?;
+    m.add_class::<network::RoadNetwork>()?;
+    m.add_function(wrap_pyfunction!(simplify_polyline, m)?)?;
+    Ok(())
+}
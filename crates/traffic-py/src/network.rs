@@ -0,0 +1,128 @@
+//! `traffic_tower.RoadNetwork`: map loading, map matching and network
+//! distances.
+
+use geo::Point;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use traffic_common::map::{BoundingBox, MatchedPoint, RoadGraph};
+use crate::to_python;
+
+/// A road network loaded from map files.
+#[pyclass(module = "traffic_tower", frozen)]
+pub struct RoadNetwork {
+    graph: RoadGraph,
+}
+
+#[pymethods]
+impl RoadNetwork {
+    /// Loads one or more map files (OSM PBF or XML, GeoJSON), using and
+    /// filling the same cache as the services.
+    ///
+    /// `bbox` is `(min_lon, min_lat, max_lon, max_lat)`.
+    #[staticmethod]
+    #[pyo3(signature = (paths, bbox = None))]
+    fn load(py: Python<'_>, paths: PathArg, bbox: Option<(f64, f64, f64, f64)>) -> PyResult<Self> {
+        let paths = paths.into_vec();
+        let paths: Vec<&str> = paths.iter().map(String::as_str).collect();
+        let bbox = bbox.map(|(min_lon, min_lat, max_lon, max_lat)| BoundingBox { min_lon, min_lat, max_lon, max_lat });
+        let graph = py
+            .allow_threads(|| RoadGraph::load_or_build_many(&paths, bbox))
+            .map_err(|e| PyRuntimeError::new_err(format!("{:#}", e)))?;
+        Ok(Self { graph })
+    }
+
+    /// Number of intersections and other graph nodes.
+    #[getter]
+    fn node_count(&self) -> usize {
+        self.graph.nodes.len()
+    }
+
+    /// Number of directed road segments.
+    #[getter]
+    fn edge_count(&self) -> usize {
+        self.graph.edges.len()
+    }
+
+    /// Snaps a point to the nearest road.
+    ///
+    /// Returns a dict with `edge`, `road_id`, `snapped` (lon, lat),
+    /// `distance_m`, `offset_m` and `confidence` (0 to 1), or `None` for an
+    /// empty network.
+    fn snap(&self, py: Python<'_>, lon: f64, lat: f64) -> PyResult<PyObject> {
+        to_python(py, &self.graph.match_point(Point::new(lon, lat), None, None))
+    }
+
+    /// Matches a GPS trace of `(lon, lat)` points in driving order like
+    /// ingest does, using the direction of travel to tell parallel roads
+    /// apart. Returns one dict per point, as from `snap`.
+    fn snap_trace(&self, py: Python<'_>, points: Vec<(f64, f64)>) -> PyResult<PyObject> {
+        let trace: Vec<Point> = points.into_iter().map(|(lon, lat)| Point::new(lon, lat)).collect();
+        let matched = py.allow_threads(|| self.graph.match_trace(&trace));
+        to_python(py, &matched)
+    }
+
+    /// Returns the length in meters of the shortest drive between two
+    /// `(lon, lat)` points, each snapped to its nearest road first, or
+    /// `None` if the destination cannot be reached.
+    fn network_distance(&self, py: Python<'_>, start: (f64, f64), end: (f64, f64)) -> PyResult<Option<f64>> {
+        let snap = |(lon, lat): (f64, f64)| {
+            self.graph
+                .match_point(Point::new(lon, lat), None, None)
+                .ok_or_else(|| PyValueError::new_err("the network has no roads"))
+        };
+        let (start, end) = (snap(start)?, snap(end)?);
+        Ok(py.allow_threads(|| self.distance_between(&start, &end)))
+    }
+}
+
+impl RoadNetwork {
+    /// Returns the shortest drive from one snapped position to another,
+    /// trying both directions of two-way roads at either end.
+    fn distance_between(&self, start: &MatchedPoint, end: &MatchedPoint) -> Option<f64> {
+        let starts = self.both_directions(start);
+        let ends = self.both_directions(end);
+        starts
+            .iter()
+            .flat_map(|&start| ends.iter().filter_map(move |&end| self.drive(start, end)))
+            .min_by(f64::total_cmp)
+    }
+
+    /// Returns a snapped position as (edge, offset) on its edge and, on
+    /// two-way roads, on the edge in the opposite direction.
+    fn both_directions(&self, point: &MatchedPoint) -> Vec<(usize, f64)> {
+        let mut positions = vec![(point.edge, point.offset_m)];
+        if let Some(reverse) = self.graph.reverse_edge(point.edge) {
+            positions.push((reverse, self.graph.edges[reverse].length - point.offset_m));
+        }
+        positions
+    }
+
+    /// Drives along the rest of the first edge, the shortest path between
+    /// the edges and onto the last one.
+    fn drive(&self, (start_edge, start_offset): (usize, f64), (end_edge, end_offset): (usize, f64)) -> Option<f64> {
+        if start_edge == end_edge && end_offset >= start_offset {
+            return Some(end_offset - start_offset);
+        }
+        let first = &self.graph.edges[start_edge];
+        let last = &self.graph.edges[end_edge];
+        let path = self.graph.shortest_path(first.end, last.start)?;
+        let between: f64 = path.iter().map(|&edge| self.graph.edges[edge].length).sum();
+        Some((first.length - start_offset).max(0.0) + between + end_offset)
+    }
+}
+
+/// A single map path or a list of them.
+#[derive(FromPyObject)]
+enum PathArg {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl PathArg {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            Self::One(path) => vec![path],
+            Self::Many(paths) => paths,
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! Tick-duration benchmark (`traffic-sim bench`).
+//!
+//! Runs the configured scenario offline at full speed and reports how long
+//! ticks take, to size fleets against the 16 ms frame budget or to compare
+//! changes to the systems. Nothing is published to Kafka.
+
+use anyhow::Result;
+use std::time::{Duration, Instant};
+use traffic_common::Config;
+use crate::cli::{BenchArgs, RunArgs};
+use crate::components::{DeltaTime, Fidelity};
+use crate::scenario::Scenario;
+use crate::systems::broadcast::KafkaProducer;
+use crate::{build_schedule, build_world, load_graph, memory, FIXED_TICK_SECS};
+
+/// Runs the benchmark and logs the tick-duration statistics.
+///
+/// # Errors
+///
+/// Returns an error if the scenario is invalid or the map cannot be loaded.
+pub fn run(config: &Config, args: &BenchArgs) -> Result<()> {
+    let run_args = RunArgs {
+        ticks: Some(args.ticks),
+        seed: args.seed,
+        dry_run: true,
+        ..Default::default()
+    };
+    let scenario = Scenario::load(config, &run_args)?;
+    let seed = scenario.seed.unwrap_or_else(rand::random);
+
+    let mut world = build_world(&scenario, load_graph(&scenario)?, KafkaProducer::Offline(Vec::new()), seed);
+    let mut schedule = build_schedule();
+    *world.resource_mut::<DeltaTime>() = DeltaTime(FIXED_TICK_SECS * scenario.time_scale);
+    memory::log_memory_usage(&world);
+    tracing::info!("⏱️ Benchmarking {} ticks with {} vehicles (seed {})", args.ticks, scenario.vehicle_count, seed);
+
+    let mut durations = Vec::with_capacity(args.ticks as usize);
+    let mut frames = 0;
+    for tick in 1..=args.ticks {
+        world.resource_mut::<Fidelity>().tick = tick;
+        let started = Instant::now();
+        schedule.run(&mut world);
+        durations.push(started.elapsed());
+
+        if let KafkaProducer::Offline(sent) = &mut *world.resource_mut::<KafkaProducer>() {
+            frames += sent.len();
+            sent.clear();
+        }
+    }
+
+    let total: Duration = durations.iter().sum();
+    durations.sort_unstable();
+    let percentile = |p: f64| durations[((durations.len() - 1) as f64 * p).round() as usize];
+    tracing::info!(
+        "⏱️ {} ticks in {:.2?} ({:.0} ticks/s, {} telemetry frames): mean {:.2?}, p50 {:.2?}, p95 {:.2?}, p99 {:.2?}, max {:.2?}",
+        args.ticks,
+        total,
+        args.ticks as f64 / total.as_secs_f64(),
+        frames,
+        total.div_f64(args.ticks as f64),
+        percentile(0.5),
+        percentile(0.95),
+        percentile(0.99),
+        durations[durations.len() - 1]
+    );
+    Ok(())
+}
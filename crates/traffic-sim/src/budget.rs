@@ -0,0 +1,80 @@
+//! Frame-budget guard for the real-time loop.
+//!
+//! When ticks take longer than the frame budget, the loop falls behind the
+//! wall clock, the next tick gets a larger delta and even more work, and the
+//! simulation spirals. The guard watches tick durations and lowers the
+//! update rate of ordinary cars (see [`crate::components::LevelOfDetail`])
+//! while the budget is exceeded, restoring full fidelity once there is
+//! headroom again.
+
+use std::time::Duration;
+
+/// Consecutive over-budget ticks before fidelity is reduced (~0.5 s at 60 FPS).
+const OVER_BUDGET_TICKS: u32 = 30;
+
+/// Consecutive ticks with headroom before fidelity is restored (~5 s at 60 FPS).
+const HEADROOM_TICKS: u32 = 300;
+
+/// Fraction of the budget below which a tick counts as having headroom.
+const HEADROOM_FRACTION: f64 = 0.6;
+
+/// Coarsest update stride for reducible vehicles.
+const MAX_STRIDE: u32 = 4;
+
+/// Tracks tick durations against the frame budget.
+#[derive(Debug)]
+pub struct FrameBudgetGuard {
+    budget: Duration,
+    over: u32,
+    under: u32,
+}
+
+impl FrameBudgetGuard {
+    /// Creates a guard for the given per-tick budget.
+    pub fn new(budget: Duration) -> Self {
+        Self { budget, over: 0, under: 0 }
+    }
+
+    /// Records the duration of a tick.
+    ///
+    /// Requires a sustained overrun (or sustained headroom) before acting,
+    /// so single slow ticks don't make fidelity oscillate.
+    ///
+    /// # Arguments
+    ///
+    /// * `elapsed` - Time the tick took
+    /// * `stride` - Current update stride of reducible vehicles
+    ///
+    /// # Returns
+    ///
+    /// The new stride if fidelity should change.
+    pub fn record(&mut self, elapsed: Duration, stride: u32) -> Option<u32> {
+        if elapsed > self.budget {
+            self.over += 1;
+            self.under = 0;
+        } else if elapsed.as_secs_f64() < self.budget.as_secs_f64() * HEADROOM_FRACTION {
+            self.under += 1;
+            self.over = 0;
+        } else {
+            self.over = 0;
+            self.under = 0;
+        }
+
+        if self.over >= OVER_BUDGET_TICKS && stride < MAX_STRIDE {
+            self.over = 0;
+            tracing::warn!(
+                "⚠️ Ticks exceed the {:?} frame budget, updating cars every {} ticks",
+                self.budget,
+                stride * 2
+            );
+            return Some(stride * 2);
+        }
+        if self.under >= HEADROOM_TICKS && stride > 1 {
+            self.under = 0;
+            let stride = stride / 2;
+            tracing::info!("✅ Frame budget headroom regained, updating cars every {} ticks", stride);
+            return Some(stride);
+        }
+        None
+    }
+}
@@ -0,0 +1,97 @@
+//! Command line of the simulator.
+//!
+//! ```text
+//! traffic-sim [run] [--ticks N] [--report PATH] [--seed N] [--check-determinism] [--dry-run]
+//! traffic-sim bench [--ticks N] [--seed N]
+//! traffic-sim replay --from TS [--to TS] [--speed FACTOR]
+//! ```
+//!
+//! Without a subcommand the simulator runs as before (`run`).
+
+use clap::{Args, Parser, Subcommand};
+use traffic_common::cli::CommonArgs;
+
+/// ECS-based vehicle movement simulator.
+#[derive(Debug, Parser)]
+#[command(name = "traffic-sim", version, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(flatten)]
+    pub common: CommonArgs,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Options of `run` when no subcommand is given
+    #[command(flatten)]
+    pub run: RunArgs,
+}
+
+impl Cli {
+    /// Returns the subcommand to execute, defaulting to `run`.
+    pub fn command(self) -> Command {
+        self.command.unwrap_or(Command::Run(self.run))
+    }
+}
+
+/// Subcommands of the simulator.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Simulate the scenario and publish telemetry to Kafka (default)
+    Run(RunArgs),
+    /// Measure tick durations offline, without publishing anything
+    Bench(BenchArgs),
+    /// Publish recorded positions from TimescaleDB to Kafka again
+    Replay(ReplayArgs),
+}
+
+/// Options of `run`, overriding the scenario.
+#[derive(Debug, Clone, Default, Args)]
+pub struct RunArgs {
+    /// Stop after this many ticks and write a KPI report
+    #[arg(long, value_name = "N")]
+    pub ticks: Option<u64>,
+
+    /// Where the KPI report is written (`.json` or `.csv`)
+    #[arg(long, value_name = "PATH")]
+    pub report: Option<String>,
+
+    /// Seed of the random number generator
+    #[arg(long, value_name = "N")]
+    pub seed: Option<u64>,
+
+    /// Run the scenario twice offline and check both runs are identical
+    #[arg(long, requires = "ticks")]
+    pub check_determinism: bool,
+
+    /// Count telemetry instead of publishing it to Kafka
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// Options of `bench`.
+#[derive(Debug, Clone, Args)]
+pub struct BenchArgs {
+    /// Number of ticks to measure
+    #[arg(long, value_name = "N", default_value_t = 600)]
+    pub ticks: u64,
+
+    /// Seed of the random number generator
+    #[arg(long, value_name = "N")]
+    pub seed: Option<u64>,
+}
+
+/// Options of `replay`.
+#[derive(Debug, Clone, Args)]
+pub struct ReplayArgs {
+    /// Start of the recorded window as a Unix timestamp
+    #[arg(long, value_name = "TS")]
+    pub from: i64,
+
+    /// End of the recorded window as a Unix timestamp (default: now)
+    #[arg(long, value_name = "TS")]
+    pub to: Option<i64>,
+
+    /// Playback speed relative to the recording
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0)]
+    pub speed: f64,
+}
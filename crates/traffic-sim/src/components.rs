@@ -5,6 +5,12 @@
 
 use bevy_ecs::prelude::*;
 use glam::Vec2;
+use rand::rngs::StdRng;
+use std::collections::{HashMap, VecDeque};
+use traffic_common::map::{HeuristicTravelTimeModel, Intersection, RoadGraph, TravelTimeModel};
+use traffic_common::control::EmissionRates;
+use traffic_common::signals::{ApproachLight, IntersectionDelay, LightState, SignalPlan};
+use traffic_common::{VehicleClass, VehiclePriority};
 
 // --- RESOURCES (Global simulation data) ---
 
@@ -15,6 +21,139 @@ use glam::Vec2;
 #[derive(Resource, Debug, Clone, Copy)]
 pub struct DeltaTime(pub f32);
 
+/// Run state of the simulation, toggled through the control topic.
+///
+/// While paused, movement systems are skipped and the broadcaster only emits
+/// low-rate keepalive frames flagged as paused.
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct SimState {
+    /// Whether vehicle movement is currently frozen
+    pub paused: bool,
+}
+
+/// Simulated seconds elapsed since the start of the run.
+///
+/// Advances only while the simulation is running, and drives time-dependent
+/// logic such as signal cycles.
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct SimClock(pub f64);
+
+/// Travel-time model used to plan fleet routes.
+///
+/// Defaults to the heuristic model; a trained model (e.g. ONNX-backed) can
+/// be swapped in by replacing this resource.
+#[derive(Resource)]
+pub struct RoutingModel(pub Box<dyn TravelTimeModel>);
+
+impl Default for RoutingModel {
+    fn default() -> Self {
+        Self(Box::new(HeuristicTravelTimeModel::default()))
+    }
+}
+
+/// Active signal plans keyed by OSM node ID of the intersection.
+///
+/// Loaded from the scenario and replaced at runtime via the control topic.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct SignalPlans(pub HashMap<i64, SignalPlan>);
+
+/// [`TrafficLight`] entity of every node with a signal plan, by OSM node ID.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct TrafficLights(pub HashMap<i64, Entity>);
+
+/// Right-of-way rules of the map's intersections, keyed by OSM node ID.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Intersections(pub HashMap<i64, Intersection>);
+
+impl Intersections {
+    /// Classifies the intersections of a road network.
+    pub fn from_graph(graph: &RoadGraph) -> Self {
+        Self(
+            graph
+                .classify_intersections()
+                .into_iter()
+                .map(|intersection| (intersection.node, intersection))
+                .collect(),
+        )
+    }
+
+    /// Returns `true` if the approach from `way_id` must yield at `node_id`.
+    pub fn must_yield(&self, node_id: i64, way_id: i64) -> bool {
+        self.0
+            .get(&node_id)
+            .is_some_and(|intersection| intersection.must_yield(way_id))
+    }
+}
+
+/// Telemetry rates per vehicle priority tier.
+///
+/// Initialized from the scenario and replaced at runtime via the control topic.
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct EmissionPolicy(pub EmissionRates);
+
+/// Random number generator shared by all systems.
+///
+/// Seeded from the scenario so that runs with the same seed make the same
+/// random choices (spawn points, turns, generated tasks).
+#[derive(Resource, Debug, Clone)]
+pub struct SimRng(pub StdRng);
+
+/// Simulation fidelity, lowered by the frame-budget guard under load.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct Fidelity {
+    /// Ticks between updates of reducible vehicles (1 = every tick)
+    pub stride: u32,
+    /// Ticks simulated since startup
+    pub tick: u64,
+}
+
+impl Default for Fidelity {
+    fn default() -> Self {
+        Self { stride: 1, tick: 0 }
+    }
+}
+
+impl Fidelity {
+    /// Returns `true` if a vehicle in update slot `slot` moves this tick.
+    pub fn is_due(&self, slot: u32) -> bool {
+        (self.tick + slot as u64).is_multiple_of(self.stride as u64)
+    }
+}
+
+/// Accumulated waiting time per signalized intersection.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct IntersectionDelays(pub HashMap<i64, IntersectionDelay>);
+
+impl IntersectionDelays {
+    /// Returns the delay record of an intersection, creating it if needed.
+    pub fn entry(&mut self, node_id: i64) -> &mut IntersectionDelay {
+        self.0.entry(node_id).or_insert_with(|| IntersectionDelay {
+            node_id,
+            ..Default::default()
+        })
+    }
+}
+
+/// Remaining warm-up time of the simulation.
+///
+/// Every vehicle starts at offset zero of its segment, which is an
+/// unrealistic transient. Until `remaining` reaches zero, telemetry is either
+/// suppressed or flagged as warm-up, depending on `mode`.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct WarmUp {
+    /// Simulated seconds left before the warm-up ends
+    pub remaining: f32,
+    /// How telemetry is treated while warming up
+    pub mode: crate::scenario::WarmupTelemetry,
+}
+
+impl WarmUp {
+    /// Returns `true` while the warm-up period is still running.
+    pub fn active(&self) -> bool {
+        self.remaining > 0.0
+    }
+}
+
 // --- COMPONENTS (Per-vehicle data) ---
 
 /// Unique identifier for a vehicle entity.
@@ -24,6 +163,74 @@ pub struct DeltaTime(pub f32);
 #[derive(Component, Debug, Clone)]
 pub struct VehicleId(pub String);
 
+/// Priority tier of a vehicle, deciding how often it is broadcast.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Priority(pub VehiclePriority);
+
+/// Kind of vehicle, setting its length and driving dynamics.
+///
+/// Vehicles without a class drive like cars.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Class(pub VehicleClass);
+
+impl Class {
+    /// Length of the vehicle in meters.
+    pub const fn length_m(self) -> f64 {
+        match self.0 {
+            VehicleClass::Car => 5.0,
+            VehicleClass::Bus => 12.0,
+            VehicleClass::Truck => 10.0,
+            VehicleClass::Emergency => 6.0,
+            VehicleClass::Motorcycle => 2.2,
+        }
+    }
+
+    /// Speed the vehicle never exceeds, whatever the speed limit, in m/s.
+    pub const fn max_speed_mps(self) -> f64 {
+        match self.0 {
+            VehicleClass::Car => 50.0,
+            VehicleClass::Bus => 22.0,
+            VehicleClass::Truck => 25.0,
+            VehicleClass::Emergency => 45.0,
+            VehicleClass::Motorcycle => 55.0,
+        }
+    }
+
+    /// Acceleration from standstill on a free road in m/s² (IDM `a`).
+    pub const fn max_acceleration(self) -> f64 {
+        match self.0 {
+            VehicleClass::Car => 1.5,
+            VehicleClass::Bus => 1.0,
+            VehicleClass::Truck => 0.8,
+            VehicleClass::Emergency => 2.0,
+            VehicleClass::Motorcycle => 2.5,
+        }
+    }
+}
+
+impl Default for Class {
+    fn default() -> Self {
+        Self(VehicleClass::Car)
+    }
+}
+
+/// Per-vehicle level of detail.
+///
+/// Under load, reducible vehicles (ordinary cars) are only moved on every
+/// [`Fidelity::stride`]-th tick, staggered by `slot`; the time they skip is
+/// accumulated in `pending` and applied in one step on their next update.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct LevelOfDetail {
+    /// Update slot, spreading reduced updates evenly over the ticks
+    pub slot: u32,
+    /// Whether fidelity may be reduced for this vehicle
+    pub reducible: bool,
+    /// Simulated seconds not yet applied to the vehicle
+    pub pending: f32,
+    /// Simulated seconds applied in the current tick (0 if skipped)
+    pub step: f32,
+}
+
 /// Visual 2D position of a vehicle in geographic coordinates.
 ///
 /// Represents the vehicle's location on the map where:
@@ -40,6 +247,9 @@ pub struct Position(pub Vec2);
 /// Represents the vehicle's current movement direction and speed.
 /// Currently used for physics simulation and may be extended for
 /// collision detection or acceleration modeling.
+///
+/// Measured in degrees of (longitude, latitude) per second, like
+/// `Position`; telemetry publishes the exact `Speed` in m/s instead.
 #[derive(Component, Debug, Clone, Copy)]
 pub struct Velocity(pub Vec2);
 
@@ -56,6 +266,11 @@ pub struct GraphPosition {
     pub distance: f64,
 }
 
+/// Lane a vehicle drives in, counted from the leftmost lane (0) in its
+/// direction of travel, like OSM `turn:lanes`.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Lane(pub u8);
+
 /// Target speed component defining a vehicle's desired velocity.
 ///
 /// Represents the speed the vehicle aims to maintain in meters per second.
@@ -63,4 +278,84 @@ pub struct GraphPosition {
 ///
 /// Typical values range from 10.0 to 20.0 m/s (~36-72 km/h).
 #[derive(Component, Debug, Clone, Copy)]
-pub struct TargetSpeed(pub f32);
\ No newline at end of file
+pub struct TargetSpeed(pub f32);
+
+/// Speed actually achieved along the road during the last tick, in m/s.
+///
+/// Differs from `TargetSpeed` when the vehicle is held up, e.g. at a dead end.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct Speed(pub f32);
+
+/// Instantaneous longitudinal acceleration in m/s².
+///
+/// Broadcast with each update so clients can dead-reckon between frames.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct Acceleration(pub f32);
+
+/// Smoothed heading in degrees clockwise from north (0.0 to 360.0).
+///
+/// Exponentially smoothed so that small geometry kinks don't make vehicle
+/// markers rotate jerkily on the frontend. Starts out as the direction of
+/// the road a vehicle spawns on, so it is meaningful before the first move.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct Heading(pub f32);
+
+/// Convoy (platoon) a vehicle belongs to.
+///
+/// Members of a convoy drive as a unit: followers take the same roads as
+/// the leader and adapt their speed to keep their place behind it.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConvoyId(pub u32);
+
+/// Marks the vehicle at the head of a convoy, which picks the roads.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ConvoyLeader;
+
+/// A vehicle following its convoy's leader.
+///
+/// Followers get the leader's roads fed into their [`Route`] and steer
+/// their target speed towards keeping `gap_m` behind the leader.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ConvoyMember {
+    /// The convoy's leader
+    pub leader: Entity,
+    /// Distance to keep behind the leader along the road, in meters
+    pub gap_m: f64,
+}
+
+/// Planned sequence of edges a vehicle follows instead of random turns.
+///
+/// While `active`, the movement system takes the next edge from `edges` at
+/// the end of each segment and holds the vehicle at the end of the final
+/// edge once the plan is exhausted (the destination). Inactive routes fall
+/// back to the scenario's turn policy (see [`crate::turns`]).
+#[derive(Component, Debug, Clone, Default)]
+pub struct Route {
+    /// Remaining edges to traverse, in order
+    pub edges: VecDeque<usize>,
+    /// Whether the vehicle is currently following a plan
+    pub active: bool,
+}
+
+/// Lights of a signalized intersection, one entity per node with a signal
+/// plan.
+///
+/// Updated from the node's plan every tick by `signal_phase_system`.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct TrafficLight {
+    /// OSM node ID of the intersection
+    pub node_id: i64,
+    /// Light per approach, ordered by way ID
+    pub approaches: Vec<ApproachLight>,
+}
+
+impl TrafficLight {
+    /// Returns the light shown to the approach from `way_id`; roads that
+    /// are not an approach of the plan never get green.
+    pub fn state(&self, way_id: i64) -> LightState {
+        self.approaches
+            .iter()
+            .find(|approach| approach.way_id == way_id)
+            .map_or(LightState::Red, |approach| approach.state)
+    }
+}
@@ -0,0 +1,110 @@
+//! Control topic listener for the simulator.
+//!
+//! Consumes [`SimCommand`]s from Kafka in a background task and hands them to
+//! the simulation loop, which applies them between ticks.
+
+use anyhow::{Context, Result};
+use bevy_ecs::prelude::*;
+use futures::StreamExt;
+use rdkafka::consumer::{Consumer, StreamConsumer};
+use rdkafka::Message;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use traffic_common::control::{SimCommand, CONTROL_TOPIC};
+use traffic_common::Config;
+use traffic_common::map::{ClosureSet, RoadGraph};
+use traffic_common::fleet::TaskStatus;
+use crate::components::{EmissionPolicy, SignalPlans, SimState};
+use crate::systems::broadcast::KafkaProducer;
+use crate::systems::fleet::{publish_task_event, TaskBook};
+use crate::systems::signals::{install_light, remove_light};
+
+/// Starts a background task forwarding control commands from Kafka.
+///
+/// # Returns
+///
+/// A receiver yielding every command received on the control topic.
+///
+/// # Errors
+///
+/// Returns an error if the Kafka consumer cannot be created or subscribed.
+pub fn spawn_control_listener(config: &Config) -> Result<UnboundedReceiver<SimCommand>> {
+    let consumer: StreamConsumer = config
+        .kafka_client()
+        .set("group.id", "traffic-sim-control")
+        .set("auto.offset.reset", "latest")
+        .create()
+        .context("Failed to create control consumer")?;
+    consumer.subscribe(&[CONTROL_TOPIC])?;
+
+    let (tx, rx) = unbounded_channel();
+
+    tokio::spawn(async move {
+        let mut stream = consumer.stream();
+        while let Some(msg_result) = stream.next().await {
+            let Ok(msg) = msg_result else { continue };
+            let Some(payload) = msg.payload() else { continue };
+
+            match SimCommand::from_json(payload) {
+                Ok(command) => {
+                    if tx.send(command).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => tracing::warn!("Ignoring malformed control command: {}", e),
+            }
+        }
+    });
+
+    Ok(rx)
+}
+
+/// Applies a single control command to the simulation world.
+pub fn apply_command(world: &mut World, command: SimCommand) {
+    tracing::info!("🎛️ Control command received: {:?}", command);
+
+    match command {
+        SimCommand::Pause => world.resource_mut::<SimState>().paused = true,
+        SimCommand::Resume => world.resource_mut::<SimState>().paused = false,
+        SimCommand::SetSignalPlan(plan) => {
+            if let Err(e) = plan.validate() {
+                tracing::warn!("Rejecting signal plan: {}", e);
+                return;
+            }
+            let node_id = plan.node_id;
+            world.resource_mut::<SignalPlans>().0.insert(node_id, plan);
+            install_light(world, node_id);
+        }
+        SimCommand::ClearSignalPlan { node_id } => {
+            world.resource_mut::<SignalPlans>().0.remove(&node_id);
+            remove_light(world, node_id);
+        }
+        SimCommand::CreateTask(task) => {
+            publish_task_event(world.resource::<KafkaProducer>(), &task, TaskStatus::Pending, None);
+            world.resource_mut::<TaskBook>().pending.push_back(task);
+        }
+        SimCommand::SetEmissionRates(rates) => {
+            if let Err(e) = rates.validate() {
+                tracing::warn!("Rejecting emission rates: {}", e);
+                return;
+            }
+            world.resource_mut::<EmissionPolicy>().0 = rates;
+        }
+        SimCommand::CloseRoad { way_id } => {
+            world.resource_scope(|world, mut closures: Mut<ClosureSet>| {
+                if closures.close_way(world.resource::<RoadGraph>(), way_id) == 0 {
+                    tracing::warn!("Cannot close unknown way {}", way_id);
+                }
+            });
+        }
+        SimCommand::ReopenRoad { way_id } => {
+            world.resource_scope(|world, mut closures: Mut<ClosureSet>| {
+                closures.reopen_way(world.resource::<RoadGraph>(), way_id);
+            });
+        }
+    }
+}
+
+/// Run condition that is true while the simulation is not paused.
+pub fn sim_running(state: Res<SimState>) -> bool {
+    !state.paused
+}
@@ -0,0 +1,78 @@
+//! Determinism check for the simulator.
+//!
+//! Runs the same scenario twice offline with the same seed and compares the
+//! telemetry both runs produce, frame by frame. Any difference means some
+//! system depends on something other than the seed and the scenario (e.g.
+//! `HashMap` iteration order, thread scheduling or the wall clock), which
+//! would make runs impossible to reproduce.
+
+use anyhow::{bail, Context, Result};
+use prost::Message;
+use traffic_common::VehiclePosition;
+use crate::components::DeltaTime;
+use crate::scenario::Scenario;
+use crate::systems::broadcast::KafkaProducer;
+use crate::{build_schedule, build_world, load_graph, FIXED_TICK_SECS};
+
+/// Runs the scenario twice and fails if the telemetry differs.
+///
+/// Both runs use the scenario's seed (or one random seed shared by both)
+/// and a fixed time step, and publish nothing to Kafka. Frame timestamps
+/// come from the wall clock and are excluded from the comparison.
+///
+/// # Errors
+///
+/// Returns an error if the map cannot be loaded, the scenario has no tick
+/// count, or the two runs diverge; the error names the first diverging
+/// tick and frame.
+pub fn check(scenario: &Scenario) -> Result<()> {
+    let ticks = scenario.ticks.context("determinism check needs a tick count (--ticks)")?;
+    let seed = scenario.seed.unwrap_or_else(rand::random);
+    tracing::info!("🔁 Checking determinism: 2 runs of {} ticks with seed {}", ticks, seed);
+
+    let first = run_offline(scenario, seed, ticks)?;
+    let second = run_offline(scenario, seed, ticks)?;
+
+    for (tick, (a, b)) in first.iter().zip(&second).enumerate() {
+        if a.len() != b.len() {
+            bail!("Runs diverged at tick {}: {} vs {} telemetry frames", tick + 1, a.len(), b.len());
+        }
+        if let Some(frame) = a.iter().zip(b).position(|(x, y)| x != y) {
+            let vehicle = VehiclePosition::decode(a[frame].as_slice()).map(|p| p.vehicle_id).unwrap_or_default();
+            bail!("Runs diverged at tick {}, frame {} (vehicle {})", tick + 1, frame, vehicle);
+        }
+    }
+
+    let frames: usize = first.iter().map(Vec::len).sum();
+    tracing::info!("✅ Runs are identical: {} ticks, {} telemetry frames", ticks, frames);
+    Ok(())
+}
+
+/// Runs the scenario without Kafka and collects the telemetry of every tick.
+fn run_offline(scenario: &Scenario, seed: u64, ticks: u64) -> Result<Vec<Vec<Vec<u8>>>> {
+    let mut world = build_world(scenario, load_graph(scenario)?, KafkaProducer::Offline(Vec::new()), seed);
+    let mut schedule = build_schedule();
+    *world.resource_mut::<DeltaTime>() = DeltaTime(FIXED_TICK_SECS * scenario.time_scale);
+
+    let mut telemetry = Vec::with_capacity(ticks as usize);
+    for _ in 0..ticks {
+        schedule.run(&mut world);
+
+        let KafkaProducer::Offline(frames) = &mut *world.resource_mut::<KafkaProducer>() else {
+            unreachable!("offline run uses an offline producer");
+        };
+        telemetry.push(std::mem::take(frames).into_iter().map(without_timestamp).collect());
+    }
+    Ok(telemetry)
+}
+
+/// Clears the wall-clock timestamp of an encoded telemetry frame.
+fn without_timestamp(frame: Vec<u8>) -> Vec<u8> {
+    match VehiclePosition::decode(frame.as_slice()) {
+        Ok(mut position) => {
+            position.timestamp = 0;
+            position.encode_to_vec()
+        }
+        Err(_) => frame,
+    }
+}
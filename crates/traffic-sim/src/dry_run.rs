@@ -0,0 +1,74 @@
+//! Dry-run telemetry accounting.
+//!
+//! With `--dry-run` the simulator runs as usual but publishes nothing to
+//! Kafka; telemetry frames are counted and dropped instead, and throughput
+//! is logged periodically. This validates scenarios and maps in CI or
+//! air-gapped environments without a broker.
+
+use bevy_ecs::world::World;
+use std::time::{Duration, Instant};
+use traffic_common::telemetry::format_bytes;
+use crate::systems::broadcast::KafkaProducer;
+
+/// Interval between two throughput log lines.
+const LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Counts telemetry produced by an offline producer.
+pub struct TelemetryStats {
+    frames: u64,
+    bytes: u64,
+    window_frames: u64,
+    window_bytes: u64,
+    window_start: Instant,
+}
+
+impl TelemetryStats {
+    /// Creates empty statistics starting now.
+    pub fn new() -> Self {
+        Self {
+            frames: 0,
+            bytes: 0,
+            window_frames: 0,
+            window_bytes: 0,
+            window_start: Instant::now(),
+        }
+    }
+
+    /// Counts and drops the frames produced since the last call, logging
+    /// throughput every [`LOG_INTERVAL`].
+    pub fn record(&mut self, world: &mut World) {
+        let KafkaProducer::Offline(frames) = &mut *world.resource_mut::<KafkaProducer>() else { return };
+        self.window_frames += frames.len() as u64;
+        self.window_bytes += frames.iter().map(|frame| frame.len() as u64).sum::<u64>();
+        frames.clear();
+
+        let elapsed = self.window_start.elapsed();
+        if elapsed >= LOG_INTERVAL {
+            tracing::info!(
+                "🧪 Dry run: {:.0} frames/s ({}/s) would have been published",
+                self.window_frames as f64 / elapsed.as_secs_f64(),
+                format_bytes((self.window_bytes as f64 / elapsed.as_secs_f64()) as u64)
+            );
+            self.flush_window();
+        }
+    }
+
+    /// Logs the totals of the run.
+    pub fn finish(mut self) {
+        self.flush_window();
+        tracing::info!(
+            "🧪 Dry run finished: {} telemetry frames ({}) not published",
+            self.frames,
+            format_bytes(self.bytes)
+        );
+    }
+
+    /// Adds the current window to the totals and starts a new one.
+    fn flush_window(&mut self) {
+        self.frames += self.window_frames;
+        self.bytes += self.window_bytes;
+        self.window_frames = 0;
+        self.window_bytes = 0;
+        self.window_start = Instant::now();
+    }
+}
@@ -3,129 +3,439 @@
 //! This service simulates realistic vehicle movement on a road network using
 //! the Bevy ECS framework. It spawns vehicles on the road graph, simulates
 //! their movement, and broadcasts position updates to Kafka for downstream
-//! processing.
+//! processing. Runs are reproducible from their random seed, which
+//! `--check-determinism` verifies. With `--dry-run` nothing is published,
+//! which validates scenarios and maps without a Kafka broker. SIGTERM or
+//! Ctrl-C stop a run gracefully, flushing buffered telemetry first. See
+//! [`cli`] for the `bench` and `replay` subcommands.
 
+mod bench;
+mod budget;
+mod cli;
 mod components;
+mod control;
+mod determinism;
+mod dry_run;
+mod memory;
+mod replay;
+mod road_speeds;
+mod scenario;
+mod shutdown;
 mod systems;
+mod turns;
 
 use bevy_ecs::prelude::*;
+use budget::FrameBudgetGuard;
+use clap::Parser;
+use cli::{Cli, Command, RunArgs};
 use components::*;
+use control::{apply_command, sim_running, spawn_control_listener};
+use dry_run::TelemetryStats;
+use road_speeds::{apply_road_speeds, spawn_road_speed_listener};
+use scenario::{ClassMix, FleetConfig, Scenario};
 use systems::movement::*;
 use systems::broadcast::*;
-use traffic_common::{init_tracing, Config};
-use traffic_common::map::RoadGraph;
-use glam::Vec2;
-use rand::Rng;
+use systems::warmup::*;
+use systems::kpi::*;
+use systems::clock::*;
+use systems::signals::*;
+use systems::fleet::*;
+use systems::convoy::*;
+use systems::lanes::*;
+use systems::car_following::*;
+use systems::checkpoints::*;
+use systems::spatial::*;
+use traffic_common::{Config, VehicleClass, VehiclePriority};
+use traffic_common::fleet::FleetKind;
+use traffic_common::startup::wait_for_kafka;
+use traffic_common::config::split_map_paths;
+use traffic_common::geo::BoundingBox;
+use traffic_common::map::{default_highway_weights, ClosureSet, CompactRoadGraph, DynamicWeights, ElevationModel, PoiSet, RoadGraph};
+use glam::{DVec2, Vec2};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use std::time::{Duration, Instant};
 use anyhow::Result;
-use rdkafka::config::ClientConfig;
 use rdkafka::producer::FutureProducer;
+use tokio::sync::mpsc::unbounded_channel;
+
+/// Wall-clock seconds represented by one tick of a finite run (60 FPS).
+const FIXED_TICK_SECS: f32 = 1.0 / 60.0;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    init_tracing("traffic-sim");
-    let config = Config::from_env()?;
+    let cli = Cli::parse();
+    let config = cli.common.init("traffic-sim")?;
 
-    let mut world = World::new();
+    match cli.command() {
+        Command::Run(args) => run(&config, &args).await,
+        Command::Bench(args) => bench::run(&config, &args),
+        Command::Replay(args) => replay::run(&config, &args).await,
+    }
+}
 
-    // Load the road network map
-    let map_path = "crates/traffic-sim/assets/berlin.osm.pbf";
-    let road_graph = RoadGraph::load_from_pbf(map_path)?;
+/// Runs the simulation, live or for a finite number of ticks.
+///
+/// # Errors
+///
+/// Returns an error if the scenario is invalid, the map cannot be loaded,
+/// Kafka is unreachable, or the KPI report cannot be written.
+async fn run(config: &Config, args: &RunArgs) -> Result<()> {
+    let scenario = Scenario::load(config, args)?;
 
-    // Initialize ECS resources
-    world.insert_resource(DeltaTime(1.0 / 60.0));
-    world.insert_resource(BroadcastCounter(0));
+    if scenario.check_determinism {
+        return determinism::check(&scenario);
+    }
 
-    // Create Kafka producer for telemetry broadcasting
-    let producer: FutureProducer = ClientConfig::new()
-        .set("bootstrap.servers", &config.kafka_brokers)
-        .set("message.timeout.ms", "5000")
-        .create()?;
-    world.insert_resource(KafkaProducer(producer));
+    let seed = scenario.seed.unwrap_or_else(rand::random);
+    tracing::info!("🎲 Random seed: {} (pass --seed to reproduce)", seed);
 
-    // Configure ECS system schedule
-    let mut schedule = Schedule::default();
-    schedule.add_systems((
-        movement_system,      // Vehicle movement along roads
-        sync_position_system, // Synchronize graph position to visual position
-        broadcast_system,     // Send telemetry to Kafka
-    ));
+    // Dry runs count telemetry instead of publishing it and need no broker
+    let producer = if scenario.dry_run {
+        tracing::info!("🧪 Dry run: nothing will be published to Kafka");
+        KafkaProducer::Offline(Vec::new())
+    } else {
+        // Kafka may still be booting (e.g. under docker-compose)
+        wait_for_kafka(&config.kafka_client(), config.startup_timeout()).await?;
 
-    // Spawn vehicles on the road network (before inserting graph as resource)
-    spawn_vehicles_on_graph(&mut world, &road_graph, 5000);
+        // Create Kafka producer for telemetry broadcasting
+        let producer: FutureProducer = config
+            .kafka_client()
+            .set("message.timeout.ms", "5000")
+            .create()?;
+        KafkaProducer::Live(producer)
+    };
+    let mut telemetry = scenario.dry_run.then(TelemetryStats::new);
 
-    // Insert road graph as ECS resource after spawning
-    world.insert_resource(road_graph);
+    let mut world = build_world(&scenario, load_graph(&scenario)?, producer, seed);
+    if scenario.pois {
+        world.insert_resource(PoiSet::load_many(&scenario.map_paths(), scenario.map_bbox)?);
+    }
+    let mut schedule = build_schedule();
+    memory::log_memory_usage(&world);
+
+    // Listen for pause/resume commands from the control topic
+    let mut control_rx = if scenario.dry_run {
+        unbounded_channel().1
+    } else {
+        spawn_control_listener(config)?
+    };
+
+    // Route the fleet around congestion reported by ingest
+    let mut road_speeds_rx = if scenario.dry_run {
+        unbounded_channel().1
+    } else {
+        spawn_road_speed_listener(config)?
+    };
+
+    // Stop gracefully on SIGTERM/Ctrl-C instead of dropping buffered telemetry
+    let mut shutdown_rx = shutdown::spawn_shutdown_listener();
 
     tracing::info!("🚀 Simulation loop starting...");
 
     let mut last_tick = Instant::now();
     let target_frametime = Duration::from_millis(16); // 60 FPS
+    let mut budget = FrameBudgetGuard::new(target_frametime);
+    let mut tick: u64 = 0;
 
     // Main simulation loop
     loop {
+        // Finite runs stop after the requested number of ticks
+        if scenario.ticks.is_some_and(|limit| tick >= limit) {
+            break;
+        }
+        if shutdown_rx.try_recv().is_ok() {
+            tracing::info!("🛑 Shutdown requested after {} ticks", tick);
+            break;
+        }
+        tick += 1;
+
         let now = Instant::now();
         let delta = (now - last_tick).as_secs_f32();
         last_tick = now;
 
-        // Apply time acceleration (10x real-time)
-        let time_scale = 10.0;
-        *world.resource_mut::<DeltaTime>() = DeltaTime(delta * time_scale);
+        // Finite runs use a fixed step and run as fast as possible;
+        // live runs follow the wall clock
+        let delta = if scenario.ticks.is_some() { FIXED_TICK_SECS } else { delta };
+
+        // Apply time acceleration
+        *world.resource_mut::<DeltaTime>() = DeltaTime(delta * scenario.time_scale);
+
+        // Apply pending control commands between ticks
+        while let Ok(command) = control_rx.try_recv() {
+            apply_command(&mut world, command);
+        }
+        while let Ok(speeds) = road_speeds_rx.try_recv() {
+            apply_road_speeds(&mut world, &speeds);
+        }
 
         // Execute all systems
+        world.resource_mut::<Fidelity>().tick = tick;
         schedule.run(&mut world);
+        if let Some(stats) = &mut telemetry {
+            stats.record(&mut world);
+        }
 
-        // Maintain consistent frame rate
+        // Maintain consistent frame rate; live runs trade fidelity for
+        // keeping up with the wall clock
         let elapsed = Instant::now() - now;
-        if elapsed < target_frametime {
-            tokio::time::sleep(target_frametime - elapsed).await;
+        if scenario.ticks.is_none() {
+            let mut fidelity = world.resource_mut::<Fidelity>();
+            if let Some(stride) = budget.record(elapsed, fidelity.stride) {
+                fidelity.stride = stride;
+            }
+            if elapsed < target_frametime {
+                tokio::time::sleep(target_frametime - elapsed).await;
+            }
         }
     }
+
+    // Publish the last positions and wait for buffered telemetry
+    shutdown::drain(&mut world);
+
+    // Summarize the run
+    if let Some(stats) = telemetry {
+        stats.finish();
+    }
+    let checkpoints = world.resource::<Checkpoints>();
+    if !checkpoints.is_empty() {
+        tracing::info!("🚧 {} checkpoint crossings published", checkpoints.crossings);
+    }
+    let report = KpiReport::from_accumulator(
+        world.resource::<KpiAccumulator>(),
+        world.resource::<IntersectionDelays>(),
+        tick,
+        scenario.vehicle_count,
+    );
+    report.write(&scenario.report_path)?;
+    tracing::info!("📊 KPI report written to {}: {:?}", scenario.report_path, report);
+
+    Ok(())
+}
+
+/// Loads the scenario's road network and applies its post-load passes.
+///
+/// # Errors
+///
+/// Returns an error if a map file cannot be loaded.
+fn load_graph(scenario: &Scenario) -> Result<RoadGraph> {
+    let mut road_graph = RoadGraph::load_or_build_many(&scenario.map_paths(), scenario.map_bbox)?;
+    road_graph.prune_components(scenario.map_components);
+    if scenario.metric_projection {
+        road_graph.enable_projection();
+    }
+    if let Some(paths) = &scenario.elevation_path {
+        let model = ElevationModel::load(&split_map_paths(paths))?;
+        let graded = road_graph.apply_elevation(&model);
+        tracing::info!("⛰️ {} of {} roads graded from {} elevation tiles", graded, road_graph.edges.len(), model.tile_count());
+    }
+    Ok(road_graph)
+}
+
+/// Creates the ECS world for a scenario: all resources plus the spawned vehicles.
+///
+/// # Arguments
+///
+/// * `scenario` - Parameters of the run
+/// * `road_graph` - Road network to simulate on
+/// * `producer` - Kafka output (live, or offline for determinism checks)
+/// * `seed` - Seed of the shared random number generator
+fn build_world(scenario: &Scenario, road_graph: RoadGraph, producer: KafkaProducer, seed: u64) -> World {
+    let mut world = World::new();
+
+    // Initialize ECS resources
+    world.insert_resource(DeltaTime(1.0 / 60.0));
+    world.insert_resource(BroadcastCounter(0));
+    world.insert_resource(SimState::default());
+    world.insert_resource(WarmUp {
+        remaining: scenario.warmup_seconds,
+        mode: scenario.warmup_telemetry,
+    });
+    world.insert_resource(KpiAccumulator::default());
+    world.insert_resource(SimClock::default());
+    world.insert_resource(RoutingModel::default());
+    world.insert_resource(SignalPlans(
+        scenario.signal_plans.iter().map(|plan| (plan.node_id, plan.clone())).collect(),
+    ));
+    world.insert_resource(TrafficLights::default());
+    world.insert_resource(IntersectionDelays::default());
+    world.insert_resource(QueuePositions::default());
+    world.insert_resource(FollowingSpeeds::default());
+    world.insert_resource(Intersections::from_graph(&road_graph));
+    world.insert_resource(SignalMetricsTimer::default());
+    world.insert_resource(TaskBook::new(&scenario.fleet));
+    world.insert_resource(EmissionPolicy(scenario.emission));
+    world.insert_resource(TelemetryTopics::new(&scenario.telemetry_topics, scenario.time_scale));
+    world.insert_resource(Fidelity::default());
+    world.insert_resource(PoiSet::default());
+    world.insert_resource(ClosureSet::default());
+    world.insert_resource(scenario.turn_choice.policy());
+    world.insert_resource(DynamicWeights::default());
+    world.insert_resource(Checkpoints::resolve(&scenario.checkpoints, &road_graph));
+    world.insert_resource(producer);
+
+    // Spawn vehicles on the road network (before inserting graph as resource)
+    let mut rng = StdRng::seed_from_u64(seed);
+    spawn_vehicles_on_graph(&mut world, &road_graph, scenario, &mut rng);
+    world.insert_resource(SimRng(rng));
+
+    // Insert road graph as ECS resource after spawning
+    // Flat copy of the topology for the movement systems
+    let compact = CompactRoadGraph::from(&road_graph);
+    if scenario.osm_signals {
+        let plans = default_plans(&compact, &road_graph.signals, world.resource::<SignalPlans>());
+        tracing::info!("🚦 {} signalized nodes of the map get a default plan", plans.len());
+        world.resource_mut::<SignalPlans>().0.extend(plans.into_iter().map(|plan| (plan.node_id, plan)));
+    }
+    world.insert_resource(compact);
+    let center = BoundingBox::enclosing(road_graph.nodes.values().map(|node| (node.pos.x, node.pos.y)))
+        .map_or(DVec2::ZERO, |bbox| DVec2::new((bbox.min_lon + bbox.max_lon) / 2.0, (bbox.min_lat + bbox.max_lat) / 2.0));
+    world.insert_resource(SpatialGrid::new(center));
+    world.insert_resource(road_graph);
+
+    // One traffic light per signal plan, in node order for determinism
+    let mut nodes: Vec<i64> = world.resource::<SignalPlans>().0.keys().copied().collect();
+    nodes.sort_unstable();
+    for node_id in nodes {
+        install_light(&mut world, node_id);
+    }
+
+    world
+}
+
+/// Builds the per-tick system schedule.
+fn build_schedule() -> Schedule {
+    let mut schedule = Schedule::default();
+    schedule.add_systems((
+        (
+            clock_system,         // Advance simulation time
+            signal_phase_system,  // Cycle traffic lights and publish changes
+            lane_queue_system,    // Keep lanes valid and queue per lane at red signals
+            car_following_system, // Keep the distance to the vehicle ahead (IDM)
+            movement_system,      // Vehicle movement along roads
+            convoy_system,        // Keep convoy followers behind their leader
+            checkpoint_system,    // Publish vehicles passing checkpoints
+            sync_position_system, // Synchronize graph position to visual position
+            spatial_grid_system,  // Bucket vehicles into grid cells for neighbour queries
+            heading_system,       // Smooth heading from the latest displacement
+            kpi_system,           // Accumulate scenario KPIs (after warm-up)
+            warmup_system,        // Count down the initial warm-up period
+            signal_metrics_system, // Publish per-intersection delay metrics
+            fleet_system,         // Generate, dispatch and serve fleet tasks
+        ).chain().run_if(sim_running),
+        broadcast_system,     // Send telemetry to Kafka (keepalives while paused)
+    ).chain());
+    schedule
 }
 
 /// Spawns vehicles at random positions on the road network.
 ///
-/// Each vehicle is placed at the start of a randomly selected road segment
-/// with a random target speed. The vehicles are assigned unique IDs and
-/// initialized with both visual and graph-based positions.
+/// Each vehicle is placed at the start of a road segment sampled with
+/// probability proportional to its length and road class importance, with
+/// a random target speed.
+/// The vehicles are assigned unique IDs and initialized with both visual
+/// and graph-based positions.
 ///
 /// # Arguments
 ///
 /// * `world` - The ECS world to spawn entities into
 /// * `graph` - Road network graph (passed separately before becoming a resource)
-/// * `count` - Number of vehicles to spawn
+/// * `scenario` - Number of vehicles and their composition: the fleet
+///   (the first vehicles become taxis and vans), emergency and transit
+///   vehicles after it, then the convoys, and the class mix of the
+///   ordinary traffic
+/// * `rng` - Random number generator for spawn points, classes and speeds
 ///
 /// # Behavior
 ///
-/// - Randomly selects road segments for each vehicle
-/// - Places vehicles at the start of their assigned road
+/// - Samples road segments via `RoadGraph::sample_spawn_points` using the
+///   default highway class weights
+/// - Places vehicles at the start of their assigned road in a random lane;
+///   convoy members share their leader's road and lane, lined up behind it
+/// - Gives transit vehicles the bus class, emergency vehicles the emergency
+///   class, fleet vehicles the car class and draws the class of the others
+///   from the scenario's class mix
 /// - Assigns random speeds between 10-20 m/s
-/// - Skips roads with no geometry data
-fn spawn_vehicles_on_graph(world: &mut World, graph: &RoadGraph, count: usize) {
-    let mut rng = rand::thread_rng();
-    let edge_count = graph.edges.len();
-
-    if edge_count == 0 {
+fn spawn_vehicles_on_graph(world: &mut World, graph: &RoadGraph, scenario: &Scenario, rng: &mut StdRng) {
+    let Scenario { vehicle_count: count, fleet, priorities, convoys, classes, .. } = scenario;
+    let count = *count;
+    let spawn_edges = graph.sample_spawn_points(count, &default_highway_weights(), rng);
+    if spawn_edges.is_empty() {
         tracing::error!("Zero roads found! Cannot spawn vehicles.");
         return;
     }
 
     tracing::info!("🅿️ Spawning {} vehicles on random roads...", count);
 
-    for i in 0..count {
-        // Select a random road segment
-        let edge_idx = rng.gen_range(0..edge_count);
-        let road = &graph.edges[edge_idx];
+    let mut convoy_leader = None;
+    let mut convoy_lane = 0;
+    for (i, &sampled_edge) in spawn_edges.iter().enumerate() {
+        // Then come the convoys: (convoy, position in the convoy)
+        let special = fleet_size(fleet) + priorities.emergency + priorities.transit;
+        let convoy = i
+            .checked_sub(special)
+            .map(|j| (j / convoys.size, j % convoys.size))
+            .filter(|&(convoy, _)| convoy < convoys.count);
 
-        if road.geometry.is_empty() {
-            continue;
-        }
+        // Convoy members start on their leader's road, lined up behind it
+        let (edge_idx, distance) = match convoy {
+            Some((_, member)) => {
+                let edge_idx = spawn_edges[i - member];
+                let length = graph.edges[edge_idx].length;
+                let leader_distance = ((convoys.size - 1) as f64 * convoys.gap_m).min(0.9 * length);
+                (edge_idx, (leader_distance - member as f64 * convoys.gap_m).max(0.0))
+            }
+            None => (sampled_edge, 0.0), // At the start of the segment
+        };
+        let road = &graph.edges[edge_idx];
 
         // Place vehicle at the start of the road
         let start_pos = road.geometry[0];
 
-        world.spawn((
-            VehicleId(format!("car_{}", i)),
+        // Any lane of the road; convoys keep to their leader's lane
+        let lane = match convoy {
+            Some((_, member)) if member > 0 => convoy_lane,
+            _ if road.lane_count() > 1 => rng.gen_range(0..road.lane_count()),
+            _ => 0,
+        };
+        if convoy.is_some() {
+            convoy_lane = lane;
+        }
+
+        // The first vehicles form the fleet; the rest is background traffic
+        let fleet_kind = if i < fleet.taxis {
+            Some(FleetKind::Taxi)
+        } else if i < fleet.taxis + fleet.delivery_vans {
+            Some(FleetKind::DeliveryVan)
+        } else {
+            None
+        };
+        // Followed by the raised priority tiers
+        let priority = match i.checked_sub(fleet_size(fleet)) {
+            Some(j) if j < priorities.emergency => VehiclePriority::PriorityEmergency,
+            Some(j) if j < priorities.emergency + priorities.transit => VehiclePriority::PriorityTransit,
+            _ => VehiclePriority::PriorityCar,
+        };
+        let class = match (fleet_kind, priority) {
+            (_, VehiclePriority::PriorityEmergency) => VehicleClass::Emergency,
+            (_, VehiclePriority::PriorityTransit) => VehicleClass::Bus,
+            (Some(_), VehiclePriority::PriorityCar) => VehicleClass::Car,
+            (None, VehiclePriority::PriorityCar) => sample_class(classes, rng),
+        };
+        let id = match (fleet_kind, class) {
+            (Some(FleetKind::Taxi), _) => format!("taxi_{}", i),
+            (Some(FleetKind::DeliveryVan), _) => format!("van_{}", i),
+            (None, VehicleClass::Emergency) => format!("emergency_{}", i),
+            (None, VehicleClass::Bus) => format!("bus_{}", i),
+            (None, VehicleClass::Truck) => format!("truck_{}", i),
+            (None, VehicleClass::Motorcycle) => format!("motorcycle_{}", i),
+            (None, VehicleClass::Car) => format!("car_{}", i),
+        };
+
+        let mut vehicle = world.spawn((
+            VehicleId(id),
+            Priority(priority),
+            Class(class),
 
             // Visual position for frontend rendering
             Position(Vec2::new(start_pos.x as f32, start_pos.y as f32)),
@@ -133,13 +443,62 @@ fn spawn_vehicles_on_graph(world: &mut World, graph: &RoadGraph, count: usize) {
             // Logical position on the road graph
             GraphPosition {
                 edge_index: edge_idx,
-                distance: 0.0, // At the start of the segment
+                distance,
             },
+            Lane(lane),
 
             Velocity(Vec2::ZERO), // Initially stationary
             TargetSpeed(rng.gen_range(10.0..20.0)), // Random speed in m/s
+            Speed::default(),
+            Acceleration::default(),
+            // Facing down the road, so it reports a real heading before moving
+            Heading(heading_along_polyline(&road.geometry, distance / road.length.max(1e-9))),
+            EdgeTrip { edge_index: edge_idx, entered_at: 0.0 },
+            PreviousEdge(edge_idx),
+            // Only ordinary cars driving alone are updated less often under load
+            LevelOfDetail {
+                slot: i as u32,
+                reducible: fleet_kind.is_none() && priority == VehiclePriority::PriorityCar && convoy.is_none(),
+                ..Default::default()
+            },
         ));
+
+        if let Some(kind) = fleet_kind {
+            vehicle.insert((FleetVehicle::new(kind), Route::default()));
+        }
+
+        match (convoy, convoy_leader) {
+            (Some((convoy, 0)), _) => {
+                vehicle.insert((ConvoyId(convoy as u32), ConvoyLeader));
+                convoy_leader = Some(vehicle.id());
+            }
+            (Some((convoy, member)), Some(leader)) => {
+                vehicle.insert((
+                    ConvoyId(convoy as u32),
+                    ConvoyMember { leader, gap_m: member as f64 * convoys.gap_m },
+                    Route::default(),
+                ));
+            }
+            _ => {}
+        }
     }
 
     tracing::info!("✅ {} vehicles spawned.", count);
-}
\ No newline at end of file
+}
+
+/// Draws the class of an ordinary vehicle from the class mix.
+fn sample_class(classes: &ClassMix, rng: &mut StdRng) -> VehicleClass {
+    let draw: f64 = rng.gen();
+    if draw < classes.trucks {
+        VehicleClass::Truck
+    } else if draw < classes.trucks + classes.motorcycles {
+        VehicleClass::Motorcycle
+    } else {
+        VehicleClass::Car
+    }
+}
+
+/// Returns the number of fleet vehicles.
+fn fleet_size(fleet: &FleetConfig) -> usize {
+    fleet.taxis + fleet.delivery_vans
+}
@@ -0,0 +1,48 @@
+//! Startup memory report for the simulator.
+//!
+//! Logs how much memory the road graph and the ECS component storage take,
+//! next to the resident size of the process, so operators can tell whether a
+//! bigger map or more vehicles will fit on a machine.
+
+use bevy_ecs::world::World;
+use traffic_common::map::{CompactRoadGraph, RoadGraph};
+use traffic_common::resident_memory_bytes;
+use traffic_common::telemetry::format_bytes;
+
+/// Estimates the memory of all component storage in bytes.
+///
+/// Counts the inline size of every component of every entity; heap data
+/// owned by components (e.g. route node lists) comes on top.
+pub fn ecs_storage_bytes(world: &World) -> usize {
+    let components = world.components();
+    world
+        .archetypes()
+        .iter()
+        .map(|archetype| {
+            let row: usize = archetype
+                .components()
+                .filter_map(|id| components.get_info(id))
+                .map(|info| info.layout().size())
+                .sum();
+            row * archetype.len()
+        })
+        .sum()
+}
+
+/// Logs the memory used by the road graphs, the ECS storage and the process.
+pub fn log_memory_usage(world: &World) {
+    if let Some(graph) = world.get_resource::<RoadGraph>() {
+        tracing::info!("🧠 Road graph memory: {}", graph.memory_usage());
+    }
+    if let Some(compact) = world.get_resource::<CompactRoadGraph>() {
+        tracing::info!("🧠 Compact road graph memory: {}", format_bytes(compact.memory_bytes() as u64));
+    }
+    tracing::info!(
+        "🧠 ECS component storage: {} for {} entities",
+        format_bytes(ecs_storage_bytes(world) as u64),
+        world.entities().len()
+    );
+    if let Some(rss) = resident_memory_bytes() {
+        tracing::info!("🧠 Resident memory: {}", format_bytes(rss));
+    }
+}
@@ -0,0 +1,116 @@
+//! Replay of recorded positions (`traffic-sim replay`).
+//!
+//! Reads a window of positions from TimescaleDB and publishes them to the
+//! telemetry topic again, keeping their original spacing in time (scaled by
+//! `--speed`). This re-drives ingest, the API and the frontend with real
+//! recorded traffic, e.g. to reproduce an incident. Timestamps are shifted
+//! to the time of replay, so the positions look live downstream.
+
+use anyhow::{ensure, Context, Result};
+use futures::TryStreamExt;
+use prost::Message;
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
+use sqlx::postgres::PgPoolOptions;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::{sleep_until, Instant};
+use traffic_common::startup::{wait_for_kafka, wait_for_postgres};
+use traffic_common::{Config, VehiclePosition};
+use crate::cli::ReplayArgs;
+
+/// Positions between two progress log lines.
+const PROGRESS_INTERVAL: u64 = 10_000;
+
+/// Wait for delivery progress when the producer queue is full.
+const QUEUE_FULL_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Longest wait for queued messages to be delivered at the end.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Publishes the recorded positions of a time window to Kafka.
+///
+/// # Errors
+///
+/// Returns an error if the window is empty or inverted, the speed is not
+/// positive, or TimescaleDB or Kafka are unreachable.
+pub async fn run(config: &Config, args: &ReplayArgs) -> Result<()> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    let to = args.to.unwrap_or(now);
+    ensure!(args.from < to, "--from must be before --to");
+    ensure!(args.speed.is_finite() && args.speed > 0.0, "--speed must be positive");
+
+    wait_for_postgres(&config.postgres_url, config.startup_timeout()).await?;
+    wait_for_kafka(&config.kafka_client(), config.startup_timeout()).await?;
+
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&config.postgres_url)
+        .await
+        .context("Failed to connect to Postgres")?;
+    let producer: FutureProducer = config
+        .kafka_client()
+        .set("message.timeout.ms", "5000")
+        .create()?;
+
+    tracing::info!("⏪ Replaying positions from {} to {} at {}x", args.from, to, args.speed);
+    let mut rows = sqlx::query!(
+        r#"
+        SELECT vehicle_id,
+               extract(epoch FROM time)::float8 AS "timestamp!",
+               latitude AS "latitude!",
+               longitude AS "longitude!",
+               speed,
+               heading
+        FROM vehicle_positions
+        WHERE time BETWEEN to_timestamp($1) AND to_timestamp($2)
+          AND latitude IS NOT NULL
+          AND longitude IS NOT NULL
+        ORDER BY time
+        "#,
+        args.from as f64,
+        to as f64
+    )
+    .fetch(&pool);
+
+    // Recording time and wall clock at the first position
+    let mut origin: Option<(f64, Instant, i64)> = None;
+    let mut published: u64 = 0;
+    while let Some(row) = rows.try_next().await? {
+        let (recorded_start, wall_start, replay_start) =
+            *origin.get_or_insert_with(|| (row.timestamp, Instant::now(), now));
+        let offset = Duration::from_secs_f64((row.timestamp - recorded_start).max(0.0) / args.speed);
+        sleep_until(wall_start + offset).await;
+
+        let position = VehiclePosition {
+            vehicle_id: row.vehicle_id,
+            latitude: row.latitude,
+            longitude: row.longitude,
+            speed: row.speed.unwrap_or_default(),
+            heading: row.heading.unwrap_or_default(),
+            timestamp: replay_start + offset.as_secs() as i64,
+            ..Default::default()
+        };
+        let payload = position.encode_to_vec();
+        let mut record = FutureRecord::to("raw-telemetry").key(&position.vehicle_id).payload(&payload);
+        // Queue without waiting for delivery; back off while the queue is full
+        loop {
+            match producer.send_result(record) {
+                Ok(_) => break,
+                Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), returned)) => {
+                    record = returned;
+                    tokio::time::sleep(QUEUE_FULL_BACKOFF).await;
+                }
+                Err((e, _)) => return Err(e.into()),
+            }
+        }
+
+        published += 1;
+        if published.is_multiple_of(PROGRESS_INTERVAL) {
+            tracing::info!("⏪ {} positions replayed (recorded at {:.0})", published, row.timestamp);
+        }
+    }
+
+    producer.flush(FLUSH_TIMEOUT)?;
+    tracing::info!("✅ Replay finished: {} positions published", published);
+    Ok(())
+}
@@ -0,0 +1,75 @@
+//! Live road speeds for congestion-aware fleet routing.
+//!
+//! traffic-ingest keeps the recent average speed of every road it matched
+//! positions onto in Redis ([`ROAD_SPEEDS_KEY`]). A background task polls
+//! them and hands them to the simulation loop, which loads them into the
+//! [`DynamicWeights`] resource the fleet's route planner uses.
+
+use std::collections::HashMap;
+use std::time::Duration;
+use anyhow::Result;
+use bevy_ecs::prelude::*;
+use redis::AsyncCommands;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use traffic_common::Config;
+use traffic_common::map::{DynamicWeights, RoadGraph, ROAD_SPEEDS_KEY};
+
+/// How often road speeds are fetched from Redis.
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Starts a background task polling road speeds from Redis.
+///
+/// A missing key (ingest not running, or no positions yet) and Redis
+/// errors are skipped until the next poll.
+///
+/// # Returns
+///
+/// A receiver yielding the speed in m/s per OSM way ID on every poll.
+///
+/// # Errors
+///
+/// Returns an error if `REDIS_URL` is malformed.
+pub fn spawn_road_speed_listener(config: &Config) -> Result<UnboundedReceiver<HashMap<i64, f64>>> {
+    let client = config.redis_client()?;
+    let (tx, rx) = unbounded_channel();
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        let mut conn = None;
+        loop {
+            interval.tick().await;
+            if conn.is_none() {
+                conn = client.get_multiplexed_async_connection().await.ok();
+            }
+            let Some(redis) = conn.as_mut() else { continue };
+
+            let payload: Option<String> = match redis.get(ROAD_SPEEDS_KEY).await {
+                Ok(payload) => payload,
+                Err(e) => {
+                    tracing::warn!("⚠️ Could not fetch road speeds: {}", e);
+                    conn = None;
+                    continue;
+                }
+            };
+            let Some(payload) = payload else { continue };
+            match serde_json::from_str(&payload) {
+                Ok(speeds) => {
+                    if tx.send(speeds).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => tracing::warn!("Ignoring malformed road speeds: {}", e),
+            }
+        }
+    });
+
+    Ok(rx)
+}
+
+/// Replaces the observed road speeds used for routing.
+pub fn apply_road_speeds(world: &mut World, speeds: &HashMap<i64, f64>) {
+    world.resource_scope(|world, mut weights: Mut<DynamicWeights>| {
+        let edges = weights.update_from_ways(world.resource::<RoadGraph>(), speeds);
+        tracing::debug!("🚦 Live speeds on {} road segments", edges);
+    });
+}
@@ -0,0 +1,443 @@
+//! Scenario definition for a simulation run.
+//!
+//! A scenario collects every tunable parameter of a run (fleet size, time
+//! acceleration, map extract, warm-up, run length, signal plans, fleet,
+//! turn choice, telemetry topics). Values come from the
+//! environment via [`Config`] and can be overridden by an optional JSON
+//! scenario file and command-line flags, so experiments don't require
+//! recompiles.
+
+use anyhow::{ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+use traffic_common::map::{BoundingBox, ComponentFilter};
+use traffic_common::control::EmissionRates;
+use traffic_common::signals::SignalPlan;
+use traffic_common::checkpoints::Checkpoint;
+use traffic_common::config::split_map_paths;
+use traffic_common::Config;
+use crate::cli::RunArgs;
+use crate::turns::TurnChoice;
+
+/// Upper bound on the fleet size accepted from configuration.
+const MAX_VEHICLES: usize = 1_000_000;
+
+/// Upper bound on the time acceleration factor accepted from configuration.
+const MAX_TIME_SCALE: f32 = 1000.0;
+
+/// Parameters of a single simulation run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scenario {
+    /// Number of vehicles spawned at startup
+    pub vehicle_count: usize,
+    /// Simulated seconds per wall-clock second
+    pub time_scale: f32,
+    /// Map file to simulate on; several comma-separated files are merged
+    pub map_path: String,
+    /// Optional area of the map to load; the whole extract when absent
+    #[serde(default)]
+    pub map_bbox: Option<BoundingBox>,
+    /// Strongly connected components of the map to simulate on; islands
+    /// outside them are dropped so vehicles never spawn on a dead end
+    #[serde(default)]
+    pub map_components: ComponentFilter,
+    /// Move vehicles on map geometry projected to meters instead of
+    /// interpolating in longitude/latitude degrees
+    #[serde(default)]
+    pub metric_projection: bool,
+    /// Elevation tiles (SRTM `.hgt` or GeoTIFF, comma-separated files or
+    /// directories) giving roads a grade; roads stay flat when absent
+    #[serde(default)]
+    pub elevation_path: Option<String>,
+    /// Collect parking, fuel and charging stations from the map files as
+    /// trip destinations and charging stops
+    #[serde(default)]
+    pub pois: bool,
+    /// Simulated seconds to run before telemetry counts as representative
+    #[serde(default)]
+    pub warmup_seconds: f32,
+    /// How telemetry is treated during the warm-up period
+    #[serde(default)]
+    pub warmup_telemetry: WarmupTelemetry,
+    /// Number of ticks to run before stopping; runs forever when absent
+    #[serde(default)]
+    pub ticks: Option<u64>,
+    /// Where the KPI report of a finite run is written (`.json` or `.csv`)
+    #[serde(default = "default_report_path")]
+    pub report_path: String,
+    /// Fixed-time signal plans for signalized intersections
+    #[serde(default)]
+    pub signal_plans: Vec<SignalPlan>,
+    /// Whether signalized nodes of the map without a plan get a default
+    /// two-phase plan
+    #[serde(default = "default_osm_signals")]
+    pub osm_signals: bool,
+    /// Virtual checkpoints publishing an event per passing vehicle
+    #[serde(default)]
+    pub checkpoints: Vec<Checkpoint>,
+    /// Fleet vehicles and task generation
+    #[serde(default)]
+    pub fleet: FleetConfig,
+    /// Vehicles spawned with a raised priority tier
+    #[serde(default)]
+    pub priorities: PriorityMix,
+    /// Shares of trucks and motorcycles in the ordinary traffic
+    #[serde(default)]
+    pub classes: ClassMix,
+    /// Convoys of vehicles driving together
+    #[serde(default)]
+    pub convoys: ConvoyConfig,
+    /// How vehicles without a planned route choose the next road
+    #[serde(default)]
+    pub turn_choice: TurnChoice,
+    /// Initial telemetry rates per priority tier; adjustable at runtime
+    #[serde(default)]
+    pub emission: EmissionRates,
+    /// Kafka topics telemetry is published to, each at full fidelity or
+    /// sampled down to a fixed rate
+    #[serde(default = "default_telemetry_topics")]
+    pub telemetry_topics: Vec<TelemetryTopic>,
+    /// Seed of the random number generator; a random seed (which is logged)
+    /// when absent
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Instead of a live run, run the scenario twice offline for `ticks`
+    /// ticks and check that both runs produce identical telemetry
+    #[serde(default)]
+    pub check_determinism: bool,
+    /// Run without Kafka: telemetry is counted and logged instead of
+    /// published, and control commands are not received
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Number of vehicles per raised priority tier; the rest are ordinary cars.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PriorityMix {
+    /// Number of emergency vehicles
+    #[serde(default)]
+    pub emergency: usize,
+    /// Number of transit vehicles (buses)
+    #[serde(default)]
+    pub transit: usize,
+}
+
+/// Vehicle classes of the ordinary traffic, as shares of the vehicles that
+/// are neither fleet nor priority vehicles; the rest are cars.
+///
+/// Transit vehicles are always buses and emergency vehicles always of the
+/// emergency class; fleet taxis and vans drive as cars.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassMix {
+    /// Share of trucks (0 to 1)
+    #[serde(default = "default_truck_share")]
+    pub trucks: f64,
+    /// Share of motorcycles (0 to 1)
+    #[serde(default = "default_motorcycle_share")]
+    pub motorcycles: f64,
+}
+
+impl Default for ClassMix {
+    fn default() -> Self {
+        Self { trucks: default_truck_share(), motorcycles: default_motorcycle_share() }
+    }
+}
+
+fn default_truck_share() -> f64 {
+    0.05
+}
+
+fn default_motorcycle_share() -> f64 {
+    0.03
+}
+
+/// Convoys (platoons) such as truck convoys or a bus with a shadow car.
+///
+/// Convoys are spawned after the fleet and priority vehicles. Each starts
+/// on one road with its members lined up behind the leader.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConvoyConfig {
+    /// Number of convoys
+    #[serde(default)]
+    pub count: usize,
+    /// Vehicles per convoy, including the leader
+    #[serde(default = "default_convoy_size")]
+    pub size: usize,
+    /// Distance between consecutive members in meters
+    #[serde(default = "default_convoy_gap")]
+    pub gap_m: f64,
+}
+
+impl Default for ConvoyConfig {
+    fn default() -> Self {
+        Self { count: 0, size: default_convoy_size(), gap_m: default_convoy_gap() }
+    }
+}
+
+fn default_convoy_size() -> usize {
+    3
+}
+
+fn default_convoy_gap() -> f64 {
+    20.0
+}
+
+/// Fleet composition and task generation settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FleetConfig {
+    /// Number of vehicles acting as taxis
+    #[serde(default)]
+    pub taxis: usize,
+    /// Number of vehicles acting as delivery vans
+    #[serde(default)]
+    pub delivery_vans: usize,
+    /// Randomly generated tasks per simulated minute
+    #[serde(default)]
+    pub tasks_per_minute: f64,
+    /// Number of fastest routes each trip picks one from at random, so fleet
+    /// vehicles between the same places don't all take the same roads
+    #[serde(default = "default_route_alternatives")]
+    pub route_alternatives: usize,
+}
+
+impl Default for FleetConfig {
+    fn default() -> Self {
+        Self {
+            taxis: 0,
+            delivery_vans: 0,
+            tasks_per_minute: 0.0,
+            route_alternatives: default_route_alternatives(),
+        }
+    }
+}
+
+fn default_route_alternatives() -> usize {
+    1
+}
+
+/// A Kafka topic telemetry is published to.
+///
+/// Without `sample_hz` the topic gets every frame, at the rates of the
+/// priority tiers. With it, the position of every vehicle is published at
+/// most that many times per wall-clock second, e.g. 1 Hz for lightweight
+/// consumers such as dashboards.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TelemetryTopic {
+    /// Topic name, e.g. `traffic.updates.sampled`
+    pub name: String,
+    /// Frames per vehicle and second; full fidelity when absent
+    #[serde(default)]
+    pub sample_hz: Option<f32>,
+}
+
+fn default_osm_signals() -> bool {
+    true
+}
+
+/// Returns the default telemetry topics: `raw-telemetry` at full fidelity,
+/// the topic traffic-ingest consumes.
+fn default_telemetry_topics() -> Vec<TelemetryTopic> {
+    vec![TelemetryTopic { name: "raw-telemetry".to_string(), sample_hz: None }]
+}
+
+/// Returns the default KPI report location.
+fn default_report_path() -> String {
+    "kpi_report.json".to_string()
+}
+
+/// Treatment of telemetry produced during the warm-up period.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WarmupTelemetry {
+    /// Broadcast nothing until warm-up is over
+    #[default]
+    Suppress,
+    /// Broadcast as usual but flag every frame as warm-up
+    Mark,
+}
+
+impl Scenario {
+    /// Builds the scenario for this run.
+    ///
+    /// Starts from the values in `config` (`SIM_VEHICLES`, `SIM_TIME_SCALE`,
+    /// `MAP_PATH`) and, if `SIM_SCENARIO` points to a JSON file, overrides
+    /// them with any fields present in that file, then with the
+    /// command-line options in `args`. The result is validated before being
+    /// returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the scenario file cannot be read or parsed, or if
+    /// the resulting values fail validation.
+    pub fn load(config: &Config, args: &RunArgs) -> Result<Self> {
+        let mut scenario = Self {
+            vehicle_count: config.sim_vehicles,
+            time_scale: config.sim_time_scale,
+            map_path: config.map_path.clone(),
+            map_bbox: config.map_bbox()?,
+            map_components: ComponentFilter::default(),
+            metric_projection: false,
+            elevation_path: config.elevation_path.clone(),
+            pois: false,
+            warmup_seconds: 0.0,
+            warmup_telemetry: WarmupTelemetry::default(),
+            ticks: None,
+            report_path: default_report_path(),
+            signal_plans: Vec::new(),
+            osm_signals: default_osm_signals(),
+            checkpoints: Vec::new(),
+            fleet: FleetConfig::default(),
+            priorities: PriorityMix::default(),
+            classes: ClassMix::default(),
+            convoys: ConvoyConfig::default(),
+            turn_choice: TurnChoice::default(),
+            emission: EmissionRates::default(),
+            telemetry_topics: default_telemetry_topics(),
+            seed: None,
+            check_determinism: false,
+            dry_run: false,
+        };
+
+        if let Some(path) = &config.sim_scenario {
+            scenario = scenario.merge_file(path)?;
+            tracing::info!("📄 Scenario loaded from {}", path);
+        }
+
+        scenario.apply_args(args);
+
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// Overrides fields of this scenario with those present in a JSON file.
+    fn merge_file(self, path: &str) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read scenario file {}", path))?;
+        let overrides: serde_json::Value = serde_json::from_str(&raw)
+            .with_context(|| format!("Scenario file {} is not valid JSON", path))?;
+
+        let mut merged = serde_json::to_value(self)?;
+        if let (Some(base), Some(fields)) = (merged.as_object_mut(), overrides.as_object()) {
+            for (key, value) in fields {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+
+        serde_json::from_value(merged).context("Scenario file has invalid fields")
+    }
+
+    /// Applies command-line overrides (`--ticks N`, `--report PATH`, `--seed N`,
+    /// `--check-determinism`, `--dry-run`).
+    fn apply_args(&mut self, args: &RunArgs) {
+        if let Some(ticks) = args.ticks {
+            self.ticks = Some(ticks);
+        }
+        if let Some(report) = &args.report {
+            self.report_path = report.clone();
+        }
+        if let Some(seed) = args.seed {
+            self.seed = Some(seed);
+        }
+        self.check_determinism |= args.check_determinism;
+        self.dry_run |= args.dry_run;
+    }
+
+    /// Returns the individual map files listed in `map_path`.
+    pub fn map_paths(&self) -> Vec<&str> {
+        split_map_paths(&self.map_path)
+    }
+
+    /// Checks that all parameters are within sane bounds.
+    fn validate(&self) -> Result<()> {
+        ensure!(
+            self.vehicle_count > 0 && self.vehicle_count <= MAX_VEHICLES,
+            "vehicle count must be between 1 and {}, got {}",
+            MAX_VEHICLES,
+            self.vehicle_count
+        );
+        ensure!(
+            self.time_scale.is_finite() && self.time_scale > 0.0 && self.time_scale <= MAX_TIME_SCALE,
+            "time scale must be in (0, {}], got {}",
+            MAX_TIME_SCALE,
+            self.time_scale
+        );
+        ensure!(
+            self.warmup_seconds.is_finite() && self.warmup_seconds >= 0.0,
+            "warm-up must be a non-negative number of seconds, got {}",
+            self.warmup_seconds
+        );
+        ensure!(self.ticks != Some(0), "tick count must be positive");
+        ensure!(
+            !self.check_determinism || self.ticks.is_some(),
+            "determinism check needs a tick count (--ticks)"
+        );
+        ensure!(
+            self.fleet.taxis + self.fleet.delivery_vans <= self.vehicle_count,
+            "fleet of {} vehicles exceeds the vehicle count {}",
+            self.fleet.taxis + self.fleet.delivery_vans,
+            self.vehicle_count
+        );
+        ensure!(
+            self.fleet.route_alternatives >= 1,
+            "fleet route alternatives must be at least 1, got {}",
+            self.fleet.route_alternatives
+        );
+        ensure!(
+            self.fleet.tasks_per_minute.is_finite() && self.fleet.tasks_per_minute >= 0.0,
+            "fleet task rate must be non-negative, got {}",
+            self.fleet.tasks_per_minute
+        );
+        let shares = [self.classes.trucks, self.classes.motorcycles];
+        ensure!(
+            shares.iter().all(|share| (0.0..=1.0).contains(share)) && shares.iter().sum::<f64>() <= 1.0,
+            "truck and motorcycle shares must be between 0 and 1 and add up to at most 1, got {} and {}",
+            self.classes.trucks,
+            self.classes.motorcycles
+        );
+        let special = self.fleet.taxis + self.fleet.delivery_vans + self.priorities.emergency + self.priorities.transit;
+        ensure!(
+            special <= self.vehicle_count,
+            "fleet and priority vehicles ({}) exceed the vehicle count {}",
+            special,
+            self.vehicle_count
+        );
+        ensure!(self.convoys.size >= 2, "convoys need at least 2 vehicles, got {}", self.convoys.size);
+        ensure!(
+            self.convoys.gap_m.is_finite() && self.convoys.gap_m > 0.0,
+            "convoy gap must be positive, got {}",
+            self.convoys.gap_m
+        );
+        let in_convoys = self.convoys.count * self.convoys.size;
+        ensure!(
+            special + in_convoys <= self.vehicle_count,
+            "fleet, priority and convoy vehicles ({}) exceed the vehicle count {}",
+            special + in_convoys,
+            self.vehicle_count
+        );
+        self.emission.validate().map_err(anyhow::Error::msg)?;
+        ensure!(!self.telemetry_topics.is_empty(), "at least one telemetry topic is required");
+        for topic in &self.telemetry_topics {
+            ensure!(!topic.name.trim().is_empty(), "telemetry topic names must not be empty");
+            ensure!(
+                topic.sample_hz.is_none_or(|hz| hz.is_finite() && hz > 0.0),
+                "sample rate of telemetry topic {} must be positive, got {:?}",
+                topic.name,
+                topic.sample_hz
+            );
+        }
+        let mut checkpoint_ids = HashSet::new();
+        for checkpoint in &self.checkpoints {
+            ensure!(!checkpoint.id.trim().is_empty(), "checkpoint IDs must not be empty");
+            ensure!(checkpoint_ids.insert(&checkpoint.id), "duplicate checkpoint ID {}", checkpoint.id);
+        }
+        for plan in &self.signal_plans {
+            plan.validate().map_err(anyhow::Error::msg)?;
+        }
+        ensure!(!self.map_paths().is_empty(), "no map file given");
+        for path in self.map_paths() {
+            ensure!(Path::new(path).is_file(), "map file not found: {}", path);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+//! Graceful shutdown of the simulator.
+//!
+//! Orchestrators stop the simulator with SIGTERM (Ctrl-C when run by hand).
+//! Instead of dying mid-queue, the simulation loop finishes its tick, sends
+//! one last frame for every vehicle and waits for Kafka to acknowledge what
+//! is still buffered, so the last seconds of telemetry are not lost.
+
+use std::time::Duration;
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::RunSystemOnce;
+use rdkafka::producer::Producer;
+use tokio::sync::oneshot;
+use crate::systems::broadcast::{broadcast_system, FinalBroadcast, KafkaProducer};
+
+/// Longest wait for Kafka to deliver the buffered telemetry on shutdown.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Starts a background task waiting for SIGTERM or Ctrl-C.
+///
+/// # Returns
+///
+/// A receiver that completes once a shutdown has been requested.
+pub fn spawn_shutdown_listener() -> oneshot::Receiver<()> {
+    let (tx, rx) = oneshot::channel();
+
+    tokio::spawn(async move {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            match signal(SignalKind::terminate()) {
+                Ok(mut terminate) => {
+                    tokio::select! {
+                        _ = terminate.recv() => {}
+                        _ = tokio::signal::ctrl_c() => {}
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("⚠️ Cannot listen for SIGTERM ({}), only Ctrl-C stops the simulation gracefully", e);
+                    let _ = tokio::signal::ctrl_c().await;
+                }
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = tokio::signal::ctrl_c().await;
+
+        let _ = tx.send(());
+    });
+
+    rx
+}
+
+/// Sends a final frame for every vehicle and flushes the Kafka producer.
+///
+/// Waits at most five seconds for the broker; telemetry still buffered
+/// after that is dropped with a warning.
+pub fn drain(world: &mut World) {
+    world.insert_resource(FinalBroadcast);
+    world.run_system_once(broadcast_system);
+    world.remove_resource::<FinalBroadcast>();
+
+    let KafkaProducer::Live(producer) = world.resource::<KafkaProducer>() else { return };
+    let pending = producer.in_flight_count();
+    tracing::info!("📤 Flushing {} buffered Kafka messages...", pending);
+
+    // Flushing blocks until the broker acknowledges or the timeout expires
+    match tokio::task::block_in_place(|| producer.flush(FLUSH_TIMEOUT)) {
+        Ok(()) => tracing::info!("✅ Kafka producer flushed"),
+        Err(e) => tracing::warn!(
+            "⚠️ Kafka flush incomplete after {:?} ({}): {} messages lost",
+            FLUSH_TIMEOUT,
+            e,
+            producer.in_flight_count()
+        ),
+    }
+}
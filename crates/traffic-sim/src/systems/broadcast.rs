@@ -1,55 +1,252 @@
 use bevy_ecs::prelude::*;
-use traffic_common::VehiclePosition;
-use rdkafka::producer::FutureProducer;
+use traffic_common::{VehicleClass, VehiclePosition, VehiclePriority};
+use rdkafka::producer::{FutureProducer, FutureRecord};
 use prost::Message;
+use crate::scenario::TelemetryTopic;
 
+/// Kafka output of the simulator.
+///
+/// An offline producer (used by determinism checks) publishes nothing and
+/// keeps the encoded telemetry frames in memory instead.
 #[derive(Resource)]
-pub struct KafkaProducer(pub FutureProducer);
+pub enum KafkaProducer {
+    /// Publishes to the Kafka cluster
+    Live(FutureProducer),
+    /// Records telemetry frames without publishing anything
+    Offline(Vec<Vec<u8>>),
+}
 
+impl KafkaProducer {
+    /// Queues a message for publishing (fire and forget).
+    ///
+    /// The message is handed to the producer's buffer right away, so a
+    /// flush on shutdown covers it; delivery reports are ignored. Messages
+    /// are dropped if the buffer is full. Does nothing for an offline
+    /// producer.
+    pub fn send(&self, topic: &str, key: Option<String>, payload: Vec<u8>) {
+        let KafkaProducer::Live(producer) = self else { return };
+
+        let mut record = FutureRecord::<String, Vec<u8>>::to(topic).payload(&payload);
+        if let Some(key) = &key {
+            record = record.key(key);
+        }
+        let _ = producer.send_result(record);
+    }
+}
+
+// Ticks since startup; each vehicle is emitted when it is a multiple of its tier's interval
 #[derive(Resource)]
-pub struct BroadcastCounter(pub u32);
+pub struct BroadcastCounter(pub u64);
+
+/// Present for a final frame of every vehicle on shutdown, whatever its
+/// emission interval.
+#[derive(Resource)]
+pub struct FinalBroadcast;
+
+/// Topics telemetry is published to, with the sampling clocks of the
+/// sampled ones.
+#[derive(Resource)]
+pub struct TelemetryTopics {
+    topics: Vec<TopicState>,
+    /// Simulated seconds per wall-clock second; sample rates are wall-clock
+    time_scale: f32,
+}
+
+/// A telemetry topic and when it is next due.
+struct TopicState {
+    name: String,
+    /// Wall-clock seconds between samples; `None` at full fidelity
+    period: Option<f32>,
+    /// Wall-clock seconds until the next sample
+    until_next: f32,
+}
 
+impl TelemetryTopics {
+    /// Sets up the topics of a scenario.
+    ///
+    /// # Arguments
+    ///
+    /// * `topics` - Topics and their sample rates
+    /// * `time_scale` - Simulated seconds per wall-clock second
+    pub fn new(topics: &[TelemetryTopic], time_scale: f32) -> Self {
+        Self {
+            topics: topics
+                .iter()
+                .map(|topic| TopicState {
+                    name: topic.name.clone(),
+                    period: topic.sample_hz.map(|hz| 1.0 / hz),
+                    until_next: 0.0,
+                })
+                .collect(),
+            time_scale,
+        }
+    }
+
+    /// Advances the sampling clocks by one tick.
+    ///
+    /// # Returns
+    ///
+    /// The topics to publish on in this tick with, for each, whether it is
+    /// a sampled topic (which gets every vehicle) rather than a full
+    /// fidelity one (which follows the priority tier intervals).
+    fn advance(&mut self, delta: f32) -> Vec<(usize, bool)> {
+        let elapsed = delta / self.time_scale;
+        let mut due = Vec::with_capacity(self.topics.len());
+        for (index, topic) in self.topics.iter_mut().enumerate() {
+            let Some(period) = topic.period else {
+                due.push((index, false));
+                continue;
+            };
+            topic.until_next -= elapsed;
+            if topic.until_next <= 0.0 {
+                // Never catch up on missed samples in a burst
+                topic.until_next = (topic.until_next + period).max(0.0);
+                due.push((index, true));
+            }
+        }
+        due
+    }
+}
+
+/// Ticks between keepalive frames while the simulation is paused (~1 Hz).
+const KEEPALIVE_INTERVAL_TICKS: u32 = 60;
+
+// Per-vehicle data needed to build a telemetry frame
+type BroadcastQuery<'a> = (
+    &'a crate::components::VehicleId,
+    &'a crate::components::Position,
+    &'a crate::components::Speed,
+    &'a crate::components::Heading,
+    &'a crate::components::Acceleration,
+    Option<&'a crate::components::Priority>,
+    Option<&'a crate::components::Class>,
+    Option<&'a crate::components::ConvoyId>,
+    Option<&'a crate::components::Lane>,
+);
+
+// Bevy systems take their resources as parameters
+#[allow(clippy::too_many_arguments)]
 pub fn broadcast_system(
-    query: Query<(&crate::components::VehicleId, &crate::components::Position, &crate::components::Velocity)>,
-    producer: Res<KafkaProducer>,
+    query: Query<BroadcastQuery>,
+    mut producer: ResMut<KafkaProducer>,
+    state: Res<crate::components::SimState>,
+    emission: Res<crate::components::EmissionPolicy>,
+    warmup: Res<crate::components::WarmUp>,
     mut counter: ResMut<BroadcastCounter>,
+    mut topics: ResMut<TelemetryTopics>,
+    delta: Res<crate::components::DeltaTime>,
+    final_broadcast: Option<Res<FinalBroadcast>>,
 ) {
-    counter.0 += 1;
-
+    // Warm-up transients are not representative; optionally keep them off the wire
+    let warming_up = warmup.active();
+    if warming_up && warmup.mode == crate::scenario::WarmupTelemetry::Suppress {
+        return;
+    }
 
-    if counter.0 < 10 {
+    counter.0 += 1;
+    let due = match final_broadcast {
+        Some(_) => (0..topics.topics.len()).map(|index| (index, true)).collect(),
+        None => topics.advance(delta.0),
+    };
+    if due.is_empty() {
         return;
     }
-    counter.0 = 0;
 
-    for (id, pos, vel) in query.iter() {
-        let msg = VehiclePosition {
+    for (id, pos, speed, heading, acceleration, priority, class, convoy, lane) in query.iter() {
+        let priority = priority.map_or(VehiclePriority::PriorityCar, |p| p.0);
+
+        // While paused keep the feed alive at a low rate so consumers can tell
+        // a paused simulation apart from a dead one; otherwise higher tiers
+        // are emitted more often so they stay fresh under load
+        let interval = if state.paused { KEEPALIVE_INTERVAL_TICKS } else { emission.0.interval(priority) };
+        let full = counter.0.is_multiple_of(interval as u64);
+        if !full && !due.iter().any(|(_, sampled)| *sampled) {
+            continue;
+        }
+
+        let mut msg = VehiclePosition {
             vehicle_id: id.0.clone(),
             latitude: pos.0.y as f64,
             longitude: pos.0.x as f64,
-            speed: vel.0.length() as f64,
+            // Already in m/s; converting `Velocity` back from degrees would
+            // be quantized by its f32 precision
+            speed: speed.0 as f64,
             timestamp: chrono::Utc::now().timestamp(),
+            paused: state.paused,
+            heading: heading.0 as f64,
+            acceleration: acceleration.0 as f64,
+            warmup: warming_up,
+            convoy_id: convoy.map(|convoy| format!("convoy_{}", convoy.0)).unwrap_or_default(),
+            lane: lane.map_or(0, |lane| lane.0 as u32 + 1),
+            ..Default::default()
         };
+        msg.set_priority(priority);
+        msg.set_vehicle_class(class.map_or(VehicleClass::Car, |class| class.0));
 
         let mut buf = Vec::new();
-        if msg.encode(&mut buf).is_ok() {
-            // 1. Clone the producer (it's cheap; internally an Arc)
-            let producer_clone = producer.0.clone();
-
-            // 2. Prepare the key (we need ownership of the string)
-            let key = msg.vehicle_id.clone();
-
-            // 3. Fire and forget
-            // move forces capturing buf and key into the task
-            tokio::spawn(async move {
-                // IMPORTANT: Create the Record INSIDE the task.
-                // Now it references buf and key which were moved here.
-                let record = rdkafka::producer::FutureRecord::to("raw-telemetry")
-                    .payload(&buf)
-                    .key(&key);
-
-                let _ = producer_clone.send(record, std::time::Duration::from_secs(0)).await;
-            });
+        if msg.encode(&mut buf).is_err() {
+            continue;
         }
+        for &(index, sampled) in &due {
+            if !sampled && !full {
+                continue;
+            }
+            match &mut *producer {
+                KafkaProducer::Offline(frames) => frames.push(buf.clone()),
+                live => live.send(&topics.topics[index].name, Some(msg.vehicle_id.clone()), buf.clone()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::*;
+    use crate::scenario::WarmupTelemetry;
+    use glam::Vec2;
+
+    /// Publishes one frame of a vehicle driving at `speed` m/s in Berlin.
+    fn broadcast_one(speed: f32) -> VehiclePosition {
+        let mut world = World::new();
+        world.insert_resource(KafkaProducer::Offline(Vec::new()));
+        world.insert_resource(SimState::default());
+        world.insert_resource(EmissionPolicy::default());
+        world.insert_resource(WarmUp { remaining: 0.0, mode: WarmupTelemetry::Suppress });
+        world.insert_resource(BroadcastCounter(0));
+        let topic = TelemetryTopic { name: "raw-telemetry".to_string(), sample_hz: None };
+        world.insert_resource(TelemetryTopics::new(&[topic], 1.0));
+        world.insert_resource(DeltaTime(1.0 / 60.0));
+        world.insert_resource(FinalBroadcast);
+        world.spawn((
+            VehicleId("car_0".to_string()),
+            Position(Vec2::new(13.405, 52.52)),
+            // Deliberately inconsistent: the published speed must not be derived from it
+            Velocity(Vec2::new(1.0, 1.0)),
+            Speed(speed),
+            Heading(90.0),
+            Acceleration(0.0),
+        ));
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems(broadcast_system);
+        schedule.run(&mut world);
+
+        let KafkaProducer::Offline(frames) = world.resource::<KafkaProducer>() else { unreachable!() };
+        assert_eq!(frames.len(), 1);
+        VehiclePosition::decode(frames[0].as_slice()).unwrap()
+    }
+
+    #[test]
+    fn publishes_speed_in_meters_per_second() {
+        // 50 km/h
+        let frame = broadcast_one(13.889);
+        assert_eq!(frame.speed, 13.889f32 as f64);
+        assert!(traffic_common::units::validate_speed(frame.speed).is_ok());
+    }
+
+    #[test]
+    fn publishes_standing_vehicles_at_zero() {
+        assert_eq!(broadcast_one(0.0).speed, 0.0);
     }
-}
\ No newline at end of file
+}
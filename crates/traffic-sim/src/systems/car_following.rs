@@ -0,0 +1,162 @@
+//! Car-following with the Intelligent Driver Model (IDM).
+//!
+//! Each vehicle accelerates towards its desired speed on a free road and
+//! brakes for the vehicle ahead in its lane on the same road, depending on
+//! the gap and on how much faster it is closing in. How hard it accelerates,
+//! how fast it may go and how much road it takes up depend on its
+//! [`Class`]. Vehicles queued at a red signal treat their stop position as
+//! a standing obstacle, so they slow down smoothly instead of stopping
+//! dead. Queues build up and dissolve with the reaction of every driver,
+//! which is what produces shockwaves.
+//!
+//! See Treiber, Hennecke and Helbing, "Congested traffic states in
+//! empirical observations and microscopic simulations" (2000).
+
+use bevy_ecs::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use crate::components::*;
+use crate::systems::lanes::QueuePositions;
+use traffic_common::map::CompactRoadGraph;
+
+/// Gap to the vehicle ahead when standing in a queue, in meters (IDM `s0`).
+pub const MIN_GAP_M: f64 = 2.0;
+
+/// Desired time gap to the vehicle ahead in seconds (IDM `T`).
+const TIME_GAP_S: f64 = 1.5;
+
+/// Comfortable deceleration in m/s² (IDM `b`).
+const COMFORTABLE_DECELERATION: f64 = 2.0;
+
+/// How sharply acceleration drops when nearing the desired speed (IDM `δ`).
+const ACCELERATION_EXPONENT: i32 = 4;
+
+/// Speed each vehicle may drive at this tick under the car-following model,
+/// in m/s.
+///
+/// Vehicles without an entry (e.g. not on any road) are not limited.
+#[derive(Resource, Debug, Default)]
+pub struct FollowingSpeeds(pub HashMap<Entity, f64>);
+
+/// A vehicle in a lane: how far along the road it is, how fast it drives,
+/// how fast it would like to and what kind of vehicle it is.
+#[derive(Debug, Clone, Copy)]
+struct Follower {
+    distance: f64,
+    entity: Entity,
+    speed: f64,
+    desired: f64,
+    class: Class,
+}
+
+/// A vehicle (or obstacle) ahead: where its rear is and how fast it moves.
+#[derive(Debug, Clone, Copy)]
+struct Leader {
+    rear_m: f64,
+    speed: f64,
+}
+
+// Per-vehicle data needed for car-following
+type FollowingQuery<'a> = (
+    Entity,
+    &'a GraphPosition,
+    &'a Speed,
+    &'a TargetSpeed,
+    Option<&'a Lane>,
+    Option<&'a Class>,
+);
+
+/// Computes the IDM speed of every vehicle for this tick.
+///
+/// # Behavior
+///
+/// - Orders the vehicles of each lane of each road by their distance along it
+/// - Accelerates each vehicle towards its target speed, capped by the
+///   road's speed limit and its class's top speed, as quickly as its class
+///   allows
+/// - Brakes for the vehicle ahead in the same lane and road, and for the
+///   stop position of vehicles queued at red signals, whichever is tighter
+/// - Never lets a vehicle move closer than [`MIN_GAP_M`] to the one ahead
+///
+/// # Parameters
+///
+/// * `time` - Delta time resource
+/// * `graph` - Compact road network with lengths and speed limits
+/// * `queues` - Stop positions of vehicles queued at red signals
+/// * `speeds` - Speeds, rebuilt every tick
+/// * `query` - Query for all vehicles on the road network, with their class
+pub fn car_following_system(
+    time: Res<DeltaTime>,
+    graph: Res<CompactRoadGraph>,
+    queues: Res<QueuePositions>,
+    mut speeds: ResMut<FollowingSpeeds>,
+    query: Query<FollowingQuery>,
+) {
+    speeds.0.clear();
+    let dt = time.0 as f64;
+    if dt <= 0.0 {
+        return;
+    }
+
+    // Vehicles per lane of each road, in a stable order for determinism
+    let mut lanes: BTreeMap<(usize, u8), Vec<Follower>> = BTreeMap::new();
+    for (entity, graph_pos, speed, target_speed, lane, class) in query.iter() {
+        let Some(road) = graph.edge(graph_pos.edge_index) else { continue };
+        let class = class.copied().unwrap_or_default();
+        let desired = road
+            .speed_limit_mps
+            .map_or(target_speed.0 as f64, |limit| (target_speed.0 as f64).min(limit))
+            .min(class.max_speed_mps());
+        lanes
+            .entry((graph_pos.edge_index, lane.map_or(0, |lane| lane.0)))
+            .or_default()
+            .push(Follower { distance: graph_pos.distance, entity, speed: speed.0 as f64, desired, class });
+    }
+
+    for mut vehicles in lanes.into_values() {
+        // Front of the lane first
+        vehicles.sort_by(|a, b| b.distance.total_cmp(&a.distance).then(a.entity.cmp(&b.entity)));
+
+        let mut ahead: Option<Leader> = None;
+        for Follower { distance, entity, speed, desired, class } in vehicles {
+            // A stop position counts as a standing vehicle whose rear is
+            // the minimum gap beyond it
+            let stop = queues.0.get(&entity).map(|&stop| Leader { rear_m: stop + MIN_GAP_M, speed: 0.0 });
+            let acceleration = [ahead, stop]
+                .into_iter()
+                .flatten()
+                .map(|leader| idm_acceleration(speed, desired, class, Some((leader.rear_m - distance, speed - leader.speed))))
+                .reduce(f64::min)
+                .unwrap_or_else(|| idm_acceleration(speed, desired, class, None));
+
+            let mut next_speed = (speed + acceleration * dt).max(0.0);
+            if let Some(leader) = ahead {
+                let room = (leader.rear_m - distance - MIN_GAP_M).max(0.0);
+                next_speed = next_speed.min(room / dt);
+            }
+            speeds.0.insert(entity, next_speed);
+
+            ahead = Some(Leader { rear_m: distance - class.length_m(), speed });
+        }
+    }
+}
+
+/// IDM acceleration in m/s².
+///
+/// # Arguments
+///
+/// * `speed` - Current speed in m/s
+/// * `desired` - Desired speed on a free road in m/s
+/// * `class` - Class of the vehicle, giving its maximum acceleration
+/// * `leader` - Gap to the leader in meters and approach rate (own speed
+///   minus the leader's) in m/s, if there is a leader
+fn idm_acceleration(speed: f64, desired: f64, class: Class, leader: Option<(f64, f64)>) -> f64 {
+    let max_acceleration = class.max_acceleration();
+    let free_road = 1.0 - (speed / desired.max(0.1)).powi(ACCELERATION_EXPONENT);
+    let interaction = leader.map_or(0.0, |(gap, approach)| {
+        let desired_gap = MIN_GAP_M
+            + (speed * TIME_GAP_S + speed * approach / (2.0 * (max_acceleration * COMFORTABLE_DECELERATION).sqrt()))
+                .max(0.0);
+        (desired_gap / gap.max(0.1)).powi(2)
+    });
+    max_acceleration * (free_road - interaction)
+}
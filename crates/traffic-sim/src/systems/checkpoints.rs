@@ -0,0 +1,105 @@
+//! Virtual checkpoints counting individual vehicles.
+//!
+//! Checkpoints are resolved once against the road graph into the segments
+//! of their way that end at their node. A vehicle moving on from such a
+//! segment has passed the checkpoint, which is published as a
+//! [`CheckpointEvent`] to the [`CHECKPOINT_EVENTS_TOPIC`] topic.
+
+use std::collections::HashMap;
+use bevy_ecs::prelude::*;
+use traffic_common::checkpoints::{Checkpoint, CheckpointEvent, CHECKPOINT_EVENTS_TOPIC};
+use traffic_common::map::RoadGraph;
+use crate::components::{GraphPosition, VehicleId};
+use crate::systems::broadcast::KafkaProducer;
+
+/// Checkpoints by the segment leading through them.
+#[derive(Resource, Debug, Default)]
+pub struct Checkpoints {
+    /// Checkpoint IDs keyed by edge index
+    edges: HashMap<usize, Vec<String>>,
+    /// Crossings published since startup
+    pub crossings: u64,
+}
+
+impl Checkpoints {
+    /// Resolves checkpoints against the road graph.
+    ///
+    /// Checkpoints whose way does not pass through their node are logged
+    /// and left out.
+    pub fn resolve(checkpoints: &[Checkpoint], graph: &RoadGraph) -> Self {
+        let mut edges: HashMap<usize, Vec<String>> = HashMap::new();
+        for checkpoint in checkpoints {
+            let through: Vec<usize> = graph
+                .edges_for_way(checkpoint.way_id)
+                .iter()
+                .copied()
+                .filter(|&edge| graph.edges[edge].end == checkpoint.node_id)
+                .collect();
+            if through.is_empty() {
+                tracing::warn!(
+                    "⚠️ Checkpoint {} ignored: way {} does not lead through node {}",
+                    checkpoint.id, checkpoint.way_id, checkpoint.node_id
+                );
+                continue;
+            }
+            for edge in through {
+                edges.entry(edge).or_default().push(checkpoint.id.clone());
+            }
+        }
+        Self { edges, crossings: 0 }
+    }
+
+    /// Returns `true` if no checkpoint is active.
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+}
+
+/// Edge a vehicle was on in the previous tick.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct PreviousEdge(pub usize);
+
+/// Publishes an event for every vehicle that passed a checkpoint.
+///
+/// # Parameters
+///
+/// * `checkpoints` - Active checkpoints and the crossing counter
+/// * `graph` - Road network graph, for way IDs and directions
+/// * `producer` - Kafka producer for checkpoint events
+/// * `query` - Query for all vehicles with their previous edge
+pub fn checkpoint_system(
+    mut checkpoints: ResMut<Checkpoints>,
+    graph: Res<RoadGraph>,
+    producer: Res<KafkaProducer>,
+    mut query: Query<(&VehicleId, &GraphPosition, &mut PreviousEdge)>,
+) {
+    if checkpoints.is_empty() {
+        return;
+    }
+
+    let timestamp_ms = chrono::Utc::now().timestamp_millis();
+    let mut crossings = 0;
+    for (id, graph_pos, mut previous) in query.iter_mut() {
+        if graph_pos.edge_index == previous.0 {
+            continue;
+        }
+        let left = std::mem::replace(&mut previous.0, graph_pos.edge_index);
+        let Some(passed) = checkpoints.edges.get(&left) else { continue };
+
+        let road = &graph.edges[left];
+        for checkpoint_id in passed {
+            let event = CheckpointEvent {
+                checkpoint_id: checkpoint_id.clone(),
+                vehicle_id: id.0.clone(),
+                way_id: road.id,
+                direction: road.direction,
+                timestamp_ms,
+            };
+            tracing::debug!("🚧 {} passed checkpoint {}", event.vehicle_id, event.checkpoint_id);
+            let Ok(payload) = serde_json::to_vec(&event) else { continue };
+            producer.send(CHECKPOINT_EVENTS_TOPIC, Some(event.checkpoint_id), payload);
+            crossings += 1;
+        }
+    }
+    checkpoints.crossings += crossings;
+}
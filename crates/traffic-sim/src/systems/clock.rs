@@ -0,0 +1,9 @@
+//! Simulation clock.
+
+use bevy_ecs::prelude::*;
+use crate::components::*;
+
+/// Advances the simulation clock by the current delta time.
+pub fn clock_system(time: Res<DeltaTime>, mut clock: ResMut<SimClock>) {
+    clock.0 += time.0 as f64;
+}
@@ -0,0 +1,72 @@
+//! Coupled car-following for convoys (platoons).
+//!
+//! A convoy's leader drives like any other vehicle. Every follower is fed
+//! the roads the leader enters as its [`Route`], so it takes the same turns,
+//! and adapts its target speed to the leader's speed and the distance
+//! between them, so the group stays together.
+
+use bevy_ecs::prelude::*;
+use crate::components::*;
+use traffic_common::map::{ClosureSet, CompactRoadGraph, RoadGraph};
+
+/// How strongly followers close a gap that is too large or too small, in
+/// (m/s) per meter of deviation.
+const GAP_GAIN: f64 = 0.5;
+
+/// Fastest a follower drives to catch up, in m/s (the road's speed limit
+/// still applies).
+const MAX_CATCH_UP_SPEED: f64 = 30.0;
+
+/// Couples convoy followers to their leader.
+///
+/// # Behavior
+///
+/// - Appends the leader's current road to each follower's route when the
+///   leader has moved on; if the leader passed through several roads in one
+///   tick, the roads in between are filled in by routing
+/// - Sets each follower's target speed to the leader's speed, corrected by
+///   the deviation from the desired gap
+///
+/// # Parameters
+///
+/// * `compact` - Compact road network, for segment lengths
+/// * `graph` - Road network graph, for filling in skipped roads
+/// * `closures` - Closed roads, avoided when filling in
+/// * `leaders` - Query for convoy leaders
+/// * `followers` - Query for convoy followers
+pub fn convoy_system(
+    compact: Res<CompactRoadGraph>,
+    graph: Res<RoadGraph>,
+    closures: Res<ClosureSet>,
+    leaders: Query<(&GraphPosition, &Speed), With<ConvoyLeader>>,
+    mut followers: Query<(&ConvoyMember, &GraphPosition, &mut Route, &mut TargetSpeed), Without<ConvoyLeader>>,
+) {
+    let length = |edge: usize| compact.edge(edge).map_or(0.0, |edge| edge.length);
+
+    for (member, position, mut route, mut target_speed) in followers.iter_mut() {
+        let Ok((leader, leader_speed)) = leaders.get(member.leader) else { continue };
+
+        // Follow the leader onto its current road
+        let last = route.edges.back().copied().unwrap_or(position.edge_index);
+        if last != leader.edge_index {
+            let (from, to) = (&graph.edges[last], &graph.edges[leader.edge_index]);
+            if from.end != to.start {
+                let skipped = graph.shortest_path_open(from.end, to.start, &closures).unwrap_or_default();
+                route.edges.extend(skipped);
+            }
+            route.edges.push_back(leader.edge_index);
+        }
+        route.active = true;
+
+        // Distance to the leader along the shared path
+        let gap = if route.edges.is_empty() {
+            leader.distance - position.distance
+        } else {
+            let between: f64 = route.edges.iter().rev().skip(1).map(|&edge| length(edge)).sum();
+            length(position.edge_index) - position.distance + between + leader.distance
+        };
+
+        let speed = leader_speed.0 as f64 + GAP_GAIN * (gap - member.gap_m);
+        target_speed.0 = speed.clamp(0.0, MAX_CATCH_UP_SPEED) as f32;
+    }
+}
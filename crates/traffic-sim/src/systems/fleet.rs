@@ -0,0 +1,312 @@
+//! Fleet subsystem: taxis and delivery vans serving pickup/drop-off tasks.
+//!
+//! Tasks are generated at a configurable rate between random locations on
+//! the network, dispatched to the nearest fleet vehicle of the right kind
+//! with spare capacity, and served by routing the vehicle to the pickup and
+//! then the drop-off node along the fastest path under the [`RoutingModel`].
+//! Every lifecycle transition is published as a [`TaskEvent`] to the fleet
+//! events topic.
+
+use bevy_ecs::prelude::*;
+use glam::Vec2;
+use rand::Rng;
+use std::collections::VecDeque;
+use traffic_common::fleet::{FleetKind, FleetTask, TaskEvent, TaskStatus, FLEET_EVENTS_TOPIC};
+use traffic_common::map::{ClosureSet, DynamicWeights, RoadGraph, TravelTimeModel};
+use crate::components::*;
+use crate::scenario::FleetConfig;
+use crate::systems::broadcast::KafkaProducer;
+use crate::systems::spatial::SpatialGrid;
+
+/// Leg of a task a vehicle is currently driving.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskLeg {
+    /// Driving to the pickup node
+    ToPickup,
+    /// Driving to the drop-off node
+    ToDropoff,
+}
+
+/// Fleet membership and task queue of a vehicle.
+#[derive(Component, Debug, Clone)]
+pub struct FleetVehicle {
+    /// Kind of fleet vehicle
+    pub kind: FleetKind,
+    /// Tasks assigned but not yet started
+    pub queue: VecDeque<FleetTask>,
+    /// Task currently being served, with the leg being driven
+    pub current: Option<(FleetTask, TaskLeg)>,
+}
+
+impl FleetVehicle {
+    /// Creates an idle fleet vehicle of the given kind.
+    pub fn new(kind: FleetKind) -> Self {
+        Self { kind, queue: VecDeque::new(), current: None }
+    }
+
+    /// Number of tasks held, including the one being served.
+    pub fn load(&self) -> usize {
+        self.queue.len() + usize::from(self.current.is_some())
+    }
+}
+
+/// Tasks waiting for dispatch plus the state of the task generator.
+#[derive(Resource, Debug)]
+pub struct TaskBook {
+    /// Tasks not yet assigned to a vehicle
+    pub pending: VecDeque<FleetTask>,
+    /// Generated tasks per simulated minute
+    pub tasks_per_minute: f64,
+    /// Share of generated tasks that are taxi rides (the rest are deliveries)
+    taxi_share: f64,
+    /// Fractional number of tasks owed by the generator
+    budget: f64,
+    /// Sequence number for generated task IDs
+    next_id: u64,
+    /// Fastest routes each trip is picked from at random
+    route_alternatives: usize,
+}
+
+impl TaskBook {
+    /// Creates an empty task book from the scenario's fleet configuration.
+    pub fn new(config: &FleetConfig) -> Self {
+        let fleet_size = config.taxis + config.delivery_vans;
+        let taxi_share = if fleet_size > 0 { config.taxis as f64 / fleet_size as f64 } else { 0.0 };
+
+        Self {
+            pending: VecDeque::new(),
+            // Generating tasks without any vehicle to serve them is pointless
+            tasks_per_minute: if fleet_size > 0 { config.tasks_per_minute } else { 0.0 },
+            taxi_share,
+            budget: 0.0,
+            next_id: 0,
+            route_alternatives: config.route_alternatives,
+        }
+    }
+}
+
+/// Generates, dispatches and progresses fleet tasks.
+///
+/// # Behavior
+///
+/// - Generates random ride and delivery tasks at the configured rate
+/// - Assigns pending tasks to the nearest vehicle of the matching kind that
+///   still has capacity
+/// - Routes vehicles to pickup and drop-off nodes, on one of the configured
+///   number of fastest alternatives, and detects arrival
+/// - Publishes a `TaskEvent` for every status transition
+///
+/// # Parameters
+///
+/// * `time` - Delta time resource (simulated seconds)
+/// * `clock` - Simulation clock, used as the routing departure time
+/// * `model` - Travel-time model used for routing
+/// * `graph` - Road network graph used for routing
+/// * `closures` - Closed road segments, avoided by new routes
+/// * `weights` - Observed road speeds, so new routes avoid congestion
+/// * `book` - Pending tasks and generator state
+/// * `producer` - Kafka producer for task events
+/// * `rng` - Seeded random number generator for task generation and route
+///   choice
+/// * `grid` - Vehicle positions by grid cell, to find the nearest fleet
+///   vehicle
+/// * `query` - Query for all fleet vehicles
+// Bevy systems take their resources as parameters
+#[allow(clippy::too_many_arguments)]
+pub fn fleet_system(
+    time: Res<DeltaTime>,
+    clock: Res<SimClock>,
+    model: Res<RoutingModel>,
+    graph: Res<RoadGraph>,
+    closures: Res<ClosureSet>,
+    weights: Res<DynamicWeights>,
+    mut book: ResMut<TaskBook>,
+    producer: Res<KafkaProducer>,
+    mut rng: ResMut<SimRng>,
+    grid: Res<SpatialGrid>,
+    mut query: Query<(&VehicleId, &GraphPosition, &mut FleetVehicle, &mut Route)>,
+) {
+    generate_tasks(&time, &graph, &mut book, &producer, &mut rng.0);
+    dispatch_pending(&graph, &grid, &mut book, &producer, &mut query);
+
+    let planner = RoutePlanner {
+        graph: &graph,
+        model: model.0.as_ref(),
+        closures: &closures,
+        live: &weights,
+        time_of_day: clock.0 % 86_400.0,
+        alternatives: book.route_alternatives,
+    };
+
+    for (id, graph_pos, mut fleet, mut route) in query.iter_mut() {
+        let Some(road) = graph.edges.get(graph_pos.edge_index) else { continue };
+
+        // Start the next queued task once idle
+        if fleet.current.is_none() {
+            if let Some(task) = fleet.queue.pop_front() {
+                if planner.plan(road.end, task.pickup_node, &mut route, &mut rng.0) {
+                    publish_task_event(&producer, &task, TaskStatus::EnRouteToPickup, Some(&id.0));
+                    fleet.current = Some((task, TaskLeg::ToPickup));
+                } else {
+                    publish_task_event(&producer, &task, TaskStatus::Failed, Some(&id.0));
+                }
+            }
+            continue;
+        }
+
+        // Arrival: plan exhausted and parked at the end of the final edge
+        let arrived = route.edges.is_empty() && graph_pos.distance >= road.length;
+        if !arrived {
+            continue;
+        }
+
+        let Some((task, leg)) = fleet.current.take() else { continue };
+        match leg {
+            TaskLeg::ToPickup => {
+                if planner.plan(road.end, task.dropoff_node, &mut route, &mut rng.0) {
+                    publish_task_event(&producer, &task, TaskStatus::PickedUp, Some(&id.0));
+                    fleet.current = Some((task, TaskLeg::ToDropoff));
+                } else {
+                    route.active = false;
+                    publish_task_event(&producer, &task, TaskStatus::Failed, Some(&id.0));
+                }
+            }
+            TaskLeg::ToDropoff => {
+                route.active = false;
+                publish_task_event(&producer, &task, TaskStatus::Completed, Some(&id.0));
+            }
+        }
+    }
+}
+
+/// Creates random tasks according to the configured generation rate.
+fn generate_tasks<R: Rng + ?Sized>(
+    time: &DeltaTime,
+    graph: &RoadGraph,
+    book: &mut TaskBook,
+    producer: &KafkaProducer,
+    rng: &mut R,
+) {
+    if book.tasks_per_minute <= 0.0 {
+        return;
+    }
+
+    book.budget += book.tasks_per_minute * time.0 as f64 / 60.0;
+    if book.budget < 1.0 {
+        return;
+    }
+
+    let count = book.budget.floor() as usize;
+    book.budget -= count as f64;
+
+    let locations = graph.sample_edges_by_length(count * 2, rng);
+    for pair in locations.chunks_exact(2) {
+        book.next_id += 1;
+        // Task kinds follow the fleet composition
+        let kind = if rng.gen_bool(book.taxi_share) { FleetKind::Taxi } else { FleetKind::DeliveryVan };
+        let task = FleetTask {
+            id: format!("task_{}", book.next_id),
+            kind,
+            pickup_node: graph.edges[pair[0]].start,
+            dropoff_node: graph.edges[pair[1]].start,
+        };
+        publish_task_event(producer, &task, TaskStatus::Pending, None);
+        book.pending.push_back(task);
+    }
+}
+
+/// Assigns pending tasks to the nearest fleet vehicle with spare capacity.
+///
+/// Tasks for which no vehicle is available stay pending until the next tick.
+fn dispatch_pending(
+    graph: &RoadGraph,
+    grid: &SpatialGrid,
+    book: &mut TaskBook,
+    producer: &KafkaProducer,
+    query: &mut Query<(&VehicleId, &GraphPosition, &mut FleetVehicle, &mut Route)>,
+) {
+    let mut still_pending = VecDeque::new();
+
+    while let Some(task) = book.pending.pop_front() {
+        let Some(pickup) = graph.nodes.get(&task.pickup_node) else {
+            publish_task_event(producer, &task, TaskStatus::Failed, None);
+            continue;
+        };
+        let pickup = Vec2::new(pickup.pos.x as f32, pickup.pos.y as f32);
+
+        // Skip the search while no vehicle could take the task
+        let available = |fleet: &FleetVehicle| fleet.kind == task.kind && fleet.load() < fleet.kind.capacity();
+        if !query.iter().any(|(_, _, fleet, _)| available(fleet)) {
+            still_pending.push_back(task);
+            continue;
+        }
+        let nearest = grid.nearest(pickup, |entity| query.get(entity).is_ok_and(|(_, _, fleet, _)| available(fleet)));
+
+        match nearest.and_then(|(entity, _)| query.get_mut(entity).ok()) {
+            Some((id, _, mut fleet, _)) => {
+                publish_task_event(producer, &task, TaskStatus::Assigned, Some(&id.0));
+                fleet.queue.push_back(task);
+            }
+            None => still_pending.push_back(task),
+        }
+    }
+
+    book.pending = still_pending;
+}
+
+/// Plans fastest routes for fleet vehicles.
+struct RoutePlanner<'a> {
+    graph: &'a RoadGraph,
+    model: &'a dyn TravelTimeModel,
+    closures: &'a ClosureSet,
+    live: &'a DynamicWeights,
+    /// Departure time in seconds since midnight
+    time_of_day: f64,
+    /// Number of fastest routes to pick from
+    alternatives: usize,
+}
+
+impl RoutePlanner<'_> {
+    /// Plans a route from `from` to `to` into the vehicle's `Route`.
+    ///
+    /// With several alternatives, one of the fastest routes is picked at
+    /// random so vehicles between the same places spread over the network.
+    ///
+    /// Returns `false` if the destination is unreachable.
+    fn plan<R: Rng>(&self, from: i64, to: i64, route: &mut Route, rng: &mut R) -> bool {
+        let path = if self.alternatives > 1 {
+            let mut routes = self.graph.k_routes_by(from, to, self.alternatives, |edge_idx| {
+                if self.closures.is_closed(edge_idx) {
+                    return f64::INFINITY;
+                }
+                let road = &self.graph.edges[edge_idx];
+                self.model.predict(road, self.time_of_day, self.live.speed(edge_idx)) * road.access.cost_factor()
+            });
+            (!routes.is_empty()).then(|| routes.swap_remove(rng.gen_range(0..routes.len())))
+        } else {
+            self.graph.fastest_path(from, to, self.model, self.time_of_day, self.live, self.closures)
+        };
+        match path {
+            Some(path) => {
+                route.edges = path.into();
+                route.active = true;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Publishes a task lifecycle event to Kafka (fire and forget).
+pub fn publish_task_event(producer: &KafkaProducer, task: &FleetTask, status: TaskStatus, vehicle_id: Option<&str>) {
+    let event = TaskEvent {
+        task_id: task.id.clone(),
+        kind: task.kind,
+        status,
+        vehicle_id: vehicle_id.map(str::to_string),
+        timestamp: chrono::Utc::now().timestamp(),
+    };
+
+    let Ok(payload) = serde_json::to_vec(&event) else { return };
+    producer.send(FLEET_EVENTS_TOPIC, Some(event.task_id), payload);
+}
@@ -0,0 +1,222 @@
+//! Key performance indicators for finite scenario runs.
+//!
+//! Accumulates outcome metrics (travel times, delay, distance, queues,
+//! emissions) while the simulation runs, and writes them as a JSON or CSV
+//! report at the end of a run so signal timings and other scenario variants
+//! can be compared automatically.
+
+use anyhow::{Context, Result};
+use bevy_ecs::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use traffic_common::map::RoadGraph;
+use traffic_common::signals::IntersectionDelay;
+use crate::components::*;
+
+/// Speed below which a vehicle counts as queued, in m/s.
+const QUEUE_SPEED_THRESHOLD: f32 = 0.5;
+
+/// Idle CO₂ emission rate of a passenger car, in g/s.
+const CO2_IDLE_G_PER_S: f64 = 0.6;
+
+/// Additional CO₂ emitted per meter travelled, in g/m.
+const CO2_G_PER_M: f64 = 0.1;
+
+/// Additional CO₂ emitted per unit of positive tractive power (v·a), in g·s/m².
+const CO2_G_PER_POWER: f64 = 0.06;
+
+/// Per-vehicle bookkeeping for the edge currently being traversed.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct EdgeTrip {
+    /// Edge the vehicle is currently traversing
+    pub edge_index: usize,
+    /// Simulated time at which the vehicle entered the edge
+    pub entered_at: f64,
+}
+
+/// Running totals of all KPIs for the current run.
+#[derive(Resource, Debug, Default)]
+pub struct KpiAccumulator {
+    /// Simulated seconds elapsed while metrics were being collected
+    pub elapsed: f64,
+    /// Number of completed edge traversals
+    pub completed_trips: u64,
+    /// Sum of travel times of all completed traversals, in seconds
+    pub total_travel_time: f64,
+    /// Sum of time lost versus free-flow travel, in seconds
+    pub total_delay: f64,
+    /// Total distance travelled by all vehicles, in meters
+    pub distance_travelled: f64,
+    /// Largest number of queued vehicles observed on a single edge
+    pub max_queue_length: usize,
+    /// Edge on which the largest queue was observed
+    pub max_queue_edge: Option<usize>,
+    /// Estimated CO₂ emissions, in grams
+    pub co2_grams: f64,
+}
+
+/// Final KPI report written at the end of a finite run.
+#[derive(Debug, Serialize)]
+pub struct KpiReport {
+    /// Number of simulation ticks executed
+    pub ticks: u64,
+    /// Number of simulated vehicles
+    pub vehicles: usize,
+    /// Simulated seconds covered by the metrics (excluding warm-up)
+    pub simulated_seconds: f64,
+    /// Number of completed edge traversals
+    pub completed_trips: u64,
+    /// Average edge travel time, in seconds
+    pub avg_travel_time_s: f64,
+    /// Total delay versus free-flow travel, in vehicle-seconds
+    pub total_delay_s: f64,
+    /// Total distance travelled, in kilometers
+    pub distance_km: f64,
+    /// Largest queue observed on a single edge, in vehicles
+    pub max_queue_length: usize,
+    /// Edge index where the largest queue was observed
+    pub max_queue_edge: Option<usize>,
+    /// Estimated CO₂ emissions, in kilograms
+    pub co2_kg: f64,
+    /// Delay per signalized intersection (JSON reports only)
+    pub intersections: Vec<IntersectionDelay>,
+}
+
+/// Accumulates KPIs for the current tick.
+///
+/// Skipped during warm-up so the initial transient does not skew results.
+///
+/// # Parameters
+///
+/// * `time` - Delta time resource (simulated seconds)
+/// * `warmup` - Warm-up state; metrics are only collected afterwards
+/// * `graph` - Road network graph, used for free-flow travel times
+/// * `kpi` - Running KPI totals
+/// * `query` - Query for all vehicles with graph position and kinematics
+pub fn kpi_system(
+    time: Res<DeltaTime>,
+    warmup: Res<WarmUp>,
+    graph: Res<RoadGraph>,
+    mut kpi: ResMut<KpiAccumulator>,
+    mut query: Query<(&GraphPosition, &TargetSpeed, &Speed, &Acceleration, &mut EdgeTrip)>,
+) {
+    if warmup.active() {
+        return;
+    }
+
+    let dt = time.0 as f64;
+    let now = kpi.elapsed + dt;
+    let mut queues: HashMap<usize, usize> = HashMap::new();
+
+    for (graph_pos, target_speed, speed, acceleration, mut trip) in query.iter_mut() {
+        let v = speed.0 as f64;
+        let a = acceleration.0 as f64;
+
+        kpi.distance_travelled += v * dt;
+        kpi.co2_grams += (CO2_IDLE_G_PER_S + CO2_G_PER_M * v + CO2_G_PER_POWER * (v * a).max(0.0)) * dt;
+
+        if speed.0 < QUEUE_SPEED_THRESHOLD {
+            *queues.entry(graph_pos.edge_index).or_default() += 1;
+        }
+
+        // A changed edge means the previous traversal has completed
+        if graph_pos.edge_index != trip.edge_index {
+            if let Some(road) = graph.edges.get(trip.edge_index) {
+                let travel_time = now - trip.entered_at;
+                let free_flow = road.length / (target_speed.0 as f64).max(f64::EPSILON);
+                kpi.completed_trips += 1;
+                kpi.total_travel_time += travel_time;
+                kpi.total_delay += (travel_time - free_flow).max(0.0);
+            }
+            trip.edge_index = graph_pos.edge_index;
+            trip.entered_at = now;
+        }
+    }
+
+    if let Some((&edge, &length)) = queues.iter().max_by_key(|(_, &length)| length) {
+        if length > kpi.max_queue_length {
+            kpi.max_queue_length = length;
+            kpi.max_queue_edge = Some(edge);
+        }
+    }
+
+    kpi.elapsed = now;
+}
+
+impl KpiReport {
+    /// Builds the final report from the accumulated totals.
+    pub fn from_accumulator(
+        kpi: &KpiAccumulator,
+        delays: &IntersectionDelays,
+        ticks: u64,
+        vehicles: usize,
+    ) -> Self {
+        let mut intersections: Vec<IntersectionDelay> = delays.0.values().cloned().collect();
+        intersections.sort_by_key(|delay| delay.node_id);
+
+        let avg_travel_time_s = if kpi.completed_trips > 0 {
+            kpi.total_travel_time / kpi.completed_trips as f64
+        } else {
+            0.0
+        };
+
+        Self {
+            ticks,
+            vehicles,
+            simulated_seconds: kpi.elapsed,
+            completed_trips: kpi.completed_trips,
+            avg_travel_time_s,
+            total_delay_s: kpi.total_delay,
+            distance_km: kpi.distance_travelled / 1000.0,
+            max_queue_length: kpi.max_queue_length,
+            max_queue_edge: kpi.max_queue_edge,
+            co2_kg: kpi.co2_grams / 1000.0,
+            intersections,
+        }
+    }
+
+    /// Writes the report to disk.
+    ///
+    /// The format is chosen by file extension: `.csv` produces a single-row
+    /// CSV with a header, anything else produces pretty-printed JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn write(&self, path: &str) -> Result<()> {
+        let is_csv = Path::new(path)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+
+        let contents = if is_csv {
+            self.to_csv()
+        } else {
+            serde_json::to_string_pretty(self)?
+        };
+
+        std::fs::write(path, contents)
+            .with_context(|| format!("Could not write KPI report to {}", path))
+    }
+
+    /// Renders the report as a CSV header plus one data row.
+    fn to_csv(&self) -> String {
+        let header = "ticks,vehicles,simulated_seconds,completed_trips,avg_travel_time_s,\
+                      total_delay_s,distance_km,max_queue_length,max_queue_edge,co2_kg";
+        let max_queue_edge = self.max_queue_edge.map(|e| e.to_string()).unwrap_or_default();
+        format!(
+            "{}\n{},{},{:.3},{},{:.3},{:.3},{:.3},{},{},{:.3}\n",
+            header,
+            self.ticks,
+            self.vehicles,
+            self.simulated_seconds,
+            self.completed_trips,
+            self.avg_travel_time_s,
+            self.total_delay_s,
+            self.distance_km,
+            self.max_queue_length,
+            max_queue_edge,
+            self.co2_kg,
+        )
+    }
+}
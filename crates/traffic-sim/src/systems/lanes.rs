@@ -0,0 +1,106 @@
+//! Lanes and per-lane queuing at signals.
+//!
+//! Every vehicle drives in a [`Lane`] of its road. While a traffic light
+//! shows red for its approach (or amber, to those that can still stop),
+//! vehicles queue behind each other in their lane instead of all stopping
+//! on the stop line, so a three-lane approach stores three times the
+//! vehicles of a single lane over the same length. Vehicles closing in on
+//! a queue move over to an adjacent lane if its queue is shorter.
+
+use bevy_ecs::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use crate::components::*;
+use crate::systems::car_following::MIN_GAP_M;
+use crate::systems::signals::Lights;
+use traffic_common::map::CompactRoadGraph;
+use traffic_common::VehicleClass;
+
+/// Road length a queued car occupies, including the gap to the vehicle
+/// ahead, in meters.
+pub const QUEUE_SPACING_M: f64 = Class(VehicleClass::Car).length_m() + MIN_GAP_M;
+
+/// Distance before the back of its queue from which a vehicle looks for a
+/// shorter queue in an adjacent lane, in meters.
+const LANE_CHANGE_DISTANCE_M: f64 = 50.0;
+
+/// Where vehicles approaching a red signal have to stop this tick: the
+/// stop line for the first vehicle in each lane, behind the vehicle ahead
+/// for the others.
+///
+/// Vehicles without an entry are not held by a signal.
+#[derive(Resource, Debug, Default)]
+pub struct QueuePositions(pub HashMap<Entity, f64>);
+
+/// Assigns lanes and queue positions at red signals.
+///
+/// # Behavior
+///
+/// - Keeps every vehicle's lane within the lanes of its current road
+/// - Orders the vehicles that have to stop at a traffic light (see
+///   `LightState::must_stop`) by their distance to the stop line and gives
+///   each lane's vehicles successive stop positions, each behind the
+///   length of the vehicle ahead plus [`MIN_GAP_M`]
+/// - Moves a vehicle within [`LANE_CHANGE_DISTANCE_M`] of its lane's queue
+///   to the adjacent lane with the shortest queue if that is shorter by at
+///   least one vehicle
+///
+/// # Parameters
+///
+/// * `graph` - Compact road network with the lane counts
+/// * `lights` - Traffic lights of the signalized intersections
+/// * `queues` - Stop positions, rebuilt every tick
+/// * `query` - Query for all vehicles with a lane, with their class
+pub fn lane_queue_system(
+    graph: Res<CompactRoadGraph>,
+    lights: Lights,
+    mut queues: ResMut<QueuePositions>,
+    mut query: Query<(Entity, &GraphPosition, &Speed, &mut Lane, Option<&Class>)>,
+) {
+    queues.0.clear();
+
+    // Vehicles that have to stop by edge, in a stable order for determinism
+    let mut approaches: BTreeMap<usize, Vec<(f64, Entity)>> = BTreeMap::new();
+    for (entity, graph_pos, speed, mut lane, _) in query.iter_mut() {
+        let Some(road) = graph.edge(graph_pos.edge_index) else { continue };
+        if lane.0 >= road.lanes {
+            lane.0 = road.lanes - 1;
+        }
+        let must_stop = lights
+            .state(graph.node_id(road.end), road.way_id)
+            .is_some_and(|light| light.must_stop(road.length - graph_pos.distance, speed.0 as f64));
+        if must_stop {
+            approaches.entry(graph_pos.edge_index).or_default().push((graph_pos.distance, entity));
+        }
+    }
+
+    for (edge, mut vehicles) in approaches {
+        let Some(road) = graph.edge(edge) else { continue };
+        // Nearest to the stop line first
+        vehicles.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+
+        // Next free stop position per lane
+        let mut backs = vec![road.length; road.lanes as usize];
+        for (distance, entity) in vehicles {
+            let Ok((_, _, _, mut lane, class)) = query.get_mut(entity) else { continue };
+            let spacing = class.copied().unwrap_or_default().length_m() + MIN_GAP_M;
+            let mut current = lane.0 as usize;
+
+            let approaching = distance < backs[current] && backs[current] - distance <= LANE_CHANGE_DISTANCE_M;
+            if approaching {
+                let shorter = [current.checked_sub(1), Some(current + 1)]
+                    .into_iter()
+                    .flatten()
+                    .filter(|&other| other < backs.len())
+                    .max_by(|&a, &b| backs[a].total_cmp(&backs[b]).then(b.cmp(&a)))
+                    .filter(|&other| backs[other] >= backs[current] + QUEUE_SPACING_M);
+                if let Some(other) = shorter {
+                    current = other;
+                    lane.0 = other as u8;
+                }
+            }
+
+            queues.0.insert(entity, backs[current].max(0.0));
+            backs[current] -= spacing;
+        }
+    }
+}
@@ -1,2 +1,12 @@
 pub mod movement;
-pub mod broadcast;
\ No newline at end of file
+pub mod broadcast;
+pub mod warmup;
+pub mod kpi;
+pub mod clock;
+pub mod signals;
+pub mod fleet;
+pub mod convoy;
+pub mod lanes;
+pub mod checkpoints;
+pub mod car_following;
+pub mod spatial;
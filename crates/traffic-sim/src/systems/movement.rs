@@ -5,57 +5,193 @@
 
 use bevy_ecs::prelude::*;
 use crate::components::*;
-use traffic_common::map::RoadGraph;
+use crate::systems::car_following::FollowingSpeeds;
+use crate::systems::lanes::QueuePositions;
+use crate::systems::signals::Lights;
+use crate::turns::{Junction, TurnPolicy};
+use traffic_common::geo::bearing;
+use traffic_common::map::{ClosureSet, CompactRoadGraph, RoadGraph};
 use glam::Vec2;
+use rand::Rng;
+
+/// Speed in m/s at which vehicles without right of way approach an
+/// intersection (about 15 km/h).
+const YIELD_SPEED_MPS: f64 = 4.0;
+
+/// Distance before an intersection in meters from which yielding vehicles
+/// slow down.
+const YIELD_DISTANCE_M: f64 = 20.0;
+
+/// Speed in m/s below which a vehicle held by a traffic light counts as
+/// waiting for it.
+const WAITING_SPEED_MPS: f64 = 1.0;
+
+// Per-vehicle state advanced by the movement system
+type MovementQuery<'a> = (
+    Entity,
+    &'a mut GraphPosition,
+    &'a TargetSpeed,
+    &'a mut Speed,
+    &'a mut Acceleration,
+    Option<&'a mut Route>,
+    Option<&'a mut LevelOfDetail>,
+    Option<&'a mut Lane>,
+);
 
 /// Updates vehicle positions along road network edges based on their speed.
 ///
 /// This system moves vehicles along their current road segment, advancing them
 /// based on their target speed and elapsed time. When a vehicle reaches the end
-/// of a road segment, it randomly selects the next connected road to continue on.
+/// of a road segment, it continues on the next connected road its route or the
+/// scenario's turn policy chooses.
 ///
 /// # Behavior
 ///
-/// - Advances each vehicle along its current road edge
+/// - Advances each vehicle along its current road edge at its target speed,
+///   capped by the road's tagged speed limit and by the vehicle ahead (see
+///   [`FollowingSpeeds`])
 /// - Handles road transitions when reaching the end of a segment
-/// - Randomly selects next road from available outgoing edges
+/// - Follows planned routes, or lets the scenario's [`TurnPolicy`] choose
+///   the next road among the outgoing edges that are not closed
 /// - Stops vehicles that reach dead ends
+/// - Holds vehicles at traffic lights while their approach is red, or amber
+///   and they can still stop, queued behind each other per lane (see
+///   [`QueuePositions`])
+/// - Picks a lane on each new road at random
+/// - Slows vehicles down before intersections where they have to yield
+/// - Records the achieved speed and resulting acceleration
+/// - Under reduced fidelity, moves reducible vehicles only on their update
+///   ticks, applying the accumulated time in one step
 ///
 /// # Parameters
 ///
 /// * `time` - Delta time resource for frame-independent movement
-/// * `graph` - Road network graph containing road segments and topology
-/// * `query` - Query for all entities with graph position and target speed
+/// * `graph` - Compact road network containing road segments and topology
+/// * `lights` - Traffic lights of the signalized intersections
+/// * `intersections` - Right-of-way rules of unsignalized intersections
+/// * `closures` - Closed road segments, never picked as the next road
+/// * `turns` - Policy choosing the next road
+/// * `queues` - Stop positions of vehicles queued at red signals
+/// * `following` - Speeds allowed by the car-following model
+/// * `delays` - Per-intersection delay accumulator
+/// * `fidelity` - Current level of detail chosen by the frame-budget guard
+/// * `rng` - Seeded random number generator for turn and lane choices
+/// * `query` - Query for all entities with graph position and target speed,
+///   plus their planned route and lane if any
+// Bevy systems take their resources as parameters
+#[allow(clippy::too_many_arguments)]
 pub fn movement_system(
     time: Res<DeltaTime>,
-    graph: Res<RoadGraph>,
-    mut query: Query<(&mut GraphPosition, &TargetSpeed)>,
+    graph: Res<CompactRoadGraph>,
+    lights: Lights,
+    intersections: Res<Intersections>,
+    closures: Res<ClosureSet>,
+    turns: Res<TurnPolicy>,
+    queues: Res<QueuePositions>,
+    following: Res<FollowingSpeeds>,
+    mut delays: ResMut<IntersectionDelays>,
+    fidelity: Res<Fidelity>,
+    mut rng: ResMut<SimRng>,
+    mut query: Query<MovementQuery>,
 ) {
-    for (mut graph_pos, target_speed) in query.iter_mut() {
+    for (entity, mut graph_pos, target_speed, mut speed, mut acceleration, mut route, lod, mut lane) in query.iter_mut() {
+        // Reducible vehicles skip ticks under load and catch up afterwards
+        let dt = match lod {
+            Some(mut lod) => {
+                lod.pending += time.0;
+                if lod.reducible && !fidelity.is_due(lod.slot) {
+                    lod.step = 0.0;
+                    continue;
+                }
+                lod.step = std::mem::take(&mut lod.pending);
+                lod.step
+            }
+            None => time.0,
+        };
+
         // Get the current road segment
-        if let Some(road) = graph.edges.get(graph_pos.edge_index) {
-            // Move along the road
-            let speed_m_per_sec = target_speed.0 as f64;
-            graph_pos.distance += speed_m_per_sec * (time.0 as f64);
+        if let Some(road) = graph.edge(graph_pos.edge_index) {
+            let end_node = graph.node_id(road.end);
+
+            // Move along the road, never faster than the posted limit
+            let mut speed_m_per_sec = road.speed_limit_mps
+                .map_or(target_speed.0 as f64, |limit| (target_speed.0 as f64).min(limit));
+
+            // Keep the distance to the vehicle ahead (see `car_following_system`)
+            if let Some(&following) = following.0.get(&entity) {
+                speed_m_per_sec = speed_m_per_sec.min(following);
+            }
+
+            // Approach intersections without right of way at yielding speed;
+            // traffic lights take precedence where they exist
+            if road.length - graph_pos.distance <= YIELD_DISTANCE_M
+                && !lights.is_signalized(end_node)
+                && intersections.must_yield(end_node, road.way_id)
+            {
+                speed_m_per_sec = speed_m_per_sec.min(YIELD_SPEED_MPS);
+            }
+            let step = speed_m_per_sec * (dt as f64);
+            let start_distance = graph_pos.distance;
+            graph_pos.distance += step;
+            let mut travelled = step;
+
+            // Queue behind the vehicles ahead in the lane while the signal is red
+            if let Some(&stop) = queues.0.get(&entity) {
+                if stop < road.length && graph_pos.distance > stop {
+                    graph_pos.distance = stop.max(start_distance);
+                    travelled = graph_pos.distance - start_distance;
+                }
+            }
 
             // Check if we've reached the end of the current road
             if graph_pos.distance >= road.length {
-                // Look for outgoing roads from the end of the current road
-                if let Some(next_edges) = graph.out_edges.get(&road.end) {
-                    if !next_edges.is_empty() {
-                        // Randomly select the next road
-                        let next_idx = next_edges[rand::random::<usize>() % next_edges.len()];
-                        graph_pos.edge_index = next_idx;
-                        graph_pos.distance = 0.0;
-                    } else {
-                        // Dead end - stop at the end of the road
-                        graph_pos.distance = road.length;
-                    }
-                } else {
-                    // No outgoing roads - stop here
+                let must_stop = lights
+                    .state(end_node, road.way_id)
+                    .is_some_and(|light| light.must_stop(road.length - start_distance, speed.0 as f64));
+                if must_stop {
+                    // Red light (or amber in time to stop) - hold at the stop line
                     graph_pos.distance = road.length;
+                    travelled = (road.length - start_distance).max(0.0);
+                } else {
+                    if lights.is_signalized(end_node) {
+                        delays.entry(end_node).vehicles_served += 1;
+                    }
+
+                    // Follow the planned route if there is one, otherwise
+                    // let the scenario's policy pick among the open roads
+                    let junction = Junction { graph: &graph, closures: &closures, from: graph_pos.edge_index };
+                    let next_edge = turns.0.next_edge(&junction, route.as_deref_mut(), &mut rng.0);
+
+                    match next_edge {
+                        Some(next_idx) => {
+                            graph_pos.edge_index = next_idx;
+                            graph_pos.distance = 0.0;
+                            if let Some(lane) = lane.as_deref_mut() {
+                                let lanes = graph.edge(next_idx).map_or(1, |next| next.lanes);
+                                lane.0 = if lanes > 1 { rng.0.gen_range(0..lanes) } else { 0 };
+                            }
+                        }
+                        None => {
+                            // Dead end or route destination - stop at the end of the road
+                            graph_pos.distance = road.length;
+                            travelled = (road.length - start_distance).max(0.0);
+                        }
+                    }
                 }
             }
+
+            // Standing (or creeping) while held by a light counts as its delay;
+            // car-following brings most vehicles to rest short of their stop
+            if queues.0.contains_key(&entity) && travelled < WAITING_SPEED_MPS * dt as f64 {
+                delays.entry(end_node).total_delay_seconds += dt as f64;
+            }
+
+            // Derive achieved speed and acceleration for dead-reckoning hints
+            if dt > 0.0 {
+                let new_speed = (travelled / dt as f64) as f32;
+                acceleration.0 = (new_speed - speed.0) / dt;
+                speed.0 = new_speed;
+            }
         }
     }
 }
@@ -64,39 +200,103 @@ pub fn movement_system(
 ///
 /// This system converts abstract graph positions (edge index + distance)
 /// into concrete 2D coordinates for rendering. It handles both simple
-/// straight road segments and complex curved roads with multiple geometry points.
+/// straight road segments and complex curved roads with multiple geometry points,
+/// using the graph's metric geometry when projection is enabled.
+/// The displacement since the previous frame is stored as the vehicle's
+/// velocity (in degrees per second). Vehicles skipped by the level of
+/// detail this tick are left untouched.
 ///
 /// # Parameters
 ///
-/// * `graph` - Road network graph with geometric road data
+/// * `time` - Delta time resource used to derive velocity
+/// * `compact` - Compact road network with the geometry in degrees
+/// * `graph` - Road network graph holding the metric geometry, if enabled
 /// * `query` - Query for all entities with both graph and visual positions
 pub fn sync_position_system(
+    time: Res<DeltaTime>,
+    compact: Res<CompactRoadGraph>,
     graph: Res<RoadGraph>,
-    mut query: Query<(&GraphPosition, &mut Position)>,
+    mut query: Query<(&GraphPosition, &mut Position, &mut Velocity, Option<&LevelOfDetail>)>,
 ) {
-    for (graph_pos, mut pos) in query.iter_mut() {
-        if let Some(road) = graph.edges.get(graph_pos.edge_index) {
-            if road.geometry.len() >= 2 {
+    for (graph_pos, mut pos, mut velocity, lod) in query.iter_mut() {
+        // Vehicles skipped this tick keep their position and velocity
+        if lod.is_some_and(|lod| lod.step <= 0.0) {
+            continue;
+        }
+        let dt = lod.map_or(time.0, |lod| lod.step);
+
+        if let Some(road) = compact.edge(graph_pos.edge_index) {
+            let geometry = compact.edge_geometry(graph_pos.edge_index);
+            if geometry.len() >= 2 {
                 // Calculate progress along the road (0.0 to 1.0)
                 let progress = (graph_pos.distance / road.length).clamp(0.0, 1.0);
 
+                // With projected geometry, interpolate in meters so progress is
+                // proportional to true distance, then convert back to degrees
+                let projected = graph.projected().and_then(|projected| {
+                    let geometry = projected.edges.get(graph_pos.edge_index)?;
+                    let local = interpolate_along_polyline(geometry, progress);
+                    Some(projected.projection.to_wgs84(local))
+                });
+
                 // For roads with only 2 points (simple segment), do linear interpolation
-                if road.geometry.len() == 2 {
-                    let start = road.geometry[0];
-                    let end = road.geometry[1];
+                let new_pos = if let Some(wgs84) = projected {
+                    Vec2::new(wgs84.x as f32, wgs84.y as f32)
+                } else if geometry.len() == 2 {
+                    let start = geometry[0];
+                    let end = geometry[1];
                     let interpolated = start + (end - start) * progress;
-                    pos.0 = Vec2::new(interpolated.x as f32, interpolated.y as f32);
+                    Vec2::new(interpolated.x as f32, interpolated.y as f32)
                 } else {
                     // For roads with multiple geometry points, interpolate along the polyline
                     // This provides smooth movement along curved roads
-                    let interpolated = interpolate_along_polyline(&road.geometry, progress);
-                    pos.0 = Vec2::new(interpolated.x as f32, interpolated.y as f32);
+                    let interpolated = interpolate_along_polyline(geometry, progress);
+                    Vec2::new(interpolated.x as f32, interpolated.y as f32)
+                };
+
+                if dt > 0.0 {
+                    velocity.0 = (new_pos - pos.0) / dt;
                 }
+                pos.0 = new_pos;
             }
         }
     }
 }
 
+/// Weight of the newest raw heading sample in the exponential smoothing.
+const HEADING_SMOOTHING: f32 = 0.3;
+
+/// Minimum displacement (in degrees per second) for a heading update.
+///
+/// Below this, the direction of the velocity vector is dominated by noise,
+/// so stationary vehicles keep their last heading.
+const MIN_HEADING_VELOCITY: f32 = 1e-7;
+
+/// Updates the smoothed heading of each vehicle from its velocity.
+///
+/// The raw bearing is the direction of the velocity vector (see
+/// [`traffic_common::geo::bearing`]) and is blended into the previous
+/// heading along the shortest arc, so markers turn smoothly instead of
+/// snapping at segment joints.
+///
+/// # Parameters
+///
+/// * `query` - Query for all entities with position, velocity and heading
+pub fn heading_system(mut query: Query<(&Position, &Velocity, &mut Heading)>) {
+    for (pos, velocity, mut heading) in query.iter_mut() {
+        if velocity.0.length() < MIN_HEADING_VELOCITY {
+            continue;
+        }
+
+        let ahead = pos.0 + velocity.0;
+        let raw = bearing((pos.0.x as f64, pos.0.y as f64), (ahead.x as f64, ahead.y as f64)) as f32;
+
+        // Blend along the shortest arc (-180..180) to avoid spinning at 0/360
+        let delta = (raw - heading.0 + 540.0).rem_euclid(360.0) - 180.0;
+        heading.0 = (heading.0 + HEADING_SMOOTHING * delta).rem_euclid(360.0);
+    }
+}
+
 /// Interpolates a position along a polyline based on normalized progress.
 ///
 /// For curved roads represented by multiple points, this function calculates
@@ -159,4 +359,38 @@ fn interpolate_along_polyline(geometry: &[glam::DVec2], progress: f64) -> glam::
 
     // If we get here, return the last point
     geometry[geometry.len() - 1]
-}
\ No newline at end of file
+}
+
+/// Returns the direction of travel at a position along a polyline, in
+/// degrees clockwise from north.
+///
+/// Gives vehicles their heading before they have moved, when their
+/// velocity says nothing about where they are pointing.
+///
+/// # Arguments
+///
+/// * `geometry` - Sequence of points defining the road's shape
+/// * `progress` - Normalized distance along the road (0.0 to 1.0)
+///
+/// # Returns
+///
+/// The bearing of the segment containing the position, or `0.0` (north)
+/// for a polyline without length.
+pub fn heading_along_polyline(geometry: &[glam::DVec2], progress: f64) -> f32 {
+    let segments: Vec<(glam::DVec2, glam::DVec2, f64)> = geometry
+        .windows(2)
+        .map(|pair| (pair[0], pair[1], (pair[1] - pair[0]).length()))
+        .filter(|&(_, _, length)| length > 0.0)
+        .collect();
+    let total_length: f64 = segments.iter().map(|&(_, _, length)| length).sum();
+
+    let target_distance = progress.clamp(0.0, 1.0) * total_length;
+    let mut accumulated_distance = 0.0;
+    for &(start, end, length) in &segments {
+        accumulated_distance += length;
+        if accumulated_distance >= target_distance {
+            return bearing((start.x, start.y), (end.x, end.y)) as f32;
+        }
+    }
+    0.0
+}
@@ -0,0 +1,201 @@
+//! Traffic lights and per-intersection signal delay metrics.
+//!
+//! Every node with a signal plan has a [`TrafficLight`] entity whose
+//! approach lights follow the plan; vehicles read them through [`Lights`].
+//! Light changes are published to the [`SIGNAL_STATES_TOPIC`] topic for the
+//! map. External signal timing optimizers consume delay metrics from the
+//! [`SIGNAL_METRICS_TOPIC`] topic, adjust plans, and send them back through
+//! the control topic.
+
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::SystemParam;
+use std::collections::HashSet;
+use traffic_common::geo::bearing;
+use traffic_common::map::CompactRoadGraph;
+use traffic_common::signals::{
+    ApproachLight, LightState, SignalPlan, SignalState, SIGNAL_METRICS_TOPIC, SIGNAL_STATES_TOPIC,
+};
+use crate::components::*;
+use crate::systems::broadcast::KafkaProducer;
+
+/// Simulated seconds between two signal metrics snapshots.
+const SIGNAL_METRICS_INTERVAL_SECS: f64 = 60.0;
+
+/// Simulation time at which signal metrics were last published.
+#[derive(Resource, Debug, Default)]
+pub struct SignalMetricsTimer(pub f64);
+
+/// Read access to the traffic lights by intersection.
+#[derive(SystemParam)]
+pub struct Lights<'w, 's> {
+    index: Res<'w, TrafficLights>,
+    lights: Query<'w, 's, &'static TrafficLight>,
+}
+
+impl Lights<'_, '_> {
+    /// Returns the light shown to the approach from `way_id` at `node_id`,
+    /// or `None` if the node has no traffic light.
+    pub fn state(&self, node_id: i64, way_id: i64) -> Option<LightState> {
+        let entity = self.index.0.get(&node_id)?;
+        self.lights.get(*entity).ok().map(|light| light.state(way_id))
+    }
+
+    /// Returns `true` if the node has a traffic light.
+    pub fn is_signalized(&self, node_id: i64) -> bool {
+        self.index.0.contains_key(&node_id)
+    }
+}
+
+/// Builds default plans for the map's signalized nodes (OSM
+/// `highway=traffic_signals`) that have none, see [`SignalPlan::two_phase`].
+///
+/// # Arguments
+///
+/// * `graph` - Compact road network
+/// * `signals` - OSM node IDs of the signalized nodes
+/// * `plans` - Plans already set, which take precedence
+///
+/// # Returns
+///
+/// The new plans, ordered by node ID. Nodes no road leads to are skipped.
+pub fn default_plans(graph: &CompactRoadGraph, signals: &HashSet<i64>, plans: &SignalPlans) -> Vec<SignalPlan> {
+    let mut nodes: Vec<i64> = signals.iter().copied().filter(|node| !plans.0.contains_key(node)).collect();
+    nodes.sort_unstable();
+
+    nodes
+        .into_iter()
+        .filter_map(|node_id| {
+            let node = graph.node_index(node_id)?;
+            let approaches: Vec<(i64, f64)> = graph
+                .in_edges(node)
+                .iter()
+                .filter_map(|&edge| {
+                    let road = graph.edge(edge as usize)?;
+                    let (from, to) = match graph.edge_geometry(edge as usize) {
+                        [.., from, to] => (*from, *to),
+                        _ => (graph.node_position(road.start), graph.node_position(road.end)),
+                    };
+                    Some((road.way_id, bearing((from.x, from.y), (to.x, to.y))))
+                })
+                .collect();
+            (!approaches.is_empty()).then(|| SignalPlan::two_phase(node_id, &approaches))
+        })
+        .collect()
+}
+
+/// Creates or updates the traffic light of a node after its plan was set.
+///
+/// The light gets an approach for every road entering the node and every
+/// way the plan gives green to; all show red until the next
+/// [`signal_phase_system`] run.
+pub fn install_light(world: &mut World, node_id: i64) {
+    let Some(plan) = world.resource::<SignalPlans>().0.get(&node_id) else { return };
+    let mut ways: Vec<i64> = plan.phases.iter().flat_map(|phase| phase.green_ways.iter().copied()).collect();
+    let graph = world.resource::<CompactRoadGraph>();
+    if let Some(node) = graph.node_index(node_id) {
+        ways.extend(graph.in_edges(node).iter().filter_map(|&edge| graph.edge(edge as usize)).map(|road| road.way_id));
+    }
+    ways.sort_unstable();
+    ways.dedup();
+
+    let light = TrafficLight {
+        node_id,
+        approaches: ways.into_iter().map(|way_id| ApproachLight { way_id, state: LightState::Red }).collect(),
+    };
+    match world.resource::<TrafficLights>().0.get(&node_id).copied() {
+        Some(entity) => {
+            world.entity_mut(entity).insert(light);
+        }
+        None => {
+            let entity = world.spawn(light).id();
+            world.resource_mut::<TrafficLights>().0.insert(node_id, entity);
+        }
+    }
+}
+
+/// Removes the traffic light of a node after its plan was cleared.
+pub fn remove_light(world: &mut World, node_id: i64) {
+    if let Some(entity) = world.resource_mut::<TrafficLights>().0.remove(&node_id) {
+        world.despawn(entity);
+    }
+}
+
+/// Advances every traffic light to the current phase of its plan.
+///
+/// # Behavior
+///
+/// - Sets each approach to green, amber or red as its plan prescribes at
+///   the current simulation time
+/// - Publishes the lights of each intersection whose state changed as a
+///   [`SignalState`] to [`SIGNAL_STATES_TOPIC`], keyed by node ID
+///
+/// # Parameters
+///
+/// * `clock` - Simulation clock driving signal cycles
+/// * `plans` - Active signal plans
+/// * `producer` - Kafka producer
+/// * `lights` - Query for all traffic lights
+pub fn signal_phase_system(
+    clock: Res<SimClock>,
+    plans: Res<SignalPlans>,
+    producer: Res<KafkaProducer>,
+    mut lights: Query<&mut TrafficLight>,
+) {
+    for mut light in lights.iter_mut() {
+        let Some(plan) = plans.0.get(&light.node_id) else { continue };
+        let mut changed = false;
+        for approach in light.approaches.iter_mut() {
+            let state = plan.light_state(approach.way_id, clock.0);
+            changed |= approach.state != state;
+            approach.state = state;
+        }
+        if !changed {
+            continue;
+        }
+
+        let state = SignalState {
+            node_id: light.node_id,
+            sim_time: clock.0,
+            approaches: light.approaches.clone(),
+        };
+        if let Ok(payload) = serde_json::to_vec(&state) {
+            producer.send(SIGNAL_STATES_TOPIC, Some(light.node_id.to_string()), payload);
+        }
+    }
+}
+
+/// Publishes a snapshot of cumulative intersection delays.
+///
+/// Each snapshot is a JSON array of per-intersection records, keyed by the
+/// intersection's node ID. Nothing is sent when no plans are active.
+///
+/// # Parameters
+///
+/// * `clock` - Simulation clock
+/// * `delays` - Per-intersection delay accumulator
+/// * `producer` - Kafka producer
+/// * `timer` - Time of the last snapshot
+pub fn signal_metrics_system(
+    clock: Res<SimClock>,
+    delays: Res<IntersectionDelays>,
+    producer: Res<KafkaProducer>,
+    mut timer: ResMut<SignalMetricsTimer>,
+) {
+    if clock.0 - timer.0 < SIGNAL_METRICS_INTERVAL_SECS || delays.0.is_empty() {
+        return;
+    }
+    timer.0 = clock.0;
+
+    let snapshot: Vec<_> = delays.0.values()
+        .map(|delay| serde_json::json!({
+            "node_id": delay.node_id,
+            "sim_time": clock.0,
+            "total_delay_seconds": delay.total_delay_seconds,
+            "vehicles_served": delay.vehicles_served,
+            "average_delay_seconds": delay.average_delay_seconds(),
+        }))
+        .collect();
+    let payload = serde_json::Value::Array(snapshot).to_string();
+
+    producer.send(SIGNAL_METRICS_TOPIC, None, payload.into_bytes());
+}
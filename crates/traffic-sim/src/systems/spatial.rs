@@ -0,0 +1,137 @@
+//! Spatial hash grid for neighbour queries.
+//!
+//! Vehicles are bucketed into square cells of [`CELL_SIZE_M`] meters on a
+//! local metric projection of the map, rebuilt every tick from their
+//! positions. Finding the vehicles around a point then only looks at the
+//! few cells the search circle touches instead of at every vehicle, which
+//! keeps neighbour queries cheap with tens of thousands of vehicles.
+
+use bevy_ecs::prelude::*;
+use glam::{DVec2, Vec2};
+use std::collections::HashMap;
+use traffic_common::map::LocalProjection;
+use crate::components::Position;
+
+/// Edge length of a grid cell in meters.
+///
+/// A few vehicle lengths: large enough that typical queries touch a handful
+/// of cells, small enough that a cell holds few vehicles in dense traffic.
+const CELL_SIZE_M: f64 = 50.0;
+
+/// A vehicle in the grid.
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    entity: Entity,
+    /// Position in meters on the grid's projection
+    local: DVec2,
+}
+
+/// Vehicles by grid cell, as of the last position sync.
+#[derive(Resource, Debug)]
+pub struct SpatialGrid {
+    projection: LocalProjection,
+    cells: HashMap<(i32, i32), Vec<Entry>>,
+    /// Smallest and largest occupied cell coordinates
+    bounds: Option<((i32, i32), (i32, i32))>,
+}
+
+impl SpatialGrid {
+    /// Creates an empty grid projected around `origin` (longitude,
+    /// latitude), usually the center of the map.
+    pub fn new(origin: DVec2) -> Self {
+        Self {
+            projection: LocalProjection::new(origin),
+            cells: HashMap::new(),
+            bounds: None,
+        }
+    }
+
+    /// Returns the vehicles within `radius_m` meters of a point.
+    ///
+    /// # Arguments
+    ///
+    /// * `center` - Point as (longitude, latitude)
+    /// * `radius_m` - Search radius in meters
+    ///
+    /// # Returns
+    ///
+    /// The vehicles with their distance in meters, nearest first.
+    pub fn within(&self, center: Vec2, radius_m: f64) -> Vec<(Entity, f64)> {
+        let local = self.projection.to_local(center.as_dvec2());
+        let (min, max) = (cell_of(local - DVec2::splat(radius_m)), cell_of(local + DVec2::splat(radius_m)));
+
+        let mut found: Vec<(Entity, f64)> = (min.0..=max.0)
+            .flat_map(|x| (min.1..=max.1).map(move |y| (x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .map(|entry| (entry.entity, entry.local.distance(local)))
+            .filter(|&(_, distance)| distance <= radius_m)
+            .collect();
+        found.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        found
+    }
+
+    /// Returns the vehicle nearest to a point among those `accept` lets
+    /// through, with its distance in meters.
+    ///
+    /// Searches with a radius that doubles, starting at one cell, until a
+    /// vehicle is accepted or the circle covers every occupied cell.
+    pub fn nearest(&self, center: Vec2, mut accept: impl FnMut(Entity) -> bool) -> Option<(Entity, f64)> {
+        let ((min_x, min_y), (max_x, max_y)) = self.bounds?;
+        let local = self.projection.to_local(center.as_dvec2());
+        let (low, high) = (
+            DVec2::new(min_x as f64, min_y as f64) * CELL_SIZE_M,
+            DVec2::new((max_x + 1) as f64, (max_y + 1) as f64) * CELL_SIZE_M,
+        );
+        let covering = [low, high, DVec2::new(low.x, high.y), DVec2::new(high.x, low.y)]
+            .into_iter()
+            .map(|corner| corner.distance(local))
+            .fold(0.0, f64::max);
+
+        let mut radius = CELL_SIZE_M;
+        loop {
+            let found = self.within(center, radius).into_iter().find(|&(entity, _)| accept(entity));
+            if found.is_some() || radius >= covering {
+                return found;
+            }
+            radius *= 2.0;
+        }
+    }
+}
+
+/// Returns the cell containing a point in meters.
+fn cell_of(local: DVec2) -> (i32, i32) {
+    ((local.x / CELL_SIZE_M).floor() as i32, (local.y / CELL_SIZE_M).floor() as i32)
+}
+
+/// Rebuilds the spatial grid from the current vehicle positions.
+///
+/// # Behavior
+///
+/// - Empties every cell, keeping the allocations of cells that were
+///   occupied in the previous tick and dropping the others
+/// - Inserts every vehicle into the cell of its position
+///
+/// # Parameters
+///
+/// * `grid` - Spatial grid, rebuilt every tick
+/// * `query` - Query for all vehicles with a position
+pub fn spatial_grid_system(mut grid: ResMut<SpatialGrid>, query: Query<(Entity, &Position)>) {
+    let grid = &mut *grid;
+    grid.cells.retain(|_, entries| {
+        let occupied = !entries.is_empty();
+        entries.clear();
+        occupied
+    });
+    grid.bounds = None;
+
+    for (entity, position) in query.iter() {
+        let local = grid.projection.to_local(position.0.as_dvec2());
+        let cell = cell_of(local);
+        grid.cells.entry(cell).or_default().push(Entry { entity, local });
+        grid.bounds = Some(match grid.bounds {
+            None => (cell, cell),
+            Some((min, max)) => ((min.0.min(cell.0), min.1.min(cell.1)), (max.0.max(cell.0), max.1.max(cell.1))),
+        });
+    }
+}
@@ -0,0 +1,25 @@
+//! Warm-up countdown for the start of a simulation run.
+
+use bevy_ecs::prelude::*;
+use crate::components::*;
+
+/// Counts down the warm-up period using simulated time.
+///
+/// Logs once when the warm-up ends so recordings can be aligned with the
+/// start of representative telemetry.
+///
+/// # Parameters
+///
+/// * `time` - Delta time resource (simulated seconds)
+/// * `warmup` - Remaining warm-up time
+pub fn warmup_system(time: Res<DeltaTime>, mut warmup: ResMut<WarmUp>) {
+    if !warmup.active() {
+        return;
+    }
+
+    warmup.remaining -= time.0;
+    if !warmup.active() {
+        warmup.remaining = 0.0;
+        tracing::info!("🌡️ Warm-up complete, telemetry is now representative");
+    }
+}
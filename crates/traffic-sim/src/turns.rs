@@ -0,0 +1,305 @@
+//! Choice of the next road at intersections.
+//!
+//! Vehicles without a planned route pick their next road at the end of
+//! every segment. How they pick is a [`NextEdgePolicy`], chosen per
+//! scenario through [`TurnChoice`]; planned routes (fleet trips, convoy
+//! followers) are always followed first by wrapping the policy in
+//! [`RouteFollowing`]. The movement system only ever talks to the
+//! [`TurnPolicy`] resource, so policies can be swapped and exercised on
+//! their own with any random number generator.
+
+use bevy_ecs::prelude::*;
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use traffic_common::map::{default_highway_weights, ClosureSet, CompactRoadGraph};
+use crate::components::Route;
+
+/// Where a vehicle is when it chooses its next road.
+pub struct Junction<'a> {
+    /// Road network
+    pub graph: &'a CompactRoadGraph,
+    /// Closed roads, never chosen
+    pub closures: &'a ClosureSet,
+    /// Index of the road the vehicle is leaving
+    pub from: usize,
+}
+
+impl Junction<'_> {
+    /// Returns the open roads leaving the junction, preferring those open
+    /// to through traffic: driveways and private roads are only returned
+    /// when there is no other way on.
+    fn open_edges(&self) -> Vec<usize> {
+        let Some(from) = self.graph.edge(self.from) else { return Vec::new() };
+        let open: Vec<usize> = self
+            .graph
+            .out_edges(from.end)
+            .iter()
+            .map(|&edge| edge as usize)
+            .filter(|&edge| !self.closures.is_closed(edge))
+            .collect();
+        let through: Vec<usize> = open
+            .iter()
+            .copied()
+            .filter(|&edge| self.graph.edge(edge).is_some_and(|edge| edge.access.allows_through_traffic()))
+            .collect();
+        if through.is_empty() { open } else { through }
+    }
+
+    /// Returns `true` if `edge` leads straight back to where the vehicle
+    /// came from.
+    fn is_u_turn(&self, edge: usize) -> bool {
+        match (self.graph.edge(self.from), self.graph.edge(edge)) {
+            (Some(from), Some(to)) => to.end == from.start,
+            _ => false,
+        }
+    }
+}
+
+/// Chooses the road a vehicle takes at the end of its current one.
+pub trait NextEdgePolicy: Send + Sync {
+    /// Chooses the next road.
+    ///
+    /// # Arguments
+    ///
+    /// * `junction` - The road being left and the network around it
+    /// * `route` - The vehicle's planned route, if it has one
+    /// * `rng` - Random number generator for the choice
+    ///
+    /// # Returns
+    ///
+    /// The index of the next road, or `None` to stop at the end of the
+    /// current one (a dead end, or the destination of a route).
+    fn next_edge(&self, junction: &Junction, route: Option<&mut Route>, rng: &mut dyn RngCore) -> Option<usize>;
+}
+
+/// Every open road is equally likely.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Uniform;
+
+impl NextEdgePolicy for Uniform {
+    fn next_edge(&self, junction: &Junction, _route: Option<&mut Route>, rng: &mut dyn RngCore) -> Option<usize> {
+        let end = junction.graph.edge(junction.from)?.end;
+        junction.graph.random_open_out_edge(end, junction.closures, rng)
+    }
+}
+
+/// Major roads are more likely than minor ones, in proportion to the
+/// weight of their highway class.
+#[derive(Debug, Clone)]
+pub struct RoadClassWeighted {
+    weights: HashMap<String, f64>,
+}
+
+impl RoadClassWeighted {
+    /// Creates the policy with the given weight per highway class; classes
+    /// without a weight count 1.
+    pub fn new(weights: HashMap<String, f64>) -> Self {
+        Self { weights }
+    }
+}
+
+impl Default for RoadClassWeighted {
+    /// Weights the classes like spawning does.
+    fn default() -> Self {
+        Self::new(default_highway_weights())
+    }
+}
+
+impl NextEdgePolicy for RoadClassWeighted {
+    fn next_edge(&self, junction: &Junction, _route: Option<&mut Route>, rng: &mut dyn RngCore) -> Option<usize> {
+        let edges = junction.open_edges();
+        let weights = edges.iter().map(|&edge| {
+            let class = junction.graph.edge(edge).map_or("", |edge| junction.graph.highway_type(edge));
+            self.weights.get(class).copied().unwrap_or(1.0).max(0.0)
+        });
+        match WeightedIndex::new(weights) {
+            Ok(distribution) => Some(edges[distribution.sample(rng)]),
+            // All weights zero: fall back to a uniform choice
+            Err(_) => edges.choose(rng).copied(),
+        }
+    }
+}
+
+/// Like [`Uniform`], but never turns back onto the road just driven unless
+/// it is the only way on.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoUTurn;
+
+impl NextEdgePolicy for NoUTurn {
+    fn next_edge(&self, junction: &Junction, _route: Option<&mut Route>, rng: &mut dyn RngCore) -> Option<usize> {
+        let edges = junction.open_edges();
+        let onward: Vec<usize> = edges.iter().copied().filter(|&edge| !junction.is_u_turn(edge)).collect();
+        if onward.is_empty() { edges.choose(rng).copied() } else { onward.choose(rng).copied() }
+    }
+}
+
+/// Follows the vehicle's planned route while it has an active one and
+/// leaves the choice to another policy otherwise.
+pub struct RouteFollowing {
+    fallback: Box<dyn NextEdgePolicy>,
+}
+
+impl RouteFollowing {
+    /// Creates the policy, choosing with `fallback` for vehicles without
+    /// an active route.
+    pub fn new(fallback: Box<dyn NextEdgePolicy>) -> Self {
+        Self { fallback }
+    }
+}
+
+impl NextEdgePolicy for RouteFollowing {
+    fn next_edge(&self, junction: &Junction, route: Option<&mut Route>, rng: &mut dyn RngCore) -> Option<usize> {
+        match route {
+            Some(route) if route.active => route.edges.pop_front(),
+            route => self.fallback.next_edge(junction, route, rng),
+        }
+    }
+}
+
+/// How vehicles without a planned route choose their next road, as set in
+/// a scenario.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TurnChoice {
+    /// Every open road equally likely ([`Uniform`])
+    #[default]
+    Uniform,
+    /// Major roads more likely ([`RoadClassWeighted`])
+    RoadClass,
+    /// Like `uniform`, without turning back ([`NoUTurn`])
+    NoUTurn,
+}
+
+impl TurnChoice {
+    /// Builds the policy, following planned routes first.
+    pub fn policy(self) -> TurnPolicy {
+        let fallback: Box<dyn NextEdgePolicy> = match self {
+            Self::Uniform => Box::new(Uniform),
+            Self::RoadClass => Box::new(RoadClassWeighted::default()),
+            Self::NoUTurn => Box::new(NoUTurn),
+        };
+        TurnPolicy(Box::new(RouteFollowing::new(fallback)))
+    }
+}
+
+/// The next-edge policy of the running simulation.
+#[derive(Resource)]
+pub struct TurnPolicy(pub Box<dyn NextEdgePolicy>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::DVec2;
+    use std::collections::{BTreeSet, VecDeque};
+    use traffic_common::map::{Access, Direction, Node, Road, RoadGraph};
+
+    /// Draws per test; enough to hit every edge of the junction many times.
+    const DRAWS: usize = 200;
+
+    /// Road of way `id` between two of the nodes.
+    fn road(graph: &RoadGraph, id: i64, direction: Direction, (start, end): (i64, i64), highway_type: &str) -> Road {
+        Road {
+            id,
+            direction,
+            start,
+            end,
+            length: 100.0,
+            geometry: vec![graph.nodes[&start].pos, graph.nodes[&end].pos],
+            highway_type: highway_type.to_string(),
+            speed_limit_mps: None,
+            name: None,
+            ref_: None,
+            grade: None,
+            turn_lanes: None,
+            lanes: None,
+            bridge: false,
+            tunnel: false,
+            layer: None,
+            roundabout: false,
+            access: Access::Public,
+        }
+    }
+
+    /// A junction (node 2) entered from node 1 over edge 0, with a way back
+    /// (edge 1) and ways on to nodes 3 (edge 2, residential) and 4 (edge 3,
+    /// service). Node 3 is a dead end whose only way on is back (edge 4).
+    fn junction_graph() -> CompactRoadGraph {
+        let mut graph = RoadGraph::default();
+        for (id, pos) in [(1, (13.40, 52.52)), (2, (13.41, 52.52)), (3, (13.42, 52.52)), (4, (13.41, 52.53))] {
+            graph.nodes.insert(id, Node { id, pos: DVec2::from(pos) });
+        }
+        graph.edges = vec![
+            road(&graph, 10, Direction::Forward, (1, 2), "residential"),
+            road(&graph, 10, Direction::Backward, (2, 1), "residential"),
+            road(&graph, 11, Direction::Forward, (2, 3), "residential"),
+            road(&graph, 12, Direction::Forward, (2, 4), "service"),
+            road(&graph, 11, Direction::Backward, (3, 2), "residential"),
+        ];
+        CompactRoadGraph::from(&graph)
+    }
+
+    /// Collects the roads a policy picks when leaving `from`, over many draws.
+    fn picks(policy: &dyn NextEdgePolicy, graph: &CompactRoadGraph, from: usize) -> BTreeSet<Option<usize>> {
+        let closures = ClosureSet::default();
+        let junction = Junction { graph, closures: &closures, from };
+        let mut rng = StdRng::seed_from_u64(42);
+        (0..DRAWS).map(|_| policy.next_edge(&junction, None, &mut rng)).collect()
+    }
+
+    /// Always picks the same road, to tell when a fallback is consulted.
+    struct Fixed(usize);
+
+    impl NextEdgePolicy for Fixed {
+        fn next_edge(&self, _junction: &Junction, _route: Option<&mut Route>, _rng: &mut dyn RngCore) -> Option<usize> {
+            Some(self.0)
+        }
+    }
+
+    #[test]
+    fn no_u_turn_never_turns_back_when_another_way_exists() {
+        let graph = junction_graph();
+        assert_eq!(picks(&NoUTurn, &graph, 0), BTreeSet::from([Some(2), Some(3)]));
+    }
+
+    #[test]
+    fn no_u_turn_turns_back_at_dead_ends() {
+        let graph = junction_graph();
+        assert_eq!(picks(&NoUTurn, &graph, 2), BTreeSet::from([Some(4)]));
+    }
+
+    #[test]
+    fn road_class_weighted_skips_zero_weight_classes() {
+        let graph = junction_graph();
+        let policy = RoadClassWeighted::new(HashMap::from([("residential".to_string(), 0.0), ("service".to_string(), 1.0)]));
+        assert_eq!(picks(&policy, &graph, 0), BTreeSet::from([Some(3)]));
+    }
+
+    #[test]
+    fn road_class_weighted_falls_back_to_uniform_when_all_weights_are_zero() {
+        let graph = junction_graph();
+        let policy = RoadClassWeighted::new(HashMap::from([("residential".to_string(), 0.0), ("service".to_string(), 0.0)]));
+        assert_eq!(picks(&policy, &graph, 0), BTreeSet::from([Some(1), Some(2), Some(3)]));
+    }
+
+    #[test]
+    fn route_following_pops_the_route_before_using_its_fallback() {
+        let graph = junction_graph();
+        let closures = ClosureSet::default();
+        let junction = Junction { graph: &graph, closures: &closures, from: 0 };
+        let mut rng = StdRng::seed_from_u64(42);
+        let policy = RouteFollowing::new(Box::new(Fixed(2)));
+
+        let mut route = Route { edges: VecDeque::from([3]), active: true };
+        assert_eq!(policy.next_edge(&junction, Some(&mut route), &mut rng), Some(3));
+        assert!(route.edges.is_empty());
+        // An exhausted route ends at its destination instead of driving on
+        assert_eq!(policy.next_edge(&junction, Some(&mut route), &mut rng), None);
+
+        let mut inactive = Route { edges: VecDeque::from([3]), active: false };
+        assert_eq!(policy.next_edge(&junction, Some(&mut inactive), &mut rng), Some(2));
+        assert_eq!(inactive.edges, VecDeque::from([3]));
+        assert_eq!(policy.next_edge(&junction, None, &mut rng), Some(2));
+    }
+}
@@ -34,6 +34,10 @@ interface Road {
   id: number;
   /** Sequence of coordinates defining the road path */
   geometry: Coordinate[];
+  /** Street name, if tagged in OpenStreetMap */
+  name?: string;
+  /** Route number such as "A100", if tagged */
+  ref?: string;
 }
 
 /**